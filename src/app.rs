@@ -1,20 +1,155 @@
 use clap::{self, Arg, Command};
 
 fn add_input(cmd: Command) -> Command {
-    cmd.arg(
+    add_input_options(cmd).arg(
         Arg::new("input")
             .help("Input file")
             .default_value("-")
-            .long_help("If not present or a single dash, standard input will be used"),
+            .long_help(
+                "If not present or a single dash, standard input will be used.  An \
+                 address like 'udp://0.0.0.0:8125' can be used for listening to UDP \
+                 datagrams (one or more lines each, like in statsd protocol) for as \
+                 long as specified with --duration.  Addresses like \
+                 'tcp://localhost:5140' or 'unix:///run/app.sock' connect to a stream \
+                 socket, which is read until it is closed (or --duration elapses).",
+            ),
     )
 }
 
 fn add_input_as_option(cmd: Command) -> Command {
-    cmd.arg(
+    add_input_options(cmd).arg(
         Arg::new("input")
             .long("input")
             .default_value("-")
-            .long_help("If not present or a single dash, standard input will be used")
+            .long_help(
+                "If not present or a single dash, standard input will be used.  An \
+                 address like 'udp://0.0.0.0:8125' can be used for listening to UDP \
+                 datagrams (one or more lines each, like in statsd protocol) for as \
+                 long as specified with --duration.  Addresses like \
+                 'tcp://localhost:5140' or 'unix:///run/app.sock' connect to a stream \
+                 socket, which is read until it is closed (or --duration elapses).",
+            )
+            .takes_value(true),
+    )
+}
+
+fn add_input_options(cmd: Command) -> Command {
+    // Some subcommands use the duration for something else too
+    let cmd = if cmd.get_arguments().any(|arg| arg.get_id() == "duration") {
+        cmd
+    } else {
+        cmd.arg(
+            Arg::new("duration")
+                .long("duration")
+                .help("Read network input for that duration (example: '30s')")
+                .takes_value(true),
+        )
+    };
+    cmd.arg(
+        Arg::new("multiline-join")
+            .long("multiline-join")
+            .help("Append lines matching this regex to the previous record")
+            .long_help(
+                "Lines matching this regular expression are considered continuation \
+                 lines (like the ones in a stack trace) and they will be appended to \
+                 the previous input record before any processing happens.  For \
+                 instance: '^\\s'.",
+            )
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("multiline")
+            .long("multiline")
+            .help("Start a new record with the lines matching this regex, folding the rest")
+            .long_help(
+                "Lines matching this regular expression start a new input record, \
+                 and the ones not matching it (like the lines of a stack trace) are \
+                 appended to the previous record before any processing happens, so \
+                 that multi-line log records count once.  For instance: \
+                 '^\\d{4}-\\d{2}-\\d{2}'.",
+            )
+            .value_name("START_REGEX")
+            .conflicts_with("multiline-join")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("encoding")
+            .long("encoding")
+            .help("Encoding of the input text")
+            .long_help(
+                "Encoding of the input text.  With 'auto', input is read as UTF-8, \
+                 unless it starts with a UTF-16LE byte order mark (like files written \
+                 by some Windows tools).  Byte order marks are skipped, and invalid \
+                 UTF-8 is replaced rather than discarding the lines with it.",
+            )
+            .possible_values(["auto", "utf-8", "utf-16le"])
+            .default_value("auto")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("delimiter")
+            .long("delimiter")
+            .help("Split input records on this string, rather than on newlines")
+            .long_help(
+                "Split input records on this string, rather than on newlines.  \
+                 Escape sequences '\\0', '\\t', '\\n' and '\\r' are understood, so \
+                 that '\\0' reads NUL-separated records (like the ones written by \
+                 'find -print0').  With 'json-array', every element of the JSON \
+                 arrays in the input (like '[1, 2.5, 3]') is a record.  CSV input is \
+                 always split on newlines.",
+            )
+            .allow_hyphen_values(true)
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("skip")
+            .long("skip")
+            .help("Skip this number of lines at the start of the input (like headers)")
+            .value_name("N")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("head")
+            .long("head")
+            .help("Read only this number of lines (after the skipped ones)")
+            .long_help(
+                "Read only this number of lines (after the ones skipped with \
+                 --skip).  The rest of the input is not read at all, which makes \
+                 lowcharts finish early when reading from a pipe.",
+            )
+            .value_name("N")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("tail")
+            .long("tail")
+            .help("Read only this number of lines at the end of the input")
+            .long_help(
+                "Read only this number of lines at the end of the input (of the \
+                 ones left by --skip and --head).  Lines before them are not even \
+                 read in regular files.",
+            )
+            .value_name("N")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("every")
+            .long("every")
+            .help("Read just one of every N lines, for quick previews of huge inputs")
+            .value_name("N")
+            .conflicts_with("sample-rate")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("sample-rate")
+            .long("sample-rate")
+            .help("Read lines with this probability (like 0.01), for quick previews")
+            .long_help(
+                "Read lines with this probability (like 0.01 for 1% of them), chosen \
+                 at random, for quick approximate charts of huge inputs.  The \
+                 fraction of lines read is noted in the output.",
+            )
+            .value_name("RATE")
             .takes_value(true),
     )
 }
@@ -62,12 +197,172 @@ the named one will be used).
     )
 }
 
+fn add_logfmt(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("logfmt")
+            .long("logfmt")
+            .help("Use the value of this key in logfmt formatted lines")
+            .long_help(
+                "Use the value of this key in input lines formatted as logfmt (a \
+                 sequence of key=value pairs, like 'level=info took=12.5').  This is \
+                 an alternative to --regex for structured logs.",
+            )
+            .value_name("KEY")
+            .conflicts_with("regex")
+            .takes_value(true),
+    )
+}
+
+fn add_json_field(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("json-field")
+            .long("json-field")
+            .help("Use the value of this field in JSON formatted lines")
+            .long_help(
+                "Parse every input line as a JSON object and use the value of the \
+                 field addressed by this dot separated path (like '.latency_ms' or \
+                 '.http.timings.0').  This is an alternative to --regex for \
+                 structured logs.",
+            )
+            .value_name("PATH")
+            .conflicts_with_all(&["regex", "logfmt"])
+            .takes_value(true),
+    )
+}
+
+fn add_csv(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("csv")
+            .long("csv")
+            .help("Parse input as CSV, using the values of a column")
+            .long_help(
+                "Parse input as CSV (quoted fields are supported) and use the \
+                 values of the column selected with --column (first column by \
+                 default)",
+            )
+            .conflicts_with_all(&["regex", "logfmt", "json-field", "preset"])
+            .takes_value(false),
+    )
+    .arg(
+        Arg::new("column")
+            .long("column")
+            .help("Column to use in CSV input, by number (starting at 1) or by name")
+            .long_help(
+                "Column to use in CSV input.  Either a number (starting at 1) or the \
+                 name of the column in the header row.",
+            )
+            .requires("csv")
+            .takes_value(true),
+    )
+}
+
+fn add_preset(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("preset")
+            .long("preset")
+            .help("Use the regex and timestamp format of a well known log format")
+            .long_help(
+                "Use the regex and timestamp format of the logs of a common server \
+                 (nginx, apache, haproxy or envoy), of the results of a load-testing \
+                 tool (vegeta, hey, wrk, jmeter or gatling), or of a preset defined in the \
+                 config file.  The regex exposes fields like status, bytes or \
+                 request_time (the default one for values, when present in logs), \
+                 that can be selected with --capture.",
+            )
+            .value_name("NAME")
+            .conflicts_with_all(&["regex", "logfmt", "json-field"])
+            .takes_value(true),
+    )
+}
+
+fn add_capture(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("capture")
+            .long("capture")
+            .help("Use this named capture group of the regex (or preset) for values")
+            .value_name("NAME")
+            .takes_value(true),
+    )
+}
+
+// Options for extracting numerical values from input lines
+fn add_value_extraction(cmd: Command) -> Command {
+    add_all_matches(add_regex(add_preset(add_capture(add_logfmt(
+        add_json_field(add_csv(add_human_input(add_duration_input(cmd)))),
+    )))))
+}
+
+fn add_all_matches(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("all-matches")
+            .long("all-matches")
+            .help("Take a value from every match of the regex in a line")
+            .long_help(
+                "Take a value from every match of the regex in a line, not just from \
+                 the first one, for lines carrying several measurements (like \
+                 'shard1=12ms shard2=15ms' with --regex '=([0-9]+)ms').",
+            ),
+    )
+}
+
+fn add_duration_input(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("duration-input")
+            .long("duration-input")
+            .help("Parse durations, like 250ms or 3m20s, in this unit [default: s]")
+            .long_help(
+                "Parse values that are durations, like 250ms, 1.2s or 3m20s, \
+                 converting them to this unit (ns, us, ms, s, m, h or d; seconds if \
+                 omitted).  Plain numbers are taken as they are.  The unit needs an \
+                 equal sign, like '--duration-input=ms', since a separate word is \
+                 taken as the input.",
+            )
+            .value_name("UNIT")
+            .possible_values(["ns", "us", "ms", "s", "m", "h", "d"])
+            .min_values(0)
+            .multiple_values(false)
+            .require_equals(true)
+            .default_missing_value("s")
+            .takes_value(true),
+    )
+}
+
+fn add_human_input(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("human-input")
+            .long("human-input")
+            .help("Parse values with magnitude suffixes, like 3.5K, 2.1M or 512KiB")
+            .long_help(
+                "Parse values with magnitude suffixes, like 3.5K, 2.1M or 512KiB, as \
+                 printed by `du -h`, `ls -lh` or many metric dumps.  Suffixes like Ki \
+                 are powers of 1024, and bare suffixes like K are powers of 1000 \
+                 with 'si' (the default) or powers of 1024 with 'iec' (what `du` and \
+                 `ls` mean).  A trailing B is ignored.  The choice needs an equal \
+                 sign, like '--human-input=iec', since a separate word is taken as \
+                 the input.",
+            )
+            .value_name("MAGNITUDES")
+            .possible_values(["si", "iec"])
+            .min_values(0)
+            .multiple_values(false)
+            .require_equals(true)
+            .default_missing_value("si")
+            .takes_value(true),
+    )
+}
+
 fn add_non_capturing_regex(cmd: Command) -> Command {
     cmd.arg(
         Arg::new("regex")
             .long("regex")
             .short('R')
             .help("Filter out lines where regex is not present")
+            .long_help(
+                "Filter out lines where regex is not present.  If the regex has a \
+                 capture group named `ts` (like in '^\\S+ (?P<ts>\\S+ \\S+)'), \
+                 timestamps will be looked for in the text it captures, instead of in \
+                 the same position as in the first line.",
+            )
             .takes_value(true),
     )
 }
@@ -77,8 +372,7 @@ fn add_width(cmd: Command) -> Command {
         Arg::new("width")
             .long("width")
             .short('w')
-            .help("Use this many characters as terminal width")
-            .default_value("110")
+            .help("Use this many characters as terminal width [default: width of the terminal, or 110]")
             .takes_value(true),
     )
 }
@@ -105,22 +399,672 @@ fn add_precision(cmd: Command) -> Command {
     )
 }
 
+fn add_normalize(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("normalize")
+            .long("normalize")
+            .help("Display the fraction of values in every bucket, instead of counts")
+            .long_help(
+                "Display the fraction of values in every bucket, instead of counts, \
+                 and scale bars accordingly.  Handy for comparing inputs of different \
+                 sizes.",
+            )
+            .takes_value(false),
+    )
+}
+
 fn add_log_scale(cmd: Command) -> Command {
     cmd.arg(
         Arg::new("log-scale")
             .long("log-scale")
             .help("Use a logarithmic scale in buckets")
+            .conflicts_with("symlog")
+            .takes_value(false),
+    )
+}
+
+fn add_base_date(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("base-date")
+            .long("base-date")
+            .help("Date (YYYY-MM-DD) for timestamps with no date (defaults to today)")
+            .long_help(
+                "Date (in YYYY-MM-DD format) used for timestamps that have no date \
+                 information, like the ones generated by `strace -t`.  If not present, \
+                 today will be used.  A timestamp going backwards more than 12 hours is \
+                 assumed to be in the following day.",
+            )
+            .takes_value(true),
+    )
+}
+
+fn add_bucket_label(cmd: Command) -> Command {
+    cmd.arg(
+        // Some subcommands use `label` for other purposes, hence the id
+        Arg::new("bucket-label")
+            .long("label")
+            .help("Label buckets with their start, end, both, or time since the first one")
+            .possible_values(["start", "end", "range", "relative"])
+            .default_value("start")
+            .takes_value(true),
+    )
+}
+
+fn add_strict_position(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("strict-position")
+            .long("strict-position")
+            .help("Look for timestamps only where they were found in the first line")
+            .long_help(
+                "Look for timestamps only in the same position they were found in the \
+                 first line.  By default, when no timestamp is found in that position, \
+                 it is looked for in the rest of the line (as the length of the text \
+                 preceding it, like log levels or hostnames, may vary).",
+            )
+            .takes_value(false),
+    )
+}
+
+fn add_symlog(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("symlog")
+            .long("symlog")
+            .help("Use a symmetric logarithmic scale, linear in [-THRESHOLD, THRESHOLD]")
+            .long_help(
+                "Use a symmetric logarithmic scale: linear in the vicinity of zero \
+                 (roughly between -THRESHOLD and THRESHOLD) and logarithmic beyond it, \
+                 for both negative and positive values",
+            )
+            .value_name("THRESHOLD")
+            .allow_hyphen_values(true)
+            .takes_value(true),
+    )
+}
+
+fn add_sort(cmd: Command<'static>, default: &'static str) -> Command<'static> {
+    cmd.arg(
+        Arg::new("sort")
+            .long("sort")
+            .help("Sort rows by count (most frequent first), label or none")
+            .long_help(
+                "Sort rows by count (most frequent first), by label (alphabetically) or \
+                 none (in the order matches are given, or in the order terms are first \
+                 seen in the input).",
+            )
+            .possible_values(["count", "label", "none"])
+            .default_value(default)
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("reverse")
+            .long("reverse")
+            .help("Reverse the order of rows")
+            .takes_value(false),
+    )
+}
+
+fn add_transform(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("transform")
+            .long("transform")
+            .help("Transform values before plotting them")
+            .long_help(
+                "Transform values before plotting them: 'zscore' for the number of \
+                 standard deviations from the average (handy for comparing metrics \
+                 with different scales), 'log' for natural logarithm or 'sqrt' for \
+                 square root.  Values outside of the domain of the transformation \
+                 are discarded.",
+            )
+            .possible_values(["zscore", "log", "sqrt"])
+            .takes_value(true),
+    )
+}
+
+fn add_show_extremes(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("show-extremes")
+            .long("show-extremes")
+            .help("Display the N smallest and N biggest values, and where they were found")
+            .value_name("N")
+            .takes_value(true),
+    )
+}
+
+fn add_sample(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("sample")
+            .long("sample")
+            .help("Use a random sample of N values, for bounded memory usage")
+            .long_help(
+                "Keep only a random sample of N values of the input (chosen \
+                 uniformly with reservoir sampling), so that huge inputs can be \
+                 processed with bounded memory usage.  Statistics and buckets are \
+                 computed from the sample.",
+            )
+            .value_name("N")
+            .takes_value(true),
+    )
+}
+
+fn add_parallel(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("parallel")
+            .long("parallel")
+            .help("Parse big input files using several threads")
+            .long_help(
+                "Parse the input file in chunks, using as many threads as CPUs are \
+                 available, which is much faster for big files.  Standard input, \
+                 network inputs and CSV are still read sequentially, as well as \
+                 inputs with --multiline, --multiline-join, --locate or \
+                 --show-extremes.",
+            ),
+    )
+}
+
+fn add_graphics(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("graphics")
+            .long("graphics")
+            .help("Draw the plot as an image, for terminals supporting it")
+            .long_help(
+                "Draw the plot as an image (instead of with characters) using the \
+                 sixel or the kitty graphics protocols.  With 'auto', the protocol \
+                 supported by the terminal is guessed from the environment, falling \
+                 back to text output.",
+            )
+            .possible_values(["auto", "sixel", "kitty", "off"])
+            .default_value("off")
+            .takes_value(true),
+    )
+}
+
+// Output formats for plots ("text" is the same as "ansi-text")
+const FORMATS: [&str; 6] = ["text", "ansi-text", "plain-text", "markdown", "json", "csv"];
+
+fn add_output(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("output")
+            .long("output")
+            .short('o')
+            .help("Also write the plot as a SVG image into this file")
+            .value_name("FILE")
+            .takes_value(true),
+    )
+}
+
+fn add_export_data(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("export-data")
+            .long("export-data")
+            .help("Also write the data of the plot as CSV into this file")
+            .long_help(
+                "Also write the data of the plot (after aggregation) as CSV into \
+                 this file: the edges and counts of the buckets of histograms, or \
+                 the value of every column of plots.  Fields are separated by tabs \
+                 if the name of the file ends in .tsv.",
+            )
+            .value_name("FILE")
+            .takes_value(true),
+    )
+}
+
+fn add_save_state(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("save-state")
+            .long("save-state")
+            .help("Also write the state of the histogram as JSON into this file")
+            .long_help(
+                "Also write the state of the histogram (its buckets and statistics) \
+                 as JSON into this file, so that the ones of several inputs (like \
+                 the logs of several hosts) can be merged into a single chart with \
+                 'lowcharts merge', without gathering the inputs.",
+            )
+            .value_name("FILE")
+            .takes_value(true),
+    )
+}
+
+fn add_fail_if(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("fail-if")
+            .long("fail-if")
+            .help("Exit with code 3 if this condition holds, like 'p99 > 250'")
+            .long_help(
+                "Exit with code 3 (after displaying the output) if this condition \
+                 holds for the input data, which is handy for CI pipelines.  The \
+                 condition compares a statistic (like p99 or avg, see --assert) with \
+                 a number or another statistic, using >, >=, <, <=, == or !=.  It \
+                 can be used several times.",
+            )
+            .value_name("CONDITION")
+            .multiple_occurrences(true)
+            .takes_value(true),
+    )
+}
+
+fn add_assert(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("assert")
+            .long("assert")
+            .help("Exit with code 3 unless this condition holds, like 'p99.9 < 250'")
+            .long_help(
+                "Exit with code 3 (after displaying the output) unless this condition \
+                 holds for the input data, like a test in a CI pipeline.  The \
+                 condition compares a statistic (min, max, avg, std, var, samples, \
+                 outliers, trimmed_avg, trimmed_std or any percentile, like p99.9) \
+                 with a number, another statistic or a number times another \
+                 statistic (like 'p99 < 3 * p50'), using >, >=, <, <=, == or !=.  \
+                 It can be used several times.",
+            )
+            .value_name("CONDITION")
+            .multiple_occurrences(true)
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("assert-report")
+            .long("assert-report")
+            .help("Write the results of the assertions in this format")
+            .long_help(
+                "Write the results of the assertions in this format (after \
+                 displaying the output), so that CI systems display them as test \
+                 cases: TAP or JUnit XML.",
+            )
+            .possible_values(["tap", "junit"])
+            .value_name("FORMAT")
+            .requires("assert")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("assert-report-file")
+            .long("assert-report-file")
+            .help("Write the results of the assertions to this file, rather than stdout")
+            .value_name("FILE")
+            .requires("assert-report")
+            .takes_value(true),
+    )
+}
+
+fn add_baseline(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("baseline")
+            .long("baseline")
+            .help("Compare the statistics with the ones in this file")
+            .long_help(
+                "Compare the statistics with the ones in this file, as written by \
+                 a previous run of 'lowcharts summary --output-format json', \
+                 displaying the change of each of them and highlighting the ones \
+                 growing beyond the tolerance (regressions).",
+            )
+            .value_name("FILE")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("tolerance")
+            .long("tolerance")
+            .help("Growth of a statistic since the baseline considered a regression")
+            .value_name("FRACTION")
+            .default_value("5%")
+            .requires("baseline")
+            .takes_value(true),
+    )
+}
+
+fn add_trim(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("trim")
+            .long("trim")
+            .help("Display statistics excluding this fraction of values at both tails")
+            .long_help(
+                "Display the average and the standard deviation of the values \
+                 excluding this fraction of them at both tails (like '1%' or \
+                 '0.01'), and the average with the tails clamped (winsorized), \
+                 along with the statistics.  A handful of garbage values can wreck \
+                 the average, but not these.",
+            )
+            .value_name("FRACTION")
+            .takes_value(true),
+    )
+}
+
+// Options (among the ones in `cmd`) that make no sense when charting several
+// series of values at once
+fn series_conflicts(cmd: &Command) -> Vec<&'static str> {
+    vec![
+        "overlay",
+        "pre-aggregated",
+        "hdr",
+        "capture",
+        "preset",
+        "logfmt",
+        "json-field",
+        "csv",
+        "sample",
+        "parallel",
+        "graphics",
+        "output",
+        "export-data",
+        "fail-if",
+        "assert",
+        "baseline",
+        "save-state",
+        "state",
+        "rank-of",
+        "outliers",
+        "trim",
+        "confidence",
+        "show-extremes",
+        "locate",
+        "symlog",
+        "downsampler",
+        "stream",
+    ]
+    .into_iter()
+    .filter(|name| cmd.get_arguments().any(|arg| arg.get_id() == *name))
+    .collect()
+}
+
+// Must be applied after the rest of options, since it conflicts with them
+fn add_series(cmd: Command) -> Command {
+    let conflicts = series_conflicts(&cmd);
+    cmd.arg(
+        Arg::new("series")
+            .long("series")
+            .help("Chart a series of values per named capture group of the regex")
+            .long_help(
+                "Chart a series of values per named capture group of the regex (like \
+                 'rt=(?P<rt>[0-9.]+) upstream_rt=(?P<upstream_rt>[0-9.]+)'), all of \
+                 them together with a legend.",
+            )
+            .requires("regex")
+            .conflicts_with_all(&conflicts),
+    )
+}
+
+// Must be applied after the rest of options, since it conflicts with them
+fn add_group_by(cmd: Command) -> Command {
+    let mut conflicts = series_conflicts(&cmd);
+    conflicts.push("series");
+    cmd.arg(
+        Arg::new("group-by")
+            .long("group-by")
+            .help("Chart a compact histogram per key matched by this regex")
+            .long_help(
+                "Chart a compact histogram per distinct key matched by this regex in \
+                 the lines (its `key` capture group, its first one or the whole \
+                 match, like '(?P<key>GET /[a-z]+)'), one below the other, all of \
+                 them with the same buckets and scale.  Lines with no key are \
+                 skipped.",
+            )
+            .value_name("REGEX")
+            .takes_value(true)
+            .conflicts_with_all(&conflicts),
+    )
+}
+
+fn add_hdr(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("hdr")
+            .long("hdr")
+            .help("Read the output of HdrHistogram tools, like wrk2")
+            .long_help(
+                "Read the output of HdrHistogram tools: an interval log, or a \
+                 percentile distribution like the one printed by wrk2 (plain or CSV).",
+            )
+            .conflicts_with_all(&[
+                "pre-aggregated",
+                "overlay",
+                "sample",
+                "parallel",
+                "transform",
+                "rank-of",
+                "outliers",
+                "trim",
+                "show-extremes",
+                "locate",
+                "regex",
+                "logfmt",
+                "json-field",
+                "csv",
+                "preset",
+                "capture",
+            ]),
+    )
+    .arg(
+        Arg::new("hdr-output")
+            .long("hdr-output")
+            .help("Write the buckets in a HdrHistogram interval log to this file")
+            .value_name("FILE")
+            .conflicts_with("overlay")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("hdr-divisor")
+            .long("hdr-divisor")
+            .help("Values in HdrHistogram interval logs are these times the ones plotted")
+            .long_help(
+                "Values in HdrHistogram interval logs (read with --hdr or written with \
+                 --hdr-output) are the ones plotted multiplied by this, which is \
+                 handy for plotting in milliseconds logs recorded in microseconds \
+                 (with 1000).  HdrHistogram records integers, so it sets the \
+                 resolution of exported values too.",
+            )
+            .value_name("DIVISOR")
+            .default_value("1")
+            .takes_value(true),
+    )
+}
+
+fn add_confidence(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("confidence")
+            .long("confidence")
+            .help("Display the confidence interval of the average, like --confidence=99% [default: 95%]")
+            .long_help(
+                "Display the confidence interval of the average at this level (like \
+                 '99%' or '0.99'; 95% if omitted) along with the statistics.  If the \
+                 intervals of two runs do not overlap, the difference between their \
+                 averages is unlikely to be noise.  The level needs an equal sign, \
+                 like '--confidence=99%', since a separate word is taken as the \
+                 input.",
+            )
+            .value_name("LEVEL")
+            .min_values(0)
+            .multiple_values(false)
+            .require_equals(true)
+            .default_missing_value("95%")
+            .takes_value(true),
+    )
+}
+
+fn add_outliers(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("outliers")
+            .long("outliers")
+            .help("Display the number of outliers and the most extreme of them, like --outliers=zscore [default: iqr]")
+            .long_help(
+                "Display the number of outliers and the most extreme of them along \
+                 with the statistics.  With 'iqr' (the default), outliers are values \
+                 further than 1.5 interquartile ranges from the quartiles.  With \
+                 'zscore', they are values further than 3 standard deviations from \
+                 the average.  The method needs an equal sign, like \
+                 '--outliers=zscore', since a separate word is taken as the input.",
+            )
+            .value_name("METHOD")
+            .possible_values(["iqr", "zscore"])
+            .min_values(0)
+            .multiple_values(false)
+            .require_equals(true)
+            .default_missing_value("iqr")
+            .takes_value(true),
+    )
+}
+
+fn add_rank_of(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("rank-of")
+            .long("rank-of")
+            .help("Display the percentile at which this value falls")
+            .long_help(
+                "Display the percentile rank of this value in the input data (the \
+                 percentage of values that are less than or equal to it) along with \
+                 the statistics.  For instance, '350' could be reported as p97.2.",
+            )
+            .value_name("VALUE")
+            .allow_hyphen_values(true)
+            .takes_value(true),
+    )
+}
+
+fn add_locate(cmd: Command) -> Command {
+    cmd.arg(
+        Arg::new("locate")
+            .long("locate")
+            .help("Display where the extreme values were found in the input")
             .takes_value(false),
     )
 }
 
+// Arguments for reading timestamps from the input
+fn add_time_input(cmd: Command) -> Command {
+    let cmd = cmd
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .short('f')
+                .help("Use this string formatting")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("logfmt")
+                .long("logfmt")
+                .help("Read timestamps from the value of this key in logfmt formatted lines")
+                .value_name("KEY")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("json-field")
+                .long("json-field")
+                .help("Read timestamps from this field (like '.ts') in JSON formatted lines")
+                .value_name("PATH")
+                .conflicts_with("logfmt")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("duration")
+                .long("duration")
+                .help("Cap the time interval at that duration (example: '3h 5min')")
+                .long_help(
+                    "Cap the time interval at that duration (example: '3h 5min').  \
+                     For network inputs, this is also for how long to listen.",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("early-stop").long("early-stop").help(
+                "If duration flag is used, assume monotonic times and stop as soon as possible",
+            ),
+        );
+    add_input(add_non_capturing_regex(add_preset(add_base_date(
+        add_strict_position(cmd),
+    ))))
+}
+
 pub fn get_app() -> Command<'static> {
     let mut hist = Command::new("hist")
         .version(clap::crate_version!())
-        .about("Plot an histogram from input values");
-    hist = add_input(add_regex(add_width(add_min_max(add_precision(
-        add_intervals(add_log_scale(hist)),
+        .about("Plot an histogram from input values")
+        .arg(
+            Arg::new("overlay")
+                .long("overlay")
+                .help("Overlay the histogram of the values in this file, with shared buckets")
+                .value_name("FILE")
+                .conflicts_with_all(&[
+                    "graphics",
+                    "output",
+                    "fail-if",
+                    "assert",
+                    "baseline",
+                    "save-state",
+                    "sample",
+                    "rank-of",
+                    "show-extremes",
+                    "locate",
+                ])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("pre-aggregated")
+                .long("pre-aggregated")
+                .help("Read lines with a value and its count, like '0.25 1200'")
+                .long_help(
+                    "Read pre-aggregated input: lines with a value and its number of \
+                     occurrences, separated by blanks or a comma (like '0.25 1200'), as \
+                     exported by metrics systems.  With a regex, they are the 'value' \
+                     and 'count' named capture groups, or the first two groups.",
+                )
+                .conflicts_with_all(&[
+                    "overlay",
+                    "sample",
+                    "parallel",
+                    "transform",
+                    "rank-of",
+                    "outliers",
+                    "trim",
+                    "show-extremes",
+                    "locate",
+                    "logfmt",
+                    "json-field",
+                    "csv",
+                    "preset",
+                    "capture",
+                ]),
+        )
+        .arg(
+            Arg::new("state")
+                .long("state")
+                .help("Resume the processing of a growing input file from this state file")
+                .long_help(
+                    "Resume the processing of a growing input file (like a log file) \
+                     from this state file, which keeps the buckets and statistics so \
+                     far and where the input was left.  The first run creates it, and \
+                     later runs just read the lines added since then, updating the \
+                     saved histogram.  If new values fall out of its buckets, they are \
+                     rebuilt, spreading the previous counts (so they become \
+                     approximate).  Partial lines at the end of the input are left \
+                     for the next run.",
+                )
+                .value_name("FILE")
+                .conflicts_with_all(&[
+                    "overlay",
+                    "pre-aggregated",
+                    "hdr",
+                    "sample",
+                    "rank-of",
+                    "outliers",
+                    "trim",
+                    "confidence",
+                    "show-extremes",
+                    "locate",
+                    "delimiter",
+                    "skip",
+                    "head",
+                    "tail",
+                    "every",
+                    "sample-rate",
+                ])
+                .takes_value(true),
+        );
+    hist = add_hdr(add_confidence(add_trim(add_baseline(add_save_state(
+        add_export_data(hist),
+    )))));
+    hist = add_input(add_value_extraction(add_width(add_min_max(add_precision(
+        add_intervals(add_log_scale(add_symlog(add_transform(add_outliers(
+            add_rank_of(add_show_extremes(add_locate(add_fail_if(add_assert(
+                add_sample(add_parallel(add_graphics(add_output(add_normalize(hist))))),
+            ))))),
+        ))))),
     )))));
+    hist = add_group_by(add_series(hist));
 
     let mut plot = Command::new("plot")
         .version(clap::crate_version!())
@@ -129,45 +1073,262 @@ pub fn get_app() -> Command<'static> {
             Arg::new("height")
                 .long("height")
                 .short('H')
-                .help("Use that many `rows` for the plot")
-                .default_value("40")
+                .help("Use that many `rows` for the plot [default: fit the terminal, or 40]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("downsampler")
+                .long("downsampler")
+                .help("How to reduce the values to the columns of the plot")
+                .long_help(
+                    "How to reduce the values to the columns of the plot, when there \
+                     are more values than columns: their average, their maximum, \
+                     their minimum, or the one chosen by the Largest-Triangle-Three-\
+                     Buckets algorithm ('lttb'), which keeps spikes and the shape of \
+                     the line.",
+                )
+                .possible_values(["avg", "max", "min", "lttb"])
+                .default_value("avg")
                 .takes_value(true),
+        )
+        .arg(
+            Arg::new("stream")
+                .long("stream")
+                .help("Read the input in constant memory, estimating percentiles")
+                .long_help(
+                    "Read the input in constant memory, without keeping the values, \
+                     so that huge inputs can be plotted.  Values are aggregated in \
+                     chunks as they are read, percentiles are estimated (with a \
+                     relative error of 1%), and lttb picks among the averages of the \
+                     chunks.",
+                )
+                .conflicts_with_all(&[
+                    "sample",
+                    "parallel",
+                    "csv",
+                    "transform",
+                    "rank-of",
+                    "outliers",
+                    "trim",
+                    "show-extremes",
+                    "locate",
+                ]),
         );
-    plot = add_input(add_regex(add_width(add_min_max(add_precision(plot)))));
+    plot = add_confidence(add_trim(add_baseline(add_export_data(plot))));
+    plot = add_input(add_value_extraction(add_width(add_min_max(add_precision(
+        add_symlog(add_transform(add_outliers(add_rank_of(add_show_extremes(
+            add_locate(add_fail_if(add_assert(add_sample(add_parallel(
+                add_graphics(add_output(plot)),
+            ))))),
+        ))))),
+    )))));
+    plot = add_series(plot);
 
     let mut matches = Command::new("matches")
         .version(clap::crate_version!())
         .allow_missing_positional(true)
         .about("Plot barchar with counts of occurrences of matches params");
-    matches = add_input_as_option(add_width(matches)).arg(
-        Arg::new("match")
-            .help("Count matches for those strings")
-            .required(true)
-            .takes_value(true)
-            .multiple_occurrences(true),
-    );
+    matches = add_input_as_option(add_width(add_sort(matches, "none")))
+        .arg(
+            Arg::new("match")
+                .help("Count matches for those strings")
+                .required_unless_present("patterns-file")
+                .takes_value(true)
+                .multiple_occurrences(true),
+        )
+        .arg(
+            Arg::new("patterns-file")
+                .long("patterns-file")
+                .help("Count matches for the strings in this file too, one per line")
+                .long_help(
+                    "Count matches for the strings in this file too (after the ones \
+                     given as arguments), one per line.  Empty lines and lines \
+                     starting with '#' are ignored.  With --regex-match, they are \
+                     regular expressions.",
+                )
+                .value_name("FILE")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("regex-match")
+                .long("regex-match")
+                .help("Interpret match params as regular expressions")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("count-occurrences")
+                .long("count-occurrences")
+                .help("Count every occurrence of matches, not just lines with them")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("word")
+                .long("word")
+                .help("Match whole words only (so that ERR does not match ERROR)")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("invert")
+                .long("invert")
+                .alias("no-match")
+                .help("Add a <no match> row counting the lines matching none")
+                .takes_value(false),
+        );
 
-    let mut timehist =
-        Command::new("timehist")
-            .version(clap::crate_version!())
-            .about("Plot histogram with amount of matches over time")
-            .arg(
-                Arg::new("format")
-                    .long("format")
-                    .short('f')
-                    .help("Use this string formatting")
-                    .takes_value(true),
-            )
-            .arg(
-                Arg::new("duration")
-                    .long("duration")
-                    .help("Cap the time interval at that duration (example: '3h 5min')")
-                    .takes_value(true),
-            )
-            .arg(Arg::new("early-stop").long("early-stop").help(
-                "If duration flag is used, assume monotonic times and stop as soon as possible",
-            ));
-    timehist = add_input(add_width(add_non_capturing_regex(add_intervals(timehist))));
+    let mut timehist = Command::new("timehist")
+        .version(clap::crate_version!())
+        .about("Plot histogram with amount of matches over time")
+        .arg(
+            Arg::new("value-regex")
+                .long("value-regex")
+                .help("Aggregate the values captured by this regex, rather than counting lines")
+                .long_help(
+                    "Aggregate in every bucket the values captured by this regex (its \
+                     `value` capture group, or the first one), rather than counting \
+                     matching lines.  Lines with no value are skipped.",
+                )
+                .value_name("REGEX")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("agg")
+                .long("agg")
+                .help("How to aggregate the values in every bucket, with --value-regex")
+                .possible_values(["sum", "avg", "max"])
+                .default_value("sum")
+                .requires("value-regex")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("with-counts")
+                .long("with-counts")
+                .help("Display the count of values in every bucket too, with --value-regex")
+                .requires("value-regex")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("anomalies")
+                .long("anomalies")
+                .help("Highlight the buckets that are spikes [default: 3 sigmas]")
+                .long_help(
+                    "Highlight the buckets over the average of the previous 10 buckets \
+                     by more than this number of standard deviations (3 if omitted), \
+                     and list them after the histogram.  The number needs an equal \
+                     sign, like '--anomalies=2', since a separate word is taken as the \
+                     input.",
+                )
+                .value_name("SIGMAS")
+                .min_values(0)
+                .multiple_values(false)
+                .require_equals(true)
+                .default_missing_value("3")
+                .takes_value(true),
+        );
+    timehist = add_time_input(add_width(add_intervals(add_bucket_label(add_output(
+        add_save_state(add_export_data(timehist)),
+    )))));
+    timehist = timehist.mut_arg("save-state", |arg| arg.conflicts_with("value-regex"));
+
+    let mut timeplot = Command::new("timeplot")
+        .version(clap::crate_version!())
+        .about("Plot an 2d x-y graph of input values over time")
+        .after_help(
+            "The regex needs a capture group for the values: one named `value`, or \
+             the first one that is not `ts`.",
+        )
+        .arg(
+            Arg::new("height")
+                .long("height")
+                .short('H')
+                .help("Use that many `rows` for the plot [default: fit the terminal, or 40]")
+                .takes_value(true),
+        );
+    timeplot = add_time_input(add_width(add_precision(timeplot)))
+        .mut_arg("regex", |arg| arg.required(true));
+
+    let mut timepercentiles = Command::new("timepercentiles")
+        .version(clap::crate_version!())
+        .about("Plot percentiles of input values over time")
+        .after_help(
+            "The regex needs a capture group for the values: one named `value`, or \
+             the first one that is not `ts`.",
+        );
+    timepercentiles = add_time_input(add_width(add_intervals(add_precision(timepercentiles))))
+        .mut_arg("regex", |arg| arg.required(true));
+
+    let mut heatmap = Command::new("heatmap")
+        .version(clap::crate_version!())
+        .about("Plot a heat map of input values over time")
+        .after_help(
+            "The regex needs a capture group for the values: one named `value`, or \
+             the first one that is not `ts`.",
+        )
+        .arg(
+            Arg::new("height")
+                .long("height")
+                .short('H')
+                .help("Use that many `rows` for the plot [default: fit the terminal, or 40]")
+                .takes_value(true),
+        );
+    heatmap = add_time_input(add_width(add_precision(add_log_scale(add_symlog(heatmap)))))
+        .mut_arg("regex", |arg| arg.required(true));
+
+    let mut gaps = Command::new("gaps")
+        .version(clap::crate_version!())
+        .about("Report periods of time with no matches")
+        .arg(
+            Arg::new("threshold")
+                .long("threshold")
+                .help("Report periods with no matches longer than this (example: '30s')")
+                .default_value("1m")
+                .takes_value(true),
+        );
+    gaps = add_time_input(gaps);
+
+    let mut interarrival = Command::new("interarrival")
+        .version(clap::crate_version!())
+        .about("Plot histogram of the time in seconds between consecutive matches");
+    interarrival = add_time_input(add_width(add_intervals(add_precision(add_log_scale(
+        add_symlog(interarrival),
+    )))));
+
+    let mut sessions = Command::new("sessions")
+        .version(clap::crate_version!())
+        .about("Report the time span of every key in input lines, and plot their histogram")
+        .after_help(
+            "The regex needs a capture group for the keys (like session or job ids): \
+             one named `key`, or the first one that is not `ts`.  Durations are in \
+             seconds.",
+        )
+        .arg(
+            Arg::new("lines")
+                .long("lines")
+                .short('l')
+                .help("Display that many keys, sorting by longest duration")
+                .default_value("10")
+                .takes_value(true),
+        );
+    sessions = add_time_input(add_width(add_intervals(add_precision(sessions))))
+        .mut_arg("regex", |arg| arg.required(true));
+
+    let mut period = Command::new("period")
+        .version(clap::crate_version!())
+        .about("Report periodicities in the amount of matches over time")
+        .arg(
+            Arg::new("plot")
+                .long("plot")
+                .help("Plot the autocorrelation of the amount of matches for every lag")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("height")
+                .long("height")
+                .short('H')
+                .help("Use that many `rows` for the plot [default: fit the terminal, or 40]")
+                .takes_value(true),
+        );
+    period = add_time_input(add_width(add_intervals(period)))
+        .mut_arg("intervals", |arg| arg.default_value("200"));
 
     let mut splittimehist = Command::new("split-timehist")
         .version(clap::crate_version!())
@@ -179,10 +1340,43 @@ pub fn get_app() -> Command<'static> {
                 .help("Use this string formatting")
                 .takes_value(true),
         );
-    splittimehist = add_input_as_option(add_width(add_intervals(splittimehist))).arg(
+    splittimehist = add_input_as_option(add_width(add_intervals(add_bucket_label(
+        add_base_date(add_strict_position(splittimehist)),
+    ))))
+    .arg(
+        Arg::new("style")
+            .long("style")
+            .help("Display the bars of every bucket one after the other, in a line each, or as percentages")
+            .possible_values(["stacked", "grouped", "percent"])
+            .default_value("stacked")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("sort-legend")
+            .long("sort-legend")
+            .help("List the terms in the legend from the most to the least frequent")
+            .takes_value(false),
+    )
+    .arg(
+        Arg::new("group-by-regex")
+            .long("group-by-regex")
+            .help("Split matches by the value of the `key` capture group of this regex, instead of by a list of strings")
+            .value_name("REGEX")
+            .conflicts_with("match")
+            .takes_value(true),
+    )
+    .arg(
+        Arg::new("top")
+            .long("top")
+            .help("Display only that many groups, the most common ones, with --group-by-regex")
+            .value_name("N")
+            .default_value("5")
+            .takes_value(true),
+    )
+    .arg(
         Arg::new("match")
             .help("Count matches for those strings")
-            .required(true)
+            .required_unless_present("group-by-regex")
             .takes_value(true)
             .multiple_occurrences(true),
     );
@@ -190,14 +1384,215 @@ pub fn get_app() -> Command<'static> {
     let mut common_terms = Command::new("common-terms")
         .version(clap::crate_version!())
         .about("Plot histogram with most common terms in input lines");
-    common_terms = add_input(add_regex(add_width(common_terms))).arg(
-        Arg::new("lines")
-            .long("lines")
-            .short('l')
-            .help("Display that many lines, sorting by most frequent")
-            .default_value("10")
-            .takes_value(true),
-    );
+    common_terms = add_input(add_regex(add_width(add_sort(common_terms, "count"))))
+        .arg(
+            Arg::new("lines")
+                .long("lines")
+                .short('l')
+                .help("Display that many lines, sorting by most frequent")
+                .default_value("10")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("approx")
+                .long("approx")
+                .help("Use bounded memory, at the cost of approximate counts")
+                .long_help(
+                    "Use bounded memory, at the cost of approximate counts: only a \
+                     number of terms (100 per displayed line) are tracked at a time, \
+                     evicting the least frequent one when a new term shows up \
+                     (Space-Saving algorithm).  Counts of frequent terms may be \
+                     overestimated by the error displayed.  Handy for inputs with lots \
+                     of distinct terms.",
+                )
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("totals")
+                .long("totals")
+                .help("Display rows with the count of the rest of the terms and the total")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("over-time")
+                .long("over-time")
+                .help("Plot the occurrences over time of that many most common terms")
+                .value_name("N")
+                .conflicts_with_all(&["approx", "totals"])
+                .takes_value(true),
+        );
+    common_terms = add_intervals(common_terms);
+
+    let mut zipf = Command::new("zipf")
+        .version(clap::crate_version!())
+        .about("Plot frequency of terms in input lines versus their rank, in log-log axes")
+        .arg(
+            Arg::new("height")
+                .long("height")
+                .short('H')
+                .help("Use that many `rows` for the plot")
+                .default_value("20")
+                .takes_value(true),
+        );
+    zipf = add_input(add_regex(add_width(zipf)));
+
+    let mut distinct = Command::new("distinct")
+        .version(clap::crate_version!())
+        .about("Estimate the number of distinct terms in input lines")
+        .arg(
+            Arg::new("over-time")
+                .long("over-time")
+                .help("Plot an histogram with the number of distinct terms over time")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .short('f')
+                .help("Use this string formatting for timestamps, with --over-time")
+                .requires("over-time")
+                .takes_value(true),
+        );
+    distinct = add_input(add_regex(add_width(add_intervals(distinct))));
+
+    let mut compare = Command::new("compare")
+        .version(clap::crate_version!())
+        .about("Compare the histograms of two sets of input values")
+        .arg(
+            Arg::new("before")
+                .help("Input file with the reference values")
+                .required(true),
+        )
+        .arg(
+            Arg::new("after")
+                .help("Input file with the values to compare")
+                .required(true),
+        );
+    compare = add_input_options(add_value_extraction(add_width(add_min_max(add_precision(
+        add_intervals(add_log_scale(add_symlog(add_transform(add_parallel(
+            add_normalize(compare),
+        ))))),
+    )))));
+
+    let mut groupstats = Command::new("groupstats")
+        .version(clap::crate_version!())
+        .about("Display a table with statistics of input values per group")
+        .after_help(
+            "The regex needs a capture group named `key`, used as group label, and \
+             another one for the values.",
+        );
+    groupstats = add_input(add_regex(add_min_max(add_precision(groupstats))));
+
+    let mut top = Command::new("top")
+        .version(clap::crate_version!())
+        .about("Plot the keys with the biggest sum, average or maximum of their values")
+        .after_help(
+            "The regex needs a capture group named `key` (like a client IP), and \
+             another one for the values (like bytes sent).",
+        );
+    top = add_input(add_regex(add_min_max(add_precision(add_width(top)))))
+        .arg(
+            Arg::new("lines")
+                .long("lines")
+                .short('l')
+                .help("Display that many keys, with the biggest values")
+                .default_value("10")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("agg")
+                .long("agg")
+                .help("How to aggregate the values of every key")
+                .possible_values(["sum", "avg", "max"])
+                .default_value("sum")
+                .takes_value(true),
+        );
+
+    let mut summary = Command::new("summary")
+        .version(clap::crate_version!())
+        .about("Print the statistics of input values, with no chart");
+    summary = add_confidence(add_trim(add_fail_if(add_assert(add_baseline(summary)))));
+    summary = add_input(add_value_extraction(add_min_max(add_precision(
+        add_transform(add_outliers(add_rank_of(add_sample(add_parallel(summary))))),
+    ))));
+
+    let mut describe = Command::new("describe")
+        .version(clap::crate_version!())
+        .about("Describe input values: statistics, percentiles and a small histogram")
+        .long_about(
+            "Describe input values in a single screen: their statistics (including \
+             outliers and the 95% confidence interval of the average), a table of \
+             percentiles (from p1 to p99.9) and a small histogram.",
+        )
+        .arg(
+            Arg::new("intervals")
+                .long("intervals")
+                .short('i')
+                .help("Use no more than this amount of buckets [default: based on the number of values]")
+                .takes_value(true),
+        );
+    describe = add_input(add_value_extraction(add_min_max(add_precision(add_width(
+        add_transform(add_sample(add_parallel(describe))),
+    )))));
+
+    let mut merge = Command::new("merge")
+        .version(clap::crate_version!())
+        .about("Merge histograms saved with --save-state into a single one")
+        .long_about(
+            "Merge histograms saved with --save-state (by hist or timehist, like \
+             the ones of the same metric in several hosts) into a single one.  \
+             Bucket counts are added if all histograms have the same buckets.  \
+             Otherwise they are spread over buckets covering all of them, which \
+             is approximate.  Statistics are exact, except for percentiles, \
+             which are approximated.",
+        )
+        .arg(
+            Arg::new("input")
+                .help("Files with the states of the histograms")
+                .value_name("FILE")
+                .multiple_values(true)
+                .required(true),
+        );
+    merge = add_width(add_precision(merge));
+
+    let mut dash = Command::new("dash")
+        .version(clap::crate_version!())
+        .about("Display several plots of the same input at once, refreshed as it is read")
+        .after_help(
+            "Every panel is a hist, summary, matches, common-terms or timehist \
+             command line with no input, like 'timehist --intervals 10' or \
+             \"matches ERROR WARN\", whose plot is updated as lines are read.  \
+             Panels share the height of the terminal, and their output is cut to \
+             fit it.",
+        )
+        .arg(
+            Arg::new("panel")
+                .long("panel")
+                .short('p')
+                .help("Add a panel displaying the plot of this command line")
+                .value_name("COMMAND")
+                .required(true)
+                .multiple_occurrences(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("refresh")
+                .long("refresh")
+                .help("Refresh the panels this often, if there is new input (example: '2s')")
+                .default_value("1s")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("window")
+                .long("window")
+                .help("Display only the input read in this trailing period (example: '5m')")
+                .takes_value(true),
+        );
+    dash = add_input(dash);
+
+    let examples = Command::new("examples")
+        .version(clap::crate_version!())
+        .about("Print command lines for common tasks");
 
     Command::new("lowcharts")
         .author(clap::crate_authors!())
@@ -214,6 +1609,59 @@ pub fn get_app() -> Command<'static> {
                 .default_value("auto")
                 .takes_value(true),
         )
+        .arg(
+            // Not `--format`, which some sub-commands use for the format of
+            // timestamps, nor `--output`, the file for SVG plots
+            Arg::new("output-format")
+                .long("output-format")
+                .help("Write plots in this format")
+                .long_help(
+                    "Write plots in this format: text with colors (if enabled), \
+                     plain text, markdown, or the data of the plot (like the edges \
+                     and counts of buckets) as json or csv.  'markdown' is handy for \
+                     pasting into GitHub issues and pull requests: it renders a table \
+                     with the buckets of the plot (if it has them) and the chart, \
+                     without colors and with ASCII characters, in a code block.  \
+                     Sub-commands that cannot write some format fail with it.",
+                )
+                .possible_values(FORMATS)
+                .default_value("text")
+                .global(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("theme")
+                .long("theme")
+                .help("Use this set of colors in the output")
+                .possible_values(["default", "colorblind"])
+                .default_value("default")
+                .global(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("bar-char")
+                .long("bar-char")
+                .help("Use this character for drawing bars")
+                .global(true)
+                .validator(|s| s.parse::<char>())
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("fine-bars")
+                .long("fine-bars")
+                .help("Draw bars with blocks, for a finer resolution")
+                .global(true)
+                .conflicts_with("bar-char")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("marker")
+                .long("marker")
+                .help("Use this character for drawing points in x-y plots")
+                .global(true)
+                .validator(|s| s.parse::<char>())
+                .takes_value(true),
+        )
         .arg(
             Arg::new("verbose")
                 .short('v')
@@ -225,8 +1673,25 @@ pub fn get_app() -> Command<'static> {
         .subcommand(plot)
         .subcommand(matches)
         .subcommand(timehist)
+        .subcommand(timeplot)
+        .subcommand(timepercentiles)
+        .subcommand(heatmap)
+        .subcommand(gaps)
+        .subcommand(period)
+        .subcommand(interarrival)
+        .subcommand(sessions)
         .subcommand(splittimehist)
         .subcommand(common_terms)
+        .subcommand(zipf)
+        .subcommand(distinct)
+        .subcommand(groupstats)
+        .subcommand(top)
+        .subcommand(summary)
+        .subcommand(describe)
+        .subcommand(merge)
+        .subcommand(compare)
+        .subcommand(dash)
+        .subcommand(examples)
 }
 
 #[cfg(test)]
@@ -244,7 +1709,8 @@ mod tests {
         assert!(sub_m.value_of("max").is_none());
         assert!(sub_m.value_of("min").is_none());
         assert!(sub_m.value_of("regex").is_none());
-        assert_eq!("110", sub_m.value_of("width").unwrap());
+        assert!(sub_m.value_of("multiline-join").is_none());
+        assert!(sub_m.value_of("width").is_none());
         assert_eq!("20", sub_m.value_of("intervals").unwrap());
     }
 
@@ -267,6 +1733,26 @@ mod tests {
         assert_eq!("1.1", sub_m.value_of("max").unwrap());
         assert_eq!("0.9", sub_m.value_of("min").unwrap());
         assert_eq!("11", sub_m.value_of("height").unwrap());
+        assert!(sub_m.value_of("symlog").is_none());
+        assert!(get_app()
+            .try_get_matches_from(vec!["lowcharts", "hist", "--symlog", "1", "--log-scale"])
+            .is_err());
+        assert!(get_app()
+            .try_get_matches_from(vec!["lowcharts", "hist", "--transform", "cube"])
+            .is_err());
+        assert!(get_app()
+            .try_get_matches_from(vec!["lowcharts", "hist", "--logfmt", "a", "--regex", "b"])
+            .is_err());
+        assert!(get_app()
+            .try_get_matches_from(vec![
+                "lowcharts",
+                "hist",
+                "--logfmt",
+                "a",
+                "--json-field",
+                "b"
+            ])
+            .is_err());
     }
 
     #[test]
@@ -287,6 +1773,19 @@ mod tests {
             vec!["A", "C"],
             sub_m.values_of("match").unwrap().collect::<Vec<&str>>()
         );
+        let arg_vec = vec![
+            "lowcharts",
+            "matches",
+            "A",
+            "--input",
+            "udp://127.0.0.1:8125",
+            "--duration",
+            "5s",
+        ];
+        let m = get_app().get_matches_from(arg_vec);
+        let sub_m = m.subcommand_matches("matches").unwrap();
+        assert_eq!("udp://127.0.0.1:8125", sub_m.value_of("input").unwrap());
+        assert_eq!("5s", sub_m.value_of("duration").unwrap());
     }
 
     #[test]
@@ -296,6 +1795,7 @@ mod tests {
         let sub_m = m.subcommand_matches("timehist").unwrap();
         assert_eq!("some", sub_m.value_of("input").unwrap());
         assert_eq!("foo", sub_m.value_of("regex").unwrap());
+        assert!(sub_m.value_of("base-date").is_none());
     }
 
     #[test]
@@ -309,6 +1809,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn groupstats_subcommand_arg_parsing() {
+        let arg_vec = vec![
+            "lowcharts",
+            "groupstats",
+            "-R",
+            "(?P<key>.) (.*)",
+            "-p",
+            "2",
+        ];
+        let m = get_app().get_matches_from(arg_vec);
+        let sub_m = m.subcommand_matches("groupstats").unwrap();
+        assert_eq!("-", sub_m.value_of("input").unwrap());
+        assert_eq!("(?P<key>.) (.*)", sub_m.value_of("regex").unwrap());
+        assert_eq!("2", sub_m.value_of("precision").unwrap());
+    }
+
+    #[test]
+    fn top_subcommand_arg_parsing() {
+        let arg_vec = vec!["lowcharts", "top", "-R", "(?P<key>.) (.*)", "--agg", "max"];
+        let m = get_app().get_matches_from(arg_vec);
+        let sub_m = m.subcommand_matches("top").unwrap();
+        assert_eq!("-", sub_m.value_of("input").unwrap());
+        assert_eq!("max", sub_m.value_of("agg").unwrap());
+        assert_eq!("10", sub_m.value_of("lines").unwrap());
+    }
+
+    #[test]
+    fn compare_subcommand_arg_parsing() {
+        let arg_vec = vec!["lowcharts", "compare", "old", "new", "--log-scale"];
+        let m = get_app().get_matches_from(arg_vec);
+        let sub_m = m.subcommand_matches("compare").unwrap();
+        assert_eq!("old", sub_m.value_of("before").unwrap());
+        assert_eq!("new", sub_m.value_of("after").unwrap());
+        assert!(sub_m.is_present("log-scale"));
+        assert!(get_app()
+            .try_get_matches_from(vec!["lowcharts", "compare", "old"])
+            .is_err());
+    }
+
     #[test]
     fn terms_subcommand_arg_parsing() {
         let arg_vec = vec!["lowcharts", "common-terms", "--regex", "foo", "some"];
@@ -317,4 +1857,13 @@ mod tests {
         assert_eq!("some", sub_m.value_of("input").unwrap());
         assert_eq!("foo", sub_m.value_of("regex").unwrap());
     }
+
+    #[test]
+    fn zipf_subcommand_arg_parsing() {
+        let arg_vec = vec!["lowcharts", "zipf", "-H", "10", "some"];
+        let m = get_app().get_matches_from(arg_vec);
+        let sub_m = m.subcommand_matches("zipf").unwrap();
+        assert_eq!("some", sub_m.value_of("input").unwrap());
+        assert_eq!("10", sub_m.value_of("height").unwrap());
+    }
 }
@@ -0,0 +1,266 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use clap::{ArgMatches, Command, ValueSource};
+use regex::Regex;
+use toml::value::{Table, Value};
+
+use crate::read::Preset;
+
+/// Environment variable with the path of the config file, for overriding the
+/// default one
+const CONFIG_ENV: &str = "LOWCHARTS_CONFIG";
+
+/// Settings read from the config file of the user, like:
+///
+/// ```toml
+/// # For all sub-commands with these options
+/// [defaults]
+/// width = 150
+/// precision = 2
+///
+/// # Only for the hist sub-command (taking precedence over the above)
+/// [defaults.hist]
+/// intervals = 30
+///
+/// # Used as `--preset myapp-latency`
+/// [presets.myapp-latency]
+/// regex = '^(?P<time>\S+ \S+) .* took (?P<latency>[0-9.]+) ms'
+/// format = "%Y-%m-%d %H:%M:%S"
+/// value = "latency"
+/// ```
+///
+/// Defaults are keyed by the long name of the options, and they are only used
+/// for options missing in the command line.
+#[derive(Debug, Default)]
+pub struct Config {
+    defaults: Table,
+    presets: Vec<Preset>,
+}
+
+impl Config {
+    /// Returns the path of the config file: `~/.config/lowcharts.toml`, unless
+    /// overridden by the `LOWCHARTS_CONFIG` or `XDG_CONFIG_HOME` environment
+    /// variables.
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os(CONFIG_ENV) {
+            return Some(PathBuf::from(path));
+        }
+        let dir = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(dir.join("lowcharts.toml"))
+    }
+
+    /// Reads the config file.  A missing file is not an error, but an empty
+    /// config.
+    pub fn load() -> Result<Self, String> {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Ok(Self::default()),
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text)
+                .map_err(|err| format!("Invalid config file {}: {}", path.display(), err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(format!("Failed to read {}: {}", path.display(), err)),
+        }
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut table: Table = toml::from_str(text).map_err(|err| err.to_string())?;
+        let defaults = match table.remove("defaults") {
+            Some(Value::Table(defaults)) => defaults,
+            Some(_) => return Err(String::from("defaults must be a table")),
+            None => Table::new(),
+        };
+        let presets = match table.remove("presets") {
+            Some(Value::Table(presets)) => presets
+                .into_iter()
+                .map(|(name, value)| parse_preset(name, value))
+                .collect::<Result<Vec<Preset>, String>>()?,
+            Some(_) => return Err(String::from("presets must be a table")),
+            None => Vec::new(),
+        };
+        Ok(Self { defaults, presets })
+    }
+
+    /// Makes the presets of the config file available for `--preset`.
+    pub fn register_presets(self) {
+        Preset::register(self.presets);
+    }
+
+    /// Adds to `args` (the command line already parsed into `matches`) the
+    /// options with defaults in the config file that are missing in it.
+    /// Returns true if any option was added.
+    pub fn apply_defaults(
+        &self,
+        app: &Command,
+        args: &mut Vec<OsString>,
+        matches: &ArgMatches,
+    ) -> bool {
+        let (name, sub_matches) = match matches.subcommand() {
+            Some(subcommand) => subcommand,
+            None => return false,
+        };
+        let mut defaults: Vec<(&String, &Value)> = self
+            .defaults
+            .iter()
+            .filter(|(_, value)| !value.is_table())
+            .collect();
+        if let Some(Value::Table(table)) = self.defaults.get(name) {
+            defaults.retain(|(key, _)| !table.contains_key(*key));
+            defaults.extend(table.iter());
+        }
+        let subcommand = app.find_subcommand(name).unwrap();
+        let mut main_options = Vec::new();
+        let mut sub_options = Vec::new();
+        for (key, value) in defaults {
+            // Options of the main command go before the sub-command
+            let (cmd, cmd_matches, options) =
+                if subcommand.get_arguments().any(|a| a.get_id() == key) {
+                    (subcommand, sub_matches, &mut sub_options)
+                } else {
+                    (app, matches, &mut main_options)
+                };
+            if let Some(arg) = missing_option(cmd, cmd_matches, key) {
+                options.extend(option_from(
+                    arg.get_long().unwrap(),
+                    arg.is_takes_value_set(),
+                    value,
+                ));
+            }
+        }
+        let changed = !(main_options.is_empty() && sub_options.is_empty());
+        let sub_index = args.iter().position(|arg| arg == name).unwrap();
+        args.splice(sub_index + 1..sub_index + 1, sub_options);
+        args.splice(1..1, main_options);
+        changed
+    }
+}
+
+/// Returns the option `key` of `cmd` if it was not given in the command line.
+fn missing_option<'a, 'help>(
+    cmd: &'a Command<'help>,
+    matches: &ArgMatches,
+    key: &str,
+) -> Option<&'a clap::Arg<'help>> {
+    let arg = cmd
+        .get_arguments()
+        .find(|a| a.get_id() == key && a.get_long().is_some())?;
+    match matches.value_source(key) {
+        Some(ValueSource::CommandLine) | Some(ValueSource::EnvVariable) => None,
+        _ => Some(arg),
+    }
+}
+
+/// Returns the command line option with `value` for the option named `long`.
+fn option_from(long: &str, takes_value: bool, value: &Value) -> Option<OsString> {
+    let value = match value {
+        Value::Boolean(true) if !takes_value => return Some(format!("--{long}").into()),
+        Value::String(s) => s.clone(),
+        Value::Integer(n) => n.to_string(),
+        Value::Float(n) => n.to_string(),
+        Value::Boolean(b) if takes_value => b.to_string(),
+        _ => return None,
+    };
+    Some(format!("--{long}={value}").into())
+}
+
+/// Returns the preset named `name`, defined in `value`.
+fn parse_preset(name: String, value: Value) -> Result<Preset, String> {
+    let field = |key: &str| match value.get(key) {
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(format!("{key} of preset {name} must be a string")),
+        None => Ok(None),
+    };
+    let regex = field("regex")?.ok_or(format!("Missing regex in preset {name}"))?;
+    if let Err(err) = Regex::new(&regex) {
+        return Err(format!("Bad regex in preset {name}: {err}"));
+    }
+    // Presets live as long as the program does
+    Ok(Preset {
+        ts_format: leak(field("format")?.unwrap_or_default()),
+        value: leak(field("value")?.unwrap_or_else(|| String::from("value"))),
+        durations: "",
+        since: "",
+        regex: leak(regex),
+        name: leak(name),
+    })
+}
+
+fn leak(s: String) -> &'static str {
+    Box::leak(s.into_boxed_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::get_app;
+
+    const CONFIG: &str = r#"
+[defaults]
+width = 50
+precision = 2
+intervals = 10
+color = "no"
+fine-bars = true
+
+[defaults.hist]
+intervals = 30
+log-scale = true
+
+[presets.myapp]
+regex = '^(?P<time>\S+) took (?P<latency>[0-9.]+)'
+value = "latency"
+"#;
+
+    fn apply(config: &Config, args: &[&str]) -> Vec<OsString> {
+        let mut args: Vec<OsString> = args.iter().map(OsString::from).collect();
+        let matches = get_app().get_matches_from(&args);
+        config.apply_defaults(&get_app(), &mut args, &matches);
+        args
+    }
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse(CONFIG).unwrap();
+        assert_eq!(config.presets.len(), 1);
+        assert_eq!(config.presets[0].name, "myapp");
+        assert_eq!(config.presets[0].value, "latency");
+        assert_eq!(config.presets[0].ts_format, "");
+        assert!(Config::parse("[presets.foo]\nvalue = 'x'").is_err());
+        assert!(Config::parse("[presets.foo]\nregex = '('").is_err());
+        assert!(Config::parse("defaults = 3").is_err());
+        assert!(Config::parse("nonsense").is_err());
+    }
+
+    #[test]
+    fn test_apply_defaults() {
+        let config = Config::parse(CONFIG).unwrap();
+        let args = apply(&config, &["lowcharts", "hist", "-i", "5", "foo"]);
+        let matches = get_app().get_matches_from(args);
+        assert_eq!(matches.value_of("color"), Some("no"));
+        assert!(matches.is_present("fine-bars"));
+        let sub_m = matches.subcommand_matches("hist").unwrap();
+        assert_eq!(sub_m.value_of("width"), Some("50"));
+        assert_eq!(sub_m.value_of("precision"), Some("2"));
+        assert_eq!(sub_m.value_of("intervals"), Some("5"));
+        assert_eq!(sub_m.value_of("input"), Some("foo"));
+        assert!(sub_m.is_present("log-scale"));
+
+        let args = apply(&config, &["lowcharts", "--color", "yes", "plot", "-w", "7"]);
+        let matches = get_app().get_matches_from(args);
+        assert_eq!(matches.value_of("color"), Some("yes"));
+        let sub_m = matches.subcommand_matches("plot").unwrap();
+        assert_eq!(sub_m.value_of("width"), Some("7"));
+        assert_eq!(sub_m.value_of("precision"), Some("2"));
+
+        let args = apply(&Config::default(), &["lowcharts", "hist"]);
+        assert_eq!(args, ["lowcharts", "hist"]);
+    }
+}
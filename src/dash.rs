@@ -0,0 +1,709 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, FixedOffset};
+use clap::{ArgMatches, Command, ValueSource};
+
+use crate::plot::{
+    CommonTerms, Histogram, HistogramBuilder, MatchBarRow, Render, RenderOptions, SortOrder,
+    TimeHistogram, TimeHistogramBuilder,
+};
+use crate::read::{DataReader, TimeStream};
+use crate::stats::Stats;
+
+/// Subcommands that can be used as panels of a dashboard, along with the
+/// options they support there.  Input options are the ones of the dashboard,
+/// and panels display just their plot, updated as the input lines arrive.
+const PANELS: [(&str, &[&str]); 5] = [
+    (
+        "hist",
+        &[
+            "intervals",
+            "log-scale",
+            "symlog",
+            "normalize",
+            "precision",
+            "regex",
+            "min",
+            "max",
+            "preset",
+            "capture",
+            "all-matches",
+            "logfmt",
+            "json-field",
+            "human-input",
+            "duration-input",
+        ],
+    ),
+    (
+        "summary",
+        &[
+            "confidence",
+            "precision",
+            "regex",
+            "min",
+            "max",
+            "preset",
+            "capture",
+            "all-matches",
+            "logfmt",
+            "json-field",
+            "human-input",
+            "duration-input",
+        ],
+    ),
+    (
+        "matches",
+        &[
+            "match",
+            "patterns-file",
+            "regex-match",
+            "word",
+            "count-occurrences",
+            "invert",
+            "sort",
+            "reverse",
+        ],
+    ),
+    (
+        "common-terms",
+        &["regex", "lines", "approx", "totals", "sort", "reverse"],
+    ),
+    (
+        "timehist",
+        &[
+            "intervals",
+            "bucket-label",
+            "anomalies",
+            "regex",
+            "preset",
+            "format",
+            "logfmt",
+            "json-field",
+            "base-date",
+            "strict-position",
+        ],
+    ),
+];
+
+/// Values buffered by panels before adding them to their plots, at most.
+const FRESH_VALUES: usize = 10_000;
+
+/// Escape sequence clearing the terminal and moving the cursor to its top.
+pub const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// A panel of a dashboard: a lowcharts command line (subcommand first, with no
+/// input) plotting the input read so far, like `timehist --intervals 10`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Panel {
+    /// Arguments of the command line.
+    pub args: Vec<String>,
+    /// The command line, as given by the user.
+    pub title: String,
+}
+
+impl Panel {
+    /// Returns the name of the subcommand of the panel.
+    pub fn subcommand(&self) -> &str {
+        &self.args[0]
+    }
+
+    /// Checks that the arguments of the panel, parsed by the subcommand `cmd`
+    /// into `matches`, have no options unsupported in panels.
+    pub fn check_options(&self, cmd: &Command, matches: &ArgMatches) -> Result<(), String> {
+        let supported = PANELS
+            .iter()
+            .find(|(name, _)| *name == self.subcommand())
+            .map_or(&[][..], |(_, options)| options);
+        for arg in cmd.get_arguments() {
+            let id = arg.get_id();
+            if !supported.contains(&id)
+                && matches.value_source(id) == Some(ValueSource::CommandLine)
+            {
+                let name = arg
+                    .get_long()
+                    .map_or(id.to_string(), |long| format!("--{long}"));
+                return Err(format!("{name} cannot be used in a panel"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Panel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let args = split_words(s)?;
+        match args.first() {
+            None => Err(String::from("Empty panel")),
+            Some(name) if !PANELS.iter().any(|(panel, _)| panel == name) => {
+                Err(format!("{name} cannot be used in a panel"))
+            }
+            Some(_) => Ok(Panel {
+                args,
+                title: s.trim().to_string(),
+            }),
+        }
+    }
+}
+
+/// Splits a command line in words the way a shell would do it, for the
+/// simple cases: words are separated by blanks, unless they are in single or
+/// double quotes.  Backslashes escape the next character, but inside single
+/// quotes.
+pub fn split_words(s: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('\''), c) => word.get_or_insert_with(String::new).push(c),
+            (_, '\\') => match chars.next() {
+                Some(next) => word.get_or_insert_with(String::new).push(next),
+                None => return Err(String::from("Trailing backslash")),
+            },
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err(String::from("Unterminated quote"));
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// The input lines read in a trailing window of time, so that a dashboard
+/// displays only what is happening right now.
+#[derive(Debug)]
+pub struct Window {
+    span: Duration,
+    lines: VecDeque<(Instant, String)>,
+}
+
+impl Window {
+    /// Creates an empty `Window` holding the lines read in the last `span`.
+    pub fn new(span: Duration) -> Self {
+        Self {
+            span,
+            lines: VecDeque::new(),
+        }
+    }
+
+    /// Adds a line read at `now`, discarding the ones out of the window.
+    pub fn push(&mut self, line: String, now: Instant) {
+        self.lines.push_back((now, line));
+        self.expire(now);
+    }
+
+    /// Discards the lines read before the window ending at `now`.
+    pub fn expire(&mut self, now: Instant) {
+        while let Some((read, _)) = self.lines.front() {
+            if now.duration_since(*read) <= self.span {
+                break;
+            }
+            self.lines.pop_front();
+        }
+    }
+
+    /// Returns the lines in the window, oldest first.
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().map(|(_, line)| line.as_str())
+    }
+}
+
+/// The plot of a panel of a dashboard, fed with the input lines as they are
+/// read.
+pub trait Feed {
+    /// Adds a line of the input to the plot.
+    fn observe(&mut self, line: &str);
+
+    /// Forgets the lines observed so far (like the ones out of the window).
+    fn reset(&mut self);
+
+    /// Returns the plot of the lines observed so far, or None if there is not
+    /// enough data for it yet.
+    fn plot(&mut self, opts: &RenderOptions) -> Option<String>;
+}
+
+fn render(plot: &dyn Render, opts: &RenderOptions) -> Option<String> {
+    let mut output = Vec::new();
+    plot.render(&mut output, opts).ok()?;
+    Some(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// The histogram of the values in the lines, for `hist` panels.  Values are
+/// added to the buckets and then dropped, so that memory does not grow with
+/// the input.  New values out of the buckets are merged (see
+/// `Histogram::merge`), spreading the counts over buckets covering all of
+/// them.
+pub struct HistogramFeed {
+    reader: DataReader,
+    builder: HistogramBuilder,
+    intervals: usize,
+    // Values of histograms with fewer buckets than requested, for building
+    // them again with more
+    values: Vec<f64>,
+    // Values not in the histogram yet
+    fresh: Vec<f64>,
+    histogram: Option<Histogram>,
+}
+
+impl HistogramFeed {
+    /// Creates a `HistogramFeed` reading the values with `reader` and
+    /// building the histogram (of `intervals` buckets) with `builder`.
+    pub fn new(reader: DataReader, builder: HistogramBuilder, intervals: usize) -> Self {
+        Self {
+            reader,
+            builder,
+            intervals,
+            values: Vec::new(),
+            fresh: Vec::new(),
+            histogram: None,
+        }
+    }
+
+    // Adds the fresh values to the histogram
+    fn flush(&mut self) {
+        let mut fresh = std::mem::take(&mut self.fresh);
+        match &mut self.histogram {
+            Some(histogram) if histogram.rows().len() == self.intervals => {
+                if !fresh.is_empty() && !histogram.update(&fresh) {
+                    histogram.merge(&self.builder.build(&mut fresh));
+                }
+            }
+            // Histograms of a few values have fewer buckets than requested
+            _ => {
+                self.values.append(&mut fresh);
+                if !self.values.is_empty() {
+                    let histogram = self.builder.build(&mut self.values);
+                    if histogram.rows().len() == self.intervals {
+                        self.values = Vec::new();
+                    }
+                    self.histogram = Some(histogram);
+                }
+            }
+        }
+    }
+}
+
+impl Feed for HistogramFeed {
+    fn observe(&mut self, line: &str) {
+        let fresh = &mut self.fresh;
+        self.reader.parse_line(line, |n| fresh.push(n));
+        if self.fresh.len() >= FRESH_VALUES {
+            self.flush();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.values.clear();
+        self.fresh.clear();
+        self.histogram = None;
+    }
+
+    fn plot(&mut self, opts: &RenderOptions) -> Option<String> {
+        self.flush();
+        render(self.histogram.as_ref()?, opts)
+    }
+}
+
+/// The statistics of the values in the lines, for `summary` panels.
+/// Percentiles are estimated (see `Stats::add`).
+pub struct SummaryFeed {
+    reader: DataReader,
+    precision: Option<usize>,
+    confidence: Option<f64>,
+    stats: Stats,
+    // Values not in the statistics yet
+    fresh: Vec<f64>,
+}
+
+impl SummaryFeed {
+    /// Creates a `SummaryFeed` reading the values with `reader`, displaying
+    /// them with `precision` and, if requested, the confidence interval of
+    /// the average at the `confidence` level.
+    pub fn new(reader: DataReader, precision: Option<usize>, confidence: Option<f64>) -> Self {
+        Self {
+            reader,
+            precision,
+            confidence,
+            stats: Stats::new_empty(precision),
+            fresh: Vec::new(),
+        }
+    }
+}
+
+impl Feed for SummaryFeed {
+    fn observe(&mut self, line: &str) {
+        let fresh = &mut self.fresh;
+        self.reader.parse_line(line, |n| fresh.push(n));
+        if self.fresh.len() >= FRESH_VALUES {
+            self.stats.add_all(&self.fresh);
+            self.fresh.clear();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.stats = Stats::new_empty(self.precision);
+        self.fresh.clear();
+    }
+
+    fn plot(&mut self, _opts: &RenderOptions) -> Option<String> {
+        self.stats.add_all(&self.fresh);
+        self.fresh.clear();
+        if let Some(level) = self.confidence.filter(|_| self.stats.samples > 0) {
+            self.stats.set_confidence(level);
+        }
+        match self.stats.samples {
+            0 => None,
+            _ => Some(self.stats.to_string()),
+        }
+    }
+}
+
+/// The number of lines matching every pattern, for `matches` panels.
+pub struct MatchesFeed {
+    reader: DataReader,
+    rows: Vec<MatchBarRow>,
+    unmatched: usize,
+    sort: SortOrder,
+    reverse: bool,
+}
+
+impl MatchesFeed {
+    /// Creates a `MatchesFeed` counting the matches of `rows` with `reader`,
+    /// displayed in the order given by `sort` and `reverse`.
+    pub fn new(reader: DataReader, rows: Vec<MatchBarRow>, sort: SortOrder, reverse: bool) -> Self {
+        Self {
+            reader,
+            rows,
+            unmatched: 0,
+            sort,
+            reverse,
+        }
+    }
+}
+
+impl Feed for MatchesFeed {
+    fn observe(&mut self, line: &str) {
+        if !self.reader.count_matches(line, &mut self.rows) {
+            self.unmatched += 1;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.rows.iter_mut().for_each(|row| row.count = 0);
+        self.unmatched = 0;
+    }
+
+    fn plot(&mut self, opts: &RenderOptions) -> Option<String> {
+        let mut matchbar = self.reader.match_bar(self.rows.clone(), self.unmatched);
+        matchbar.set_sort(self.sort, self.reverse);
+        render(&matchbar, opts)
+    }
+}
+
+/// The most common terms in the lines, for `common-terms` panels, with the
+/// change of their counts since the previous refresh.
+pub struct TermsFeed {
+    reader: DataReader,
+    terms: CommonTerms,
+}
+
+impl TermsFeed {
+    /// Creates a `TermsFeed` finding the terms with `reader`, for observing
+    /// them in `terms`.
+    pub fn new(reader: DataReader, terms: CommonTerms) -> Self {
+        Self { reader, terms }
+    }
+}
+
+impl Feed for TermsFeed {
+    fn observe(&mut self, line: &str) {
+        if let Some(term) = self.reader.parse_term(line) {
+            self.terms.observe(term.to_string());
+        }
+    }
+
+    fn reset(&mut self) {
+        self.terms.clear();
+    }
+
+    fn plot(&mut self, opts: &RenderOptions) -> Option<String> {
+        let output = render(&self.terms, opts);
+        self.terms.snapshot();
+        output
+    }
+}
+
+/// The histogram of the timestamps of the lines, for `timehist` panels.  As
+/// the time span usually grows with every line, the histogram is built again
+/// from the timestamps (rather than from the lines) when there are new ones.
+pub struct TimeFeed {
+    stream: TimeStream,
+    builder: TimeHistogramBuilder,
+    timestamps: Vec<DateTime<FixedOffset>>,
+    histogram: Option<TimeHistogram>,
+}
+
+impl TimeFeed {
+    /// Creates a `TimeFeed` finding the timestamps with `stream` and building
+    /// the histogram with `builder`.
+    pub fn new(stream: TimeStream, builder: TimeHistogramBuilder) -> Self {
+        Self {
+            stream,
+            builder,
+            timestamps: Vec::new(),
+            histogram: None,
+        }
+    }
+}
+
+impl Feed for TimeFeed {
+    fn observe(&mut self, line: &str) {
+        if let Some(ts) = self.stream.timestamp(line) {
+            self.timestamps.push(ts);
+            self.histogram = None;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.timestamps.clear();
+        self.histogram = None;
+    }
+
+    fn plot(&mut self, opts: &RenderOptions) -> Option<String> {
+        if self.timestamps.len() < 2 {
+            return None;
+        }
+        if self.histogram.is_none() {
+            self.histogram = Some(self.builder.build(&self.timestamps));
+        }
+        render(self.histogram.as_ref()?, opts)
+    }
+}
+
+/// Returns the first `rows` lines of the output of a panel, so that it fits
+/// the room given to it in the dashboard.
+pub fn fit(output: &str, rows: usize) -> String {
+    output
+        .lines()
+        .take(rows)
+        .map(|line| format!("{line}\n"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app;
+    use crate::read::{DataReaderBuilder, TimeReaderBuilder};
+    use regex::Regex;
+    use yansi::Paint;
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(split_words("  "), Ok(vec![]));
+        assert_eq!(
+            split_words(r#"hist --regex 'took (\d+) ms' -i 5"#),
+            Ok(vec![
+                String::from("hist"),
+                String::from("--regex"),
+                String::from(r"took (\d+) ms"),
+                String::from("-i"),
+                String::from("5"),
+            ])
+        );
+        assert_eq!(
+            split_words(r#"matches "a \"b\"" c\ d ''"#),
+            Ok(vec![
+                String::from("matches"),
+                String::from(r#"a "b""#),
+                String::from("c d"),
+                String::from(""),
+            ])
+        );
+        assert!(split_words("matches 'ERROR").is_err());
+    }
+
+    #[test]
+    fn test_panel() {
+        let panel = Panel::from_str("timehist --intervals 10").unwrap();
+        assert_eq!(panel.subcommand(), "timehist");
+        assert_eq!(panel.title, "timehist --intervals 10");
+        assert!(Panel::from_str("").is_err());
+        assert!(Panel::from_str("dash --panel hist").is_err());
+        assert!(Panel::from_str("plot").is_err());
+    }
+
+    #[test]
+    fn test_panel_options() {
+        let app = app::get_app();
+        for (name, options) in PANELS {
+            let cmd = app.find_subcommand(name).unwrap();
+            for option in options {
+                assert!(cmd.get_arguments().any(|arg| arg.get_id() == *option));
+            }
+        }
+        let cmd = app.find_subcommand("hist").unwrap();
+        let check = |spec: &str| {
+            let panel = Panel::from_str(spec).unwrap();
+            let matches = cmd.clone().try_get_matches_from(&panel.args).unwrap();
+            panel.check_options(cmd, &matches)
+        };
+        assert_eq!(check("hist --intervals 5"), Ok(()));
+        assert_eq!(
+            check("hist --outliers"),
+            Err(String::from("--outliers cannot be used in a panel"))
+        );
+        assert!(check("hist some.log").is_err());
+    }
+
+    #[test]
+    fn test_window() {
+        let start = Instant::now();
+        let mut window = Window::new(Duration::from_secs(60));
+        window.push(String::from("a"), start);
+        window.push(String::from("b"), start + Duration::from_secs(30));
+        window.push(String::from("c"), start + Duration::from_secs(61));
+        assert_eq!(window.lines().collect::<Vec<&str>>(), vec!["b", "c"]);
+        window.expire(start + Duration::from_secs(200));
+        assert_eq!(window.lines().count(), 0);
+    }
+
+    #[test]
+    fn test_histogram_feed() {
+        Paint::disable();
+        let reader = DataReaderBuilder::default().build().unwrap();
+        let mut builder = HistogramBuilder::default();
+        builder.intervals(2).precision(1);
+        let mut feed = HistogramFeed::new(reader, builder, 2);
+        let opts = RenderOptions {
+            width: 20,
+            color: false,
+            ..Default::default()
+        };
+        assert_eq!(feed.plot(&opts), None);
+        for line in ["1", "2", "foo", "3", "4"] {
+            feed.observe(line);
+        }
+        let output = feed.plot(&opts).unwrap();
+        assert!(output.contains("[1.0 .. 2.5] [2] ∎∎\n"));
+        assert!(output.contains("[2.5 .. 4.0] [2] ∎∎\n"));
+        // Values are dropped once the histogram has every bucket
+        assert!(feed.values.is_empty());
+        // New values in the buckets are added to them
+        feed.observe("4");
+        let output = feed.plot(&opts).unwrap();
+        assert!(output.contains("[2.5 .. 4.0] [3] ∎∎∎\n"));
+        // New values out of the buckets are spread over new ones
+        feed.observe("9");
+        let output = feed.plot(&opts).unwrap();
+        assert!(output.contains("[1.0 .. 5.0] [5] ∎∎∎∎∎\n"));
+        assert!(output.contains("[5.0 .. 9.0] [1] ∎\n"));
+        feed.reset();
+        assert_eq!(feed.plot(&opts), None);
+    }
+
+    #[test]
+    fn test_summary_feed() {
+        Paint::disable();
+        let reader = DataReaderBuilder::default().build().unwrap();
+        let mut feed = SummaryFeed::new(reader, Some(0), Some(0.95));
+        let opts = RenderOptions::default();
+        assert_eq!(feed.plot(&opts), None);
+        feed.observe("1");
+        feed.observe("3");
+        let output = feed.plot(&opts).unwrap();
+        assert!(output.starts_with("Samples = 2; Min = 1; Max = 3\n"));
+        assert!(output.contains("95% confidence interval of average"));
+    }
+
+    #[test]
+    fn test_matches_feed() {
+        Paint::disable();
+        let mut builder = DataReaderBuilder::default();
+        builder.count_unmatched(true);
+        let rows = vec![MatchBarRow::new("GET"), MatchBarRow::new("POST")];
+        let mut feed = MatchesFeed::new(builder.build().unwrap(), rows, SortOrder::None, false);
+        for line in ["GET /", "POST /a", "GET /b", "PUT /c"] {
+            feed.observe(line);
+        }
+        let opts = RenderOptions {
+            width: 30,
+            color: false,
+            ..Default::default()
+        };
+        let output = feed.plot(&opts).unwrap();
+        assert!(output.contains("[GET       ] [2] ∎∎\n"));
+        assert!(output.contains("[<no match>] [1] ∎\n"));
+        feed.reset();
+        feed.observe("GET /d");
+        let output = feed.plot(&opts).unwrap();
+        assert!(output.contains("[GET       ] [1] ∎\n"));
+        assert!(output.contains("[POST      ] [0]"));
+    }
+
+    #[test]
+    fn test_terms_feed() {
+        Paint::disable();
+        let mut builder = DataReaderBuilder::default();
+        builder.regex(Regex::new("(GET|POST)").unwrap());
+        let mut feed = TermsFeed::new(builder.build().unwrap(), CommonTerms::new(5));
+        let opts = RenderOptions {
+            width: 30,
+            color: false,
+            ..Default::default()
+        };
+        feed.observe("GET /");
+        let output = feed.plot(&opts).unwrap();
+        assert!(output.contains("[GET] [1] ∎\n"));
+        feed.observe("GET /a");
+        feed.observe("POST /b");
+        let output = feed.plot(&opts).unwrap();
+        assert!(output.contains("[ GET] [2] ∎∎ ▲1\n"));
+        assert!(output.contains("[POST] [1] ∎ ▲1\n"));
+        // Like with the lines out of the window
+        feed.reset();
+        feed.observe("GET /c");
+        let output = feed.plot(&opts).unwrap();
+        assert!(output.contains("[GET] [1] ∎ ▼1\n"));
+    }
+
+    #[test]
+    fn test_time_feed() {
+        Paint::disable();
+        let reader = TimeReaderBuilder::default().build().unwrap();
+        let mut builder = TimeHistogramBuilder::default();
+        builder.intervals(2);
+        let mut feed = TimeFeed::new(TimeStream::new(reader), builder);
+        let opts = RenderOptions {
+            width: 30,
+            color: false,
+            ..Default::default()
+        };
+        feed.observe("[2021-04-15T06:25:31+00:00] foo");
+        assert_eq!(feed.plot(&opts), None);
+        feed.observe("[2021-04-15T06:25:32+00:00] bar");
+        feed.observe("[2021-04-15T06:25:33+00:00] bar");
+        let output = feed.plot(&opts).unwrap();
+        assert!(output.contains("[06:25:31.000] [1] ∎\n"));
+        assert!(output.contains("[06:25:32.000] [2] ∎∎\n"));
+    }
+
+    #[test]
+    fn test_fit() {
+        assert_eq!(fit("a\nb\nc\n", 2), "a\nb\n");
+        assert_eq!(fit("a\n", 2), "a\n");
+    }
+}
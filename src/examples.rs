@@ -0,0 +1,72 @@
+use std::fmt;
+
+/// A command line for a common task.
+///
+/// Every example reads from an input file, named after one of the fixtures in
+/// `tests/fixtures`, where the example is tested to work.
+#[derive(Debug)]
+pub struct Example {
+    /// What the example is useful for.
+    pub title: &'static str,
+    /// Arguments of the command line (subcommand first), but the input.
+    pub args: &'static [&'static str],
+    /// Name of the input file.
+    pub input: &'static str,
+}
+
+/// Examples displayed by the `examples` subcommand.
+pub const EXAMPLES: &[Example] = &[
+    Example {
+        title: "Histogram of request latencies in nginx access logs",
+        args: &["hist", "--preset", "nginx"],
+        input: "access.log",
+    },
+    Example {
+        title: "Most common HTTP status codes in nginx access logs",
+        args: &["common-terms", "--regex", r#"" ([0-9]{3}) "#],
+        input: "access.log",
+    },
+    Example {
+        title: "Requests over time in nginx access logs",
+        args: &["timehist", "--preset", "nginx", "--intervals", "10"],
+        input: "access.log",
+    },
+    Example {
+        title: "Errors over time in application logs",
+        args: &["timehist", "--regex", "ERROR", "--intervals", "10"],
+        input: "app.log",
+    },
+    Example {
+        title: "Periods of silence in application logs",
+        args: &["gaps", "--threshold", "9s"],
+        input: "app.log",
+    },
+    Example {
+        title: "Log levels over time in application logs",
+        args: &["split-timehist", "INFO", "WARNING", "ERROR", "--input"],
+        input: "app.log",
+    },
+    Example {
+        title: "Top exceptions in application logs",
+        args: &["common-terms", "--regex", r"([A-Za-z]+(Error|Exception))"],
+        input: "app.log",
+    },
+];
+
+// Quotes a string so that a shell does not interpret it
+fn quote(arg: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./=:".contains(c);
+    if !arg.is_empty() && arg.chars().all(safe) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+impl fmt::Display for Example {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let args: Vec<String> = self.args.iter().map(|arg| quote(arg)).collect();
+        writeln!(f, "# {}", self.title)?;
+        writeln!(f, "lowcharts {} {}", args.join(" "), self.input)
+    }
+}
@@ -1,12 +1,78 @@
+use std::cell::Cell;
 use std::fmt;
 use std::ops::Range;
 
-use yansi::Color::{Blue, Green, Red};
 use yansi::Paint;
 
+pub use self::theme::Theme;
+
+mod theme;
+
 // Units-based suffixes for human formatting.
 const UNITS: &[&str] = &["", " K", " M", " G", " T", " P", " E", " Z", " Y"];
 pub static BAR_CHAR: &str = "∎";
+pub static MARKER: &str = "●";
+pub static FULL_BLOCK: &str = "█";
+// Blocks from 1/8 to 7/8 of the width of a character
+const PARTIAL_BLOCKS: &[char] = &['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Units of normalized counts (fractions of the values): basis points, or
+/// hundredths of a percent.
+pub const BASIS_POINTS: usize = 10_000;
+
+thread_local! {
+    // Style of the output displayed by this thread (see `with_style`)
+    static STYLE: Cell<Style> = Cell::new(Style::default());
+}
+
+/// Settings of the output that `fmt::Display` implementations cannot be
+/// given, so they take them from the style in effect (see `with_style`).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Style {
+    /// Colors of the output.
+    pub theme: Theme,
+    /// Whether bars are drawn with block characters, including partial ones
+    /// for the remainder of the bar, which gives eight times finer
+    /// resolution than the default.
+    pub fine_bars: bool,
+}
+
+impl Style {
+    /// Returns the style in effect in this thread.
+    pub fn current() -> Self {
+        STYLE.with(Cell::get)
+    }
+}
+
+// Puts a style back in effect when dropped, even if displaying panicked
+struct Restore(Style);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        STYLE.with(|style| style.set(self.0));
+    }
+}
+
+/// Calls `f` with `style` in effect in this thread, and returns what it
+/// returns.  The previous style is in effect again afterwards.
+pub fn with_style<T, F: FnOnce() -> T>(style: Style, f: F) -> T {
+    let _restore = Restore(STYLE.with(|current| current.replace(style)));
+    f()
+}
+
+/// Returns `count` as a fraction of `total` in basis points, rounded to the
+/// nearest one (zero if `total` is zero).
+pub fn basis_points(count: usize, total: usize) -> usize {
+    match total {
+        0 => 0,
+        total => (count as f64 * BASIS_POINTS as f64 / total as f64).round() as usize,
+    }
+}
+
+/// Formats a fraction of the values, given in basis points, as a percentage.
+pub fn format_share(units: usize) -> String {
+    format!("{}.{:02}%", units / 100, units % 100)
+}
 
 #[derive(Debug)]
 pub struct F64Formatter {
@@ -71,21 +137,52 @@ impl F64Formatter {
 pub struct HorizontalScale {
     /// How many units are represented by a char
     scale: usize,
+    theme: Theme,
+    fine: bool,
+    /// Whether units are basis points of the values, rather than counts
+    normalized: bool,
 }
 
 impl HorizontalScale {
     pub fn new(scale: usize) -> Self {
         Self {
             scale: 1.max(scale),
+            theme: Theme::current(),
+            fine: Style::current().fine_bars,
+            normalized: false,
         }
     }
 
+    /// Returns this scale, displaying units as fractions of the values (see
+    /// `BASIS_POINTS`) if `normalized` is true.
+    pub fn normalized(mut self, normalized: bool) -> Self {
+        self.normalized = normalized;
+        self
+    }
+
     pub fn get_bar(&self, units: usize) -> Paint<String> {
-        Red.paint(format!("{:∎<width$}", "", width = units / self.scale))
+        if !self.fine {
+            return self
+                .theme
+                .bar
+                .paint(format!("{:∎<width$}", "", width = units / self.scale));
+        }
+        let eighths = units * 8 / self.scale;
+        let mut bar = FULL_BLOCK.repeat(eighths / 8);
+        if let Some(partial) = (eighths % 8).checked_sub(1) {
+            bar.push(PARTIAL_BLOCKS[partial]);
+        }
+        self.theme.bar.paint(bar)
     }
 
     pub fn get_count(&self, units: usize, width: usize) -> Paint<String> {
-        Green.paint(format!("{units:width$}"))
+        if self.normalized {
+            return self
+                .theme
+                .count
+                .paint(format!("{:>width$}", format_share(units)));
+        }
+        self.theme.count.paint(format!("{units:width$}"))
     }
 
     pub fn get_scale(&self) -> usize {
@@ -95,11 +192,23 @@ impl HorizontalScale {
 
 impl fmt::Display for HorizontalScale {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.normalized {
+            return writeln!(
+                formatter,
+                "Each {} represents {} of the values",
+                self.theme
+                    .bar
+                    .paint(if self.fine { FULL_BLOCK } else { BAR_CHAR }),
+                self.theme.label.paint(format_share(self.scale)),
+            );
+        }
         writeln!(
             formatter,
             "Each {} represents a count of {}",
-            Red.paint(BAR_CHAR),
-            Blue.paint(self.scale.to_string()),
+            self.theme
+                .bar
+                .paint(if self.fine { FULL_BLOCK } else { BAR_CHAR }),
+            self.theme.label.paint(self.scale.to_string()),
         )
     }
 }
@@ -109,6 +218,18 @@ mod tests {
     use super::*;
     use yansi::Paint;
 
+    #[test]
+    fn test_with_style() {
+        let style = Style {
+            theme: Theme::COLORBLIND,
+            fine_bars: true,
+        };
+        assert_eq!(with_style(style, Theme::current), Theme::COLORBLIND);
+        assert!(with_style(style, || HorizontalScale::new(1).fine));
+        assert_eq!(Theme::current(), Theme::DEFAULT);
+        assert!(!HorizontalScale::new(1).fine);
+    }
+
     #[test]
     fn test_basic_format() {
         assert_eq!(F64Formatter::new(0).format(1000.0), "1000");
@@ -207,6 +328,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_horizontal_scale_normalized() {
+        Paint::disable();
+        let scale = HorizontalScale::new(25).normalized(true);
+        assert_eq!(
+            format!("{scale}"),
+            format!("Each {BAR_CHAR} represents 0.25% of the values\n")
+        );
+        assert_eq!(scale.get_count(1250, 7).to_string(), " 12.50%");
+        assert_eq!(format_share(BASIS_POINTS), "100.00%");
+        assert_eq!(basis_points(2, 3), 6667);
+        assert_eq!(basis_points(1, 15), 667);
+        assert_eq!(basis_points(1, 0), 0);
+    }
+
     #[test]
     fn test_horizontal_scale_with_zero_scale() {
         Paint::disable();
@@ -223,13 +359,33 @@ mod tests {
         let scale = HorizontalScale::new(10);
         assert_eq!(
             scale.get_bar(80),
-            Red.paint(format!("{:∎<width$}", "", width = 8))
+            scale.theme.bar.paint(format!("{:∎<width$}", "", width = 8))
         );
     }
 
+    #[test]
+    fn test_horizontal_scale_fine_bar() {
+        let scale = HorizontalScale {
+            scale: 10,
+            theme: Theme::DEFAULT,
+            fine: true,
+            normalized: false,
+        };
+        assert_eq!(
+            scale.get_bar(80),
+            scale.theme.bar.paint("████████".to_string())
+        );
+        assert_eq!(scale.get_bar(25), scale.theme.bar.paint("██▌".to_string()));
+        assert_eq!(scale.get_bar(9), scale.theme.bar.paint("▉".to_string()));
+        assert_eq!(scale.get_bar(1), scale.theme.bar.paint("".to_string()));
+    }
+
     #[test]
     fn test_horizontal_scale_count() {
         let scale = HorizontalScale::new(10);
-        assert_eq!(scale.get_count(80, 5), Green.paint("   80".to_string()));
+        assert_eq!(
+            scale.get_count(80, 5),
+            scale.theme.count.paint("   80".to_string())
+        );
     }
 }
@@ -0,0 +1,78 @@
+use std::str::FromStr;
+
+use yansi::Color::{self, Blue, Cyan, Fixed, Green, Magenta, Red};
+
+use crate::format::Style;
+
+/// The colors used for displaying plots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Color of the bars (and of the points in plots).
+    pub bar: Color,
+    /// Color of the labels and of the highlighted values.
+    pub label: Color,
+    /// Color of the counts next to the bars.
+    pub count: Color,
+    /// Colors of the series in plots with several of them.
+    pub palette: [Color; 5],
+}
+
+impl Theme {
+    /// Red bars, blue labels and green counts.
+    pub const DEFAULT: Theme = Theme {
+        bar: Red,
+        label: Blue,
+        count: Green,
+        palette: [Red, Blue, Magenta, Green, Cyan],
+    };
+
+    /// A theme with colors distinguishable by people with color blindness
+    /// (the Okabe-Ito palette, in its closest 256 colors approximation).
+    pub const COLORBLIND: Theme = Theme {
+        bar: Fixed(214),  // Orange
+        label: Fixed(74), // Sky blue
+        count: Fixed(36), // Bluish green
+        palette: [
+            Fixed(214), // Orange
+            Fixed(74),  // Sky blue
+            Fixed(175), // Reddish purple
+            Fixed(36),  // Bluish green
+            Fixed(227), // Yellow
+        ],
+    };
+
+    /// Returns the theme of the style in effect, used for displaying plots.
+    pub(crate) fn current() -> Self {
+        Style::current().theme
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(Self::DEFAULT),
+            "colorblind" => Ok(Self::COLORBLIND),
+            _ => Err(format!("Unknown theme: {s}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(Theme::from_str("default"), Ok(Theme::default()));
+        assert_eq!(Theme::from_str("colorblind"), Ok(Theme::COLORBLIND));
+        assert!(Theme::from_str("neon").is_err());
+    }
+}
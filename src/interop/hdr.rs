@@ -0,0 +1,166 @@
+use std::io::Write;
+use std::time::Duration;
+
+use base64::Engine;
+use hdrhistogram::serialization::interval_log::{
+    IntervalLogIterator, IntervalLogWriterBuilder, LogEntry,
+};
+use hdrhistogram::serialization::{Deserializer, V2DeflateSerializer};
+
+use crate::plot::Histogram;
+
+// Encoded histograms (V2, compressed or not) start with this, in base64
+const ENCODED_PREFIX: &str = ",HIST";
+
+// Significant digits of the histograms exported
+const SIGNIFICANT_DIGITS: u8 = 3;
+
+/// Parses the output of HdrHistogram tools (an interval log, or a percentile
+/// distribution like the one printed by wrk2) into pairs of a value and its
+/// number of occurrences.
+///
+/// Values in interval logs are divided by `divisor`, so that they are in the
+/// same units used in the percentile distributions (where the division is
+/// done by the tool writing them).
+pub fn parse(data: &[u8], divisor: f64) -> Result<Vec<(f64, usize)>, String> {
+    let text = String::from_utf8_lossy(data);
+    match text.contains(ENCODED_PREFIX) {
+        true => parse_interval_log(data, divisor),
+        false => Ok(parse_percentiles(&text)),
+    }
+}
+
+// Merges all the histograms in the log, regardless of their tags
+fn parse_interval_log(data: &[u8], divisor: f64) -> Result<Vec<(f64, usize)>, String> {
+    let mut merged = hdrhistogram::Histogram::<u64>::new(SIGNIFICANT_DIGITS).unwrap();
+    let mut deserializer = Deserializer::new();
+    for entry in IntervalLogIterator::new(data) {
+        let interval = match entry {
+            Ok(LogEntry::Interval(interval)) => interval,
+            Ok(_) => continue,
+            Err(error) => return Err(format!("Invalid interval log: {error:?}")),
+        };
+        let encoded = base64::engine::general_purpose::STANDARD
+            .decode(interval.encoded_histogram())
+            .map_err(|error| format!("Invalid encoded histogram: {error}"))?;
+        let histogram: hdrhistogram::Histogram<u64> = deserializer
+            .deserialize(&mut encoded.as_slice())
+            .map_err(|error| format!("Invalid encoded histogram: {error}"))?;
+        merged
+            .add(histogram)
+            .map_err(|error| format!("Could not merge histograms: {error}"))?;
+    }
+    Ok(merged
+        .iter_recorded()
+        .map(|v| {
+            let value = merged.median_equivalent(v.value_iterated_to());
+            (value as f64 / divisor, v.count_at_value() as usize)
+        })
+        .collect())
+}
+
+// Rows are like "value percentile total-count 1/(1-percentile)", separated by
+// blanks or commas.  Anything else (headers, summaries) is ignored.
+fn parse_percentiles(text: &str) -> Vec<(f64, usize)> {
+    let mut pairs = Vec::new();
+    let mut total = 0;
+    for line in text.lines() {
+        let fields: Vec<&str> = line
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|field| !field.is_empty())
+            .collect();
+        if fields.len() < 3 || fields[1].parse::<f64>().is_err() {
+            continue;
+        }
+        let (value, count) = match (fields[0].parse::<f64>(), fields[2].parse::<usize>()) {
+            (Ok(value), Ok(count)) => (value, count),
+            _ => continue,
+        };
+        if count > total {
+            pairs.push((value, count - total));
+            total = count;
+        }
+    }
+    pairs
+}
+
+/// Writes the buckets of `histogram` as an HdrHistogram interval log with a
+/// single interval, where every value of a bucket is recorded at its middle,
+/// multiplied by `divisor` (HdrHistogram records integers).
+pub fn write_interval_log<W: Write>(
+    histogram: &Histogram,
+    writer: &mut W,
+    divisor: f64,
+) -> Result<(), String> {
+    let mut hdr = hdrhistogram::Histogram::<u64>::new(SIGNIFICANT_DIGITS).unwrap();
+    for (range, count) in histogram.buckets().filter(|(_, count)| *count > 0) {
+        let middle = (range.start + range.end) / 2.0 * divisor;
+        if middle < 0.0 {
+            return Err(String::from("HdrHistogram cannot hold negative values"));
+        }
+        hdr.record_n(middle.round() as u64, count as u64)
+            .map_err(|error| format!("Could not record {middle}: {error:?}"))?;
+    }
+    let mut serializer = V2DeflateSerializer::new();
+    let mut log = IntervalLogWriterBuilder::new()
+        .add_comment("Buckets exported by lowcharts")
+        .with_max_value_divisor(divisor)
+        .begin_log_with(writer, &mut serializer)
+        .map_err(|error| error.to_string())?;
+    log.write_histogram(&hdr, Duration::ZERO, Duration::ZERO, None)
+        .map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plot::HistogramOptions;
+
+    #[test]
+    fn test_percentiles() {
+        let output = "\
+  Detailed Percentile spectrum:
+       Value   Percentile   TotalCount 1/(1-Percentile)
+
+       0.921     0.000000            1         1.00
+       1.500     0.500000           50         2.00
+       1.500     0.600000           50         2.50
+      12.000     1.000000          100          inf
+#[Mean    =        2.000, StdDeviation   =        1.000]
+#[Max     =       12.000, Total count    =          100]
+";
+        let pairs = parse(output.as_bytes(), 1000.0).unwrap();
+        assert_eq!(pairs, [(0.921, 1), (1.5, 49), (12.0, 50)]);
+        let csv =
+            "Value,Percentile,TotalCount,1/(1-Percentile)\n3.0,0.5,4,2.00\n5.0,1.0,8,Infinity\n";
+        assert_eq!(parse(csv.as_bytes(), 1.0).unwrap(), [(3.0, 4), (5.0, 4)]);
+    }
+
+    #[test]
+    fn test_interval_log_round_trip() {
+        let options = HistogramOptions {
+            intervals: 2,
+            ..Default::default()
+        };
+        let histogram = Histogram::new_weighted(&mut [(1.0, 3), (2.0, 5), (3.0, 2)], options);
+        let mut log = Vec::new();
+        write_interval_log(&histogram, &mut log, 100.0).unwrap();
+        let text = String::from_utf8_lossy(&log);
+        assert!(text.starts_with("#Buckets exported by lowcharts\n"));
+        assert!(text.contains("#[MaxValueDivisor: 100.000]\n"));
+        let pairs = parse(&log, 100.0).unwrap();
+        assert_eq!(pairs, [(1.5, 3), (2.5, 7)]);
+        assert!(parse(b"0.0,1.0,2.0,HISTnonsense\n", 1.0).is_err());
+    }
+
+    #[test]
+    fn test_negative_values() {
+        let options = HistogramOptions {
+            intervals: 1,
+            ..Default::default()
+        };
+        let histogram = Histogram::new_weighted(&mut [(-3.0, 1), (-1.0, 1)], options);
+        let mut log = Vec::new();
+        assert!(write_interval_log(&histogram, &mut log, 1.0).is_err());
+    }
+}
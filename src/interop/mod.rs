@@ -0,0 +1,2 @@
+pub mod hdr;
+pub mod report;
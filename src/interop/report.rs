@@ -0,0 +1,157 @@
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// The result of checking an assertion, like `p99 < 250`.
+#[derive(Debug, PartialEq)]
+pub struct Outcome {
+    /// The assertion, as given by the user.
+    pub name: String,
+    /// Whether the assertion holds.
+    pub passed: bool,
+    /// Details on the values compared, or on why they could not be.
+    pub message: String,
+}
+
+/// Formats of the reports of the results of assertions, for CI systems.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Test Anything Protocol, version 13.
+    Tap,
+    /// JUnit XML, understood by most CI systems.
+    Junit,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "tap" => Ok(Self::Tap),
+            "junit" => Ok(Self::Junit),
+            _ => Err(format!("Unknown report format '{s}'")),
+        }
+    }
+}
+
+/// Writes the outcomes of assertions in `format`, as a test suite named
+/// `suite` (like the input file).
+pub fn write_report(
+    outcomes: &[Outcome],
+    format: ReportFormat,
+    suite: &str,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    match format {
+        ReportFormat::Tap => write_tap(outcomes, writer),
+        ReportFormat::Junit => write_junit(outcomes, suite, writer),
+    }
+}
+
+fn write_tap(outcomes: &[Outcome], writer: &mut impl Write) -> io::Result<()> {
+    writeln!(writer, "TAP version 13")?;
+    writeln!(writer, "1..{}", outcomes.len())?;
+    for (index, outcome) in outcomes.iter().enumerate() {
+        let status = if outcome.passed { "ok" } else { "not ok" };
+        writeln!(writer, "{} {} - {}", status, index + 1, outcome.name)?;
+        if !outcome.passed {
+            writeln!(writer, "  ---")?;
+            writeln!(
+                writer,
+                "  message: '{}'",
+                outcome.message.replace('\'', "''")
+            )?;
+            writeln!(writer, "  ...")?;
+        }
+    }
+    Ok(())
+}
+
+fn write_junit(outcomes: &[Outcome], suite: &str, writer: &mut impl Write) -> io::Result<()> {
+    let failures = outcomes.iter().filter(|o| !o.passed).count();
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<testsuite name="{}" tests="{}" failures="{}">"#,
+        escape_xml(suite),
+        outcomes.len(),
+        failures
+    )?;
+    for outcome in outcomes {
+        let name = escape_xml(&outcome.name);
+        if outcome.passed {
+            writeln!(
+                writer,
+                r#"  <testcase name="{name}" classname="lowcharts"/>"#
+            )?;
+        } else {
+            writeln!(
+                writer,
+                r#"  <testcase name="{name}" classname="lowcharts">"#
+            )?;
+            writeln!(
+                writer,
+                r#"    <failure message="{}"/>"#,
+                escape_xml(&outcome.message)
+            )?;
+            writeln!(writer, "  </testcase>")?;
+        }
+    }
+    writeln!(writer, "</testsuite>")
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcomes() -> Vec<Outcome> {
+        vec![
+            Outcome {
+                name: String::from("p99 < 250"),
+                passed: true,
+                message: String::from("p99 is 200"),
+            },
+            Outcome {
+                name: String::from("max < 300"),
+                passed: false,
+                message: String::from("max is 400"),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_tap() {
+        let mut output = Vec::new();
+        write_report(&outcomes(), ReportFormat::Tap, "stdin", &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "TAP version 13\n1..2\nok 1 - p99 < 250\nnot ok 2 - max < 300\n  ---\n  \
+             message: 'max is 400'\n  ...\n"
+        );
+    }
+
+    #[test]
+    fn test_junit() {
+        let mut output = Vec::new();
+        write_report(&outcomes(), ReportFormat::Junit, "a&b", &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(r#"<testsuite name="a&amp;b" tests="2" failures="1">"#));
+        assert!(output.contains(r#"<testcase name="p99 &lt; 250" classname="lowcharts"/>"#));
+        assert!(output.contains(r#"<failure message="max is 400"/>"#));
+        assert!(output.ends_with("</testsuite>\n"));
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!("TAP".parse::<ReportFormat>(), Ok(ReportFormat::Tap));
+        assert_eq!("junit".parse::<ReportFormat>(), Ok(ReportFormat::Junit));
+        assert!("xml".parse::<ReportFormat>().is_err());
+    }
+}
@@ -14,8 +14,17 @@
 //! let options = plot::HistogramOptions { intervals: 4, ..Default::default() };
 //! let histogram = plot::Histogram::new(vec, options);
 //! print!("{}", histogram);
+//!
+//! // Same thing, but using a builder
+//! let histogram = plot::HistogramBuilder::default().intervals(4).build(vec);
+//! print!("{}", histogram);
 //! ```
 
+#[macro_use]
+extern crate derive_builder;
+
 mod format;
 pub mod plot;
 pub mod stats;
+
+pub use format::Theme;
@@ -1,21 +1,335 @@
 mod app;
+mod config;
+mod dash;
+mod examples;
 mod format;
+mod interop;
 mod plot;
 mod read;
 mod stats;
 
+use std::collections::HashMap;
 use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
 
 #[macro_use]
 extern crate derive_builder;
 #[macro_use]
 extern crate log;
-use chrono::Duration;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate};
 use clap::ArgMatches;
 use regex::Regex;
 use simplelog::{ColorChoice, ConfigBuilder, LevelFilter, TermLogger, TerminalMode};
+use terminal_size::{terminal_size, Height, Width};
 use yansi::Paint;
 
+/// Number of biggest values whose location is displayed with `--locate`
+const LOCATED_VALUES: usize = 3;
+
+/// Number of terms tracked with `--approx` for every line displayed
+const APPROX_COUNTERS_PER_LINE: usize = 100;
+
+/// Exit code when some `--fail-if` condition holds, or `--assert` does not
+const FAILED_CONDITION: i32 = 3;
+
+/// Size in pixels of a character, for drawing plots as images
+const PIXELS_PER_COLUMN: usize = 8;
+const PIXELS_PER_ROW: usize = 16;
+
+/// Height in characters of histograms drawn as images
+const HISTOGRAM_IMAGE_ROWS: usize = 20;
+
+/// Width in pixels of SVG images
+const SVG_WIDTH: usize = 800;
+
+/// Width of the output when not given and stdout is not a terminal
+const DEFAULT_WIDTH: usize = 110;
+
+/// Height of x-y plots when not given and stdout is not a terminal
+const DEFAULT_HEIGHT: usize = 40;
+
+/// Lines in the output of x-y plots other than the plot itself (statistics),
+/// plus one for the prompt
+const PLOT_EXTRA_LINES: usize = 4;
+
+/// Input lines read ahead of the panels of a dashboard, at most
+const DASH_QUEUED_LINES: usize = 10_000;
+
+/// Returns the width requested in the command line or, if missing, the one
+/// of the terminal.
+fn get_width(matches: &ArgMatches) -> usize {
+    match matches
+        .is_valid_arg("width")
+        .then(|| matches.value_of("width"))
+        .flatten()
+    {
+        Some(_) => matches.value_of_t("width").unwrap(),
+        None => match terminal_size() {
+            Some((Width(width), _)) => width as usize,
+            None => DEFAULT_WIDTH,
+        },
+    }
+}
+
+/// Returns the height requested in the command line or, if missing, the one
+/// that makes the plot fit in the terminal.
+fn get_height(matches: &ArgMatches) -> usize {
+    match matches.value_of("height") {
+        Some(_) => matches.value_of_t("height").unwrap(),
+        None => match terminal_size() {
+            Some((_, Height(height))) => (height as usize).saturating_sub(PLOT_EXTRA_LINES).max(1),
+            None => DEFAULT_HEIGHT,
+        },
+    }
+}
+
+/// Returns the options for rendering plots requested in the command line of
+/// a sub-command: format, width, colors and characters.
+fn get_render_options(matches: &ArgMatches) -> plot::RenderOptions {
+    let mut options = plot::RenderOptions {
+        width: get_width(matches),
+        theme: matches.value_of_t("theme").unwrap(),
+        fine_bars: matches.is_present("fine-bars"),
+        format: matches.value_of_t("output-format").unwrap(),
+        ..Default::default()
+    };
+    if let Ok(bar_char) = matches.value_of_t("bar-char") {
+        options.bar_char = bar_char;
+    }
+    if let Ok(marker) = matches.value_of_t("marker") {
+        options.marker = marker;
+    }
+    options
+}
+
+/// Returns the protocol for drawing plots as images requested in the command
+/// line, if the output is a terminal supporting it and plots are written as
+/// text in `format`.
+fn get_graphics(matches: &ArgMatches, format: plot::Format) -> plot::Graphics {
+    let graphics: plot::Graphics = matches.value_of_t("graphics").unwrap();
+    if format != plot::Format::Text {
+        return plot::Graphics::Off;
+    }
+    if graphics == plot::Graphics::Auto && atty::isnt(atty::Stream::Stdout) {
+        return plot::Graphics::Off;
+    }
+    graphics.resolve()
+}
+
+/// Writes the statistics of a plot and the plot itself as an image.  Returns
+/// false on errors.
+fn print_image(
+    plot: &dyn plot::Raster,
+    stats: &stats::Stats,
+    graphics: plot::Graphics,
+    size: (usize, usize),
+    options: &plot::RenderOptions,
+) -> bool {
+    if !print_plot(stats, options) {
+        return false;
+    }
+    println!(
+        "{}",
+        graphics.encode(&plot.raster(size.0, size.1, options.theme))
+    );
+    true
+}
+
+/// Writes a plot as SVG into the file requested in the command line, if any.
+/// Returns false on errors.
+fn write_svg(plot: &dyn plot::ToSvg, matches: &ArgMatches, theme: format::Theme) -> bool {
+    let path = match matches.value_of("output") {
+        Some(path) => path,
+        None => return true,
+    };
+    match fs::write(path, plot.to_svg(SVG_WIDTH, theme)) {
+        Ok(()) => true,
+        Err(error) => {
+            error!("Could not write {}: {}", path, error);
+            false
+        }
+    }
+}
+
+/// Writes the data of a plot as CSV (or TSV, if the file name ends in .tsv)
+/// into the file requested in the command line, if any.  Returns false on
+/// errors.
+fn write_data(plot: &dyn plot::Render, matches: &ArgMatches) -> bool {
+    let path = match matches.value_of("export-data") {
+        Some(path) => path,
+        None => return true,
+    };
+    let delimiter = match path.ends_with(".tsv") {
+        true => '\t',
+        false => ',',
+    };
+    let table = match plot.table() {
+        Some(table) => table,
+        None => {
+            error!("This plot has no data to write into {}", path);
+            return false;
+        }
+    };
+    match fs::write(path, table.to_csv(delimiter)) {
+        Ok(()) => true,
+        Err(error) => {
+            error!("Could not write {}: {}", path, error);
+            false
+        }
+    }
+}
+
+/// Writes the state of a histogram (returned by `state`) as JSON into the
+/// file requested in the command line, if any.  Returns false on errors.
+fn write_state(state: impl FnOnce() -> serde_json::Value, matches: &ArgMatches) -> bool {
+    let path = match matches.value_of("save-state") {
+        Some(path) => path,
+        None => return true,
+    };
+    match fs::write(path, state().to_string()) {
+        Ok(()) => true,
+        Err(error) => {
+            error!("Could not write {}: {}", path, error);
+            false
+        }
+    }
+}
+
+/// Where to resume the processing of a growing input file, as requested with
+/// the `state` flag.
+struct Resume {
+    /// The state file.
+    path: String,
+    /// The bytes of the input not processed yet (up to its last complete line).
+    window: Range<u64>,
+    /// The histogram saved by the previous run, if any.
+    previous: Option<serde_json::Value>,
+}
+
+/// Reads the state file requested in the command line, if any.  A missing
+/// state file means starting from scratch.
+fn load_resume(matches: &ArgMatches) -> Result<Option<Resume>, ()> {
+    let path = match matches.value_of("state") {
+        Some(path) => path.to_string(),
+        None => return Ok(None),
+    };
+    let input = matches.value_of("input").unwrap();
+    match fs::metadata(input) {
+        Ok(metadata) if metadata.is_file() => (),
+        _ => {
+            error!("Input {} must be a regular file for resuming it", input);
+            return Err(());
+        }
+    }
+    let state = match fs::read_to_string(&path) {
+        Ok(text) => match serde_json::from_str::<serde_json::Value>(&text) {
+            Ok(state) => Some(state),
+            Err(error) => {
+                error!("Could not read state {}: {}", path, error);
+                return Err(());
+            }
+        },
+        Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+        Err(error) => {
+            error!("Could not read state {}: {}", path, error);
+            return Err(());
+        }
+    };
+    let (mut start, mut previous) = match state {
+        Some(state) => {
+            if state["input"].as_str() != Some(input) {
+                error!(
+                    "State {} is for input {}, not {}",
+                    path, state["input"], input
+                );
+                return Err(());
+            }
+            (
+                state["offset"].as_u64().unwrap_or(0),
+                Some(state["histogram"].clone()),
+            )
+        }
+        None => (0, None),
+    };
+    let length = fs::metadata(input).map(|m| m.len()).unwrap_or(0);
+    if start > length {
+        warn!("Input {} shrank (rotated?), so starting over", input);
+        start = 0;
+        previous = None;
+    }
+    match read::input::complete_lines_end(input, start) {
+        Ok(end) => Ok(Some(Resume {
+            path,
+            window: start..end,
+            previous,
+        })),
+        Err(error) => {
+            error!("Could not read {}: {}", input, error);
+            Err(())
+        }
+    }
+}
+
+/// Builds the histogram of `vec` on top of the one saved in a previous run
+/// (if any), keeping its buckets if possible.
+fn resume_histogram(
+    resume: &Resume,
+    builder: &plot::HistogramBuilder,
+    vec: &mut [f64],
+    precision: Option<usize>,
+) -> Result<plot::Histogram, String> {
+    let previous = match &resume.previous {
+        Some(previous) => previous,
+        None => return Ok(builder.build(vec)),
+    };
+    let (mut histogram, _) =
+        plot::Histogram::from_states(std::slice::from_ref(previous), precision)?;
+    if histogram.update(vec) {
+        return Ok(histogram);
+    }
+    warn!("New values fall out of the saved buckets, so counts are approximate");
+    histogram.merge(&builder.build(vec));
+    Ok(histogram)
+}
+
+/// Writes the buckets of a histogram as a HdrHistogram interval log into the
+/// file requested in the command line, if any.  Returns false on errors.
+fn write_hdr(histogram: &plot::Histogram, matches: &ArgMatches) -> bool {
+    let path = match matches.value_of("hdr-output") {
+        Some(path) => path,
+        None => return true,
+    };
+    let divisor = match parse_hdr_divisor(matches) {
+        Ok(divisor) => divisor,
+        Err(()) => return false,
+    };
+    let result = fs::File::create(path)
+        .map_err(|error| error.to_string())
+        .and_then(|mut file| interop::hdr::write_interval_log(histogram, &mut file, divisor));
+    match result {
+        Ok(()) => true,
+        Err(error) => {
+            error!("Could not write {}: {}", path, error);
+            false
+        }
+    }
+}
+
+/// True if the regex in the command line has a capture group named `key`, as
+/// needed for grouping values
+fn has_key_group(matches: &ArgMatches) -> bool {
+    match matches.value_of("regex").map(Regex::new) {
+        Some(Ok(regex)) => regex.capture_names().any(|name| name == Some("key")),
+        _ => false,
+    }
+}
+
 /// True if vec has al least 'min' elements
 fn assert_data<T>(vec: &[T], min: usize) -> bool {
     if vec.len() < min {
@@ -24,6 +338,40 @@ fn assert_data<T>(vec: &[T], min: usize) -> bool {
     vec.len() >= min
 }
 
+/// Writes a plot to standard output, with the options (format, width,
+/// colors and characters) chosen in the command line.  Returns false on
+/// errors.
+fn print_plot(plot: &dyn plot::Render, options: &plot::RenderOptions) -> bool {
+    match plot.render(&mut io::stdout(), options) {
+        Ok(()) => true,
+        Err(err) => {
+            error!("{}", err);
+            false
+        }
+    }
+}
+
+/// Writes a plot that comes along with the main one (like the comparison with
+/// a baseline) to standard output or, when writing the data of the main plot,
+/// to standard error as plain text, so that standard output has just data.
+/// Returns false on errors.
+fn print_footer(plot: &dyn plot::Render, options: &plot::RenderOptions) -> bool {
+    if !options.format.is_data() {
+        return print_plot(plot, options);
+    }
+    let options = plot::RenderOptions {
+        format: plot::Format::Plain,
+        ..options.clone()
+    };
+    match plot.render(&mut io::stderr(), &options) {
+        Ok(()) => true,
+        Err(err) => {
+            error!("{}", err);
+            false
+        }
+    }
+}
+
 /// Sets up color choices and verbosity in the two libraries used for output:
 /// simplelog and yansi
 fn configure_output(option: &str, verbose: bool) {
@@ -65,175 +413,1823 @@ fn configure_output(option: &str, verbose: bool) {
     }
 }
 
-fn parse_duration(duration: &str) -> Result<Duration, humantime::DurationError> {
-    match humantime::parse_duration(duration) {
-        Ok(d) => Ok(Duration::milliseconds(d.as_millis() as i64)),
-        Err(error) => Err(error),
+fn parse_base_date(matches: &ArgMatches) -> Result<Option<NaiveDate>, ()> {
+    match matches.value_of("base-date") {
+        Some(string) => match NaiveDate::parse_from_str(string, "%Y-%m-%d") {
+            Ok(date) => Ok(Some(date)),
+            Err(err) => {
+                error!("Failed to parse base date {}: {}", string, err);
+                Err(())
+            }
+        },
+        None => Ok(None),
     }
 }
 
-/// Build a reader able to read floats (potentially capturing them with regex)
-/// from an input source.
-fn get_float_reader(matches: &ArgMatches) -> Result<read::DataReader, ()> {
-    let mut builder = read::DataReaderBuilder::default();
-    if matches.is_present("min") || matches.is_present("max") {
-        let min = matches.value_of_t("min").unwrap_or(f64::NEG_INFINITY);
-        let max = matches.value_of_t("max").unwrap_or(f64::INFINITY);
-        if min > max {
-            error!("Minimum should be smaller than maximum");
-            return Err(());
+/// Returns the threshold of symlog scale, if requested and valid.
+fn parse_symlog(matches: &ArgMatches) -> Result<Option<f64>, ()> {
+    if !matches.is_present("symlog") {
+        return Ok(None);
+    }
+    match matches.value_of_t::<f64>("symlog") {
+        Ok(threshold) if threshold > 0.0 => Ok(Some(threshold)),
+        _ => {
+            error!("Symlog threshold should be a positive number");
+            Err(())
         }
-        builder.range(min..max);
     }
-    if let Some(string) = matches.value_of("regex") {
-        match Regex::new(string) {
-            Ok(re) => {
-                builder.regex(re);
-            }
-            _ => {
-                error!("Failed to parse regex {}", string);
-                return Err(());
-            }
-        };
+}
+
+fn parse_rank_of(matches: &ArgMatches) -> Result<Option<f64>, ()> {
+    if !matches.is_present("rank-of") {
+        return Ok(None);
+    }
+    match matches.value_of_t::<f64>("rank-of") {
+        Ok(value) => Ok(Some(value)),
+        Err(_) => {
+            error!("Value for rank should be a number");
+            Err(())
+        }
     }
-    Ok(builder.build().unwrap())
 }
 
-/// Implements the hist cli-subcommand
-fn histogram(matches: &ArgMatches) -> i32 {
-    let reader = match get_float_reader(matches) {
-        Ok(r) => r,
-        _ => return 2,
+/// Parses the fraction of values to trim, given like "1%" or "0.01".
+fn parse_trim(matches: &ArgMatches) -> Result<Option<f64>, ()> {
+    let value = match matches.value_of("trim") {
+        Some(value) => value,
+        None => return Ok(None),
     };
-    let mut vec = reader.read(matches.value_of("input").unwrap());
-    if !assert_data(&vec, 1) {
-        return 1;
+    let fraction = match value.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map(|p| p / 100.0),
+        None => value.parse::<f64>(),
+    };
+    match fraction {
+        Ok(fraction) if (0.0..0.5).contains(&fraction) => Ok(Some(fraction)),
+        _ => {
+            error!("The fraction to trim should be a number between 0 and 50%");
+            Err(())
+        }
     }
-    let mut options = plot::HistogramOptions::default();
-    let precision_arg: i32 = matches.value_of_t("precision").unwrap();
-    if precision_arg > 0 {
-        options.precision = Some(precision_arg as usize);
+}
+
+/// Parses the tolerance for regressions since a baseline, given like "5%" or
+/// "0.05".
+fn parse_tolerance(matches: &ArgMatches) -> Result<f64, ()> {
+    let value = matches.value_of("tolerance").unwrap();
+    let fraction = match value.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map(|p| p / 100.0),
+        None => value.parse::<f64>(),
     };
-    options.log_scale = matches.is_present("log-scale");
-    options.intervals = matches.value_of_t("intervals").unwrap();
-    let width = matches.value_of_t("width").unwrap();
-    let histogram = plot::Histogram::new(&mut vec, options);
-    print!("{histogram:width$}");
-    0
+    match fraction {
+        Ok(fraction) if fraction >= 0.0 => Ok(fraction),
+        _ => {
+            error!("The tolerance should be non-negative (like 0, 5% or 0.05)");
+            Err(())
+        }
+    }
 }
 
-/// Implements the plot cli-subcommand
-fn plot(matches: &ArgMatches) -> i32 {
-    let reader = match get_float_reader(matches) {
-        Ok(r) => r,
-        _ => return 2,
+/// Compares `stats` with the baseline given with `--baseline`, if any,
+/// logging the statistics that regressed.
+fn compare_baseline(
+    stats: &stats::Stats,
+    matches: &ArgMatches,
+) -> Result<Option<stats::BaselineComparison>, ()> {
+    let path = match matches.value_of("baseline") {
+        Some(path) => path,
+        None => return Ok(None),
     };
-    let vec = reader.read(matches.value_of("input").unwrap());
-    if !assert_data(&vec, 1) {
-        return 1;
+    let tolerance = parse_tolerance(matches)?;
+    let baseline = fs::read_to_string(path)
+        .map_err(|error| error.to_string())
+        .and_then(|text| stats::Baseline::from_json(&text));
+    let comparison = match baseline {
+        Ok(baseline) => baseline.compare(stats, tolerance),
+        Err(error) => {
+            error!("Could not read baseline {}: {}", path, error);
+            return Err(());
+        }
+    };
+    let regressions: Vec<&str> = comparison
+        .regressions()
+        .iter()
+        .map(|delta| delta.field)
+        .collect();
+    if !regressions.is_empty() {
+        warn!("Regressions since the baseline: {}", regressions.join(", "));
     }
-    let precision_arg: i32 = matches.value_of_t("precision").unwrap();
-    let precision = if precision_arg < 0 {
-        None
-    } else {
-        Some(precision_arg as usize)
+    Ok(Some(comparison))
+}
+
+/// Displays the comparison of `stats` with the baseline given with
+/// `--baseline`, if any.  Returns false on errors.
+fn print_baseline(
+    stats: &stats::Stats,
+    matches: &ArgMatches,
+    options: &plot::RenderOptions,
+) -> bool {
+    match compare_baseline(stats, matches) {
+        Ok(Some(comparison)) => print_footer(&comparison, options),
+        Ok(None) => true,
+        Err(()) => false,
+    }
+}
+
+fn parse_hdr_divisor(matches: &ArgMatches) -> Result<f64, ()> {
+    match matches.value_of_t::<f64>("hdr-divisor") {
+        Ok(divisor) if divisor > 0.0 => Ok(divisor),
+        _ => {
+            error!("The HdrHistogram divisor should be a positive number");
+            Err(())
+        }
+    }
+}
+
+/// Parses the level of the confidence interval, given like "95%" or "0.95".
+fn parse_confidence(matches: &ArgMatches) -> Result<Option<f64>, ()> {
+    let value = match matches.value_of("confidence") {
+        Some(value) => value,
+        None => return Ok(None),
     };
-    let plot = plot::XyPlot::new(
-        &vec,
-        matches.value_of_t("width").unwrap(),
-        matches.value_of_t("height").unwrap(),
-        precision,
-    );
-    print!("{plot}");
-    0
+    let level = match value.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map(|p| p / 100.0),
+        None => value.parse::<f64>(),
+    };
+    match level {
+        Ok(level) if level > 0.0 && level < 1.0 => Ok(Some(level)),
+        _ => {
+            error!("The confidence level should be a number between 0 and 100%");
+            Err(())
+        }
+    }
 }
 
-/// Implements the matches cli-subcommand
-fn matchbar(matches: &ArgMatches) -> i32 {
-    let reader = read::DataReader::default();
-    let width = matches.value_of_t("width").unwrap();
-    print!(
-        "{:width$}",
-        reader.read_matches(
-            matches.value_of("input").unwrap(),
-            matches.values_of("match").unwrap().collect()
+fn parse_conditions(matches: &ArgMatches) -> Result<Conditions, ()> {
+    let parse = |id: &str| match matches.values_of(id) {
+        None => Ok(Vec::new()),
+        Some(values) => values
+            .map(|s| {
+                s.parse::<stats::Condition>().map_err(|err| {
+                    error!("{}", err);
+                })
+            })
+            .collect(),
+    };
+    Ok(Conditions {
+        fail_if: parse("fail-if")?,
+        asserts: parse("assert")?,
+    })
+}
+
+/// Conditions over the statistics of the input given in the command line,
+/// for CI pipelines.
+struct Conditions {
+    /// `--fail-if` conditions: failing if any holds.
+    fail_if: Vec<stats::Condition>,
+    /// `--assert` conditions: failing unless all hold.
+    asserts: Vec<stats::Condition>,
+}
+
+/// Returns the exit code for the `--fail-if` and `--assert` conditions,
+/// logging the ones failing for `stats` and writing the report of the
+/// assertions, if requested.
+fn check_conditions(conditions: &Conditions, stats: &stats::Stats, matches: &ArgMatches) -> i32 {
+    let mut code = 0;
+    for condition in &conditions.fail_if {
+        match condition.holds(stats) {
+            Some(false) => (),
+            Some(true) => {
+                error!(
+                    "Condition '{}' holds (value: {})",
+                    condition,
+                    condition.field_value(stats).unwrap()
+                );
+                code = FAILED_CONDITION;
+            }
+            None => {
+                error!("Condition '{}' uses statistics not computed", condition);
+                return 2;
+            }
+        }
+    }
+    let outcomes: Vec<interop::report::Outcome> = conditions
+        .asserts
+        .iter()
+        .map(|condition| assertion_outcome(condition, stats))
+        .collect();
+    for outcome in outcomes.iter().filter(|outcome| !outcome.passed) {
+        error!("Assertion '{}' failed: {}", outcome.name, outcome.message);
+        code = FAILED_CONDITION;
+    }
+    if let Some(format) = matches.value_of("assert-report") {
+        let format = format.parse().unwrap();
+        let suite = match matches.value_of("input") {
+            Some("-") | None => "stdin",
+            Some(path) => path,
+        };
+        let result = match matches.value_of("assert-report-file") {
+            Some(path) => fs::File::create(path).and_then(|mut file| {
+                interop::report::write_report(&outcomes, format, suite, &mut file)
+            }),
+            None => interop::report::write_report(&outcomes, format, suite, &mut io::stdout()),
+        };
+        if let Err(error) = result {
+            error!("Could not write the report of assertions: {}", error);
+            return 2;
+        }
+    }
+    code
+}
+
+/// Checks an `--assert` condition over `stats`.
+fn assertion_outcome(
+    condition: &stats::Condition,
+    stats: &stats::Stats,
+) -> interop::report::Outcome {
+    let (passed, message) = match (
+        condition.holds(stats),
+        condition.field_value(stats),
+        condition.threshold(stats),
+    ) {
+        (Some(passed), Some(value), Some(threshold)) => (
+            passed,
+            format!("value is {value}, compared with {threshold}"),
         ),
-        width = width
-    );
-    0
+        _ => (
+            false,
+            String::from("it uses statistics not computed (see --outliers and --trim)"),
+        ),
+    };
+    interop::report::Outcome {
+        name: condition.to_string(),
+        passed,
+        message,
+    }
 }
 
-/// Implements the common-terms cli-subcommand
-fn common_terms(matches: &ArgMatches) -> i32 {
-    let mut builder = read::DataReaderBuilder::default();
-    if let Some(string) = matches.value_of("regex") {
+fn parse_duration(duration: &str) -> Result<Duration, humantime::DurationError> {
+    match humantime::parse_duration(duration) {
+        Ok(d) => Ok(Duration::milliseconds(d.as_millis() as i64)),
+        Err(error) => Err(error),
+    }
+}
+
+/// Build the options for splitting input into records, shared by all readers.
+fn get_input_options(matches: &ArgMatches) -> Result<read::InputOptions, ()> {
+    let mut builder = read::InputOptionsBuilder::default();
+    if let Some(duration) = matches.value_of("duration") {
+        match humantime::parse_duration(duration) {
+            Ok(d) => {
+                builder.listen(d);
+            }
+            Err(err) => {
+                error!("Failed to parse duration {}: {}", duration, err);
+                return Err(());
+            }
+        };
+    }
+    if let Some(string) = matches.value_of("multiline-join") {
         match Regex::new(string) {
             Ok(re) => {
-                builder.regex(re);
+                builder.multiline_join(re);
             }
             _ => {
                 error!("Failed to parse regex {}", string);
-                return 1;
+                return Err(());
             }
         };
-    } else {
-        builder.regex(Regex::new("(.*)").unwrap());
-    };
-    let reader = builder.build().unwrap();
-    let width = matches.value_of_t("width").unwrap();
-    let lines = matches.value_of_t("lines").unwrap();
-    if lines < 1 {
-        error!("You should specify a potitive number of lines");
-        return 2;
-    };
-    print!(
-        "{:width$}",
-        reader.read_terms(matches.value_of("input").unwrap(), lines),
-        width = width
-    );
-    0
-}
-
-/// Implements the timehist cli-subcommand
-fn timehist(matches: &ArgMatches) -> i32 {
-    let mut builder = read::TimeReaderBuilder::default();
-    if let Some(string) = matches.value_of("regex") {
+    }
+    if let Some(string) = matches.value_of("multiline") {
         match Regex::new(string) {
             Ok(re) => {
-                builder.regex(re);
+                builder.multiline_start(re);
             }
             _ => {
                 error!("Failed to parse regex {}", string);
-                return 2;
+                return Err(());
             }
         };
     }
-    if let Some(as_str) = matches.value_of("format") {
-        builder.ts_format(as_str.to_string());
+    builder.encoding(matches.value_of_t("encoding").unwrap());
+    for name in ["skip", "head", "tail"] {
+        let lines = match matches.value_of(name).map(|n| n.parse::<usize>()) {
+            Some(Ok(lines)) => lines,
+            Some(Err(_)) => {
+                error!(
+                    "The number of lines of --{} should be a non-negative integer",
+                    name
+                );
+                return Err(());
+            }
+            None => continue,
+        };
+        match name {
+            "skip" => builder.skip(lines),
+            "head" => builder.head(lines),
+            _ => builder.tail(lines),
+        };
     }
-    builder.early_stop(matches.is_present("early-stop"));
-    if let Some(duration) = matches.value_of("duration") {
-        match parse_duration(duration) {
-            Ok(d) => builder.duration(d),
-            Err(err) => {
-                error!("Failed to parse duration {}: {}", duration, err);
-                return 2;
+    if matches.is_present("every") {
+        match matches.value_of_t::<usize>("every") {
+            Ok(n) if n > 0 => builder.every(n),
+            _ => {
+                error!("The value of --every should be a positive integer");
+                return Err(());
             }
         };
-    };
-    let width = matches.value_of_t("width").unwrap();
-    let reader = builder.build().unwrap();
+    }
+    if matches.is_present("sample-rate") {
+        match matches.value_of_t::<f64>("sample-rate") {
+            Ok(rate) if rate > 0.0 && rate <= 1.0 => builder.sample_rate(rate),
+            _ => {
+                error!("The sample rate should be a number greater than 0 and up to 1");
+                return Err(());
+            }
+        };
+    }
+    if let Some(string) = matches.value_of("delimiter") {
+        match string.parse::<read::Delimiter>() {
+            Ok(delimiter) => {
+                builder.delimiter(delimiter);
+            }
+            Err(error) => {
+                error!("Bad delimiter {}: {}", string, error);
+                return Err(());
+            }
+        };
+    }
+    Ok(builder.build().unwrap())
+}
+
+/// Build a reader able to read floats (potentially capturing them with regex)
+/// from an input source.
+fn get_float_reader(matches: &ArgMatches) -> Result<read::DataReader, ()> {
+    let mut builder = read::DataReaderBuilder::default();
+    builder.input(get_input_options(matches)?);
+    if matches.is_present("min") || matches.is_present("max") {
+        let min = matches.value_of_t("min").unwrap_or(f64::NEG_INFINITY);
+        let max = matches.value_of_t("max").unwrap_or(f64::INFINITY);
+        if min > max {
+            error!("Minimum should be smaller than maximum");
+            return Err(());
+        }
+        builder.range(min..max);
+    }
+    if let Some(string) = matches.value_of("regex") {
+        match Regex::new(string) {
+            Ok(re) => {
+                builder.regex(re);
+            }
+            _ => {
+                error!("Failed to parse regex {}", string);
+                return Err(());
+            }
+        };
+    }
+    if matches.is_valid_arg("sample") && matches.is_present("sample") {
+        match matches.value_of_t::<usize>("sample") {
+            Ok(n) if n > 0 => builder.sample(n),
+            _ => {
+                error!("The sample size should be a positive integer");
+                return Err(());
+            }
+        };
+    }
+    if matches.is_valid_arg("parallel") {
+        builder.parallel(matches.is_present("parallel"));
+    }
+    // Groupstats does not support other ways of extracting values
+    if matches.is_valid_arg("preset") {
+        set_value_extraction(&mut builder, matches)?;
+    }
+    Ok(builder.build().unwrap())
+}
+
+/// Sets up the alternatives to plain regex for extracting values from input:
+/// presets, logfmt, JSON and CSV.
+fn set_value_extraction(
+    builder: &mut read::DataReaderBuilder,
+    matches: &ArgMatches,
+) -> Result<(), ()> {
+    if let Some(capture) = matches.value_of("capture") {
+        builder.capture(capture.to_string());
+    }
+    builder.all_matches(matches.is_present("all-matches"));
+    if let Some(name) = matches.value_of("preset") {
+        let preset = match read::Preset::find(name) {
+            Some(preset) => preset,
+            None => {
+                error!("Unknown preset {}", name);
+                return Err(());
+            }
+        };
+        if let Err(error) = preset.configure_data(builder, matches.value_of("capture")) {
+            error!("{}", error);
+            return Err(());
+        }
+    }
+    if let Some(key) = matches.value_of("logfmt") {
+        builder.logfmt(key.to_string());
+    }
+    if let Some(path) = matches.value_of("json-field") {
+        builder.json_field(path.to_string());
+    }
+    if matches.is_present("human-input") {
+        builder.human(matches.value_of_t("human-input").unwrap());
+    }
+    if matches.is_present("duration-input") {
+        builder.durations(matches.value_of_t("duration-input").unwrap());
+    }
+    if matches.is_present("csv") {
+        match matches
+            .value_of("column")
+            .unwrap_or("1")
+            .parse::<read::CsvColumn>()
+        {
+            Ok(column) => {
+                builder.csv_column(column);
+            }
+            Err(error) => {
+                error!("{}", error);
+                return Err(());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads input floats, keeping track of where the extreme values are if
+/// requested by `locate` or `show-extremes` flags.
+fn read_floats(
+    reader: &read::DataReader,
+    matches: &ArgMatches,
+    precision: Option<usize>,
+) -> Result<(Vec<f64>, Option<stats::Extremes>), ()> {
+    let input = matches.value_of("input").unwrap();
+    let (mut top, mut bottom) = (0, 0);
+    if matches.is_present("locate") {
+        top = LOCATED_VALUES;
+        bottom = 1;
+    }
+    if matches.is_present("show-extremes") {
+        match matches.value_of_t::<usize>("show-extremes") {
+            Ok(n) if n > 0 => {
+                top = top.max(n);
+                bottom = n;
+            }
+            _ => {
+                error!("The number of extreme values should be a positive integer");
+                return Err(());
+            }
+        }
+    }
+    if top > 0 {
+        let mut extremes = stats::Extremes::new(top, bottom, precision);
+        let vec = reader.read_locating(input, &mut extremes);
+        Ok((vec, Some(extremes)))
+    } else {
+        Ok((reader.read(input), None))
+    }
+}
+
+/// Applies to input values the transformation requested by `transform` flag,
+/// if any.
+fn transform_values(matches: &ArgMatches, vec: Vec<f64>) -> Vec<f64> {
+    let transform: stats::Transform = match matches.value_of_t("transform") {
+        Ok(transform) => transform,
+        Err(_) => return vec,
+    };
+    let transformed = transform.apply(&vec);
+    if transformed.len() < vec.len() {
+        warn!(
+            "Discarded {} values out of the domain of {}",
+            vec.len() - transformed.len(),
+            transform
+        );
+    }
+    transformed
+}
+
+/// Reads a series of values per named capture group of the regex, applying
+/// the transformation requested by `transform` flag, if any.  Series with no
+/// values are discarded.  Returns None if there is no data at all.
+fn read_series(reader: &read::DataReader, matches: &ArgMatches) -> Option<Vec<(String, Vec<f64>)>> {
+    let mut series = Vec::new();
+    for (name, values) in reader.read_series(matches.value_of("input").unwrap()) {
+        let values = transform_values(matches, values);
+        match values.is_empty() {
+            true => warn!("No values found for {}", name),
+            false => series.push((name, values)),
+        }
+    }
+    match assert_data(&series, 1) {
+        true => Some(series),
+        false => None,
+    }
+}
+
+/// Prints a label noting the sampling of the input, if some values were
+/// discarded by it (unless writing the data of the plot in `format`).
+fn print_sampling(reader: &read::DataReader, kept: usize, format: plot::Format) {
+    print_line_sampling(reader.input(), format);
+    let seen = reader.values_seen();
+    if kept < seen && !format.is_data() {
+        println!(
+            "Sampled {} of {} values ({:.2}%)",
+            kept,
+            seen,
+            kept as f64 * 100.0 / seen as f64
+        );
+    }
+}
+
+/// Prints a label noting the sampling of the lines of the input, if requested
+/// (unless writing the data of the plot in `format`).
+fn print_line_sampling(input: &read::InputOptions, format: plot::Format) {
+    if format.is_data() {
+        return;
+    }
+    if let Some((kept, read)) = input.sampled_lines() {
+        println!(
+            "Sampled {} of {} lines ({:.2}%)",
+            kept,
+            read,
+            kept as f64 * 100.0 / read.max(1) as f64
+        );
+    }
+}
+
+/// Prints a label noting the transformation requested by `transform` flag, if
+/// any (unless writing the data of the plot in `format`).
+fn print_transform(matches: &ArgMatches, format: plot::Format) {
+    if format.is_data() {
+        return;
+    }
+    if let Ok(transform) = matches.value_of_t::<stats::Transform>("transform") {
+        println!("Transformed values: {transform}");
+    }
+}
+
+/// Implements the hist cli-subcommand
+fn histogram(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let mut reader = match get_float_reader(matches) {
+        Ok(r) => r,
+        _ => return 2,
+    };
+    let resume = match load_resume(matches) {
+        Ok(resume) => resume,
+        Err(()) => return 2,
+    };
+    if let Some(resume) = &resume {
+        reader.set_window(resume.window.clone());
+    }
+    let conditions = match parse_conditions(matches) {
+        Ok(conditions) => conditions,
+        Err(()) => return 2,
+    };
+    let (mut builder, precision) = match get_histogram_builder(matches) {
+        Ok(result) => result,
+        Err(()) => return 2,
+    };
+    if matches.is_present("series") {
+        let series = match read_series(&reader, matches) {
+            Some(series) => series,
+            None => return 1,
+        };
+        if !print_plot(&builder.build_series(&series), options) {
+            return 2;
+        }
+        return 0;
+    }
+    if let Some(string) = matches.value_of("group-by") {
+        let group_by = match Regex::new(string) {
+            Ok(re) => re,
+            _ => {
+                error!("Failed to parse regex {}", string);
+                return 2;
+            }
+        };
+        let mut groups = Vec::new();
+        for (key, values) in reader.read_grouped_by(matches.value_of("input").unwrap(), &group_by) {
+            let values = transform_values(matches, values);
+            if !values.is_empty() {
+                groups.push((key, values));
+            }
+        }
+        if !assert_data(&groups, 1) {
+            return 1;
+        }
+        let mut histograms = builder.build_series(&groups);
+        histograms.set_small_multiples(true);
+        if !print_plot(&histograms, options) {
+            return 2;
+        }
+        return 0;
+    }
+    if matches.is_present("pre-aggregated") || matches.is_present("hdr") {
+        let input = matches.value_of("input").unwrap();
+        let pairs = match matches.is_present("hdr") {
+            true => parse_hdr_divisor(matches).and_then(|divisor| {
+                reader
+                    .read_hdr(input, divisor)
+                    .map_err(|error| error!("{}", error))
+            }),
+            false => Ok(reader.read_weighted(input)),
+        };
+        let mut pairs = match pairs {
+            Ok(pairs) => pairs,
+            Err(()) => return 2,
+        };
+        if !assert_data(&pairs, 1) {
+            return 1;
+        }
+        let histogram = builder.build_weighted(&mut pairs);
+        return print_histogram(&histogram, None, &conditions, matches, options);
+    }
+    let (vec, extremes) = match read_floats(&reader, matches, precision) {
+        Ok(result) => result,
+        Err(()) => return 2,
+    };
+    let sampled = vec.len();
+    let mut vec = transform_values(matches, vec);
+    let resumed = matches!(
+        &resume,
+        Some(Resume {
+            previous: Some(_),
+            ..
+        })
+    );
+    if !resumed && !assert_data(&vec, 1) {
+        return 1;
+    }
+    if let Some(path) = matches.value_of("overlay") {
+        let mut other = transform_values(matches, reader.read(path));
+        if !assert_data(&other, 1) {
+            return 1;
+        }
+        let (first, second) = builder.build_pair(&mut vec, &mut other);
+        let mut overlay: plot::HistogramOverlay = first.overlay(&second);
+        overlay.set_labels(matches.value_of("input").unwrap(), path);
+        print_transform(matches, options.format);
+        if !print_plot(&overlay, options) {
+            return 2;
+        }
+        return 0;
+    }
+    match parse_rank_of(matches) {
+        Ok(Some(value)) => builder.rank_of(value),
+        Ok(None) => &mut builder,
+        Err(()) => return 2,
+    };
+    if matches.is_present("outliers") {
+        builder.outliers(matches.value_of_t("outliers").unwrap());
+    }
+    match parse_trim(matches) {
+        Ok(Some(fraction)) => builder.trim(fraction),
+        Ok(None) => &mut builder,
+        Err(()) => return 2,
+    };
+    let histogram = match &resume {
+        Some(resume) => match resume_histogram(resume, &builder, &mut vec, precision) {
+            Ok(histogram) => histogram,
+            Err(error) => {
+                error!("Could not resume from state {}: {}", resume.path, error);
+                return 2;
+            }
+        },
+        None => builder.build(&mut vec),
+    };
+    if let Some(resume) = &resume {
+        let state = serde_json::json!({
+            "input": matches.value_of("input").unwrap(),
+            "offset": resume.window.end,
+            "histogram": histogram.to_state(),
+        });
+        if let Err(error) = fs::write(&resume.path, state.to_string()) {
+            error!("Could not write {}: {}", resume.path, error);
+            return 2;
+        }
+    }
+    print_sampling(&reader, sampled, options.format);
+    print_transform(matches, options.format);
+    print_histogram(&histogram, extremes, &conditions, matches, options)
+}
+
+/// Returns a builder of histograms with the buckets and statistics requested
+/// in the command line, along with the precision requested (if any).
+fn get_histogram_builder(
+    matches: &ArgMatches,
+) -> Result<(plot::HistogramBuilder, Option<usize>), ()> {
+    let mut builder = plot::HistogramBuilder::default();
+    let precision_arg: i32 = matches.value_of_t("precision").unwrap();
+    let precision = if precision_arg > 0 {
+        builder.precision(precision_arg as usize);
+        Some(precision_arg as usize)
+    } else {
+        None
+    };
+    builder.log_scale(matches.is_present("log-scale"));
+    builder.normalize(matches.is_present("normalize"));
+    if let Some(threshold) = parse_symlog(matches)? {
+        builder.symlog(threshold);
+    }
+    builder.intervals(matches.value_of_t("intervals").unwrap());
+    if let Some(level) = parse_confidence(matches)? {
+        builder.confidence(level);
+    }
+    Ok((builder, precision))
+}
+
+/// Displays a histogram (and the extreme values, if located) as requested in
+/// the command line, returning the exit code.
+fn print_histogram(
+    histogram: &plot::Histogram,
+    extremes: Option<stats::Extremes>,
+    conditions: &Conditions,
+    matches: &ArgMatches,
+    options: &plot::RenderOptions,
+) -> i32 {
+    let printed = match get_graphics(matches, options.format) {
+        plot::Graphics::Off => print_plot(histogram, options),
+        graphics => print_image(
+            histogram,
+            histogram.stats(),
+            graphics,
+            (
+                options.width * PIXELS_PER_COLUMN,
+                HISTOGRAM_IMAGE_ROWS * PIXELS_PER_ROW,
+            ),
+            options,
+        ),
+    };
+    if !printed {
+        return 2;
+    }
+    if let Some(extremes) = extremes {
+        if !print_footer(&extremes, options) {
+            return 2;
+        }
+    }
+    if !print_baseline(histogram.stats(), matches, options)
+        || !write_svg(histogram, matches, options.theme)
+        || !write_data(histogram, matches)
+        || !write_hdr(histogram, matches)
+        || !write_state(|| histogram.to_state(), matches)
+    {
+        return 2;
+    }
+    check_conditions(conditions, histogram.stats(), matches)
+}
+
+/// Implements the describe cli-subcommand
+fn describe(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let reader = match get_float_reader(matches) {
+        Ok(r) => r,
+        _ => return 2,
+    };
+    let mut histogram_options = plot::HistogramOptions {
+        intervals: 0,
+        ..Default::default()
+    };
+    if matches.is_present("intervals") {
+        histogram_options.intervals = matches.value_of_t("intervals").unwrap();
+    }
+    let precision_arg: i32 = matches.value_of_t("precision").unwrap();
+    if precision_arg > 0 {
+        histogram_options.precision = Some(precision_arg as usize);
+    }
+    let vec = reader.read(matches.value_of("input").unwrap());
+    let sampled = vec.len();
+    let mut vec = transform_values(matches, vec);
+    if !assert_data(&vec, 1) {
+        return 1;
+    }
+    let description = plot::Description::new(&mut vec, histogram_options);
+    print_sampling(&reader, sampled, options.format);
+    print_transform(matches, options.format);
+    if !print_plot(&description, options) {
+        return 2;
+    }
+    0
+}
+
+/// Implements the summary cli-subcommand
+fn summary(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let reader = match get_float_reader(matches) {
+        Ok(r) => r,
+        _ => return 2,
+    };
+    let conditions = match parse_conditions(matches) {
+        Ok(conditions) => conditions,
+        Err(()) => return 2,
+    };
+    let precision_arg: i32 = matches.value_of_t("precision").unwrap();
+    let precision = if precision_arg > 0 {
+        Some(precision_arg as usize)
+    } else {
+        None
+    };
+    let vec = reader.read(matches.value_of("input").unwrap());
+    let sampled = vec.len();
+    let mut vec = transform_values(matches, vec);
+    if !assert_data(&vec, 1) {
+        return 1;
+    }
+    let mut stats = stats::Stats::new(&mut vec, precision);
+    match parse_rank_of(matches) {
+        Ok(Some(value)) => stats.set_rank_of(&vec, value),
+        Ok(None) => (),
+        Err(()) => return 2,
+    };
+    match parse_confidence(matches) {
+        Ok(Some(level)) => stats.set_confidence(level),
+        Ok(None) => (),
+        Err(()) => return 2,
+    };
+    match parse_trim(matches) {
+        Ok(Some(fraction)) => stats.set_trim(&vec, fraction),
+        Ok(None) => (),
+        Err(()) => return 2,
+    };
+    if matches.is_present("outliers") {
+        stats.set_outliers(&vec, matches.value_of_t("outliers").unwrap());
+    }
+    let comparison = match compare_baseline(&stats, matches) {
+        Ok(comparison) => comparison,
+        Err(()) => return 2,
+    };
+    // The comparison with the baseline is part of the JSON object (unlike in
+    // the table of statistics of CSV)
+    if options.format == plot::Format::Json {
+        let mut value = stats.to_json();
+        if let Some(comparison) = comparison {
+            value["baseline"] = comparison.to_json();
+        }
+        println!("{value}");
+    } else {
+        print_sampling(&reader, sampled, options.format);
+        print_transform(matches, options.format);
+        if !print_plot(&stats, options) {
+            return 2;
+        }
+        if let Some(comparison) = comparison {
+            if !print_footer(&comparison, options) {
+                return 2;
+            }
+        }
+    }
+    check_conditions(&conditions, &stats, matches)
+}
+
+/// Implements the merge cli-subcommand
+fn merge(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let mut states = Vec::new();
+    for path in matches.values_of("input").unwrap() {
+        let state = fs::read_to_string(path)
+            .map_err(|error| error.to_string())
+            .and_then(|text| {
+                serde_json::from_str::<serde_json::Value>(&text).map_err(|error| error.to_string())
+            });
+        match state {
+            Ok(state) => states.push(state),
+            Err(error) => {
+                error!("Could not read state {}: {}", path, error);
+                return 2;
+            }
+        }
+    }
+    let precision_arg: i32 = matches.value_of_t("precision").unwrap();
+    let precision = if precision_arg > 0 {
+        Some(precision_arg as usize)
+    } else {
+        None
+    };
+    // Whether the plot was printed, and whether the merge was exact
+    let merged = match states[0]["kind"].as_str() {
+        Some("histogram") => plot::Histogram::from_states(&states, precision)
+            .map(|(histogram, exact)| (print_plot(&histogram, options), exact)),
+        Some("timehist") => plot::TimeHistogram::from_states(&states)
+            .map(|(timehist, exact)| (print_plot(&timehist, options), exact)),
+        _ => Err(String::from("Unknown kind of state")),
+    };
+    match merged {
+        Ok((false, _)) => 2,
+        Ok((true, true)) => 0,
+        Ok((true, false)) => {
+            warn!("Buckets differ between inputs, so merged counts are approximate");
+            0
+        }
+        Err(error) => {
+            error!("Could not merge states: {}", error);
+            2
+        }
+    }
+}
+
+/// Implements the compare cli-subcommand
+fn compare(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let reader = match get_float_reader(matches) {
+        Ok(r) => r,
+        _ => return 2,
+    };
+    let before = reader.read(matches.value_of("before").unwrap());
+    let after = reader.read(matches.value_of("after").unwrap());
+    let mut before = transform_values(matches, before);
+    let mut after = transform_values(matches, after);
+    if !assert_data(&before, 1) || !assert_data(&after, 1) {
+        return 1;
+    }
+    let mut histogram_options = plot::HistogramOptions::default();
+    let precision_arg: i32 = matches.value_of_t("precision").unwrap();
+    if precision_arg > 0 {
+        histogram_options.precision = Some(precision_arg as usize);
+    };
+    histogram_options.log_scale = matches.is_present("log-scale");
+    histogram_options.normalize = matches.is_present("normalize");
+    histogram_options.symlog = match parse_symlog(matches) {
+        Ok(symlog) => symlog,
+        Err(()) => return 2,
+    };
+    histogram_options.intervals = matches.value_of_t("intervals").unwrap();
+    let comparison = plot::HistogramComparison::new(&mut before, &mut after, histogram_options);
+    print_transform(matches, options.format);
+    if !print_plot(&comparison, options) {
+        return 2;
+    }
+    0
+}
+
+/// Implements the plot cli-subcommand
+fn plot(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let reader = match get_float_reader(matches) {
+        Ok(r) => r,
+        _ => return 2,
+    };
+    let conditions = match parse_conditions(matches) {
+        Ok(conditions) => conditions,
+        Err(()) => return 2,
+    };
+    let precision_arg: i32 = matches.value_of_t("precision").unwrap();
+    let precision = if precision_arg < 0 {
+        None
+    } else {
+        Some(precision_arg as usize)
+    };
+    let graphics = get_graphics(matches, options.format);
+    let (width, height) = (get_width(matches), get_height(matches));
+    let mut builder = plot::XyPlotBuilder::default();
+    builder.width(width).height(height);
+    if graphics != plot::Graphics::Off {
+        // One column per pixel
+        builder.width(width * PIXELS_PER_COLUMN);
+    }
+    if let Some(precision) = precision {
+        builder.precision(precision);
+    }
+    builder.downsampler(
+        matches
+            .value_of_t::<plot::Downsampler>("downsampler")
+            .unwrap(),
+    );
+    if matches.is_present("series") {
+        let series = match read_series(&reader, matches) {
+            Some(series) => series,
+            None => return 1,
+        };
+        if !print_plot(&builder.build_series(&series), options) {
+            return 2;
+        }
+        return 0;
+    }
+    match parse_symlog(matches) {
+        Ok(Some(threshold)) => builder.symlog(threshold),
+        Ok(None) => &mut builder,
+        Err(()) => return 2,
+    };
+    match parse_confidence(matches) {
+        Ok(Some(level)) => builder.confidence(level),
+        Ok(None) => &mut builder,
+        Err(()) => return 2,
+    };
+    if matches.is_present("stream") {
+        let mut stream: plot::XyPlotStream = builder.build_stream();
+        reader.read_each(matches.value_of("input").unwrap(), |value| {
+            stream.add(value)
+        });
+        if stream.samples() == 0 {
+            warn!("Not enough data to process");
+            return 1;
+        }
+        return print_xy_plot(&stream.build(), None, &conditions, matches, options);
+    }
+    let (vec, extremes) = match read_floats(&reader, matches, precision) {
+        Ok(result) => result,
+        Err(()) => return 2,
+    };
+    let sampled = vec.len();
+    let vec = transform_values(matches, vec);
+    if !assert_data(&vec, 1) {
+        return 1;
+    }
+    match parse_rank_of(matches) {
+        Ok(Some(value)) => builder.rank_of(value),
+        Ok(None) => &mut builder,
+        Err(()) => return 2,
+    };
+    if matches.is_present("outliers") {
+        builder.outliers(matches.value_of_t("outliers").unwrap());
+    }
+    match parse_trim(matches) {
+        Ok(Some(fraction)) => builder.trim(fraction),
+        Ok(None) => &mut builder,
+        Err(()) => return 2,
+    };
+    let plot: plot::XyPlot = builder.build(&vec);
+    print_sampling(&reader, sampled, options.format);
+    print_transform(matches, options.format);
+    print_xy_plot(&plot, extremes, &conditions, matches, options)
+}
+
+/// Displays a x-y plot (and the extreme values, if located) as requested in
+/// the command line, returning the exit code.
+fn print_xy_plot(
+    plot: &plot::XyPlot,
+    extremes: Option<stats::Extremes>,
+    conditions: &Conditions,
+    matches: &ArgMatches,
+    options: &plot::RenderOptions,
+) -> i32 {
+    let graphics = get_graphics(matches, options.format);
+    let (width, height) = (options.width, get_height(matches));
+    let printed = match graphics {
+        plot::Graphics::Off => print_plot(plot, options),
+        graphics => print_image(
+            plot,
+            plot.stats(),
+            graphics,
+            (width * PIXELS_PER_COLUMN, height * PIXELS_PER_ROW),
+            options,
+        ),
+    };
+    if !printed {
+        return 2;
+    }
+    if let Some(extremes) = extremes {
+        if !print_footer(&extremes, options) {
+            return 2;
+        }
+    }
+    if !print_baseline(plot.stats(), matches, options)
+        || !write_svg(plot, matches, options.theme)
+        || !write_data(plot, matches)
+    {
+        return 2;
+    }
+    check_conditions(conditions, plot.stats(), matches)
+}
+
+/// Reads the patterns in a file, one per line with no surrounding blanks,
+/// skipping empty lines and the ones starting with '#' (comments).
+fn read_patterns(path: &str) -> io::Result<Vec<String>> {
+    let text = fs::read_to_string(path)?;
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+/// Implements the matches cli-subcommand
+fn matchbar(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let (reader, rows) = match get_match_reader(matches) {
+        Ok(result) => result,
+        Err(()) => return 2,
+    };
+    let mut matchbar = reader.read_matches(matches.value_of("input").unwrap(), rows);
+    matchbar.set_sort(
+        matches.value_of_t::<plot::SortOrder>("sort").unwrap(),
+        matches.is_present("reverse"),
+    );
+    if !print_plot(&matchbar, options) {
+        return 2;
+    }
+    0
+}
+
+/// Returns the reader for the matches cli-subcommand, along with the rows for
+/// the patterns in the command line.
+fn get_match_reader(
+    matches: &ArgMatches,
+) -> Result<(read::DataReader, Vec<plot::MatchBarRow>), ()> {
+    let mut builder = read::DataReaderBuilder::default();
+    builder.input(get_input_options(matches)?);
+    let mut patterns: Vec<String> = matches
+        .values_of("match")
+        .map(|values| values.map(String::from).collect())
+        .unwrap_or_default();
+    if let Some(path) = matches.value_of("patterns-file") {
+        match read_patterns(path) {
+            Ok(more) => patterns.extend(more),
+            Err(error) => {
+                error!("Could not read patterns from {}: {}", path, error);
+                return Err(());
+            }
+        }
+    }
+    if patterns.is_empty() {
+        error!("No patterns to match");
+        return Err(());
+    }
+    let mut rows = Vec::new();
+    for string in &patterns {
+        let row = if !matches.is_present("regex-match") {
+            plot::MatchBarRow::new(string)
+        } else {
+            match Regex::new(string) {
+                Ok(re) => plot::MatchBarRow::new_with_regex(re),
+                _ => {
+                    error!("Failed to parse regex {}", string);
+                    return Err(());
+                }
+            }
+        };
+        rows.push(match matches.is_present("word") {
+            true => row.whole_words(),
+            false => row,
+        });
+    }
+    builder.count_occurrences(matches.is_present("count-occurrences"));
+    builder.count_unmatched(matches.is_present("invert"));
+    Ok((builder.build().unwrap(), rows))
+}
+
+/// Implements the examples cli-subcommand
+fn examples() -> i32 {
+    let examples: Vec<String> = examples::EXAMPLES.iter().map(|e| e.to_string()).collect();
+    print!("{}", examples.join("\n"));
+    0
+}
+
+/// Implements the common-terms cli-subcommand
+fn common_terms(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let input = match get_input_options(matches) {
+        Ok(input) => input,
+        Err(()) => return 2,
+    };
+    let regex = match matches.value_of("regex") {
+        Some(string) => match Regex::new(string) {
+            Ok(re) => re,
+            _ => {
+                error!("Failed to parse regex {}", string);
+                return 1;
+            }
+        },
+        None => Regex::new("(.*)").unwrap(),
+    };
+    if matches.is_present("over-time") {
+        return common_terms_over_time(matches, input, regex, options);
+    }
+    let mut builder = read::DataReaderBuilder::default();
+    builder.input(input).regex(regex);
+    let reader = builder.build().unwrap();
+    let mut terms = match new_common_terms(matches) {
+        Ok(terms) => terms,
+        Err(()) => return 2,
+    };
+    reader.read_terms(matches.value_of("input").unwrap(), &mut terms);
+    if !print_plot(&terms, options) {
+        return 2;
+    }
+    0
+}
+
+/// Returns an empty `CommonTerms` with the options of the common-terms
+/// cli-subcommand.
+fn new_common_terms(matches: &ArgMatches) -> Result<plot::CommonTerms, ()> {
+    let lines = matches.value_of_t("lines").unwrap();
+    if lines < 1 {
+        error!("You should specify a potitive number of lines");
+        return Err(());
+    };
+    let mut terms = match matches.is_present("approx") {
+        true => plot::CommonTerms::new_approx(lines, lines * APPROX_COUNTERS_PER_LINE),
+        false => plot::CommonTerms::new(lines),
+    };
+    terms.set_totals(matches.is_present("totals"));
+    terms.set_sort(
+        matches.value_of_t::<plot::SortOrder>("sort").unwrap(),
+        matches.is_present("reverse"),
+    );
+    Ok(terms)
+}
+
+/// Plots the occurrences over time of the most common terms in the input, for
+/// the `--over-time` flag of the common-terms cli-subcommand.
+fn common_terms_over_time(
+    matches: &ArgMatches,
+    input: read::InputOptions,
+    regex: Regex,
+    options: &plot::RenderOptions,
+) -> i32 {
+    let count: usize = match matches.value_of_t("over-time") {
+        Ok(count) if count > 0 => count,
+        _ => {
+            error!("At least a term is needed for plotting over time");
+            return 2;
+        }
+    };
+    let mut builder = read::TimeReaderBuilder::default();
+    builder.input(input).regex(regex);
+    let terms = builder
+        .build()
+        .unwrap()
+        .read_terms(matches.value_of("input").unwrap());
+    plot_top_terms(matches, &terms, count, options)
+}
+
+/// Plots the occurrences over time of the `count` most common terms of a
+/// slice of pairs of timestamps and terms.
+fn plot_top_terms(
+    matches: &ArgMatches,
+    terms: &[(DateTime<FixedOffset>, String)],
+    count: usize,
+    options: &plot::RenderOptions,
+) -> i32 {
+    if !assert_data(terms, 2) {
+        return 1;
+    }
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (_, term) in terms {
+        *counts.entry(term).or_default() += 1;
+    }
+    let mut top: Vec<(&str, usize)> = counts.into_iter().collect();
+    top.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let strings: Vec<String> = top
+        .iter()
+        .take(count)
+        .map(|(term, _)| term.to_string())
+        .collect();
+    let vec: Vec<(DateTime<FixedOffset>, usize)> = terms
+        .iter()
+        .filter_map(|(ts, term)| strings.iter().position(|s| s == term).map(|i| (*ts, i)))
+        .collect();
+    let mut histogram =
+        plot::SplitTimeHistogram::new(matches.value_of_t("intervals").unwrap(), strings, &vec);
+    if matches.is_valid_arg("style") {
+        histogram.set_style(matches.value_of_t::<plot::SplitStyle>("style").unwrap());
+    }
+    if matches.is_valid_arg("sort-legend") {
+        histogram.set_sort_legend(matches.is_present("sort-legend"));
+    }
+    if matches.is_valid_arg("bucket-label") {
+        histogram.set_label(matches.value_of_t("bucket-label").unwrap());
+    }
+    if !print_plot(&histogram, options) {
+        return 2;
+    }
+    0
+}
+
+/// Implements the distinct cli-subcommand
+fn distinct(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let input = match get_input_options(matches) {
+        Ok(input) => input,
+        Err(()) => return 2,
+    };
+    let regex = match matches.value_of("regex") {
+        Some(string) => match Regex::new(string) {
+            Ok(re) => re,
+            _ => {
+                error!("Failed to parse regex {}", string);
+                return 1;
+            }
+        },
+        None => Regex::new("(.*)").unwrap(),
+    };
+    let path = matches.value_of("input").unwrap();
+    if !matches.is_present("over-time") {
+        let mut builder = read::DataReaderBuilder::default();
+        builder.input(input).regex(regex);
+        let mut hll = stats::HyperLogLog::default();
+        builder
+            .build()
+            .unwrap()
+            .read_terms_with(path, |term| hll.insert(term));
+        if !print_plot(&hll, options) {
+            return 2;
+        }
+        return 0;
+    }
+    let mut builder = read::TimeReaderBuilder::default();
+    builder.input(input).regex(regex);
+    if let Some(as_str) = matches.value_of("format") {
+        builder.ts_format(as_str.to_string());
+    }
+    let terms = builder.build().unwrap().read_terms(path);
+    if assert_data(&terms, 2) {
+        let histogram =
+            plot::DistinctTimeHistogram::new(matches.value_of_t("intervals").unwrap(), &terms);
+        if !print_plot(&histogram, options) {
+            return 2;
+        }
+    }
+    0
+}
+
+/// Implements the zipf cli-subcommand
+fn zipf(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let mut builder = read::DataReaderBuilder::default();
+    match get_input_options(matches) {
+        Ok(input) => builder.input(input),
+        Err(()) => return 2,
+    };
+    let regex = match matches.value_of("regex") {
+        Some(string) => match Regex::new(string) {
+            Ok(re) => re,
+            _ => {
+                error!("Failed to parse regex {}", string);
+                return 1;
+            }
+        },
+        None => Regex::new("(.*)").unwrap(),
+    };
+    builder.regex(regex);
+    let reader = builder.build().unwrap();
+    let mut terms = plot::CommonTerms::new(0);
+    reader.read_terms(matches.value_of("input").unwrap(), &mut terms);
+    let zipf = plot::ZipfPlot::new(&terms, matches.value_of_t("height").unwrap());
+    if !print_plot(&zipf, options) {
+        return 2;
+    }
+    0
+}
+
+/// Implements the groupstats cli-subcommand
+fn groupstats(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let reader = match get_float_reader(matches) {
+        Ok(r) => r,
+        _ => return 2,
+    };
+    if !has_key_group(matches) {
+        error!("A regex with a capture group named `key` is needed");
+        return 2;
+    }
+    let groups = reader.read_groups(matches.value_of("input").unwrap());
+    if !assert_data(&groups, 1) {
+        return 1;
+    }
+    let precision_arg: i32 = matches.value_of_t("precision").unwrap();
+    let precision = if precision_arg < 0 {
+        None
+    } else {
+        Some(precision_arg as usize)
+    };
+    if !print_plot(&plot::GroupStats::new(groups, precision), options) {
+        return 2;
+    }
+    0
+}
+
+/// Implements the top cli-subcommand
+fn top(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let reader = match get_float_reader(matches) {
+        Ok(r) => r,
+        _ => return 2,
+    };
+    if !has_key_group(matches) {
+        error!("A regex with a capture group named `key` is needed");
+        return 2;
+    }
+    let lines = matches.value_of_t("lines").unwrap();
+    if lines < 1 {
+        error!("You should specify a positive number of lines");
+        return 2;
+    };
+    let precision_arg: i32 = matches.value_of_t("precision").unwrap();
+    let precision = if precision_arg < 0 {
+        None
+    } else {
+        Some(precision_arg as usize)
+    };
+    let mut top = plot::TopKeys::new(
+        lines,
+        matches.value_of_t::<plot::Aggregation>("agg").unwrap(),
+        precision,
+    );
+    reader.read_groups_with(matches.value_of("input").unwrap(), |key, n| {
+        top.observe(&key, n)
+    });
+    if !print_plot(&top, options) {
+        return 2;
+    }
+    0
+}
+
+/// Builds a reader of timestamps with the options given in the command line.
+fn get_time_reader(matches: &ArgMatches) -> Result<read::TimeReader, ()> {
+    let mut builder = read::TimeReaderBuilder::default();
+    builder.input(get_input_options(matches)?);
+    if let Some(string) = matches.value_of("regex") {
+        match Regex::new(string) {
+            Ok(re) => {
+                if re.capture_names().any(|name| name == Some("ts")) {
+                    builder.ts_capture(String::from("ts"));
+                }
+                builder.regex(re);
+            }
+            _ => {
+                error!("Failed to parse regex {}", string);
+                return Err(());
+            }
+        };
+    }
+    if let Some(name) = matches.value_of("preset") {
+        match read::Preset::find(name) {
+            Some(preset) => preset.configure_time(&mut builder),
+            None => {
+                error!("Unknown preset {}", name);
+                return Err(());
+            }
+        }
+    }
+    if let Some(as_str) = matches.value_of("format") {
+        builder.ts_format(as_str.to_string());
+    }
+    if let Some(key) = matches.value_of("logfmt") {
+        builder.logfmt(key.to_string());
+    }
+    if let Some(path) = matches.value_of("json-field") {
+        builder.json_field(path.to_string());
+    }
+    if let Some(date) = parse_base_date(matches)? {
+        builder.base_date(date);
+    }
+    if matches.is_valid_arg("value-regex") {
+        if let Some(string) = matches.value_of("value-regex") {
+            match Regex::new(string) {
+                Ok(re) => builder.value_regex(re),
+                _ => {
+                    error!("Failed to parse regex {}", string);
+                    return Err(());
+                }
+            };
+        }
+    }
+    builder.strict_position(matches.is_present("strict-position"));
+    builder.early_stop(matches.is_present("early-stop"));
+    if let Some(duration) = matches.value_of("duration") {
+        match parse_duration(duration) {
+            Ok(d) => builder.duration(d),
+            Err(err) => {
+                error!("Failed to parse duration {}: {}", duration, err);
+                return Err(());
+            }
+        };
+    };
+    Ok(builder.build().unwrap())
+}
+
+/// Implements the timehist cli-subcommand
+fn timehist(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let reader = match get_time_reader(matches) {
+        Ok(r) => r,
+        _ => return 2,
+    };
+    let mut builder = match get_timehist_builder(matches) {
+        Ok(builder) => builder,
+        Err(()) => return 2,
+    };
+    if matches.is_present("value-regex") {
+        let points = reader.read_values(matches.value_of("input").unwrap());
+        if assert_data(&points, 2) {
+            let timehist = builder
+                .aggregation(matches.value_of_t::<plot::Aggregation>("agg").unwrap())
+                .with_counts(matches.is_present("with-counts"))
+                .build_values(&points);
+            print_line_sampling(reader.input(), options.format);
+            if !print_plot(&timehist, options) {
+                return 2;
+            }
+            if !write_svg(&timehist, matches, options.theme) || !write_data(&timehist, matches) {
+                return 2;
+            }
+        }
+        return 0;
+    }
     let vec = reader.read(matches.value_of("input").unwrap());
     if assert_data(&vec, 2) {
-        let timehist = plot::TimeHistogram::new(matches.value_of_t("intervals").unwrap(), &vec);
-        print!("{timehist:width$}");
+        let timehist: plot::TimeHistogram = builder.build(&vec);
+        print_line_sampling(reader.input(), options.format);
+        if !print_plot(&timehist, options) {
+            return 2;
+        }
+        if !write_svg(&timehist, matches, options.theme)
+            || !write_data(&timehist, matches)
+            || !write_state(|| timehist.to_state(), matches)
+        {
+            return 2;
+        }
     };
     0
 }
 
-/// Implements the timehist cli-subcommand
-fn splittime(matches: &ArgMatches) -> i32 {
+/// Returns a builder of time histograms with the options of the timehist
+/// cli-subcommand.
+fn get_timehist_builder(matches: &ArgMatches) -> Result<plot::TimeHistogramBuilder, ()> {
+    let mut builder = plot::TimeHistogramBuilder::default();
+    builder.intervals(matches.value_of_t("intervals").unwrap());
+    builder.label(matches.value_of_t("bucket-label").unwrap());
+    if matches.is_present("anomalies") {
+        match matches.value_of_t::<f64>("anomalies") {
+            Ok(sigmas) if sigmas > 0.0 => builder.anomalies(sigmas),
+            _ => {
+                error!("The number of sigmas for anomalies should be a positive number");
+                return Err(());
+            }
+        };
+    }
+    Ok(builder)
+}
+
+/// Reads the values captured by the regex in the input along with their
+/// timestamps, skipping those that are not numbers.
+fn read_timed_values(
+    reader: &read::TimeReader,
+    matches: &ArgMatches,
+) -> Vec<(DateTime<FixedOffset>, f64)> {
+    reader
+        .read_terms(matches.value_of("input").unwrap())
+        .into_iter()
+        .filter_map(|(ts, term)| match term.trim().parse::<f64>() {
+            Ok(value) => Some((ts, value)),
+            Err(_) => {
+                debug!("Failed to parse value '{}'", term);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Implements the timeplot cli-subcommand
+fn timeplot(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let reader = match get_time_reader(matches) {
+        Ok(r) => r,
+        _ => return 2,
+    };
+    let points = read_timed_values(&reader, matches);
+    if !assert_data(&points, 2) {
+        return 1;
+    }
+    let mut builder = plot::XyPlotBuilder::default();
+    builder
+        .width(get_width(matches))
+        .height(get_height(matches));
+    let precision_arg: i32 = matches.value_of_t("precision").unwrap();
+    if precision_arg >= 0 {
+        builder.precision(precision_arg as usize);
+    }
+    if !print_plot(&builder.build_timed(&points), options) {
+        return 2;
+    }
+    0
+}
+
+/// Implements the timepercentiles cli-subcommand
+fn timepercentiles(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let reader = match get_time_reader(matches) {
+        Ok(r) => r,
+        _ => return 2,
+    };
+    let points = read_timed_values(&reader, matches);
+    if !assert_data(&points, 2) {
+        return 1;
+    }
+    let precision_arg: i32 = matches.value_of_t("precision").unwrap();
+    let precision = match precision_arg < 0 {
+        true => None,
+        false => Some(precision_arg as usize),
+    };
+    let percentiles =
+        plot::TimePercentiles::new(matches.value_of_t("intervals").unwrap(), &points, precision);
+    if !print_plot(&percentiles, options) {
+        return 2;
+    }
+    0
+}
+
+/// Implements the heatmap cli-subcommand
+fn heatmap(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let reader = match get_time_reader(matches) {
+        Ok(r) => r,
+        _ => return 2,
+    };
+    let points = read_timed_values(&reader, matches);
+    if !assert_data(&points, 2) {
+        return 1;
+    }
+    let mut histogram_options = plot::HistogramOptions {
+        intervals: get_height(matches),
+        log_scale: matches.is_present("log-scale"),
+        ..Default::default()
+    };
+    match parse_symlog(matches) {
+        Ok(threshold) => histogram_options.symlog = threshold,
+        Err(()) => return 2,
+    };
+    let precision_arg: i32 = matches.value_of_t("precision").unwrap();
+    if precision_arg >= 0 {
+        histogram_options.precision = Some(precision_arg as usize);
+    }
+    let heatmap = plot::HeatMap::new(&points, options.width, histogram_options);
+    if !print_plot(&heatmap, options) {
+        return 2;
+    }
+    0
+}
+
+/// Implements the gaps cli-subcommand
+fn gaps(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let threshold = matches.value_of("threshold").unwrap();
+    let threshold = match parse_duration(threshold) {
+        Ok(d) => d,
+        Err(err) => {
+            error!("Failed to parse threshold {}: {}", threshold, err);
+            return 2;
+        }
+    };
+    let reader = match get_time_reader(matches) {
+        Ok(r) => r,
+        _ => return 2,
+    };
+    let vec = reader.read(matches.value_of("input").unwrap());
+    if assert_data(&vec, 2) && !print_plot(&plot::Gaps::new(&vec, threshold), options) {
+        return 2;
+    }
+    0
+}
+
+/// Implements the interarrival cli-subcommand
+fn interarrival(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let reader = match get_time_reader(matches) {
+        Ok(r) => r,
+        _ => return 2,
+    };
+    let mut vec = reader.read(matches.value_of("input").unwrap());
+    if !assert_data(&vec, 2) {
+        return 1;
+    }
+    vec.sort();
+    let mut deltas: Vec<f64> = vec
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).num_microseconds().unwrap() as f64 / 1e6)
+        .collect();
+    let mut builder = plot::HistogramBuilder::default();
+    let precision_arg: i32 = matches.value_of_t("precision").unwrap();
+    if precision_arg >= 0 {
+        builder.precision(precision_arg as usize);
+    }
+    builder.log_scale(matches.is_present("log-scale"));
+    match parse_symlog(matches) {
+        Ok(Some(threshold)) => builder.symlog(threshold),
+        Ok(None) => &mut builder,
+        Err(()) => return 2,
+    };
+    builder.intervals(matches.value_of_t("intervals").unwrap());
+    let histogram = builder.build(&mut deltas);
+    if !print_plot(&histogram, options) {
+        return 2;
+    }
+    // Deltas of events arriving at random (a Poisson process) are spread as
+    // much as their average
+    let stats = histogram.stats();
+    if stats.avg > 0.0 {
+        let cv = stats.std / stats.avg;
+        let verdict = match cv {
+            cv if cv > 1.2 => "in bursts",
+            cv if cv < 0.8 => "evenly",
+            _ => "at random",
+        };
+        let verdict = format!("Coefficient of variation = {cv:.2}; events are arriving {verdict}.");
+        match options.format.is_data() {
+            true => eprintln!("{verdict}"),
+            false => println!("{verdict}"),
+        }
+    }
+    0
+}
+
+/// Implements the sessions cli-subcommand
+fn sessions(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    let reader = match get_time_reader(matches) {
+        Ok(r) => r,
+        _ => return 2,
+    };
+    let terms = reader.read_terms(matches.value_of("input").unwrap());
+    if !assert_data(&terms, 1) {
+        return 1;
+    }
+    let sessions = plot::Sessions::new(&terms, matches.value_of_t("lines").unwrap());
+    if !print_plot(&sessions, options) {
+        return 2;
+    }
+    // The data of every session is all there is to write (durations included)
+    if options.format.is_data() {
+        return 0;
+    }
+    let mut builder = plot::HistogramBuilder::default();
+    let precision_arg: i32 = matches.value_of_t("precision").unwrap();
+    if precision_arg >= 0 {
+        builder.precision(precision_arg as usize);
+    }
+    builder.intervals(matches.value_of_t("intervals").unwrap());
+    if !print_plot(&builder.build(&mut sessions.durations()), options) {
+        return 2;
+    }
+    0
+}
+
+/// Implements the period cli-subcommand
+fn period(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    if matches.is_present("plot") && options.format.is_data() {
+        error!("The autocorrelation plot cannot be written as JSON or CSV");
+        return 2;
+    }
+    let reader = match get_time_reader(matches) {
+        Ok(r) => r,
+        _ => return 2,
+    };
+    let vec = reader.read(matches.value_of("input").unwrap());
+    if !assert_data(&vec, 2) {
+        return 0;
+    }
+    let period = plot::Periodicity::new(&vec, matches.value_of_t("intervals").unwrap());
+    if !print_plot(&period, options) {
+        return 2;
+    }
+    if matches.is_present("plot") && !period.acf().is_empty() {
+        let plot = plot::XyPlotBuilder::default()
+            .width(get_width(matches))
+            .height(get_height(matches))
+            .precision(2)
+            .build(period.acf());
+        if !print_plot(&plot, options) {
+            return 2;
+        }
+    }
+    0
+}
+
+/// Implements the split-timehist cli-subcommand when the groups are the values
+/// of a capture group of a regex, for the `--group-by-regex` flag.
+fn splittime_by_regex(matches: &ArgMatches, string: &str, options: &plot::RenderOptions) -> i32 {
+    let count: usize = match matches.value_of_t("top") {
+        Ok(count) if count > 0 => count,
+        _ => {
+            error!("At least a group is needed");
+            return 2;
+        }
+    };
+    let mut builder = read::TimeReaderBuilder::default();
+    match get_input_options(matches) {
+        Ok(input) => builder.input(input),
+        Err(()) => return 2,
+    };
+    match Regex::new(string) {
+        Ok(re) => builder.regex(re),
+        _ => {
+            error!("Failed to parse regex {}", string);
+            return 2;
+        }
+    };
+    if let Some(as_str) = matches.value_of("format") {
+        builder.ts_format(as_str.to_string());
+    }
+    match parse_base_date(matches) {
+        Ok(Some(date)) => {
+            builder.base_date(date);
+        }
+        Ok(None) => (),
+        Err(()) => return 2,
+    }
+    builder.strict_position(matches.is_present("strict-position"));
+    let terms = builder
+        .build()
+        .unwrap()
+        .read_terms(matches.value_of("input").unwrap());
+    plot_top_terms(matches, &terms, count, options)
+}
+
+/// Implements the split-timehist cli-subcommand
+fn splittime(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    if let Some(string) = matches.value_of("group-by-regex") {
+        return splittime_by_regex(matches, string, options);
+    }
     let mut builder = read::SplitTimeReaderBuilder::default();
+    match get_input_options(matches) {
+        Ok(input) => builder.input(input),
+        Err(()) => return 2,
+    };
     let string_list: Vec<String> = match matches.values_of("match") {
         Some(s) => s.map(|s| s.to_string()).collect(),
         None => {
@@ -241,43 +2237,306 @@ fn splittime(matches: &ArgMatches) -> i32 {
             return 2;
         }
     };
-    if string_list.len() > 5 {
-        error!("Only 5 different sub-groups are supported");
-        return 2;
-    }
     if let Some(as_str) = matches.value_of("format") {
         builder.ts_format(as_str.to_string());
     }
+    match parse_base_date(matches) {
+        Ok(Some(date)) => {
+            builder.base_date(date);
+        }
+        Ok(None) => (),
+        Err(()) => return 2,
+    }
+    builder.strict_position(matches.is_present("strict-position"));
     builder.matches(string_list.iter().map(|s| s.to_string()).collect());
-    let width = matches.value_of_t("width").unwrap();
     let reader = builder.build().unwrap();
     let vec = reader.read(matches.value_of("input").unwrap());
     if assert_data(&vec, 2) {
-        let timehist = plot::SplitTimeHistogram::new(
+        let mut timehist = plot::SplitTimeHistogram::new(
             matches.value_of_t("intervals").unwrap(),
             string_list,
             &vec,
         );
-        print!("{timehist:width$}");
+        timehist.set_style(matches.value_of_t::<plot::SplitStyle>("style").unwrap());
+        timehist.set_sort_legend(matches.is_present("sort-legend"));
+        timehist.set_label(matches.value_of_t("bucket-label").unwrap());
+        if !print_plot(&timehist, options) {
+            return 2;
+        }
+    };
+    0
+}
+
+/// Implements the dash cli-subcommand
+fn dash(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    if !matches!(options.format, plot::Format::Text | plot::Format::Plain) {
+        error!("Dashboards can only be written as text");
+        return 2;
+    }
+    let app = app::get_app();
+    let mut panels = Vec::new();
+    for spec in matches.values_of("panel").unwrap() {
+        let panel: dash::Panel = match spec.parse() {
+            Ok(panel) => panel,
+            Err(err) => {
+                error!("Invalid panel '{}': {}", spec, err);
+                return 2;
+            }
+        };
+        let cmd = match app.find_subcommand(panel.subcommand()) {
+            Some(cmd) => cmd,
+            None => {
+                error!("Unknown subcommand in panel '{}'", spec);
+                return 2;
+            }
+        };
+        let panel_matches = match cmd.clone().try_get_matches_from(&panel.args) {
+            Ok(panel_matches) => panel_matches,
+            Err(err) => {
+                error!("Invalid panel '{}': {}", spec, err);
+                return 2;
+            }
+        };
+        if let Err(err) = panel.check_options(cmd, &panel_matches) {
+            error!("Invalid panel '{}': {}", spec, err);
+            return 2;
+        }
+        match dash_feed(panel.subcommand(), &panel_matches) {
+            Ok(feed) => panels.push((panel, feed)),
+            Err(()) => return 2,
+        }
+    }
+    let refresh = matches.value_of("refresh").unwrap();
+    let refresh = match humantime::parse_duration(refresh) {
+        Ok(d) => d,
+        Err(err) => {
+            error!("Failed to parse refresh {}: {}", refresh, err);
+            return 2;
+        }
+    };
+    let mut window = match matches.value_of("window") {
+        Some(span) => match humantime::parse_duration(span) {
+            Ok(d) => Some(dash::Window::new(d)),
+            Err(err) => {
+                error!("Failed to parse window {}: {}", span, err);
+                return 2;
+            }
+        },
+        None => None,
     };
+    let input = match get_input_options(matches) {
+        Ok(input) => input,
+        Err(()) => return 2,
+    };
+    // Lines are read in another thread, so that the dashboard is refreshed
+    // while waiting for them
+    let (sender, receiver) = mpsc::sync_channel(DASH_QUEUED_LINES);
+    let path = matches.value_of("input").unwrap().to_string();
+    thread::spawn(move || {
+        for line in input.lines(&path) {
+            match line {
+                Ok(line) => {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+                Err(error) => {
+                    error!("{}", error);
+                    break;
+                }
+            }
+        }
+    });
+    // Only the final state is displayed if stdout is not a terminal
+    let interactive = atty::is(atty::Stream::Stdout);
+    let (mut lines, mut displayed) = (0, 0);
+    let mut deadline = Instant::now() + refresh;
+    loop {
+        let done = match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(line) => {
+                lines += 1;
+                match &mut window {
+                    Some(window) => window.push(line, Instant::now()),
+                    None => panels.iter_mut().for_each(|(_, feed)| feed.observe(&line)),
+                }
+                if Instant::now() < deadline {
+                    continue;
+                }
+                false
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => false,
+            Err(mpsc::RecvTimeoutError::Disconnected) => true,
+        };
+        // Lines out of the window expire even if there are no new ones
+        let changed = lines != displayed || window.is_some();
+        if lines > 0 && (done || (interactive && changed)) {
+            if let Some(window) = &mut window {
+                window.expire(Instant::now());
+                for (_, feed) in panels.iter_mut() {
+                    feed.reset();
+                    window.lines().for_each(|line| feed.observe(line));
+                }
+            }
+            print_dash(&mut panels, interactive, options);
+            displayed = lines;
+        }
+        if done {
+            break;
+        }
+        deadline = Instant::now() + refresh;
+    }
+    if displayed == 0 {
+        warn!("Not enough data to process");
+        return 1;
+    }
     0
 }
 
+/// Returns the plot of a dashboard panel of the `subcommand`, with the options
+/// in `matches`.
+fn dash_feed(subcommand: &str, matches: &ArgMatches) -> Result<Box<dyn dash::Feed>, ()> {
+    let feed: Box<dyn dash::Feed> = match subcommand {
+        "hist" => {
+            let (builder, _) = get_histogram_builder(matches)?;
+            let intervals = matches.value_of_t("intervals").unwrap();
+            Box::new(dash::HistogramFeed::new(
+                get_float_reader(matches)?,
+                builder,
+                intervals,
+            ))
+        }
+        "summary" => {
+            let precision: i32 = matches.value_of_t("precision").unwrap();
+            Box::new(dash::SummaryFeed::new(
+                get_float_reader(matches)?,
+                (precision > 0).then_some(precision as usize),
+                parse_confidence(matches)?,
+            ))
+        }
+        "matches" => {
+            let (reader, rows) = get_match_reader(matches)?;
+            Box::new(dash::MatchesFeed::new(
+                reader,
+                rows,
+                matches.value_of_t("sort").unwrap(),
+                matches.is_present("reverse"),
+            ))
+        }
+        "common-terms" => {
+            let regex = match Regex::new(matches.value_of("regex").unwrap_or("(.*)")) {
+                Ok(re) => re,
+                _ => {
+                    error!(
+                        "Failed to parse regex {}",
+                        matches.value_of("regex").unwrap()
+                    );
+                    return Err(());
+                }
+            };
+            let mut builder = read::DataReaderBuilder::default();
+            builder.regex(regex);
+            Box::new(dash::TermsFeed::new(
+                builder.build().unwrap(),
+                new_common_terms(matches)?,
+            ))
+        }
+        "timehist" => Box::new(dash::TimeFeed::new(
+            read::TimeStream::new(get_time_reader(matches)?),
+            get_timehist_builder(matches)?,
+        )),
+        _ => unreachable!("Invalid panel"),
+    };
+    Ok(feed)
+}
+
+/// Displays the plot of every panel of a dashboard, clearing the terminal
+/// first if `clear` is true.
+fn print_dash(
+    panels: &mut [(dash::Panel, Box<dyn dash::Feed>)],
+    clear: bool,
+    options: &plot::RenderOptions,
+) {
+    let (width, height) = match terminal_size() {
+        Some((Width(width), Height(height))) => (width as usize, height as usize),
+        None => (DEFAULT_WIDTH, DEFAULT_HEIGHT),
+    };
+    // Every panel has a title line, and the last line is left for the cursor
+    let rows = (height.saturating_sub(1) / panels.len())
+        .saturating_sub(1)
+        .max(1);
+    let opts = plot::RenderOptions {
+        width,
+        ..options.clone()
+    };
+    let mut frame = String::new();
+    if clear {
+        frame.push_str(dash::CLEAR_SCREEN);
+    }
+    for (panel, feed) in panels.iter_mut() {
+        let title = format!("── {} ", panel.title);
+        frame.push_str(&format!("{}\n", opts.theme.label.paint(title)));
+        if let Some(output) = feed.plot(&opts) {
+            frame.push_str(&dash::fit(&output, rows));
+        }
+    }
+    print!("{frame}");
+    let _ = io::stdout().flush();
+}
+
 fn main() {
-    let matches = app::get_app().get_matches();
+    let mut args: Vec<OsString> = env::args_os().collect();
+    let mut matches = app::get_app().get_matches_from(&args);
+    let config = config::Config::load();
+    if let Ok(config) = &config {
+        if config.apply_defaults(&app::get_app(), &mut args, &matches) {
+            matches = app::get_app().get_matches_from(&args);
+        }
+    }
     configure_output(
         matches.value_of("color").unwrap(),
         matches.is_present("verbose"),
     );
-    std::process::exit(match matches.subcommand() {
-        Some(("hist", subcommand_matches)) => histogram(subcommand_matches),
-        Some(("plot", subcommand_matches)) => plot(subcommand_matches),
-        Some(("matches", subcommand_matches)) => matchbar(subcommand_matches),
-        Some(("timehist", subcommand_matches)) => timehist(subcommand_matches),
-        Some(("common-terms", subcommand_matches)) => common_terms(subcommand_matches),
-        Some(("split-timehist", subcommand_matches)) => splittime(subcommand_matches),
+    match config {
+        Ok(config) => config.register_presets(),
+        Err(err) => {
+            error!("{}", err);
+            std::process::exit(2);
+        }
+    }
+    let options = get_render_options(matches.subcommand().unwrap().1);
+    std::process::exit(run(&matches, &options));
+}
+
+/// Runs the sub-command in the command line, returning the exit code
+fn run(matches: &ArgMatches, options: &plot::RenderOptions) -> i32 {
+    match matches.subcommand() {
+        Some(("hist", subcommand_matches)) => histogram(subcommand_matches, options),
+        Some(("plot", subcommand_matches)) => plot(subcommand_matches, options),
+        Some(("matches", subcommand_matches)) => matchbar(subcommand_matches, options),
+        Some(("timehist", subcommand_matches)) => timehist(subcommand_matches, options),
+        Some(("timeplot", subcommand_matches)) => timeplot(subcommand_matches, options),
+        Some(("timepercentiles", subcommand_matches)) => {
+            timepercentiles(subcommand_matches, options)
+        }
+        Some(("heatmap", subcommand_matches)) => heatmap(subcommand_matches, options),
+        Some(("gaps", subcommand_matches)) => gaps(subcommand_matches, options),
+        Some(("period", subcommand_matches)) => period(subcommand_matches, options),
+        Some(("interarrival", subcommand_matches)) => interarrival(subcommand_matches, options),
+        Some(("sessions", subcommand_matches)) => sessions(subcommand_matches, options),
+        Some(("summary", subcommand_matches)) => summary(subcommand_matches, options),
+        Some(("describe", subcommand_matches)) => describe(subcommand_matches, options),
+        Some(("merge", subcommand_matches)) => merge(subcommand_matches, options),
+        Some(("common-terms", subcommand_matches)) => common_terms(subcommand_matches, options),
+        Some(("zipf", subcommand_matches)) => zipf(subcommand_matches, options),
+        Some(("distinct", subcommand_matches)) => distinct(subcommand_matches, options),
+        Some(("split-timehist", subcommand_matches)) => splittime(subcommand_matches, options),
+        Some(("groupstats", subcommand_matches)) => groupstats(subcommand_matches, options),
+        Some(("top", subcommand_matches)) => top(subcommand_matches, options),
+        Some(("compare", subcommand_matches)) => compare(subcommand_matches, options),
+        Some(("dash", subcommand_matches)) => dash(subcommand_matches, options),
+        Some(("examples", _)) => examples(),
         _ => unreachable!("Invalid subcommand"),
-    });
+    }
 }
 
 #[cfg(test)]
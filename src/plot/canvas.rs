@@ -0,0 +1,74 @@
+// Bits of the dots in a braille character, indexed by [row][column] (row 0 is
+// the top one).
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+const BRAILLE_BASE: u32 = 0x2800;
+
+/// A canvas for drawing points with braille characters, where every character
+/// holds 2x4 dots.
+#[derive(Debug)]
+pub(crate) struct BrailleCanvas {
+    width: usize,
+    height: usize,
+    cells: Vec<u32>,
+}
+
+impl BrailleCanvas {
+    /// Creates an empty canvas of `width` x `height` characters.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![0; width * height],
+        }
+    }
+
+    /// Number of dots in the horizontal axis.
+    pub fn dot_width(&self) -> usize {
+        self.width * 2
+    }
+
+    /// Number of dots in the vertical axis.
+    pub fn dot_height(&self) -> usize {
+        self.height * 4
+    }
+
+    /// Sets the dot at (`x`, `y`), where (0, 0) is the bottom left corner.
+    /// Dots out of the canvas are ignored.
+    pub fn set(&mut self, x: usize, y: usize) {
+        if x >= self.dot_width() || y >= self.dot_height() {
+            return;
+        }
+        let y = self.dot_height() - 1 - y;
+        self.cells[(y / 4) * self.width + x / 2] |= BRAILLE_DOTS[y % 4][x % 2];
+    }
+
+    /// Returns the text of the row `i` of characters (0 being the top one).
+    /// Characters with no dots are rendered as spaces.
+    pub fn row(&self, i: usize) -> String {
+        self.cells[i * self.width..(i + 1) * self.width]
+            .iter()
+            .map(|c| match c {
+                0 => ' ',
+                _ => char::from_u32(BRAILLE_BASE + c).unwrap(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canvas() {
+        let mut canvas = BrailleCanvas::new(2, 2);
+        assert_eq!(canvas.dot_width(), 4);
+        assert_eq!(canvas.dot_height(), 8);
+        canvas.set(0, 0);
+        canvas.set(1, 7);
+        canvas.set(3, 4);
+        canvas.set(10, 10);
+        assert_eq!(canvas.row(0), "⠈⢀");
+        assert_eq!(canvas.row(1), "⡀ ");
+    }
+}
@@ -0,0 +1,175 @@
+use std::fmt;
+
+use crate::format::{F64Formatter, Theme, BAR_CHAR};
+use crate::plot::{Histogram, HistogramOptions, Render, Table};
+use crate::stats::Stats;
+
+/// A struct holding data to compare two sets of numerical data, by displaying
+/// their histograms interleaved over a shared bucket layout.
+pub struct HistogramComparison {
+    before: Histogram,
+    after: Histogram,
+    before_stats: Stats,
+    after_stats: Stats,
+    precision: Option<usize>,
+}
+
+impl HistogramComparison {
+    /// Creates a `HistogramComparison` from two vectors of numerical data.
+    ///
+    /// Both histograms will use the same buckets, built for holding the values
+    /// of both inputs.  `options` is a `HistogramOptions` struct with the
+    /// preferences to create those histograms.
+    pub fn new(before: &mut [f64], after: &mut [f64], options: HistogramOptions) -> Self {
+        let precision = options.precision;
+        let (before_hist, after_hist) = Histogram::new_pair(before, after, options);
+        Self {
+            before: before_hist,
+            after: after_hist,
+            before_stats: Stats::new(before, precision),
+            after_stats: Stats::new(after, precision),
+            precision,
+        }
+    }
+}
+
+impl fmt::Display for HistogramComparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
+        let width = f.width().unwrap_or(110);
+        let min = self.before_stats.min.min(self.after_stats.min);
+        let max = self.before_stats.max.max(self.after_stats.max);
+        let formatter = match self.precision {
+            None => F64Formatter::new_with_range(min..max),
+            Some(n) => F64Formatter::new(n),
+        };
+        writeln!(f, "{}:", theme.palette[0].paint("Before"))?;
+        write!(f, "{}", self.before_stats)?;
+        writeln!(f, "{}:", theme.palette[2].paint("After"))?;
+        write!(f, "{}", self.after_stats)?;
+        let top = (self.before.units(self.before.top())).max(self.after.units(self.after.top()));
+        let width_range = self
+            .before
+            .buckets()
+            .map(|(r, _)| {
+                formatter
+                    .format(r.start)
+                    .len()
+                    .max(formatter.format(r.end).len())
+            })
+            .max()
+            .unwrap_or(1);
+        let width_count = self.before.format_units(top).len();
+        let fixed_width = 2 * width_range + width_count + 20;
+        let bar_len = if width > fixed_width {
+            width - fixed_width
+        } else {
+            75
+        };
+        let horizontal_scale = self.before.horizontal_scale(top / bar_len);
+        writeln!(f, "{horizontal_scale}")?;
+        let divisor = horizontal_scale.get_scale();
+        for ((range, before), (_, after)) in self.before.buckets().zip(self.after.buckets()) {
+            let (before, after) = (self.before.units(before), self.after.units(after));
+            let label = format!(
+                "{:>width$} .. {:>width$}",
+                formatter.format(range.start),
+                formatter.format(range.end),
+                width = width_range,
+            );
+            writeln!(
+                f,
+                "[{label}] [{count}] {bar}",
+                label = theme.label.paint(&label),
+                count = theme.palette[0].paint(format!(
+                    "{:>width_count$}",
+                    self.before.format_units(before)
+                )),
+                bar = theme.palette[0].paint(BAR_CHAR.repeat(before / divisor)),
+            )?;
+            let delta = match after >= before {
+                true => format!("+{}", self.after.format_units(after - before)),
+                false => format!("-{}", self.after.format_units(before - after)),
+            };
+            writeln!(
+                f,
+                "{padding}[{count}] {bar} ({delta})",
+                padding = " ".repeat(label.chars().count() + 3),
+                count = theme.palette[2]
+                    .paint(format!("{:>width_count$}", self.after.format_units(after))),
+                bar = theme.palette[2].paint(BAR_CHAR.repeat(after / divisor)),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Render for HistogramComparison {
+    fn table(&self) -> Option<Table> {
+        let mut table = Table::new(&["lower", "upper", "before", "after"]);
+        for ((range, before), (_, after)) in self.before.buckets().zip(self.after.buckets()) {
+            table.push(vec![
+                range.start.into(),
+                range.end.into(),
+                before.into(),
+                after.into(),
+            ]);
+        }
+        Some(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yansi::Paint;
+
+    #[test]
+    fn test_comparison() {
+        let comparison = HistogramComparison::new(
+            &mut [1.0, 1.5, 2.0, 3.5],
+            &mut [3.0, 3.2, 3.8, 5.0, 4.0],
+            HistogramOptions {
+                intervals: 4,
+                precision: Some(1),
+                ..Default::default()
+            },
+        );
+        Paint::disable();
+        let display = format!("{comparison}");
+        assert!(display.contains("Before:\nSamples = 4; Min = 1.0; Max = 3.5\n"));
+        assert!(display.contains("After:\nSamples = 5; Min = 3.0; Max = 5.0\n"));
+        assert!(display.contains("[1.0 .. 2.0] [2] ∎∎\n"));
+        assert!(display.contains("             [0]  (-2)\n"));
+        assert!(display.contains("[3.0 .. 4.0] [1] ∎\n"));
+        assert!(display.contains("             [3] ∎∎∎ (+2)\n"));
+        assert!(display.contains("[4.0 .. 5.0] [0] \n"));
+        assert!(display.contains("             [2] ∎∎ (+2)\n"));
+        assert_eq!(
+            comparison.table().unwrap().to_csv(','),
+            "lower,upper,before,after\n1,2,2,0\n2,3,1,0\n3,4,1,3\n4,5,0,2\n"
+        );
+    }
+
+    #[test]
+    fn test_comparison_log_scale() {
+        let comparison = HistogramComparison::new(
+            &mut [0.5, 2.0, 3.0, -1.0],
+            &mut [6.0, 7.0],
+            HistogramOptions {
+                intervals: 3,
+                log_scale: true,
+                precision: Some(0),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            comparison.before.buckets().map(|b| b.1).collect::<Vec<_>>(),
+            vec![1, 2, 0]
+        );
+        assert_eq!(
+            comparison.after.buckets().map(|b| b.1).collect::<Vec<_>>(),
+            vec![0, 0, 2]
+        );
+    }
+}
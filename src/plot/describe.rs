@@ -0,0 +1,149 @@
+use std::fmt;
+
+use crate::format::{F64Formatter, Theme};
+use crate::plot::{Histogram, HistogramOptions, Render, Table};
+use crate::stats::{OutlierMethod, Stats};
+
+// Percentiles in the table of a description
+const PERCENTILES: [f64; 10] = [1.0, 5.0, 10.0, 25.0, 50.0, 75.0, 90.0, 95.0, 99.0, 99.9];
+
+// Most buckets chosen for the histogram of a description, for keeping it small
+const MAX_AUTO_INTERVALS: usize = 12;
+
+/// A struct holding a compact description of numerical data (in the fashion
+/// of `describe()` in pandas): its statistics (including outliers and the
+/// confidence interval of the average), a table of percentiles and a small
+/// histogram.
+pub struct Description {
+    histogram: Histogram,
+    precision: Option<usize>,
+}
+
+impl Description {
+    /// Creates a `Description` from a vector of numerical data.
+    ///
+    /// `options` are the ones of the histogram.  If its `intervals` is zero,
+    /// the number of buckets is chosen from the number of values (with
+    /// Sturges' rule, up to a dozen).  Outliers (with the IQR method) and the
+    /// 95% confidence interval of the average are displayed with the
+    /// statistics unless `options` say otherwise.
+    pub fn new(vec: &mut [f64], mut options: HistogramOptions) -> Self {
+        if options.intervals == 0 {
+            options.intervals = auto_intervals(vec.len());
+        }
+        options.outliers = options.outliers.or(Some(OutlierMethod::Iqr));
+        options.confidence = options.confidence.or(Some(0.95));
+        Self {
+            precision: options.precision,
+            histogram: Histogram::new(vec, options),
+        }
+    }
+
+    /// Returns the statistics of the data.
+    pub fn stats(&self) -> &Stats {
+        self.histogram.stats()
+    }
+
+    /// Returns the percentiles in the table (from 0 to 100), along with their
+    /// values.
+    pub fn percentiles(&self) -> Vec<(f64, f64)> {
+        PERCENTILES
+            .iter()
+            .map(|p| (*p, self.stats().percentile(*p)))
+            .collect()
+    }
+}
+
+// Number of buckets for `samples` values, with Sturges' rule
+fn auto_intervals(samples: usize) -> usize {
+    let intervals = (samples.max(1) as f64).log2().ceil() as usize + 1;
+    intervals.min(MAX_AUTO_INTERVALS)
+}
+
+impl fmt::Display for Description {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
+        let stats = self.stats();
+        write!(f, "{stats}")?;
+        let formatter = match self.precision {
+            None => F64Formatter::new_with_range(stats.min..stats.max),
+            Some(n) => F64Formatter::new(n),
+        };
+        let cells: Vec<(String, String)> = self
+            .percentiles()
+            .iter()
+            .map(|(p, value)| (format!("p{p}"), formatter.format(*value)))
+            .collect();
+        let width = cells
+            .iter()
+            .map(|(name, value)| name.len().max(value.len()))
+            .max()
+            .unwrap_or(0);
+        let names: Vec<String> = cells
+            .iter()
+            .map(|(name, _)| format!("{name:>width$}"))
+            .collect();
+        let values: Vec<String> = cells
+            .iter()
+            .map(|(_, value)| format!("{}", theme.label.paint(format!("{value:>width$}"))))
+            .collect();
+        writeln!(f, "Percentiles:")?;
+        writeln!(f, "{}", names.join(" "))?;
+        writeln!(f, "{}", values.join(" "))?;
+        self.histogram.fmt_buckets(f)
+    }
+}
+
+impl Render for Description {
+    fn rows(&self) -> Vec<(String, usize)> {
+        self.histogram.rows()
+    }
+
+    /// The data of a description is its table of percentiles.
+    fn table(&self) -> Option<Table> {
+        let mut table = Table::new(&["percentile", "value"]);
+        for (p, value) in self.percentiles() {
+            table.push(vec![p.into(), value.into()]);
+        }
+        Some(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yansi::Paint;
+
+    #[test]
+    fn test_description() {
+        let mut vec: Vec<f64> = (1..=1000).map(f64::from).collect();
+        vec.push(10000.0);
+        let options = HistogramOptions {
+            precision: Some(0),
+            ..Default::default()
+        };
+        let description = Description::new(&mut vec, options);
+        Paint::disable();
+        let display = format!("{description:40}");
+        assert!(display.starts_with("Samples = 1001; Min = 1; Max = 10000\n"));
+        assert!(display.contains("95% confidence interval of average"));
+        assert!(display.contains("Outliers (IQR) = 1;"));
+        assert!(display.contains(concat!(
+            "Percentiles:\n",
+            "   p1    p5   p10   p25   p50   p75   p90   p95   p99 p99.9\n",
+            "   11    51   101   251   501   751   901   951   991  1000\n",
+        )));
+        // 11 buckets, from Sturges' rule
+        assert_eq!(description.histogram.rows().len(), 11);
+        assert_eq!(description.percentiles()[4], (50.0, 501.0));
+        let table = description.table().unwrap();
+        assert_eq!(table.rows.len(), 10);
+    }
+
+    #[test]
+    fn test_auto_intervals() {
+        assert_eq!(auto_intervals(0), 1);
+        assert_eq!(auto_intervals(8), 4);
+        assert_eq!(auto_intervals(1_000_000), MAX_AUTO_INTERVALS);
+    }
+}
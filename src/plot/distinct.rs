@@ -0,0 +1,107 @@
+use std::fmt;
+
+use chrono::{DateTime, Duration, FixedOffset};
+
+use crate::format::{HorizontalScale, Theme};
+use crate::plot::{date_fmt_string, Render, Table};
+use crate::stats::HyperLogLog;
+
+#[derive(Debug)]
+/// A struct holding data to plot an histogram of the (estimated) number of
+/// distinct terms over time.
+pub struct DistinctTimeHistogram {
+    vec: Vec<(DateTime<FixedOffset>, HyperLogLog)>,
+    total: HyperLogLog,
+    step: Duration,
+}
+
+impl DistinctTimeHistogram {
+    /// Creates a `DistinctTimeHistogram` from a slice of terms along with the
+    /// time they were observed.
+    ///
+    /// `size` is the number of histogram buckets to display.
+    pub fn new(size: usize, terms: &[(DateTime<FixedOffset>, String)]) -> Self {
+        let min = terms.iter().map(|t| t.0).min().unwrap();
+        let max = terms.iter().map(|t| t.0).max().unwrap();
+        let step = max - min;
+        let inc = step / size as i32;
+        let mut vec: Vec<(DateTime<FixedOffset>, HyperLogLog)> = (0..size)
+            .map(|i| (min + (inc * i as i32), HyperLogLog::default()))
+            .collect();
+        let mut total = HyperLogLog::default();
+        let micros = step.num_microseconds().unwrap() as u64;
+        for (ts, term) in terms {
+            let x = (*ts - min).num_microseconds().unwrap() as u64;
+            // If all timestamps are the same we will have a degenerate plot
+            let slot = match (x * size as u64).checked_div(micros) {
+                Some(slot) => (slot as usize).min(size - 1),
+                None => 0,
+            };
+            vec[slot].1.insert(term);
+            total.insert(term);
+        }
+        Self { vec, total, step }
+    }
+}
+
+impl fmt::Display for DistinctTimeHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
+        let width = f.width().unwrap_or(100);
+        let counts: Vec<usize> = self
+            .vec
+            .iter()
+            .map(|(_, hll)| hll.estimate().round() as usize)
+            .collect();
+        let top = counts.iter().copied().max().unwrap_or(0);
+        let horizontal_scale = HorizontalScale::new(top / width);
+        let width_count = format!("{top}").len();
+        write!(f, "{}", self.total)?;
+        writeln!(f, "{horizontal_scale}")?;
+        let ts_fmt = date_fmt_string(self.step.num_seconds());
+        for ((start, _), count) in self.vec.iter().zip(counts) {
+            writeln!(
+                f,
+                "[{label}] [{count}] {bar}",
+                label = theme.label.paint(format!("{}", start.format(ts_fmt))),
+                count = horizontal_scale.get_count(count, width_count),
+                bar = horizontal_scale.get_bar(count)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Render for DistinctTimeHistogram {
+    fn table(&self) -> Option<Table> {
+        let mut table = Table::new(&["start", "distinct"]);
+        for (start, hll) in &self.vec {
+            let count = hll.estimate().round() as usize;
+            table.push(vec![start.to_rfc3339().into(), count.into()]);
+        }
+        Some(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yansi::Paint;
+
+    #[test]
+    fn test_distinct_time_histogram() {
+        let ts = |s| DateTime::parse_from_rfc3339(s).unwrap();
+        let mut terms = Vec::new();
+        for i in 0..30 {
+            terms.push((ts("2021-04-15T04:25:00+00:00"), format!("ip{}", i % 3)));
+            terms.push((ts("2021-04-15T04:26:00+00:00"), format!("ip{i}")));
+        }
+        let th = DistinctTimeHistogram::new(2, &terms);
+        Paint::disable();
+        let display = format!("{th:20}");
+        assert!(display.starts_with("Distinct values: ~30 (standard error"));
+        assert!(display.contains("Each ∎ represents a count of 1\n"));
+        assert!(display.contains("[04:25:00.000] [ 3] ∎∎∎\n"));
+        assert!(display.contains(&format!("[04:25:30.000] [30] {}\n", "∎".repeat(30))));
+    }
+}
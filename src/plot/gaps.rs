@@ -0,0 +1,134 @@
+use std::fmt;
+
+use chrono::{DateTime, Duration, FixedOffset};
+
+use crate::format::Theme;
+use crate::plot::{human_duration, Render, Table};
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// A period of time with no timestamps in the input.
+struct Gap {
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+}
+
+impl Gap {
+    fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// A struct holding the periods of time longer than a threshold where no
+/// timestamps were observed (silent periods in logs, for instance).
+pub struct Gaps {
+    gaps: Vec<Gap>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    threshold: Duration,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    count: usize,
+}
+
+impl Gaps {
+    /// Creates a `Gaps` from a vector of `DateTime` elements (not necessarily
+    /// sorted), finding the gaps between consecutive timestamps longer than
+    /// `threshold`.
+    pub fn new(ts: &[DateTime<FixedOffset>], threshold: Duration) -> Self {
+        let mut sorted = ts.to_vec();
+        sorted.sort();
+        let gaps = sorted
+            .windows(2)
+            .filter(|pair| pair[1] - pair[0] > threshold)
+            .map(|pair| Gap {
+                start: pair[0],
+                end: pair[1],
+            })
+            .collect();
+        Self {
+            gaps,
+            threshold,
+            count: ts.len(),
+        }
+    }
+}
+
+impl fmt::Display for Gaps {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
+        let threshold = human_duration(self.threshold);
+        writeln!(
+            f,
+            "Matches: {}. Gaps longer than {}: {}.",
+            theme.label.paint(self.count),
+            threshold,
+            theme.label.paint(self.gaps.len()),
+        )?;
+        // Sub-second gaps are only meaningful if the threshold is small
+        let ts_fmt = match self.threshold.num_seconds() {
+            x if x > 1 => "%Y-%m-%d %H:%M:%S",
+            _ => "%Y-%m-%d %H:%M:%S%.3f",
+        };
+        for gap in &self.gaps {
+            writeln!(
+                f,
+                "[{}] .. [{}] {}",
+                theme.label.paint(gap.start.format(ts_fmt)),
+                theme.label.paint(gap.end.format(ts_fmt)),
+                theme.count.paint(human_duration(gap.duration())),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Render for Gaps {
+    fn table(&self) -> Option<Table> {
+        let mut table = Table::new(&["start", "end", "seconds"]);
+        for gap in &self.gaps {
+            table.push(vec![
+                gap.start.to_rfc3339().into(),
+                gap.end.to_rfc3339().into(),
+                (gap.duration().num_microseconds().unwrap() as f64 / 1e6).into(),
+            ]);
+        }
+        Some(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yansi::Paint;
+
+    #[test]
+    fn test_gaps() {
+        let vec: Vec<DateTime<FixedOffset>> = [
+            "2021-04-15T04:25:00+00:00",
+            "2021-04-15T04:26:10+00:00",
+            "2021-04-15T04:25:20+00:00",
+            "2021-04-15T04:26:20+00:00",
+            "2021-04-15T05:00:00+00:00",
+        ]
+        .iter()
+        .map(|s| DateTime::parse_from_rfc3339(s).unwrap())
+        .collect();
+        let gaps = Gaps::new(&vec, Duration::seconds(30));
+        assert_eq!(gaps.gaps.len(), 2);
+        assert_eq!(gaps.gaps[0].duration(), Duration::seconds(50));
+        Paint::disable();
+        let display = format!("{gaps}");
+        assert!(display.contains("Matches: 5. Gaps longer than 30s: 2.\n"));
+        assert!(display.contains("[2021-04-15 04:25:20] .. [2021-04-15 04:26:10] 50s\n"));
+        assert!(display.contains("[2021-04-15 04:26:20] .. [2021-04-15 05:00:00] 33m 40s\n"));
+        assert_eq!(
+            gaps.table().unwrap().to_csv(','),
+            "start,end,seconds\n\
+             2021-04-15T04:25:20+00:00,2021-04-15T04:26:10+00:00,50\n\
+             2021-04-15T04:26:20+00:00,2021-04-15T05:00:00+00:00,2020\n"
+        );
+        let gaps = Gaps::new(&vec, Duration::hours(1));
+        assert!(gaps.gaps.is_empty());
+    }
+}
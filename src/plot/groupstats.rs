@@ -0,0 +1,136 @@
+use std::cmp::Reverse;
+use std::fmt;
+
+use crate::format::{F64Formatter, Theme};
+use crate::plot::Render;
+use crate::stats::Stats;
+
+#[derive(Debug)]
+/// A struct holding a table of statistics of numerical data, one row per
+/// group (a label extracted from input data).
+pub struct GroupStats {
+    groups: Vec<(String, Stats)>,
+    min: f64,
+    max: f64,
+    precision: Option<usize>,
+}
+
+impl GroupStats {
+    /// Creates a `GroupStats` from a vector of tuples of a label and the
+    /// values belonging to that label.  Groups with no values are discarded.
+    /// Rows will be sorted by number of samples (most frequent first).
+    ///
+    /// `precision` is an Option with the number of decimals to display.  If
+    /// "None" is used, human units will be used, with an heuristic based on the
+    /// input data for deciding the units and the decimal places.
+    pub fn new(groups: Vec<(String, Vec<f64>)>, precision: Option<usize>) -> Self {
+        let mut min = f64::INFINITY;
+        let mut max = f64::NEG_INFINITY;
+        let mut rows: Vec<(String, Stats)> = groups
+            .into_iter()
+            .filter(|(_, vec)| !vec.is_empty())
+            .map(|(label, mut vec)| {
+                let stats = Stats::new(&mut vec, precision);
+                min = min.min(stats.min);
+                max = max.max(stats.max);
+                (label, stats)
+            })
+            .collect();
+        rows.sort_by_key(|row| Reverse(row.1.samples));
+        Self {
+            groups: rows,
+            min,
+            max,
+            precision,
+        }
+    }
+}
+
+impl fmt::Display for GroupStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
+        if self.groups.is_empty() {
+            return writeln!(f, "No data");
+        }
+        let formatter = match self.precision {
+            None => F64Formatter::new_with_range(self.min..self.max),
+            Some(n) => F64Formatter::new(n),
+        };
+        let rows: Vec<(&String, String, [String; 3])> = self
+            .groups
+            .iter()
+            .map(|(label, stats)| {
+                (
+                    label,
+                    stats.samples.to_string(),
+                    [
+                        formatter.format(stats.avg),
+                        formatter.format(stats.p95),
+                        formatter.format(stats.p99),
+                    ],
+                )
+            })
+            .collect();
+        let label_width = rows.iter().fold(5, |acc, r| acc.max(r.0.len()));
+        let count_width = rows.iter().fold(7, |acc, r| acc.max(r.1.len()));
+        let value_width = rows
+            .iter()
+            .flat_map(|r| r.2.iter())
+            .fold(7, |acc, v| acc.max(v.len()));
+        writeln!(
+            f,
+            "Groups: {}.",
+            theme.label.paint(self.groups.len().to_string())
+        )?;
+        writeln!(
+            f,
+            "{:<label_width$} {:>count_width$} {:>value_width$} {:>value_width$} {:>value_width$}",
+            "Group", "Samples", "Average", "p95", "p99",
+        )?;
+        for (label, count, values) in rows.iter() {
+            writeln!(
+                f,
+                "{label} {count} {avg:>value_width$} {p95:>value_width$} {p99:>value_width$}",
+                label = theme.label.paint(format!("{label:<label_width$}")),
+                count = theme.count.paint(format!("{count:>count_width$}")),
+                avg = values[0],
+                p95 = values[1],
+                p99 = values[2],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Render for GroupStats {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yansi::Paint;
+
+    #[test]
+    fn test_group_stats() {
+        let groups = vec![
+            (String::from("foo"), vec![1.0, 2.0, 3.0]),
+            (String::from("empty"), vec![]),
+            (String::from("barbarbar"), vec![10.0, 20.0, 30.0, 40.0]),
+        ];
+        let gs = GroupStats::new(groups, Some(1));
+        assert_eq!(gs.groups.len(), 2);
+        assert_eq!(gs.groups[0].0, "barbarbar");
+        Paint::disable();
+        let display = format!("{gs}");
+        assert!(display.contains("Groups: 2."));
+        assert!(display.contains("Group     Samples Average     p95     p99\n"));
+        assert!(display.contains("barbarbar       4    25.0    40.0    40.0\n"));
+        assert!(display.contains("foo             3     2.0     3.0     3.0\n"));
+    }
+
+    #[test]
+    fn test_group_stats_empty() {
+        let gs = GroupStats::new(vec![], None);
+        Paint::disable();
+        assert_eq!(format!("{gs}"), "No data\n");
+    }
+}
@@ -0,0 +1,156 @@
+use std::fmt;
+use std::ops::Range;
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::format::{F64Formatter, Theme};
+use crate::plot::{print_time_axis, Histogram, HistogramOptions, Render};
+use crate::stats::Stats;
+
+/// Characters for the cells of the heat map, from the lightest (cells with a
+/// few values) to the darkest (cells with the most values).
+const SHADES: [char; 4] = ['░', '▒', '▓', '█'];
+
+/// A struct holding data to plot a heat map of values over time: time in the
+/// x axis, buckets of values in the y axis, and the number of values of every
+/// bucket in a span of time as the shade of its cell.
+pub struct HeatMap {
+    columns: Vec<Histogram>,
+    stats: Stats,
+    time_range: Range<DateTime<FixedOffset>>,
+    top: usize,
+    precision: Option<usize>,
+}
+
+impl HeatMap {
+    /// Creates a `HeatMap` from pairs of a timestamp and a value.
+    ///
+    /// `width` is the number of columns (spans of time) to display.
+    ///
+    /// `options` is a `HistogramOptions` struct with the preferences for the
+    /// buckets of values (the rows), shared by the histograms of every column.
+    /// Options about the statistics (like `rank_of`) are ignored.
+    pub fn new(
+        points: &[(DateTime<FixedOffset>, f64)],
+        width: usize,
+        mut options: HistogramOptions,
+    ) -> Self {
+        let min = points.iter().map(|p| p.0).min().unwrap();
+        let max = points.iter().map(|p| p.0).max().unwrap();
+        let mut values: Vec<f64> = points.iter().map(|p| p.1).collect();
+        let mut stats = Stats::new(&mut values, options.precision);
+        if options.log_scale {
+            stats.min = 0.0; // We will silently discard negative values
+        }
+        options.intervals = options.intervals.clamp(1, stats.samples);
+        let width = width.clamp(1, points.len());
+        let mut columns: Vec<Histogram> = (0..width)
+            .map(|_| Histogram::new_with_stats(stats.clone(), &options))
+            .collect();
+        let micros = (max - min).num_microseconds().unwrap() as u64;
+        for (ts, value) in points {
+            let x = (*ts - min).num_microseconds().unwrap() as u64;
+            // If all timestamps are the same we will have a degenerate plot
+            let column = match (x * width as u64).checked_div(micros) {
+                Some(column) => (column as usize).min(width - 1),
+                None => 0,
+            };
+            columns[column].add(*value);
+        }
+        let top = columns.iter().map(|h| h.top()).max().unwrap_or(0);
+        Self {
+            columns,
+            stats,
+            time_range: min..max,
+            top,
+            precision: options.precision,
+        }
+    }
+
+    // Character for a cell with `count` values
+    fn shade(&self, count: usize) -> char {
+        match count {
+            0 => ' ',
+            _ => SHADES[((count * SHADES.len()).div_ceil(self.top) - 1).min(SHADES.len() - 1)],
+        }
+    }
+}
+
+impl fmt::Display for HeatMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
+        let formatter = match self.precision {
+            None => F64Formatter::new_with_range(self.stats.min..self.stats.max),
+            Some(n) => F64Formatter::new(n),
+        };
+        write!(f, "{}", self.stats)?;
+        // Upper limit of the counts of every shade (some shades are not used
+        // with small counts)
+        let mut limits: Vec<usize> = (1..=SHADES.len())
+            .map(|i| (self.top * i).div_ceil(SHADES.len()))
+            .collect();
+        limits.dedup();
+        let legend: Vec<String> = limits
+            .iter()
+            .map(|count| format!("{} <= {count}", theme.bar.paint(self.shade(*count))))
+            .collect();
+        writeln!(f, "{}", legend.join("; "))?;
+        let rows: Vec<&Range<f64>> = self.columns[0].buckets().map(|(range, _)| range).collect();
+        let y_width = rows
+            .iter()
+            .map(|range| formatter.format(range.start).len())
+            .max()
+            .unwrap_or(1);
+        let counts: Vec<Vec<usize>> = self
+            .columns
+            .iter()
+            .map(|h| h.buckets().map(|(_, count)| count).collect())
+            .collect();
+        for (i, range) in rows.iter().enumerate().rev() {
+            let row: String = counts.iter().map(|column| self.shade(column[i])).collect();
+            writeln!(
+                f,
+                "[{}] {}",
+                theme
+                    .label
+                    .paint(format!("{:>y_width$}", formatter.format(range.start))),
+                theme.bar.paint(row),
+            )?;
+        }
+        print_time_axis(f, &self.time_range, y_width + 3, self.columns.len())
+    }
+}
+
+impl Render for HeatMap {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yansi::Paint;
+
+    #[test]
+    fn test_heatmap() {
+        let ts = |s| DateTime::parse_from_rfc3339(s).unwrap();
+        let mut points = Vec::new();
+        for i in 0..8 {
+            points.push((ts("2021-04-15T04:25:00+00:00"), 1.0));
+            points.push((ts("2021-04-15T04:26:00+00:00"), (i % 2) as f64 * 3.0));
+        }
+        points.push((ts("2021-04-15T04:27:00+00:00"), 4.0));
+        let options = HistogramOptions {
+            intervals: 4,
+            precision: Some(0),
+            ..Default::default()
+        };
+        let heatmap = HeatMap::new(&points, 3, options);
+        Paint::disable();
+        let display = format!("{heatmap}");
+        assert!(display.starts_with("Samples = 17; Min = 0; Max = 4\n"));
+        assert!(display.contains("░ <= 2; ▒ <= 4; ▓ <= 6; █ <= 8\n"));
+        assert!(display.contains("[3]  ▒░\n"));
+        assert!(display.contains("[2]    \n"));
+        assert!(display.contains("[1] █  \n"));
+        assert!(display.contains("[0]  ▒ \n"));
+        assert!(display.contains("    04:25:00.000 04:27:00.000\n"));
+    }
+}
@@ -1,12 +1,18 @@
 use std::fmt;
 use std::ops::Range;
 
-use yansi::Color::Blue;
+use crate::format::{basis_points, format_share, F64Formatter, HorizontalScale, Theme};
+use crate::plot::render::{rgb, svg};
+use crate::plot::symlog::SymLog;
+use crate::plot::{merge_buckets, Image, Raster, Render, SeriesHistogram, Table, ToSvg};
+use crate::stats::{find_peaks, OutlierMethod, Stats};
 
-use crate::format::{F64Formatter, HorizontalScale};
-use crate::stats::Stats;
+// Minimum prominence of a peak in bucket counts (as a fraction of the highest
+// count) to be reported as a mode
+const MODE_PROMINENCE: f64 = 0.3;
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 /// A struct that represents a bucket of an histogram.
 struct Bucket {
     range: Range<f64>,
@@ -18,35 +24,115 @@ impl Bucket {
         Self { range, count: 0 }
     }
 
-    fn inc(&mut self) {
-        self.count += 1;
+    fn inc(&mut self, count: usize) {
+        self.count += count;
     }
 }
 
 /// A struct representing the options to build an histogram.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Histogram {
+    #[cfg_attr(feature = "serde", serde(rename = "buckets"))]
     vec: Vec<Bucket>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     step: f64,
     // Maximum of all bucket counts
+    #[cfg_attr(feature = "serde", serde(skip))]
     top: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
     last: usize,
     stats: Stats,
+    #[cfg_attr(feature = "serde", serde(skip))]
     log_scale: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    symlog: Option<SymLog>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     precision: Option<usize>, // If None, then human friendly display will be used
+    #[cfg_attr(feature = "serde", serde(skip))]
+    width: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    normalize: bool,
 }
 
 /// A struct holding data to plot a Histogram of numerical data.
-#[derive(Clone, Default)]
+///
+/// A `HistogramBuilder` can be used as well for setting the options and
+/// building the `Histogram` in one go.
+#[derive(Builder, Clone, Default)]
+#[builder(
+    name = "HistogramBuilder",
+    build_fn(private, name = "options"),
+    default
+)]
 pub struct HistogramOptions {
     /// `intervals` is the number of histogram buckets to display (capped to the
     /// length of input data).
+    #[builder(default = "20")]
     pub intervals: usize,
     /// If true, logarithmic scale will be used for buckets
     pub log_scale: bool,
+    /// If present, a symmetric logarithmic scale will be used for buckets:
+    /// linear in the vicinity of zero (with this value as threshold) and
+    /// logarithmic beyond it, for both negative and positive values.
+    #[builder(setter(strip_option))]
+    pub symlog: Option<f64>,
     /// `precision` is an Option with the number of decimals to display.  If
     /// "None" is used, human units will be used, with an heuristic based on the
     /// input data for deciding the units and the decimal places.
+    #[builder(setter(strip_option))]
     pub precision: Option<usize>,
+    /// If present, the percentile rank of this value will be displayed along
+    /// with the statistics.
+    #[builder(setter(strip_option))]
+    pub rank_of: Option<f64>,
+    /// If present, the number of outliers found with this method and the
+    /// most extreme of them will be displayed along with the statistics.
+    #[builder(setter(strip_option))]
+    pub outliers: Option<OutlierMethod>,
+    /// If present, the average and standard deviation of the values excluding
+    /// this fraction of them at both tails will be displayed along with the
+    /// statistics.
+    #[builder(setter(strip_option))]
+    pub trim: Option<f64>,
+    /// If present, the confidence interval of the average at this level (like
+    /// 0.95) will be displayed along with the statistics.
+    #[builder(setter(strip_option))]
+    pub confidence: Option<f64>,
+    /// If present, the number of characters to use as width when displaying
+    /// the histogram with no explicit width.
+    #[builder(setter(strip_option))]
+    pub width: Option<usize>,
+    /// If true, buckets display the fraction of the values in them, rather
+    /// than their counts, so that histograms of inputs of different sizes
+    /// are comparable.
+    pub normalize: bool,
+}
+
+impl HistogramBuilder {
+    /// Creates a Histogram from a vector of numerical data, using the options
+    /// set in the builder.
+    pub fn build(&self, vec: &mut [f64]) -> Histogram {
+        Histogram::new(vec, self.options().unwrap())
+    }
+
+    /// Creates two histograms with the same buckets from two vectors of
+    /// numerical data, using the options set in the builder.  See
+    /// `Histogram::new_pair`.
+    pub fn build_pair(&self, first: &mut [f64], second: &mut [f64]) -> (Histogram, Histogram) {
+        Histogram::new_pair(first, second, self.options().unwrap())
+    }
+
+    /// Creates a chart with an histogram per named series of numerical data,
+    /// using the options set in the builder.  See `SeriesHistogram::new`.
+    pub fn build_series(&self, series: &[(String, Vec<f64>)]) -> SeriesHistogram {
+        SeriesHistogram::new(series, self.options().unwrap())
+    }
+
+    /// Creates a Histogram from pre-aggregated numerical data, using the
+    /// options set in the builder.  See `Histogram::new_weighted`.
+    pub fn build_weighted(&self, pairs: &mut [(f64, usize)]) -> Histogram {
+        Histogram::new_weighted(pairs, self.options().unwrap())
+    }
 }
 
 impl Histogram {
@@ -56,6 +142,18 @@ impl Histogram {
     /// histogram.
     pub fn new(vec: &mut [f64], mut options: HistogramOptions) -> Self {
         let mut stats = Stats::new(vec, options.precision);
+        if let Some(value) = options.rank_of {
+            stats.set_rank_of(vec, value);
+        }
+        if let Some(method) = options.outliers {
+            stats.set_outliers(vec, method);
+        }
+        if let Some(fraction) = options.trim {
+            stats.set_trim(vec, fraction);
+        }
+        if let Some(level) = options.confidence {
+            stats.set_confidence(level);
+        }
         if options.log_scale {
             stats.min = 0.0; // We will silently discard negative values
         }
@@ -65,13 +163,56 @@ impl Histogram {
         histogram
     }
 
+    /// Creates a Histogram from pre-aggregated numerical data: pairs of a
+    /// value and its number of occurrences, that should be positive.
+    ///
+    /// Options about the statistics that need the individual values (like
+    /// `rank_of`, `outliers` or `trim`) are ignored.
+    pub fn new_weighted(pairs: &mut [(f64, usize)], mut options: HistogramOptions) -> Self {
+        let mut stats = Stats::new_weighted(pairs, options.precision);
+        if let Some(level) = options.confidence {
+            stats.set_confidence(level);
+        }
+        if options.log_scale {
+            stats.min = 0.0; // We will silently discard negative values
+        }
+        options.intervals = options.intervals.clamp(1, stats.samples);
+        let mut histogram = Self::new_with_stats(stats, &options);
+        for (value, count) in pairs.iter() {
+            histogram.add_count(*value, *count);
+        }
+        histogram
+    }
+
+    /// Creates two histograms with the same buckets, built for holding the
+    /// values of both inputs, so that they can be compared bucket by bucket.
+    ///
+    /// The statistics of both histograms are the ones of all the values.
+    pub fn new_pair(
+        first: &mut [f64],
+        second: &mut [f64],
+        mut options: HistogramOptions,
+    ) -> (Self, Self) {
+        let mut all: Vec<f64> = first.iter().chain(second.iter()).copied().collect();
+        let mut stats = Stats::new(&mut all, options.precision);
+        if options.log_scale {
+            stats.min = 0.0; // We will silently discard negative values
+        }
+        options.intervals = options.intervals.clamp(1, all.len());
+        let mut first_hist = Self::new_with_stats(stats.clone(), &options);
+        first_hist.load(first);
+        let mut second_hist = Self::new_with_stats(stats, &options);
+        second_hist.load(second);
+        (first_hist, second_hist)
+    }
+
     /// Creates a Histogram with no input data.
     ///
     /// Parameters are similar to those on the `new` method, but a parameter
     /// named `stats` is needed to decide how future data (to be injected with
     /// the load method) will be accommodated.
     pub fn new_with_stats(stats: Stats, options: &HistogramOptions) -> Self {
-        let step = if options.log_scale {
+        let step = if options.log_scale || options.symlog.is_some() {
             f64::NAN
         } else {
             (stats.max - stats.min) / options.intervals as f64
@@ -83,7 +224,10 @@ impl Histogram {
             last: options.intervals - 1,
             stats,
             log_scale: options.log_scale,
+            symlog: options.symlog.map(SymLog::new),
             precision: options.precision,
+            width: options.width,
+            normalize: options.normalize,
         }
     }
 
@@ -96,12 +240,244 @@ impl Histogram {
 
     /// Add to the `Histogram` a single piece of numerical data.
     pub fn add(&mut self, n: f64) {
+        self.add_count(n, 1);
+    }
+
+    /// Add to the `Histogram` `count` occurrences of a piece of numerical
+    /// data.
+    pub fn add_count(&mut self, n: f64, count: usize) {
         if let Some(slot) = self.find_slot(n) {
-            self.vec[slot].inc();
+            self.vec[slot].inc(count);
             self.top = self.top.max(self.vec[slot].count);
         }
     }
 
+    /// Returns the state of the histogram (the edges and counts of its
+    /// buckets, and its statistics) as a JSON object, so that it can be merged
+    /// with the ones of other inputs with `Histogram::from_states`.
+    pub fn to_state(&self) -> serde_json::Value {
+        serde_json::json!({
+            "kind": "histogram",
+            "edges": self.edges(),
+            "counts": self.vec.iter().map(|b| b.count).collect::<Vec<usize>>(),
+            "stats": self.stats.to_state(),
+        })
+    }
+
+    /// Creates a Histogram merging the states written by `to_state` for
+    /// several inputs (like the same metric in several hosts).
+    ///
+    /// Bucket counts are added when all histograms have the same buckets.
+    /// Otherwise, they are spread over buckets of the same size covering all
+    /// of them, which is approximate.  Returns the histogram and whether the
+    /// merge was exact.
+    pub fn from_states(
+        states: &[serde_json::Value],
+        precision: Option<usize>,
+    ) -> Result<(Self, bool), String> {
+        let mut parts = Vec::with_capacity(states.len());
+        let mut stats = Vec::with_capacity(states.len());
+        for state in states {
+            if state["kind"] != "histogram" {
+                return Err(String::from("Not the state of an histogram"));
+            }
+            let edges: Vec<f64> = state["edges"]
+                .as_array()
+                .map(|a| a.iter().filter_map(|x| x.as_f64()).collect())
+                .unwrap_or_default();
+            let counts: Vec<usize> = state["counts"]
+                .as_array()
+                .map(|a| {
+                    a.iter()
+                        .filter_map(|x| x.as_u64())
+                        .map(|x| x as usize)
+                        .collect()
+                })
+                .unwrap_or_default();
+            if counts.is_empty() || edges.len() != counts.len() + 1 {
+                return Err(String::from("Bad buckets in histogram state"));
+            }
+            parts.push((edges, counts));
+            stats.push(Stats::from_state(&state["stats"])?);
+        }
+        let stats = Stats::merge_all(&stats, precision);
+        Ok(Self::from_buckets(&parts, stats, precision))
+    }
+
+    /// Merges the values of `other` (like the ones read by another thread)
+    /// into the histogram.  Bucket counts are added if both histograms have
+    /// the same buckets.  Otherwise, they are spread over buckets of the same
+    /// size covering both, like in `Histogram::from_states`.  Returns whether
+    /// the merge was exact.
+    pub fn merge(&mut self, other: &Self) -> bool {
+        let parts = [
+            (self.edges(), self.counts()),
+            (other.edges(), other.counts()),
+        ];
+        let mut stats = self.stats.clone();
+        stats.merge(&other.stats);
+        if parts[0].0 == parts[1].0 {
+            for (bucket, count) in self.vec.iter_mut().zip(&parts[1].1) {
+                bucket.inc(*count);
+            }
+            self.top = self.vec.iter().map(|b| b.count).max().unwrap();
+            self.stats = stats;
+            return true;
+        }
+        let (merged, exact) = Self::from_buckets(&parts, stats, self.precision);
+        *self = Self {
+            width: self.width,
+            normalize: self.normalize,
+            ..merged
+        };
+        exact
+    }
+
+    // Merges the buckets of several histograms (given as their edges and
+    // counts) into a new one with `stats`, returning whether it was exact
+    fn from_buckets(
+        parts: &[(Vec<f64>, Vec<usize>)],
+        stats: Stats,
+        precision: Option<usize>,
+    ) -> (Self, bool) {
+        let (edges, counts, exact) = merge_buckets(parts);
+        let vec: Vec<Bucket> = edges
+            .windows(2)
+            .zip(counts)
+            .map(|(edge, count)| Bucket {
+                range: edge[0]..edge[1],
+                count,
+            })
+            .collect();
+        // Modes are only found in buckets of the same size (see `modes`)
+        let step = edges[1] - edges[0];
+        let uniform = edges
+            .windows(2)
+            .all(|e| ((e[1] - e[0]) - step).abs() <= step.abs() * 1e-9);
+        let histogram = Self {
+            step: if uniform { step } else { f64::NAN },
+            top: vec.iter().map(|b| b.count).max().unwrap(),
+            last: vec.len() - 1,
+            vec,
+            stats,
+            log_scale: false,
+            symlog: None,
+            precision,
+            width: None,
+            normalize: false,
+        };
+        (histogram, exact)
+    }
+
+    // Edges of the buckets (one more than buckets)
+    fn edges(&self) -> Vec<f64> {
+        let mut edges: Vec<f64> = self.vec.iter().map(|b| b.range.start).collect();
+        edges.push(self.vec[self.last].range.end);
+        edges
+    }
+
+    // Counts of the buckets
+    fn counts(&self) -> Vec<usize> {
+        self.vec.iter().map(|b| b.count).collect()
+    }
+
+    // Writes the buckets of the histogram, with no statistics
+    pub(crate) fn fmt_buckets(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let formatter = match self.precision {
+            None => F64Formatter::new_with_range(self.stats.min..self.stats.max),
+            Some(n) => F64Formatter::new(n),
+        };
+        let writer = HistWriter {
+            width: f.width().or(self.width).unwrap_or(110),
+            formatter,
+        };
+        writer.write(f, self)
+    }
+
+    /// Adds more values to the histogram, keeping its buckets, as long as all
+    /// of them fall in the range of the histogram.  Returns false (leaving the
+    /// histogram untouched) otherwise.
+    ///
+    /// Statistics are updated with `Stats::add_all`.
+    pub fn update(&mut self, vec: &[f64]) -> bool {
+        let (min, max) = (self.vec[0].range.start, self.vec[self.last].range.end);
+        if vec.is_empty() {
+            return true;
+        }
+        if vec.iter().any(|x| *x < min || *x > max) {
+            return false;
+        }
+        for x in vec.iter() {
+            // Buckets are not found with `find_slot`, since restored
+            // histograms may have lost their scale
+            let slot = self.vec.partition_point(|b| b.range.end <= *x);
+            let bucket = &mut self.vec[slot.min(self.last)];
+            bucket.inc(1);
+            self.top = self.top.max(bucket.count);
+        }
+        self.stats.add_all(vec);
+        true
+    }
+
+    /// Returns the statistics of the data used for building the histogram.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Returns the range and count of every bucket.
+    pub(crate) fn buckets(&self) -> impl Iterator<Item = (&Range<f64>, usize)> {
+        self.vec.iter().map(|b| (&b.range, b.count))
+    }
+
+    /// Returns the maximum of all bucket counts.
+    pub(crate) fn top(&self) -> usize {
+        self.top
+    }
+
+    /// Returns `count` in the units displayed: the count itself, or the
+    /// fraction of the values in the histogram it represents (in basis points)
+    /// if the histogram is normalized.
+    pub(crate) fn units(&self, count: usize) -> usize {
+        if !self.normalize {
+            return count;
+        }
+        basis_points(count, self.vec.iter().map(|b| b.count).sum())
+    }
+
+    /// Returns a horizontal scale with `scale` units per character, in the
+    /// units displayed (see `units`).
+    pub(crate) fn horizontal_scale(&self, scale: usize) -> HorizontalScale {
+        HorizontalScale::new(scale).normalized(self.normalize)
+    }
+
+    /// Formats a count given in the units displayed (see `units`).
+    pub(crate) fn format_units(&self, units: usize) -> String {
+        match self.normalize {
+            true => format_share(units),
+            false => units.to_string(),
+        }
+    }
+
+    /// Returns the number of decimals to display, if not human friendly.
+    pub(crate) fn precision(&self) -> Option<usize> {
+        self.precision
+    }
+
+    /// Returns the approximate location of the modes of the data (the centers
+    /// of the buckets that are clear peaks).  Only linear scales are
+    /// considered, since peaks in buckets of different sizes are misleading.
+    fn modes(&self) -> Vec<f64> {
+        if self.step.is_nan() {
+            return vec![];
+        }
+        let counts: Vec<usize> = self.vec.iter().map(|b| b.count).collect();
+        let min_prominence = ((self.top as f64 * MODE_PROMINENCE).ceil() as usize).max(2);
+        find_peaks(&counts, min_prominence)
+            .into_iter()
+            .map(|i| (self.vec[i].range.start + self.vec[i].range.end) / 2.0)
+            .collect()
+    }
+
     fn find_slot(&self, n: f64) -> Option<usize> {
         if n < self.stats.min || n > self.stats.max {
             return None;
@@ -115,6 +491,12 @@ impl Histogram {
                 }
             }
             bucket
+        } else if let Some(symlog) = self.symlog {
+            let start = symlog.forward(self.stats.min);
+            let total = symlog.forward(self.stats.max) - start;
+            let slot = (symlog.forward(n) - start) / total * self.vec.len() as f64;
+            // A NaN slot (all values are the same) is casted as 0
+            Some((slot as usize).min(self.last))
         } else {
             Some((((n - self.stats.min) / self.step) as usize).min(self.last))
         }
@@ -130,6 +512,11 @@ impl Histogram {
                 vec.push(Bucket::new(lower..upper));
                 lower = upper;
             }
+        } else if let Some(threshold) = options.symlog {
+            let edges = SymLog::new(threshold).edges(range.start, range.end, options.intervals);
+            for edge in edges.windows(2) {
+                vec.push(Bucket::new(edge[0]..edge[1]));
+            }
         } else {
             let step = (range.end - range.start) / options.intervals as f64;
             let mut lower = range.start;
@@ -145,15 +532,71 @@ impl Histogram {
 impl fmt::Display for Histogram {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.stats)?;
+        self.fmt_buckets(f)
+    }
+}
+
+impl Render for Histogram {
+    fn rows(&self) -> Vec<(String, usize)> {
         let formatter = match self.precision {
             None => F64Formatter::new_with_range(self.stats.min..self.stats.max),
             Some(n) => F64Formatter::new(n),
         };
-        let writer = HistWriter {
-            width: f.width().unwrap_or(110),
-            formatter,
-        };
-        writer.write(f, self)
+        self.vec
+            .iter()
+            .map(|b| {
+                let label = format!(
+                    "[{} .. {}]",
+                    formatter.format(b.range.start),
+                    formatter.format(b.range.end)
+                );
+                (label, b.count)
+            })
+            .collect()
+    }
+
+    fn table(&self) -> Option<Table> {
+        let mut table = Table::new(&["lower", "upper", "count"]);
+        for b in &self.vec {
+            table.push(vec![
+                b.range.start.into(),
+                b.range.end.into(),
+                b.count.into(),
+            ]);
+        }
+        Some(table)
+    }
+}
+
+impl Raster for Histogram {
+    /// Draws the buckets as vertical bars, with the smallest values on the
+    /// left.
+    fn raster(&self, width: usize, height: usize, theme: Theme) -> Image {
+        let mut image = Image::new(width, height);
+        if width == 0 || height == 0 {
+            return image;
+        }
+        let slot = width as f64 / self.vec.len() as f64;
+        // Some room between bars, if there is enough of it
+        let gap = (slot >= 4.0) as usize;
+        for (i, bucket) in self.vec.iter().enumerate() {
+            if bucket.count == 0 {
+                continue;
+            }
+            let bar = (bucket.count as f64 / self.top as f64 * height as f64).ceil() as usize;
+            let x0 = (i as f64 * slot) as usize;
+            let x1 = (((i + 1) as f64 * slot) as usize)
+                .saturating_sub(1 + gap)
+                .max(x0);
+            image.fill((x0, height - bar), (x1, height - 1), rgb(theme.bar));
+        }
+        image
+    }
+}
+
+impl ToSvg for Histogram {
+    fn to_svg(&self, width: usize, theme: Theme) -> String {
+        svg::bar_chart(&self.stats.to_string(), &self.rows(), width, theme)
     }
 }
 
@@ -165,35 +608,61 @@ struct HistWriter {
 impl HistWriter {
     pub fn write(&self, f: &mut fmt::Formatter, hist: &Histogram) -> fmt::Result {
         let width_range = self.get_width(hist);
-        let width_count = ((hist.top as f64).log10().ceil() as usize).max(1);
+        let top = hist.units(hist.top);
+        let width_count = match hist.normalize {
+            true => hist.format_units(top).len(),
+            false => ((hist.top as f64).log10().ceil() as usize).max(1),
+        };
         let horizontal_scale =
-            HorizontalScale::new(hist.top / self.get_max_bar_len(width_range + width_count));
+            hist.horizontal_scale(top / self.get_max_bar_len(width_range + width_count));
         writeln!(f, "{horizontal_scale}")?;
         for x in &hist.vec {
-            self.write_bucket(f, x, &horizontal_scale, width_range, width_count)?;
+            let units = hist.units(x.count);
+            self.write_bucket(f, x, units, &horizontal_scale, width_range, width_count)?;
         }
-        Ok(())
+        self.write_modes(f, &hist.modes())
+    }
+
+    fn write_modes(&self, f: &mut fmt::Formatter, modes: &[f64]) -> fmt::Result {
+        let theme = Theme::current();
+        if modes.len() < 2 {
+            return Ok(());
+        }
+        let locations: Vec<String> = modes
+            .iter()
+            .map(|m| format!("~{}", self.formatter.format(*m)))
+            .collect();
+        let (last, rest) = locations.split_last().unwrap();
+        writeln!(
+            f,
+            "{} modes detected at {} and {}",
+            theme.label.paint(modes.len().to_string()),
+            rest.join(", "),
+            last
+        )
     }
 
     fn write_bucket(
         &self,
         f: &mut fmt::Formatter,
         bucket: &Bucket,
+        units: usize,
         horizontal_scale: &HorizontalScale,
         width: usize,
         width_count: usize,
     ) -> fmt::Result {
+        let theme = Theme::current();
         writeln!(
             f,
             "[{range}] [{count}] {bar}",
-            range = Blue.paint(format!(
+            range = theme.label.paint(format!(
                 "{:>width$} .. {:>width$}",
                 self.formatter.format(bucket.range.start),
                 self.formatter.format(bucket.range.end),
                 width = width,
             )),
-            count = horizontal_scale.get_count(bucket.count, width_count),
-            bar = horizontal_scale.get_bar(bucket.count)
+            count = horizontal_scale.get_count(units, width_count),
+            bar = horizontal_scale.get_bar(units)
         )
     }
 
@@ -220,6 +689,54 @@ mod tests {
     use float_eq::assert_float_eq;
     use yansi::Paint;
 
+    #[test]
+    fn test_merge() {
+        let options = HistogramOptions {
+            intervals: 2,
+            ..Default::default()
+        };
+        let first = Histogram::new(&mut [1.0, 2.0, 3.0], options.clone());
+        let second = Histogram::new(&mut [1.0, 3.0, 3.0], options.clone());
+        let states = [first.to_state(), second.to_state()];
+        let (merged, exact) = Histogram::from_states(&states, None).unwrap();
+        assert!(exact);
+        assert_eq!(merged.stats().samples, 6);
+        assert_eq!(
+            merged.buckets().map(|(_, c)| c).collect::<Vec<_>>(),
+            vec![2, 4]
+        );
+        let third = Histogram::new(&mut [5.0, 7.0], options.clone());
+        let states = [first.to_state(), third.to_state()];
+        let (merged, exact) = Histogram::from_states(&states, None).unwrap();
+        assert!(!exact);
+        assert_eq!(merged.stats().max, 7.0);
+        assert_eq!(
+            merged.buckets().map(|(_, c)| c).collect::<Vec<_>>(),
+            vec![3, 2]
+        );
+        let (mut restored, _) = Histogram::from_states(&[first.to_state()], None).unwrap();
+        assert!(restored.update(&[3.0, 1.5]));
+        assert_eq!(restored.stats().samples, 5);
+        assert_eq!(
+            restored.buckets().map(|(_, c)| c).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert!(!restored.update(&[3.5]));
+        assert_eq!(restored.stats().samples, 5);
+        let bad = serde_json::json!({"kind": "timehist"});
+        assert!(Histogram::from_states(&[bad], None).is_err());
+        let mut merged = Histogram::new(&mut [1.0, 2.0, 3.0], options.clone());
+        assert!(merged.merge(&second));
+        assert_eq!(merged.stats().samples, 6);
+        assert_eq!(
+            merged.buckets().map(|(_, c)| c).collect::<Vec<_>>(),
+            vec![2, 4]
+        );
+        assert!(!merged.merge(&third));
+        assert_eq!(merged.stats().samples, 8);
+        assert_eq!(merged.stats().max, 7.0);
+    }
+
     #[test]
     fn test_buckets() {
         let stats = Stats::new(&mut [-2.0, 14.0], None);
@@ -252,6 +769,37 @@ mod tests {
         assert_eq!(hist.top, 2);
     }
 
+    #[test]
+    fn test_weighted() {
+        let options = HistogramOptions {
+            intervals: 2,
+            ..Default::default()
+        };
+        let hist = Histogram::new_weighted(&mut [(0.0, 3), (4.0, 40), (1.0, 2)], options);
+        assert_eq!(hist.stats.samples, 45);
+        assert_eq!(hist.vec[0].count, 5);
+        assert_eq!(hist.vec[1].count, 40);
+        assert_eq!(hist.top, 40);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let options = HistogramOptions {
+            intervals: 2,
+            ..Default::default()
+        };
+        let mut hist = Histogram::new_with_stats(Stats::new(&mut [0.0, 4.0], None), &options);
+        hist.load(&[0.0, 1.0, 3.0]);
+        let value = serde_json::to_value(&hist).unwrap();
+        assert_eq!(value["buckets"][0]["range"]["start"], 0.0);
+        assert_eq!(value["buckets"][0]["range"]["end"], 2.0);
+        assert_eq!(value["buckets"][0]["count"], 2);
+        assert_eq!(value["buckets"][1]["count"], 1);
+        assert_eq!(value["stats"]["samples"], 2);
+        assert!(value.get("top").is_none());
+    }
+
     #[test]
     fn display_test() {
         let stats = Stats::new(&mut [-2.0, 14.0], None);
@@ -289,6 +837,82 @@ mod tests {
         assert!(display.contains("[-2.000 ..  0.000] [3] ∎∎∎\n"));
     }
 
+    #[test]
+    fn svg_test() {
+        let options = HistogramOptions {
+            intervals: 2,
+            precision: Some(1),
+            ..Default::default()
+        };
+        let histogram = Histogram::new(&mut [1.0, 2.0, 2.0, 2.0], options);
+        let svg = histogram.to_svg(300, Theme::DEFAULT);
+        assert!(svg.contains(">Samples = 4; Min = 1.0; Max = 2.0</text>"));
+        assert!(svg.contains(">[1.0 .. 1.5]</text>"));
+        assert!(svg.contains(">[1.5 .. 2.0]</text>"));
+        assert!(svg.contains(">3</text>"));
+    }
+
+    #[test]
+    fn csv_test() {
+        let options = HistogramOptions {
+            intervals: 2,
+            ..Default::default()
+        };
+        let histogram = Histogram::new(&mut [1.0, 2.0, 2.0, 2.0], options);
+        assert_eq!(
+            histogram.table().unwrap().to_csv(','),
+            "lower,upper,count\n1,1.5,1\n1.5,2,3\n"
+        );
+    }
+
+    #[test]
+    fn raster_test() {
+        let options = HistogramOptions {
+            intervals: 2,
+            ..Default::default()
+        };
+        let histogram = Histogram::new(&mut [1.0, 2.0, 2.0, 2.0], options);
+        let image = histogram.raster(8, 4, Theme::DEFAULT);
+        let bar = Some(rgb(Theme::DEFAULT.bar));
+        // A third of the height (rounded up), and the full height
+        assert_eq!(image.get(0, 2), bar);
+        assert_eq!(image.get(2, 3), bar);
+        assert_eq!(image.get(0, 1), None);
+        assert_eq!(image.get(3, 3), None);
+        assert_eq!(image.get(4, 0), bar);
+        assert_eq!(image.get(6, 0), bar);
+        assert_eq!(image.get(7, 0), None);
+    }
+
+    #[test]
+    fn display_test_builder() {
+        let hist = HistogramBuilder::default()
+            .intervals(8)
+            .precision(3)
+            .width(2)
+            .build(&mut [
+                -2.0, 14.0, -1.0, -1.1, 2.0, 2.0, 2.1, -0.9, 11.0, 11.2, 1.9, 1.99, 1.98,
+            ]);
+        assert_eq!(hist.vec.len(), 8);
+        Paint::disable();
+        let display = format!("{hist}");
+        assert!(display.contains("[-2.000 ..  0.000] [4] ∎∎∎∎\n"));
+    }
+
+    #[test]
+    fn display_test_normalized() {
+        let hist = HistogramBuilder::default()
+            .intervals(2)
+            .precision(1)
+            .normalize(true)
+            .build(&mut [1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 2.0, 2.0]);
+        Paint::disable();
+        let display = format!("{hist:50}");
+        assert!(display.contains("Each ∎ represents 2.01% of the values\n"));
+        assert!(display.contains(&format!("[1.0 .. 1.5] [37.50%] {}\n", "∎".repeat(18))));
+        assert!(display.contains(&format!("[1.5 .. 2.0] [62.50%] {}\n", "∎".repeat(31))));
+    }
+
     #[test]
     fn display_test_human_units() {
         let vector = &mut [
@@ -381,6 +1005,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn display_test_symlog() {
+        let hist = Histogram::new(
+            &mut [-99.0, -50.0, -5.0, -0.5, 0.2, 0.5, 3.0, 80.0, 99.0],
+            HistogramOptions {
+                intervals: 4,
+                symlog: Some(1.0),
+                precision: Some(1),
+                ..Default::default()
+            },
+        );
+        Paint::disable();
+        let display = format!("{hist}");
+        assert!(display.contains("[-99.0 ..  -9.0] [2] ∎∎\n"));
+        assert!(display.contains("[ -9.0 ..   0.0] [2] ∎∎\n"));
+        assert!(display.contains("[  0.0 ..   9.0] [3] ∎∎∎\n"));
+        assert!(display.contains("[  9.0 ..  99.0] [2] ∎∎\n"));
+    }
+
+    #[test]
+    fn display_test_modes() {
+        let mut vec = vec![10.0; 8];
+        vec.extend([0.0, 20.0, 30.0, 40.0, 40.0, 50.0, 60.0, 70.0]);
+        vec.extend([80.0; 6]);
+        vec.push(100.0);
+        let options = HistogramOptions {
+            intervals: 10,
+            precision: Some(0),
+            ..Default::default()
+        };
+        let hist = Histogram::new(&mut vec.clone(), options.clone());
+        assert_eq!(hist.modes(), vec![15.0, 85.0]);
+        Paint::disable();
+        let display = format!("{hist}");
+        assert!(display.ends_with("\n2 modes detected at ~15 and ~85\n"));
+        // A single mode is not reported
+        let hist = Histogram::new(&mut vec[..16], options);
+        assert_eq!(hist.modes(), vec![10.5]);
+        assert!(!format!("{hist}").contains("modes"));
+    }
+
     #[test]
     fn build_buckets_no_log_scale() {
         let options = HistogramOptions {
@@ -389,10 +1054,10 @@ mod tests {
         };
         let buckets = Histogram::build_buckets(0.0..700.0, &options);
         assert!(buckets.len() == 7);
-        for i in 0..6 {
+        for (i, bucket) in buckets.iter().enumerate() {
             let min = (i * 100) as f64;
             let max = ((i + 1) * 100) as f64;
-            assert!(buckets[i].range == (min..max));
+            assert!(bucket.range == (min..max));
         }
     }
 
@@ -403,8 +1068,8 @@ mod tests {
             ..Default::default()
         };
         let hist = Histogram::new_with_stats(Stats::new(&mut [-12.0, 4.0], None), &options);
-        assert!(hist.find_slot(-13.0) == None);
-        assert!(hist.find_slot(13.0) == None);
+        assert!(hist.find_slot(-13.0).is_none());
+        assert!(hist.find_slot(13.0).is_none());
         assert!(hist.find_slot(-12.0) == Some(0));
         assert!(hist.find_slot(-11.0) == Some(0));
         assert!(hist.find_slot(-9.0) == Some(1));
@@ -423,7 +1088,7 @@ mod tests {
                 ..Default::default()
             },
         );
-        assert!(hist.find_slot(-1.0) == None);
+        assert!(hist.find_slot(-1.0).is_none());
         assert!(hist.find_slot(0.0) == Some(0));
         assert!(hist.find_slot(0.5) == Some(0));
         assert!(hist.find_slot(1.5) == Some(1));
@@ -431,6 +1096,6 @@ mod tests {
         assert!(hist.find_slot(33.1) == Some(5));
         assert!(hist.find_slot(127.1) == Some(7));
         assert!(hist.find_slot(247.1) == Some(7));
-        assert!(hist.find_slot(1000.0) == None);
+        assert!(hist.find_slot(1000.0).is_none());
     }
 }
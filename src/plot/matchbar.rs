@@ -1,39 +1,98 @@
 use std::fmt;
 
-use yansi::Color::Blue;
+use regex::Regex;
 
-use crate::format::HorizontalScale;
+use crate::format::{HorizontalScale, Theme};
+use crate::plot::sort::{sort_rows, SortOrder};
+use crate::plot::Render;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 /// A struct that represents a single match bar of a match bar histogram (a
 /// bucket for a term/label).
 pub struct MatchBarRow {
     pub label: String,
     pub count: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    regex: Option<Regex>,
 }
 
 impl MatchBarRow {
+    /// Creates a row counting the lines containing `string`.
     pub fn new(string: &str) -> Self {
         Self {
             label: string.to_string(),
             count: 0,
+            regex: None,
+        }
+    }
+
+    /// Creates a row counting the lines where `regex` matches.
+    pub fn new_with_regex(regex: Regex) -> Self {
+        Self {
+            label: regex.as_str().to_string(),
+            count: 0,
+            regex: Some(regex),
         }
     }
 
+    /// Makes the row count only matches that are whole words (with word
+    /// boundaries around them, in the sense of `\b` in regular expressions),
+    /// so that "ERR" does not match "ERROR".  The label is kept as it is.
+    pub fn whole_words(mut self) -> Self {
+        let pattern = match &self.regex {
+            Some(re) => format!(r"\b(?:{})\b", re.as_str()),
+            None => {
+                // No boundary is required next to a non-word character, like
+                // the brackets of "[ERR]"
+                let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+                let boundary = |c| if is_word(c) { r"\b" } else { "" };
+                format!(
+                    "{}{}{}",
+                    boundary(self.label.chars().next()),
+                    regex::escape(&self.label),
+                    boundary(self.label.chars().last())
+                )
+            }
+        };
+        self.regex = Some(Regex::new(&pattern).unwrap());
+        self
+    }
+
     pub fn inc_if_matches(&mut self, line: &str) {
-        if line.contains(&self.label) {
+        let matches = match &self.regex {
+            Some(re) => re.is_match(line),
+            None => line.contains(&self.label),
+        };
+        if matches {
             self.count += 1;
         }
     }
+
+    /// Increases the count by the number of (non overlapping) matches within
+    /// the line.
+    pub fn inc_by_occurrences(&mut self, line: &str) {
+        self.count += match &self.regex {
+            Some(re) => re.find_iter(line).count(),
+            None => line.matches(&self.label).count(),
+        };
+    }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 /// A struct holding data to plot a `MatchBar`: a histogram of the number of
 /// occurrences of a set of strings in some input dara.
 pub struct MatchBar {
     pub vec: Vec<MatchBarRow>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     top_values: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
     top_length: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    sort: SortOrder,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    reverse: bool,
 }
 
 impl MatchBar {
@@ -49,29 +108,64 @@ impl MatchBar {
             vec,
             top_values,
             top_length,
+            sort: SortOrder::None,
+            reverse: false,
         }
     }
+
+    /// Merges the counts of `other` (like the ones of the lines read by
+    /// another thread) into these ones.  Rows are matched by label, and the
+    /// ones only in `other` are appended.
+    #[allow(dead_code)] // Only used by library users
+    pub fn merge(&mut self, other: &Self) {
+        for theirs in &other.vec {
+            match self.vec.iter_mut().find(|row| row.label == theirs.label) {
+                Some(row) => row.count += theirs.count,
+                None => self.vec.push(MatchBarRow {
+                    label: theirs.label.clone(),
+                    count: theirs.count,
+                    regex: theirs.regex.clone(),
+                }),
+            }
+        }
+        for row in &self.vec {
+            self.top_length = self.top_length.max(row.label.len());
+            self.top_values = self.top_values.max(row.count);
+        }
+    }
+
+    /// Sets the order in which rows are displayed (being `SortOrder::None` the
+    /// order of the rows in `vec`).
+    pub fn set_sort(&mut self, sort: SortOrder, reverse: bool) {
+        self.sort = sort;
+        self.reverse = reverse;
+    }
 }
 
 impl fmt::Display for MatchBar {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
         let width = f.width().unwrap_or(100);
         let horizontal_scale = HorizontalScale::new(self.top_values / width);
         let width_count = format!("{}", self.top_values).len();
         writeln!(
             f,
             "Matches: {}.",
-            Blue.paint(format!(
+            theme.label.paint(format!(
                 "{}",
                 self.vec.iter().map(|r| r.count).sum::<usize>()
             )),
         )?;
         writeln!(f, "{horizontal_scale}")?;
-        for row in &self.vec {
+        let mut rows: Vec<&MatchBarRow> = self.vec.iter().collect();
+        sort_rows(&mut rows, self.sort, self.reverse, |r| (&r.label, r.count));
+        for row in rows {
             writeln!(
                 f,
                 "[{label}] [{count}] {bar}",
-                label = Blue.paint(format!("{:width$}", row.label, width = self.top_length)),
+                label = theme
+                    .label
+                    .paint(format!("{:width$}", row.label, width = self.top_length)),
                 count = horizontal_scale.get_count(row.count, width_count),
                 bar = horizontal_scale.get_bar(row.count)
             )?;
@@ -80,6 +174,14 @@ impl fmt::Display for MatchBar {
     }
 }
 
+impl Render for MatchBar {
+    fn rows(&self) -> Vec<(String, usize)> {
+        let mut rows: Vec<&MatchBarRow> = self.vec.iter().collect();
+        sort_rows(&mut rows, self.sort, self.reverse, |r| (&r.label, r.count));
+        rows.iter().map(|r| (r.label.clone(), r.count)).collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,4 +208,91 @@ mod tests {
         assert!(display.contains("represents a count of 1"));
         assert!(display.contains("Matches: 4"));
     }
+
+    #[test]
+    fn test_merge() {
+        let mut row = MatchBarRow::new("a");
+        row.inc_if_matches("a");
+        let mut mb = MatchBar::new(vec![row]);
+        let mut row0 = MatchBarRow::new("a");
+        row0.inc_if_matches("ab");
+        let mut row1 = MatchBarRow::new_with_regex(Regex::new("b+").unwrap());
+        row1.inc_by_occurrences("b bb");
+        mb.merge(&MatchBar::new(vec![row0, row1]));
+        let counts: Vec<(&str, usize)> =
+            mb.vec.iter().map(|r| (r.label.as_str(), r.count)).collect();
+        assert_eq!(counts, vec![("a", 2), ("b+", 2)]);
+        assert_eq!(mb.top_length, 2);
+        assert_eq!(mb.top_values, 2);
+    }
+
+    #[test]
+    fn test_whole_words() {
+        let mut row = MatchBarRow::new("ERR").whole_words();
+        for line in ["ERROR here", "TERRIBLE", "ERR: here", "an ERR", "[ERR]"] {
+            row.inc_if_matches(line);
+        }
+        assert_eq!(row.label, "ERR");
+        assert_eq!(row.count, 3);
+        let mut row = MatchBarRow::new("[ERR]").whole_words();
+        row.inc_if_matches("x[ERR]y");
+        assert_eq!(row.count, 1);
+        let mut row = MatchBarRow::new_with_regex(Regex::new("ab|cd").unwrap()).whole_words();
+        row.inc_by_occurrences("ab abc cd xcd cd");
+        assert_eq!(row.label, "ab|cd");
+        assert_eq!(row.count, 3);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let mut row = MatchBarRow::new_with_regex(Regex::new("a+").unwrap());
+        row.inc_if_matches("baa");
+        let value = serde_json::to_value(MatchBar::new(vec![row])).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"vec": [{"label": "a+", "count": 1}]})
+        );
+    }
+
+    #[test]
+    fn test_matchbar_sort() {
+        let mut rows = Vec::new();
+        for (label, count) in [("b", 1), ("c", 3), ("a", 2)] {
+            let mut row = MatchBarRow::new(label);
+            for _ in 0..count {
+                row.inc_if_matches(label);
+            }
+            rows.push(row);
+        }
+        let mut mb = MatchBar::new(rows);
+        let labels = |mb: &MatchBar| {
+            Paint::disable();
+            format!("{mb}")
+                .lines()
+                .filter(|l| l.starts_with('['))
+                .map(|l| l[1..2].to_string())
+                .collect::<String>()
+        };
+        assert_eq!(labels(&mb), "bca");
+        mb.set_sort(SortOrder::Count, false);
+        assert_eq!(labels(&mb), "cab");
+        mb.set_sort(SortOrder::Label, true);
+        assert_eq!(labels(&mb), "cba");
+    }
+
+    #[test]
+    fn test_matchbar_regex() {
+        let mut row = MatchBarRow::new_with_regex(Regex::new(r"\bid\b").unwrap());
+        row.inc_if_matches("user id 3");
+        row.inc_if_matches("pid 3");
+        row.inc_if_matches("id");
+        assert_eq!(row.label, r"\bid\b");
+        assert_eq!(row.count, 2);
+        row.inc_by_occurrences("id and id, but not pid");
+        assert_eq!(row.count, 4);
+        let mut row = MatchBarRow::new("aa");
+        row.inc_by_occurrences("aaaaa");
+        assert_eq!(row.count, 2);
+    }
 }
@@ -1,16 +1,56 @@
-pub use self::histogram::{Histogram, HistogramOptions};
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, FixedOffset};
+
+use crate::format::Theme;
+
+pub use self::compare::HistogramComparison;
+pub use self::describe::Description;
+pub use self::distinct::DistinctTimeHistogram;
+pub use self::gaps::Gaps;
+pub use self::groupstats::GroupStats;
+pub use self::heatmap::HeatMap;
+pub use self::histogram::{Histogram, HistogramBuilder, HistogramOptions};
 pub use self::matchbar::{MatchBar, MatchBarRow};
-pub use self::splittimehist::SplitTimeHistogram;
+pub use self::overlay::HistogramOverlay;
+pub use self::period::Periodicity;
+pub use self::render::{Format, Graphics, Image, Raster, Render, RenderOptions, Table, ToSvg};
+pub use self::series::{SeriesHistogram, SeriesPlot};
+pub use self::sessions::Sessions;
+pub use self::sort::SortOrder;
+pub use self::splittimehist::{SplitStyle, SplitTimeHistogram};
 pub use self::terms::CommonTerms;
-pub use self::timehist::TimeHistogram;
-pub use self::xy::XyPlot;
+pub use self::timehist::{Aggregation, TimeHistogram, TimeHistogramBuilder};
+pub use self::timepercentiles::TimePercentiles;
+pub use self::top::TopKeys;
+pub use self::xy::{Downsampler, XyPlot, XyPlotBuilder, XyPlotStream};
+pub use self::zipf::ZipfPlot;
 
+mod canvas;
+mod compare;
+mod describe;
+mod distinct;
+mod gaps;
+mod groupstats;
+mod heatmap;
 mod histogram;
 mod matchbar;
+mod overlay;
+mod period;
+mod render;
+mod series;
+mod sessions;
+mod sort;
 mod splittimehist;
+mod symlog;
 mod terms;
 mod timehist;
+mod timepercentiles;
+mod top;
 mod xy;
+mod zipf;
 
 /// Returns a datetime formatting string with a resolution that makes sense for a
 /// given number of seconds
@@ -23,11 +63,203 @@ fn date_fmt_string(seconds: i64) -> &'static str {
     }
 }
 
+/// How the buckets of time charts are labelled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TimeLabel {
+    /// Timestamp of the start of the bucket.
+    #[default]
+    Start,
+    /// Timestamp of the end of the bucket.
+    End,
+    /// Timestamps of both the start and the end of the bucket.
+    Range,
+    /// Time elapsed since the start of the first bucket, like `+00:05:00`.
+    Relative,
+}
+
+impl FromStr for TimeLabel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "start" => Ok(TimeLabel::Start),
+            "end" => Ok(TimeLabel::End),
+            "range" => Ok(TimeLabel::Range),
+            "relative" => Ok(TimeLabel::Relative),
+            _ => Err(format!("Unknown label style: {s}")),
+        }
+    }
+}
+
+impl TimeLabel {
+    /// Returns the label of the bucket spanning `bucket`, in a chart starting
+    /// at `first`, with timestamps formatted with `fmt_string` (as returned
+    /// by `date_fmt_string`).
+    fn format(
+        self,
+        bucket: &Range<DateTime<FixedOffset>>,
+        first: DateTime<FixedOffset>,
+        fmt_string: &str,
+    ) -> String {
+        match self {
+            TimeLabel::Start => bucket.start.format(fmt_string).to_string(),
+            TimeLabel::End => bucket.end.format(fmt_string).to_string(),
+            TimeLabel::Range => format!(
+                "{}..{}",
+                bucket.start.format(fmt_string),
+                bucket.end.format(fmt_string)
+            ),
+            TimeLabel::Relative => {
+                let micros = (bucket.start - first)
+                    .num_microseconds()
+                    .unwrap_or(0)
+                    .max(0);
+                let seconds = micros / 1_000_000;
+                let mut label = format!(
+                    "+{:02}:{:02}:{:02}",
+                    seconds / 3600,
+                    seconds / 60 % 60,
+                    seconds % 60
+                );
+                // Same resolution as the timestamps would have
+                if fmt_string.ends_with("%.3f") {
+                    label.push_str(&format!(".{:03}", micros / 1000 % 1000));
+                } else if fmt_string.ends_with("%.6f") {
+                    label.push_str(&format!(".{:06}", micros % 1_000_000));
+                }
+                label
+            }
+        }
+    }
+}
+
+/// Formats a chrono duration the human way, with a resolution of milliseconds.
+fn human_duration(duration: Duration) -> String {
+    let millis = duration.num_milliseconds().max(0) as u64;
+    humantime::format_duration(std::time::Duration::from_millis(millis)).to_string()
+}
+
+/// Merges the buckets of several histograms, given as the edges of their
+/// buckets (one more than buckets) and their counts.  If all of them have the
+/// same edges, counts are just added.  Otherwise, counts are spread over
+/// buckets of the same size covering all of them, in proportion to how much
+/// they overlap (as if values were evenly distributed in every bucket).
+///
+/// Returns the edges and counts of the merged buckets, and whether the merge
+/// was exact (all edges were the same).
+fn merge_buckets(parts: &[(Vec<f64>, Vec<usize>)]) -> (Vec<f64>, Vec<usize>, bool) {
+    let (edges, _) = &parts[0];
+    if parts.iter().all(|(other, _)| other == edges) {
+        let mut counts = vec![0; edges.len() - 1];
+        for (_, other) in parts {
+            counts.iter_mut().zip(other).for_each(|(c, o)| *c += o);
+        }
+        return (edges.clone(), counts, true);
+    }
+    let start = parts
+        .iter()
+        .map(|(e, _)| e[0])
+        .fold(f64::INFINITY, f64::min);
+    let end = parts
+        .iter()
+        .map(|(e, _)| e[e.len() - 1])
+        .fold(f64::NEG_INFINITY, f64::max);
+    let size = parts.iter().map(|(_, c)| c.len()).max().unwrap();
+    let step = (end - start) / size as f64;
+    let edges: Vec<f64> = (0..=size).map(|i| start + step * i as f64).collect();
+    let slot = |x: f64| match step {
+        step if step > 0.0 => (((x - start) / step) as usize).min(size - 1),
+        _ => 0,
+    };
+    let mut shares = vec![0.0; size];
+    for (part_edges, counts) in parts {
+        for (bucket, count) in part_edges.windows(2).zip(counts) {
+            let (low, high) = (bucket[0], bucket[1]);
+            if high <= low {
+                shares[slot(low)] += *count as f64;
+                continue;
+            }
+            for (i, share) in shares
+                .iter_mut()
+                .enumerate()
+                .take(slot(high) + 1)
+                .skip(slot(low))
+            {
+                let overlap = high.min(edges[i + 1]) - low.max(edges[i]);
+                *share += *count as f64 * overlap.max(0.0) / (high - low);
+            }
+        }
+    }
+    let counts = shares.iter().map(|share| share.round() as usize).collect();
+    (edges, counts, false)
+}
+
+/// Writes a line labelling the first and last columns of a plot with their
+/// timestamps.  `offset` is the width of the labels of the rows.
+fn print_time_axis(
+    f: &mut fmt::Formatter,
+    range: &Range<DateTime<FixedOffset>>,
+    offset: usize,
+    columns: usize,
+) -> fmt::Result {
+    let theme = Theme::current();
+    let fmt_string = date_fmt_string((range.end - range.start).num_seconds());
+    let start = range.start.format(fmt_string).to_string();
+    let end = range.end.format(fmt_string).to_string();
+    let gap = columns.saturating_sub(start.len() + end.len()).max(1);
+    writeln!(
+        f,
+        "{:offset$}{}{:gap$}{}",
+        "",
+        theme.label.paint(start),
+        "",
+        theme.label.paint(end),
+    )
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_merge_buckets() {
+        let a = (vec![0.0, 1.0, 2.0], vec![3, 1]);
+        let b = (vec![0.0, 1.0, 2.0], vec![1, 1]);
+        assert_eq!(
+            merge_buckets(&[a.clone(), b]),
+            (vec![0.0, 1.0, 2.0], vec![4, 2], true)
+        );
+        let c = (vec![1.0, 3.0, 4.0], vec![4, 2]);
+        assert_eq!(
+            merge_buckets(&[a, c]),
+            (vec![0.0, 2.0, 4.0], vec![6, 4], false)
+        );
+    }
+
+    #[test]
+    fn test_time_labels() {
+        let ts = |s| DateTime::parse_from_rfc3339(s).unwrap();
+        let first = ts("2021-04-15T04:00:00+00:00");
+        let bucket = ts("2021-04-15T05:05:00+00:00")..ts("2021-04-15T05:10:00+00:00");
+        assert_eq!(TimeLabel::Start.format(&bucket, first, "%H:%M"), "05:05");
+        assert_eq!(TimeLabel::End.format(&bucket, first, "%H:%M"), "05:10");
+        assert_eq!(
+            TimeLabel::Range.format(&bucket, first, "%H:%M"),
+            "05:05..05:10"
+        );
+        assert_eq!(
+            TimeLabel::Relative.format(&bucket, first, "%H:%M:%S"),
+            "+01:05:00"
+        );
+        assert_eq!(
+            TimeLabel::Relative.format(&bucket, first, "%H:%M:%S%.3f"),
+            "+01:05:00.000"
+        );
+        assert_eq!("range".parse::<TimeLabel>(), Ok(TimeLabel::Range));
+        assert!("middle".parse::<TimeLabel>().is_err());
+    }
+
     #[test]
     fn test_fmt_strings() {
         assert_eq!(date_fmt_string(100000), "%Y-%m-%d %H:%M:%S");
@@ -0,0 +1,162 @@
+use std::fmt;
+
+use yansi::Color;
+
+use crate::format::{F64Formatter, Theme, BAR_CHAR};
+use crate::plot::{Histogram, Render, Table};
+
+/// Character for the bars of the second histogram
+const SECOND_CHAR: &str = "+";
+/// Character for the part of the bars shared by both histograms
+const BOTH_CHAR: &str = "#";
+
+/// A struct for displaying two histograms with the same buckets in a single
+/// chart, with the bars of both of them overlaid in the same rows.
+///
+/// It is created with `Histogram::overlay`.
+pub struct HistogramOverlay<'a> {
+    first: &'a Histogram,
+    second: &'a Histogram,
+    labels: (String, String),
+}
+
+impl Histogram {
+    /// Returns a plot with this histogram and `other` overlaid.
+    ///
+    /// Rows are paired by position, so both histograms are expected to share
+    /// their buckets (see `Histogram::new_pair`).  Otherwise, the buckets of
+    /// this histogram are displayed.
+    pub fn overlay<'a>(&'a self, other: &'a Histogram) -> HistogramOverlay<'a> {
+        HistogramOverlay {
+            first: self,
+            second: other,
+            labels: (String::from("first"), String::from("second")),
+        }
+    }
+}
+
+impl HistogramOverlay<'_> {
+    /// Sets the names of both histograms in the legend.
+    pub fn set_labels(&mut self, first: &str, second: &str) {
+        self.labels = (first.to_string(), second.to_string());
+    }
+}
+
+impl fmt::Display for HistogramOverlay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
+        let width = f.width().unwrap_or(110);
+        let stats = self.first.stats();
+        write!(f, "{stats}")?;
+        let total = |h: &Histogram| h.buckets().map(|(_, c)| c).sum::<usize>();
+        let (first_color, second_color, both_color) =
+            (theme.palette[0], theme.palette[2], theme.palette[1]);
+        writeln!(
+            f,
+            "{} {} ({}); {} {} ({}); {} both",
+            first_color.paint(BAR_CHAR),
+            self.labels.0,
+            total(self.first),
+            second_color.paint(SECOND_CHAR),
+            self.labels.1,
+            total(self.second),
+            both_color.paint(BOTH_CHAR),
+        )?;
+        let formatter = match self.first.precision() {
+            None => F64Formatter::new_with_range(stats.min..stats.max),
+            Some(n) => F64Formatter::new(n),
+        };
+        let width_range = formatter
+            .format(stats.min)
+            .len()
+            .max(formatter.format(stats.max).len());
+        let top = (self.first.units(self.first.top())).max(self.second.units(self.second.top()));
+        let width_count = self.first.format_units(top).len();
+        let fixed_width = 2 * width_range + 2 * width_count + 15;
+        let bar_len = if width > fixed_width {
+            width - fixed_width
+        } else {
+            75
+        };
+        let horizontal_scale = self.first.horizontal_scale(top / bar_len);
+        writeln!(f, "{horizontal_scale}")?;
+        let divisor = horizontal_scale.get_scale();
+        let paint = |color: Color, c: &str, n: usize| color.paint(c.repeat(n));
+        for ((range, first), (_, second)) in self.first.buckets().zip(self.second.buckets()) {
+            let (first, second) = (self.first.units(first), self.second.units(second));
+            let (first_len, second_len) = (first / divisor, second / divisor);
+            let (rest, rest_color, rest_char) = match first_len >= second_len {
+                true => (first_len - second_len, first_color, BAR_CHAR),
+                false => (second_len - first_len, second_color, SECOND_CHAR),
+            };
+            writeln!(
+                f,
+                "[{label}] [{first_count}] [{second_count}] {both}{rest}",
+                label = theme.label.paint(format!(
+                    "{:>width$} .. {:>width$}",
+                    formatter.format(range.start),
+                    formatter.format(range.end),
+                    width = width_range,
+                )),
+                first_count =
+                    first_color.paint(format!("{:>width_count$}", self.first.format_units(first))),
+                second_count = second_color.paint(format!(
+                    "{:>width_count$}",
+                    self.second.format_units(second)
+                )),
+                both = paint(both_color, BOTH_CHAR, first_len.min(second_len)),
+                rest = paint(rest_color, rest_char, rest),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Render for HistogramOverlay<'_> {
+    fn table(&self) -> Option<Table> {
+        let mut table = Table::new(&["lower", "upper", "first", "second"]);
+        for ((range, first), (_, second)) in self.first.buckets().zip(self.second.buckets()) {
+            table.push(vec![
+                range.start.into(),
+                range.end.into(),
+                first.into(),
+                second.into(),
+            ]);
+        }
+        Some(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plot::HistogramOptions;
+    use yansi::Paint;
+
+    #[test]
+    fn test_overlay() {
+        let (first, second) = Histogram::new_pair(
+            &mut [1.0, 1.5, 1.8, 3.5],
+            &mut [1.2, 3.0, 3.2, 3.8, 5.0],
+            HistogramOptions {
+                intervals: 4,
+                precision: Some(1),
+                ..Default::default()
+            },
+        );
+        let mut overlay = first.overlay(&second);
+        overlay.set_labels("before", "after");
+        Paint::disable();
+        let display = format!("{overlay}");
+        assert!(display.contains("Samples = 9; Min = 1.0; Max = 5.0\n"));
+        assert!(display.contains("∎ before (4); + after (5); # both\n"));
+        assert!(display.contains("[1.0 .. 2.0] [3] [1] #∎∎\n"));
+        assert!(display.contains("[2.0 .. 3.0] [0] [0] \n"));
+        assert!(display.contains("[3.0 .. 4.0] [1] [3] #++\n"));
+        assert!(display.contains("[4.0 .. 5.0] [0] [1] +\n"));
+        assert_eq!(
+            overlay.table().unwrap().to_csv(','),
+            "lower,upper,first,second\n1,2,3,1\n2,3,0,0\n3,4,1,3\n4,5,0,1\n"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt;
+
+use chrono::{DateTime, Duration, FixedOffset};
+
+use crate::format::Theme;
+use crate::plot::{human_duration, Render, Table, TimeHistogram};
+use crate::stats::{autocorrelation, find_peaks};
+
+/// Autocorrelations below this are not reported as periodicities.
+const MIN_CORRELATION: f64 = 0.3;
+/// Max number of periodicities reported.
+const MAX_PERIODS: usize = 3;
+/// Resolution used for finding peaks in the autocorrelation.
+const RESOLUTION: f64 = 1000.0;
+
+#[derive(Debug)]
+/// A struct holding the dominant periodicities of the timestamps of the
+/// input (like load induced by a cron job every minute), found in the
+/// autocorrelation of the counts of a time histogram.
+pub struct Periodicity {
+    acf: Vec<f64>,
+    bucket: Duration,
+    count: usize,
+    periods: Vec<(usize, f64)>,
+}
+
+impl Periodicity {
+    /// Creates a `Periodicity` from a slice of `DateTime` elements.
+    ///
+    /// `size` is the number of buckets of the time histogram whose counts are
+    /// autocorrelated.  Periods from two buckets up to half the time span of
+    /// the input can be found.
+    pub fn new(ts: &[DateTime<FixedOffset>], size: usize) -> Self {
+        let min = *ts.iter().min().unwrap();
+        let max = *ts.iter().max().unwrap();
+        let counts: Vec<f64> = TimeHistogram::new(size, ts)
+            .counts()
+            .iter()
+            .map(|count| *count as f64)
+            .collect();
+        let acf = autocorrelation(&counts, size / 2);
+        Self {
+            periods: dominant_periods(&acf),
+            acf,
+            bucket: (max - min) / size as i32,
+            count: ts.len(),
+        }
+    }
+
+    /// Returns the autocorrelation of the counts of the buckets, for every
+    /// lag (in buckets) from zero.  It is empty if all counts are the same.
+    pub fn acf(&self) -> &[f64] {
+        &self.acf
+    }
+}
+
+// Lags (and their autocorrelation) of the most prominent peaks of the
+// autocorrelation, strongest first, skipping those that are multiples of a
+// stronger one (a cycle of a minute also repeats every two minutes).
+fn dominant_periods(acf: &[f64]) -> Vec<(usize, f64)> {
+    let levels: Vec<usize> = acf
+        .iter()
+        .map(|r| (r.max(0.0) * RESOLUTION) as usize)
+        .collect();
+    let min_prominence = (MIN_CORRELATION * RESOLUTION) as usize / 2;
+    let mut candidates: Vec<(usize, f64)> = find_peaks(&levels, min_prominence)
+        .into_iter()
+        // Lag zero always correlates, and lag one just tells data is smooth
+        .filter(|lag| *lag >= 2 && acf[*lag] >= MIN_CORRELATION)
+        .map(|lag| (lag, acf[lag]))
+        .collect();
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    let mut periods: Vec<(usize, f64)> = Vec::new();
+    for (lag, r) in candidates {
+        let harmonic = periods.iter().any(|(period, _)| {
+            let multiple = (lag as f64 / *period as f64).round() as usize * period;
+            multiple.abs_diff(lag) <= 1
+        });
+        if !harmonic && periods.len() < MAX_PERIODS {
+            periods.push((lag, r));
+        }
+    }
+    periods
+}
+
+fn strength(r: f64) -> &'static str {
+    match r {
+        r if r >= 0.7 => "Strong",
+        r if r >= 0.5 => "Moderate",
+        _ => "Weak",
+    }
+}
+
+impl fmt::Display for Periodicity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
+        writeln!(
+            f,
+            "Matches: {}. Buckets of {}.",
+            theme.label.paint(self.count),
+            human_duration(self.bucket),
+        )?;
+        if self.periods.is_empty() {
+            return writeln!(f, "No periodicities found.");
+        }
+        for (lag, r) in &self.periods {
+            writeln!(
+                f,
+                "{} {} cycle (autocorrelation {})",
+                strength(*r),
+                theme.label.paint(human_duration(self.bucket * *lag as i32)),
+                theme.count.paint(format!("{r:.2}")),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Render for Periodicity {
+    fn table(&self) -> Option<Table> {
+        let mut table = Table::new(&["seconds", "autocorrelation"]);
+        for (lag, r) in &self.periods {
+            let period = self.bucket * *lag as i32;
+            table.push(vec![
+                (period.num_microseconds().unwrap() as f64 / 1e6).into(),
+                (*r).into(),
+            ]);
+        }
+        Some(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yansi::Paint;
+
+    #[test]
+    fn test_periodicity() {
+        let start = DateTime::parse_from_rfc3339("2021-04-15T04:00:00+00:00").unwrap();
+        let mut vec = Vec::new();
+        // A burst at the start of every minute, plus a line every 10 seconds
+        for second in 0..1200 {
+            let times = match second % 60 {
+                0..=4 => 10,
+                x if x % 10 == 0 => 1,
+                _ => 0,
+            };
+            for _ in 0..times {
+                vec.push(start + Duration::seconds(second));
+            }
+        }
+        vec.push(start + Duration::seconds(1200));
+        let period = Periodicity::new(&vec, 240);
+        assert_eq!(period.acf().len(), 121);
+        assert_eq!(period.periods.len(), 1);
+        assert_eq!(period.periods[0].0, 12);
+        Paint::disable();
+        let display = format!("{period}");
+        assert!(display.starts_with("Matches: 1101. Buckets of 5s.\n"));
+        assert!(display.contains("Strong 1m cycle (autocorrelation 0.95)\n"));
+        let table = period.table().unwrap();
+        assert_eq!(table.rows.len(), 1);
+        assert_eq!(table.rows[0][0], 60.0);
+        let flat: Vec<DateTime<FixedOffset>> =
+            (0..10).map(|i| start + Duration::seconds(i * 10)).collect();
+        let period = Periodicity::new(&flat, 10);
+        assert!(period.acf().is_empty());
+        assert_eq!(
+            format!("{period}"),
+            "Matches: 10. Buckets of 9s.\nNo periodicities found.\n"
+        );
+    }
+}
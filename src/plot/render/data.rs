@@ -0,0 +1,90 @@
+use serde_json::Value;
+
+/// The data behind a plot (after aggregation, like the buckets of
+/// histograms), for writing it in machine readable formats.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    /// Names of the columns.
+    pub columns: Vec<String>,
+    /// Rows, with a value per column (null for missing values).
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    /// Creates an empty table with these columns.
+    pub fn new(columns: &[&str]) -> Self {
+        Self {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Adds a row, with a value per column.
+    pub fn push(&mut self, row: Vec<Value>) {
+        self.rows.push(row);
+    }
+
+    /// Returns the table as CSV with a header row, with fields separated by
+    /// `delimiter` (like ',' or '\t').  Numbers are written in full precision.
+    pub fn to_csv(&self, delimiter: char) -> String {
+        let mut out = String::new();
+        let mut write_row = |fields: Vec<String>| {
+            out.push_str(&fields.join(&delimiter.to_string()));
+            out.push('\n');
+        };
+        write_row(self.columns.iter().map(|c| quote(c, delimiter)).collect());
+        for row in &self.rows {
+            write_row(row.iter().map(|v| field(v, delimiter)).collect());
+        }
+        out
+    }
+
+    /// Returns the table as a JSON array with an object per row, with the
+    /// names of the columns as keys.
+    pub fn to_json(&self) -> Value {
+        let rows = self.rows.iter().map(|row| {
+            let object = self.columns.iter().cloned().zip(row.iter().cloned());
+            Value::Object(object.collect())
+        });
+        Value::Array(rows.collect())
+    }
+}
+
+// A value as a CSV field
+fn field(value: &Value, delimiter: char) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => quote(s, delimiter),
+        // Like "2" rather than "2.0"
+        Value::Number(n) if n.is_f64() => n.as_f64().unwrap().to_string(),
+        Value::Number(n) => n.to_string(),
+        other => quote(&other.to_string(), delimiter),
+    }
+}
+
+// Fields with delimiters, quotes or line breaks are quoted
+fn quote(text: &str, delimiter: char) -> String {
+    match text.contains([delimiter, '"', '\n', '\r']) {
+        true => format!("\"{}\"", text.replace('"', "\"\"")),
+        false => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_table() {
+        let mut table = Table::new(&["x", "y"]);
+        table.push(vec![json!(1), json!(2.5)]);
+        table.push(vec![json!("a,\"b\""), json!(f64::NAN)]);
+        assert_eq!(table.to_csv(','), "x,y\n1,2.5\n\"a,\"\"b\"\"\",\n");
+        assert_eq!(table.to_csv('\t'), "x\ty\n1\t2.5\n\"a,\"\"b\"\"\"\t\n");
+        assert_eq!(
+            table.to_json(),
+            json!([{"x": 1, "y": 2.5}, {"x": "a,\"b\"", "y": null}])
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use std::str::FromStr;
+
+/// Output formats for plots.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Text for terminals, with ANSI colors (if enabled).
+    #[default]
+    Text,
+    /// Text with no ANSI colors, whatever the color settings.
+    Plain,
+    /// Markdown, for pasting into GitHub issues and such: a table with the
+    /// buckets (when the plot has them) and the chart within a code block.
+    Markdown,
+    /// The data of the plot as JSON: an array with an object per row.
+    Json,
+    /// The data of the plot as CSV, with a header row.
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" | "ansi-text" => Ok(Format::Text),
+            "plain-text" => Ok(Format::Plain),
+            "markdown" => Ok(Format::Markdown),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("Unknown output format: {s}")),
+        }
+    }
+}
+
+impl Format {
+    /// Returns true for the formats with the data of the plot, rather than
+    /// the chart.
+    pub fn is_data(self) -> bool {
+        matches!(self, Format::Json | Format::Csv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(Format::from_str("text"), Ok(Format::Text));
+        assert_eq!(Format::from_str("ansi-text"), Ok(Format::Text));
+        assert_eq!(Format::from_str("plain-text"), Ok(Format::Plain));
+        assert_eq!(Format::from_str("markdown"), Ok(Format::Markdown));
+        assert_eq!(Format::from_str("csv"), Ok(Format::Csv));
+        assert!(Format::from_str("html").is_err());
+        assert!(Format::Json.is_data());
+        assert!(!Format::Plain.is_data());
+    }
+}
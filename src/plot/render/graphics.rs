@@ -0,0 +1,229 @@
+use std::env;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::plot::render::image::{Image, Rgb};
+
+// Maximum size of the payload of every escape sequence in kitty protocol
+const KITTY_CHUNK: usize = 4096;
+
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Protocols for displaying images in terminals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Graphics {
+    /// Use the protocol supported by the terminal, if any.
+    Auto,
+    /// DEC sixel graphics, supported by xterm, mlterm, foot and others.
+    Sixel,
+    /// The kitty graphics protocol, supported by kitty, WezTerm and ghostty.
+    Kitty,
+    /// Plain text output.
+    Off,
+}
+
+impl Graphics {
+    /// Resolves `Auto` into the protocol supported by the terminal, guessed
+    /// from environment variables (as querying the terminal would interfere
+    /// with standard input).  Other protocols are returned as they are.
+    pub fn resolve(self) -> Self {
+        if self != Graphics::Auto {
+            return self;
+        }
+        let var = |name| env::var(name).unwrap_or_default();
+        let (term, program) = (var("TERM"), var("TERM_PROGRAM"));
+        if term == "xterm-kitty"
+            || env::var_os("KITTY_WINDOW_ID").is_some()
+            || program == "WezTerm"
+            || program == "ghostty"
+        {
+            Graphics::Kitty
+        } else if term.contains("sixel")
+            || term.starts_with("foot")
+            || term.starts_with("mlterm")
+            || term.starts_with("yaft")
+        {
+            Graphics::Sixel
+        } else {
+            Graphics::Off
+        }
+    }
+
+    /// Returns the escape sequences for displaying `image`.  This is empty for
+    /// `Off` and unresolved `Auto`.
+    pub fn encode(self, image: &Image) -> String {
+        match self {
+            Graphics::Sixel => sixel(image),
+            Graphics::Kitty => kitty(image),
+            Graphics::Auto | Graphics::Off => String::new(),
+        }
+    }
+}
+
+impl FromStr for Graphics {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Graphics::Auto),
+            "sixel" => Ok(Graphics::Sixel),
+            "kitty" => Ok(Graphics::Kitty),
+            "off" => Ok(Graphics::Off),
+            _ => Err(format!("Unknown graphics protocol: {s}")),
+        }
+    }
+}
+
+/// Encodes an image in sixel format.  Transparent pixels are left untouched.
+fn sixel(image: &Image) -> String {
+    let mut palette: Vec<Rgb> = Vec::new();
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            if let Some(color) = image.get(x, y) {
+                if !palette.contains(&color) {
+                    palette.push(color);
+                }
+            }
+        }
+    }
+    // Second parameter set to 1 means transparent background
+    let mut out = format!("\x1bP0;1;0q\"1;1;{};{}", image.width(), image.height());
+    for (i, [r, g, b]) in palette.iter().enumerate() {
+        let percent = |c: &u8| *c as u32 * 100 / 255;
+        write!(out, "#{};2;{};{};{}", i, percent(r), percent(g), percent(b)).unwrap();
+    }
+    for band in (0..image.height()).step_by(6) {
+        for (i, color) in palette.iter().enumerate() {
+            let sixels: Vec<u8> = (0..image.width())
+                .map(|x| {
+                    (0..6)
+                        .filter(|dy| {
+                            band + dy < image.height() && image.get(x, band + dy) == Some(*color)
+                        })
+                        .fold(0, |bits, dy| bits | 1 << dy)
+                })
+                .collect();
+            if sixels.iter().all(|bits| *bits == 0) {
+                continue;
+            }
+            write!(out, "#{i}").unwrap();
+            write_sixels(&mut out, &sixels);
+            // Back to the start of the band, for the next color
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+// Writes a row of sixels with run length encoding.
+fn write_sixels(out: &mut String, sixels: &[u8]) {
+    let mut i = 0;
+    while i < sixels.len() {
+        let run = sixels[i..].iter().take_while(|s| **s == sixels[i]).count();
+        let c = (b'?' + sixels[i]) as char;
+        match run {
+            1..=3 => (0..run).for_each(|_| out.push(c)),
+            _ => write!(out, "!{run}{c}").unwrap(),
+        }
+        i += run;
+    }
+}
+
+/// Encodes an image in kitty graphics protocol, as RGBA pixels.
+fn kitty(image: &Image) -> String {
+    let mut rgba = Vec::with_capacity(image.width() * image.height() * 4);
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            match image.get(x, y) {
+                Some([r, g, b]) => rgba.extend_from_slice(&[r, g, b, 255]),
+                None => rgba.extend_from_slice(&[0, 0, 0, 0]),
+            }
+        }
+    }
+    let payload = base64(&rgba);
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK).collect();
+    let mut out = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        match i {
+            0 => write!(
+                out,
+                "\x1b_Ga=T,f=32,s={},v={},m={};",
+                image.width(),
+                image.height(),
+                more
+            )
+            .unwrap(),
+            _ => write!(out, "\x1b_Gm={more};").unwrap(),
+        }
+        out.push_str(std::str::from_utf8(chunk).unwrap());
+        out.push_str("\x1b\\");
+    }
+    out
+}
+
+fn base64(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(Graphics::from_str("kitty"), Ok(Graphics::Kitty));
+        assert_eq!(Graphics::from_str("off"), Ok(Graphics::Off));
+        assert!(Graphics::from_str("png").is_err());
+        assert_eq!(Graphics::Sixel.resolve(), Graphics::Sixel);
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn test_sixel() {
+        let mut image = Image::new(5, 7);
+        image.fill((0, 0), (4, 0), [255, 0, 0]);
+        image.set(1, 6, [0, 0, 255]);
+        assert_eq!(
+            Graphics::Sixel.encode(&image),
+            "\x1bP0;1;0q\"1;1;5;7#0;2;100;0;0#1;2;0;0;100#0!5@$-#1?@???$-\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_kitty() {
+        let mut image = Image::new(1, 1);
+        image.set(0, 0, [255, 0, 0]);
+        assert_eq!(
+            Graphics::Kitty.encode(&image),
+            "\x1b_Ga=T,f=32,s=1,v=1,m=0;/wAA/w==\x1b\\"
+        );
+        assert_eq!(Graphics::Off.encode(&image), "");
+    }
+}
@@ -0,0 +1,160 @@
+use yansi::Color;
+
+use crate::format::Theme;
+
+/// An RGB color.
+pub type Rgb = [u8; 3];
+
+/// A raster image, where pixels not painted are transparent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Option<Rgb>>,
+}
+
+impl Image {
+    /// Creates a transparent image of `width` x `height` pixels.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![None; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the color of the pixel at (`x`, `y`), where (0, 0) is the top
+    /// left corner.
+    pub fn get(&self, x: usize, y: usize) -> Option<Rgb> {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Paints the pixel at (`x`, `y`).  Pixels out of the image are ignored.
+    pub fn set(&mut self, x: usize, y: usize, color: Rgb) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = Some(color);
+        }
+    }
+
+    /// Paints the rectangle with corners (`x0`, `y0`) and (`x1`, `y1`), both
+    /// of them included.
+    pub fn fill(&mut self, (x0, y0): (usize, usize), (x1, y1): (usize, usize), color: Rgb) {
+        for y in y0.min(y1)..=y0.max(y1) {
+            for x in x0.min(x1)..=x0.max(x1) {
+                self.set(x, y, color);
+            }
+        }
+    }
+
+    /// Paints a straight line between (`x0`, `y0`) and (`x1`, `y1`).
+    pub fn line(&mut self, (x0, y0): (usize, usize), (x1, y1): (usize, usize), color: Rgb) {
+        // Bresenham's algorithm
+        let (mut x, mut y) = (x0 as i64, y0 as i64);
+        let (x1, y1) = (x1 as i64, y1 as i64);
+        let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+        let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+        let mut error = dx + dy;
+        loop {
+            self.set(x as usize, y as usize, color);
+            if x == x1 && y == y1 {
+                return;
+            }
+            let double = 2 * error;
+            if double >= dy {
+                error += dy;
+                x += sx;
+            }
+            if double <= dx {
+                error += dx;
+                y += sy;
+            }
+        }
+    }
+}
+
+/// Plots that can be drawn as images, for terminals supporting graphics.
+pub trait Raster {
+    /// Draws the plot (but not its statistics) in an image of `width` x
+    /// `height` pixels, with the colors of `theme`.
+    fn raster(&self, width: usize, height: usize, theme: Theme) -> Image;
+}
+
+// The standard xterm values for the 16 basic colors
+const BASIC_COLORS: [Rgb; 16] = [
+    [0, 0, 0],
+    [205, 0, 0],
+    [0, 205, 0],
+    [205, 205, 0],
+    [0, 0, 238],
+    [205, 0, 205],
+    [0, 205, 205],
+    [229, 229, 229],
+    [127, 127, 127],
+    [255, 0, 0],
+    [0, 255, 0],
+    [255, 255, 0],
+    [92, 92, 255],
+    [255, 0, 255],
+    [0, 255, 255],
+    [255, 255, 255],
+];
+
+/// Returns the RGB values of a terminal color, as displayed by xterm.
+pub fn rgb(color: Color) -> Rgb {
+    match color {
+        Color::Black => BASIC_COLORS[0],
+        Color::Red => BASIC_COLORS[1],
+        Color::Green => BASIC_COLORS[2],
+        Color::Yellow => BASIC_COLORS[3],
+        Color::Blue => BASIC_COLORS[4],
+        Color::Magenta => BASIC_COLORS[5],
+        Color::Cyan => BASIC_COLORS[6],
+        Color::White | Color::Unset | Color::Default => BASIC_COLORS[7],
+        Color::RGB(r, g, b) => [r, g, b],
+        Color::Fixed(n) => match n {
+            0..=15 => BASIC_COLORS[n as usize],
+            16..=231 => {
+                let level = |i: u8| if i == 0 { 0 } else { 55 + 40 * i };
+                let n = n - 16;
+                [level(n / 36), level(n / 6 % 6), level(n % 6)]
+            }
+            _ => [8 + 10 * (n - 232); 3],
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draw() {
+        let mut image = Image::new(4, 3);
+        image.line((0, 2), (3, 0), [1, 2, 3]);
+        assert_eq!(image.get(0, 2), Some([1, 2, 3]));
+        assert_eq!(image.get(3, 0), Some([1, 2, 3]));
+        assert_eq!(image.get(0, 0), None);
+        image.fill((1, 1), (2, 2), [4, 5, 6]);
+        assert_eq!(image.get(2, 2), Some([4, 5, 6]));
+        image.set(4, 4, [4, 5, 6]);
+        assert_eq!(image.get(3, 2), None);
+    }
+
+    #[test]
+    fn test_rgb() {
+        assert_eq!(rgb(Color::Red), [205, 0, 0]);
+        assert_eq!(rgb(Color::Fixed(9)), [255, 0, 0]);
+        assert_eq!(rgb(Color::Fixed(214)), [255, 175, 0]);
+        assert_eq!(rgb(Color::Fixed(74)), [95, 175, 215]);
+        assert_eq!(rgb(Color::Fixed(244)), [128, 128, 128]);
+        assert_eq!(rgb(Color::RGB(1, 2, 3)), [1, 2, 3]);
+    }
+}
@@ -0,0 +1,58 @@
+use std::fmt::Write;
+
+/// Returns a markdown document with a table of `rows` (omitted if there are
+/// none) followed by `chart`, which is expected to be plain text.
+pub fn document(chart: &str, rows: &[(String, usize)]) -> String {
+    let mut out = String::new();
+    if !rows.is_empty() {
+        let width = rows
+            .iter()
+            .map(|r| escape(&r.0).chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("Bucket".len());
+        let count_width = rows
+            .iter()
+            .map(|r| r.1.to_string().len())
+            .max()
+            .unwrap_or(0)
+            .max("Count".len());
+        writeln!(out, "| {:width$} | {:>count_width$} |", "Bucket", "Count").unwrap();
+        writeln!(out, "|-{:-<width$}-|-{:-<count_width$}:|", "", "").unwrap();
+        for (label, count) in rows {
+            writeln!(out, "| {:width$} | {count:>count_width$} |", escape(label)).unwrap();
+        }
+        out.push('\n');
+    }
+    writeln!(out, "```text\n{}```", chart).unwrap();
+    out
+}
+
+// Pipes would split the cell in two
+fn escape(label: &str) -> String {
+    label.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document() {
+        let rows = vec![(String::from("[1 .. 2]"), 12), (String::from("a|b"), 3)];
+        assert_eq!(
+            document("chart\n", &rows),
+            concat!(
+                "| Bucket   | Count |\n",
+                "|----------|------:|\n",
+                "| [1 .. 2] |    12 |\n",
+                "| a\\|b     |     3 |\n",
+                "\n",
+                "```text\n",
+                "chart\n",
+                "```\n"
+            )
+        );
+        assert_eq!(document("chart\n", &[]), "```text\nchart\n```\n");
+    }
+}
@@ -0,0 +1,203 @@
+use std::fmt;
+use std::io;
+
+use regex::Regex;
+
+pub use self::data::Table;
+pub use self::format::Format;
+pub use self::graphics::Graphics;
+pub use self::image::{rgb, Image, Raster};
+pub use self::svg::ToSvg;
+
+mod data;
+mod format;
+mod graphics;
+mod image;
+mod markdown;
+pub(crate) mod svg;
+
+use crate::format::{with_style, Style, Theme, BAR_CHAR, MARKER};
+
+/// Options for rendering a plot.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Number of characters available for every line.
+    pub width: usize,
+    /// If false, ANSI colors are not rendered (even if enabled in yansi).
+    pub color: bool,
+    /// Character used for drawing the bars of histograms.
+    pub bar_char: char,
+    /// Character used for drawing the points of XY plots.
+    pub marker: char,
+    /// Colors of the plot (`Theme::DEFAULT` by default).
+    pub theme: Theme,
+    /// If true, bars are drawn with block characters, including partial ones
+    /// for the remainder of the bar, for a resolution eight times finer.
+    pub fine_bars: bool,
+    /// Output format (`Format::Text` by default).  Formats other than
+    /// `Format::Text` imply no colors, and markdown implies ASCII bars and
+    /// markers too.
+    pub format: Format,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            width: 110,
+            color: true,
+            bar_char: BAR_CHAR.chars().next().unwrap(),
+            marker: MARKER.chars().next().unwrap(),
+            theme: Theme::DEFAULT,
+            fine_bars: false,
+            format: Format::Text,
+        }
+    }
+}
+
+/// A plot that can be rendered into any writer.
+///
+/// This is a more convenient alternative to formatting the plot with a given
+/// width (like in `format!("{:width$}", histogram)`) that also allows to
+/// choose colors, bar characters and markers.
+pub trait Render: fmt::Display {
+    /// Writes the plot into `out`.
+    fn render(&self, out: &mut dyn io::Write, opts: &RenderOptions) -> io::Result<()> {
+        if opts.format.is_data() {
+            let table = self.table().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "This plot has no data to write as JSON or CSV",
+                )
+            })?;
+            let data = match opts.format {
+                Format::Json => format!("{}\n", table.to_json()),
+                _ => table.to_csv(','),
+            };
+            return out.write_all(data.as_bytes());
+        }
+        let markdown = opts.format == Format::Markdown;
+        let style = Style {
+            theme: opts.theme,
+            fine_bars: opts.fine_bars,
+        };
+        let mut text = with_style(style, || format!("{:width$}", self, width = opts.width));
+        if !opts.color || opts.format != Format::Text {
+            text = Regex::new("\x1b\\[[0-9;]*m")
+                .unwrap()
+                .replace_all(&text, "")
+                .into_owned();
+        }
+        let ascii = |c: char, fallback| match markdown && !c.is_ascii() {
+            true => fallback,
+            false => c,
+        };
+        let bar_char = ascii(opts.bar_char, '#');
+        if !BAR_CHAR.starts_with(bar_char) {
+            text = text.replace(BAR_CHAR, &bar_char.to_string());
+        }
+        let marker = ascii(opts.marker, '*');
+        if !MARKER.starts_with(marker) {
+            text = text.replace(MARKER, &marker.to_string());
+        }
+        if markdown {
+            text = markdown::document(&text, &self.rows());
+        }
+        out.write_all(text.as_bytes())
+    }
+
+    /// Returns the label and the count of every bar, for plots made of bars
+    /// (empty for other plots).  These are rendered as a table in markdown.
+    fn rows(&self) -> Vec<(String, usize)> {
+        Vec::new()
+    }
+
+    /// Returns the data of the plot, for writing it as JSON or CSV, or None
+    /// if the plot has no data in those formats.  By default, the label and
+    /// the count of every bar (see `rows`).
+    fn table(&self) -> Option<Table> {
+        let rows = self.rows();
+        if rows.is_empty() {
+            return None;
+        }
+        let mut table = Table::new(&["label", "count"]);
+        for (label, count) in rows {
+            table.push(vec![label.into(), count.into()]);
+        }
+        Some(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plot::{CommonTerms, Histogram, HistogramOptions, XyPlot, ZipfPlot};
+
+    #[test]
+    fn test_render() {
+        let mut terms = CommonTerms::new(10);
+        terms.observe(String::from("foo"));
+        terms.observe(String::from("foo"));
+        let mut out = Vec::new();
+        let opts = RenderOptions {
+            width: 20,
+            color: false,
+            bar_char: '#',
+            ..Default::default()
+        };
+        terms.render(&mut out, &opts).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Each # represents a count of 1\n\n[foo] [2] ##\n"
+        );
+        let histogram = Histogram::new(&mut [1.0, 2.0], HistogramOptions::default());
+        let mut out = Vec::new();
+        histogram.render(&mut out, &opts).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(!out.contains('\x1b'));
+        assert!(out.contains("\n[1.000 .. 2.000] [2] ##\n"));
+        let plot = XyPlot::new(&[1.0, 2.0], 2, 2, Some(0));
+        let mut out = Vec::new();
+        let opts = RenderOptions {
+            color: false,
+            marker: '*',
+            ..Default::default()
+        };
+        plot.render(&mut out, &opts).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("[2]  *\n"));
+        assert!(out.contains("[1] * \n"));
+        let mut out = Vec::new();
+        let opts = RenderOptions {
+            width: 20,
+            format: Format::Markdown,
+            ..Default::default()
+        };
+        histogram.render(&mut out, &opts).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("| Bucket           | Count |\n"));
+        assert!(out.contains("| [1.000 .. 2.000] |     2 |\n\n```text\n"));
+        assert!(out.contains("\n[1.000 .. 2.000] [2] ##\n```\n"));
+        let mut out = Vec::new();
+        let opts = RenderOptions {
+            format: Format::Json,
+            ..Default::default()
+        };
+        terms.render(&mut out, &opts).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "[{\"count\":2,\"term\":\"foo\"}]\n"
+        );
+        assert!(ZipfPlot::new(&terms, 4)
+            .render(&mut Vec::new(), &opts)
+            .is_err());
+        let mut out = Vec::new();
+        let opts = RenderOptions {
+            format: Format::Csv,
+            ..Default::default()
+        };
+        histogram.render(&mut out, &opts).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .starts_with("lower,upper,count\n1,"));
+    }
+}
@@ -0,0 +1,243 @@
+use std::fmt::Write;
+
+use regex::Regex;
+use yansi::Color;
+
+use crate::format::Theme;
+use crate::plot::render::rgb;
+
+const FONT_SIZE: usize = 12;
+// Approximate width of a character of a monospace font of FONT_SIZE
+const CHAR_WIDTH: usize = 8;
+const ROW_HEIGHT: usize = 18;
+const MARGIN: usize = 10;
+
+/// Plots that can be exported as SVG documents.
+pub trait ToSvg {
+    /// Returns a SVG document with the plot, `width` pixels wide (the height
+    /// depends on the plot), drawn with the colors of `theme`.
+    fn to_svg(&self, width: usize, theme: Theme) -> String;
+}
+
+/// Returns a SVG document with a bar chart: a header (for instance, the
+/// statistics of the data) followed by a horizontal bar for every row, with
+/// its label and count.
+pub fn bar_chart(header: &str, rows: &[(String, usize)], width: usize, theme: Theme) -> String {
+    let header: Vec<String> = plain_lines(header);
+    let label_width = rows.iter().map(|r| r.0.chars().count()).max().unwrap_or(0);
+    let top = rows.iter().map(|r| r.1).max().unwrap_or(0).max(1);
+    let count_width = top.to_string().len();
+    let bar_x = MARGIN + (label_width + count_width + 2) * CHAR_WIDTH;
+    let bar_space = width.saturating_sub(bar_x + MARGIN) as f64;
+    let mut svg = Svg::new(width, (header.len() + rows.len()) * ROW_HEIGHT + 2 * MARGIN);
+    for (i, line) in header.iter().enumerate() {
+        svg.text(MARGIN, row_y(i), line, "start", Color::Unset);
+    }
+    for (i, (label, count)) in rows.iter().enumerate() {
+        let y = row_y(header.len() + i);
+        svg.text(MARGIN, y, label, "start", theme.label);
+        svg.text(
+            bar_x - CHAR_WIDTH,
+            y,
+            &count.to_string(),
+            "end",
+            theme.count,
+        );
+        let bar = (*count as f64 / top as f64 * bar_space).round() as usize;
+        if bar > 0 {
+            let top_y = y - FONT_SIZE + 2;
+            svg.rect(bar_x, top_y, bar, ROW_HEIGHT - 4, theme.bar);
+        }
+    }
+    svg.finish()
+}
+
+/// Returns a SVG document with a line chart: a header (for instance, the
+/// statistics of the data) followed by a line joining `points`, which are y
+/// values in the [0, 1] range (evenly distributed in the x axis).  The labels
+/// of the bottom and top of the y axis are `y_labels`.
+pub fn line_chart(
+    header: &str,
+    points: &[f64],
+    y_labels: (&str, &str),
+    width: usize,
+    height: usize,
+    theme: Theme,
+) -> String {
+    let header: Vec<String> = plain_lines(header);
+    let label_width = y_labels.0.len().max(y_labels.1.len());
+    let plot_x = MARGIN + (label_width + 1) * CHAR_WIDTH;
+    let plot_y = MARGIN + header.len() * ROW_HEIGHT;
+    let plot_width = width.saturating_sub(plot_x + MARGIN).max(1) as f64;
+    let mut svg = Svg::new(width, plot_y + height + MARGIN);
+    for (i, line) in header.iter().enumerate() {
+        svg.text(MARGIN, row_y(i), line, "start", Color::Unset);
+    }
+    svg.text(
+        plot_x - CHAR_WIDTH,
+        plot_y + FONT_SIZE,
+        y_labels.1,
+        "end",
+        theme.label,
+    );
+    svg.text(
+        plot_x - CHAR_WIDTH,
+        plot_y + height,
+        y_labels.0,
+        "end",
+        theme.label,
+    );
+    svg.line(plot_x, plot_y, plot_x, plot_y + height, theme.label);
+    let step = match points.len() {
+        0 | 1 => 0.0,
+        n => plot_width / (n - 1) as f64,
+    };
+    let coordinates: Vec<(f64, f64)> = points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            (
+                plot_x as f64 + step * i as f64,
+                (plot_y + height) as f64 - p * height as f64,
+            )
+        })
+        .collect();
+    svg.polyline(&coordinates, theme.bar);
+    svg.finish()
+}
+
+// Baseline of the text in the row `i`
+fn row_y(i: usize) -> usize {
+    MARGIN + i * ROW_HEIGHT + FONT_SIZE
+}
+
+// Lines of a text, without ANSI escape sequences for colors
+fn plain_lines(text: &str) -> Vec<String> {
+    let plain = Regex::new("\x1b\\[[0-9;]*m").unwrap().replace_all(text, "");
+    plain.lines().map(String::from).collect()
+}
+
+/// A SVG document under construction.
+struct Svg {
+    width: usize,
+    height: usize,
+    body: String,
+}
+
+impl Svg {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            body: String::new(),
+        }
+    }
+
+    fn text(&mut self, x: usize, y: usize, text: &str, anchor: &str, color: Color) {
+        writeln!(
+            self.body,
+            r#"<text x="{x}" y="{y}" text-anchor="{anchor}" fill="{}">{}</text>"#,
+            hex(color),
+            escape(text)
+        )
+        .unwrap();
+    }
+
+    fn rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: Color) {
+        writeln!(
+            self.body,
+            r#"<rect x="{x}" y="{y}" width="{width}" height="{height}" fill="{}"/>"#,
+            hex(color)
+        )
+        .unwrap();
+    }
+
+    fn line(&mut self, x1: usize, y1: usize, x2: usize, y2: usize, color: Color) {
+        writeln!(
+            self.body,
+            r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="{}"/>"#,
+            hex(color)
+        )
+        .unwrap();
+    }
+
+    fn polyline(&mut self, points: &[(f64, f64)], color: Color) {
+        let points: Vec<String> = points
+            .iter()
+            .map(|(x, y)| format!("{x:.1},{y:.1}"))
+            .collect();
+        writeln!(
+            self.body,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.5"/>"#,
+            points.join(" "),
+            hex(color)
+        )
+        .unwrap();
+    }
+
+    fn finish(self) -> String {
+        format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" "#,
+                r#"viewBox="0 0 {w} {h}" font-family="monospace" font-size="{size}">"#,
+                "\n",
+                r#"<rect width="100%" height="100%" fill="white"/>"#,
+                "\n{body}</svg>\n"
+            ),
+            w = self.width,
+            h = self.height,
+            size = FONT_SIZE,
+            body = self.body
+        )
+    }
+}
+
+// Text is black, unless colored
+fn hex(color: Color) -> String {
+    match color {
+        Color::Unset | Color::Default => String::from("#000000"),
+        color => {
+            let [r, g, b] = rgb(color);
+            format!("#{r:02x}{g:02x}{b:02x}")
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar_chart() {
+        let rows = vec![(String::from("[a <b]"), 2), (String::from("c"), 1)];
+        let svg = bar_chart("\x1b[34mTotal\x1b[0m: 3\n", &rows, 200, Theme::DEFAULT);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"200\""));
+        assert!(svg.contains(
+            r##"<text x="10" y="22" text-anchor="start" fill="#000000">Total: 3</text>"##
+        ));
+        assert!(svg.contains(">[a &lt;b]</text>"));
+        // Labels take 6 chars and counts 1, so bars start at 10 + 9 * 8
+        assert!(svg.contains(r#"<rect x="82" y="30" width="108" height="14""#));
+        assert!(svg.contains(r#"<rect x="82" y="48" width="54" height="14""#));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn test_line_chart() {
+        let svg = line_chart("", &[0.0, 1.0, 0.5], ("0", "10"), 100, 50, Theme::DEFAULT);
+        assert!(svg.contains(r#"<polyline points="34.0,60.0 62.0,10.0 90.0,35.0""#));
+        assert!(svg.contains(">10</text>"));
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(hex(Color::Fixed(214)), "#ffaf00");
+        assert_eq!(hex(Color::Unset), "#000000");
+    }
+}
@@ -0,0 +1,350 @@
+use std::fmt;
+
+use crate::format::{F64Formatter, Theme, BAR_CHAR, MARKER};
+use crate::plot::{Histogram, HistogramOptions, Render, XyPlot};
+use crate::stats::Stats;
+
+/// Characters for the bars of every series, so that they can be told apart
+/// with no colors.  Series beyond these reuse them.
+const BAR_CHARS: &[&str] = &[BAR_CHAR, "+", "#", "o", "x"];
+/// Like `BAR_CHARS`, for the markers of every series in a plot.
+const MARKERS: &[&str] = &[MARKER, "+", "#", "o", "x"];
+
+// Combined statistics of the values of all the series
+fn all_stats(series: &[(String, Vec<f64>)], precision: Option<usize>) -> Stats {
+    let mut all: Vec<f64> = series.iter().flat_map(|(_, v)| v).copied().collect();
+    Stats::new(&mut all, precision)
+}
+
+/// A struct for displaying several histograms with the same buckets in a
+/// single chart, with their bars stacked in every row.
+pub struct SeriesHistogram {
+    labels: Vec<String>,
+    histograms: Vec<Histogram>,
+    stats: Stats,
+    precision: Option<usize>,
+    small_multiples: bool,
+}
+
+impl SeriesHistogram {
+    /// Creates a chart with an histogram per named series, all of them with
+    /// the same buckets, built for holding the values of every series.
+    ///
+    /// Options about the statistics (like `rank_of`) are ignored.
+    pub fn new(series: &[(String, Vec<f64>)], mut options: HistogramOptions) -> Self {
+        let mut stats = all_stats(series, options.precision);
+        if options.log_scale {
+            stats.min = 0.0; // We will silently discard negative values
+        }
+        options.intervals = options.intervals.clamp(1, stats.samples);
+        let histograms = series
+            .iter()
+            .map(|(_, values)| {
+                let mut histogram = Histogram::new_with_stats(stats.clone(), &options);
+                histogram.load(values);
+                histogram
+            })
+            .collect();
+        Self {
+            labels: series.iter().map(|(label, _)| label.clone()).collect(),
+            histograms,
+            stats,
+            precision: options.precision,
+            small_multiples: false,
+        }
+    }
+
+    /// Sets whether to display a compact histogram per series, one below the
+    /// other (with the same buckets and scale), rather than stacking their
+    /// bars in a single chart.
+    pub fn set_small_multiples(&mut self, small_multiples: bool) {
+        self.small_multiples = small_multiples;
+    }
+
+    fn formatter(&self) -> (F64Formatter, usize) {
+        let formatter = match self.precision {
+            None => F64Formatter::new_with_range(self.stats.min..self.stats.max),
+            Some(n) => F64Formatter::new(n),
+        };
+        let width_range = formatter
+            .format(self.stats.min)
+            .len()
+            .max(formatter.format(self.stats.max).len());
+        (formatter, width_range)
+    }
+
+    fn fmt_small_multiples(&self, f: &mut fmt::Formatter, width: usize) -> fmt::Result {
+        let theme = Theme::current();
+        write!(f, "{}", self.stats)?;
+        let (formatter, width_range) = self.formatter();
+        let first = &self.histograms[0];
+        let units: Vec<Vec<usize>> = self
+            .histograms
+            .iter()
+            .map(|h| h.buckets().map(|(_, count)| h.units(count)).collect())
+            .collect();
+        let top = units.iter().flatten().copied().max().unwrap_or(0);
+        let width_count = first.format_units(top).len();
+        let fixed_width = 2 * width_range + width_count + 10;
+        let bar_len = if width > fixed_width {
+            width - fixed_width
+        } else {
+            75
+        };
+        let horizontal_scale = first.horizontal_scale(top / bar_len);
+        writeln!(f, "{horizontal_scale}")?;
+        for ((label, histogram), row) in self.labels.iter().zip(&self.histograms).zip(units) {
+            let total = histogram.buckets().map(|(_, c)| c).sum::<usize>();
+            writeln!(f, "{} ({total})", theme.label.paint(label))?;
+            for ((range, _), units) in first.buckets().zip(row) {
+                writeln!(
+                    f,
+                    "[{label}] [{count}] {bar}",
+                    label = theme.label.paint(format!(
+                        "{:>width$} .. {:>width$}",
+                        formatter.format(range.start),
+                        formatter.format(range.end),
+                        width = width_range,
+                    )),
+                    count = horizontal_scale.get_count(units, width_count),
+                    bar = horizontal_scale.get_bar(units),
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SeriesHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
+        let width = f.width().unwrap_or(110);
+        if self.small_multiples {
+            return self.fmt_small_multiples(f, width);
+        }
+        let paint = |i: usize, s: &str| theme.palette[i % theme.palette.len()].paint(s.to_string());
+        let bar_char = |i: usize| BAR_CHARS[i % BAR_CHARS.len()];
+        write!(f, "{}", self.stats)?;
+        let legend: Vec<String> = self
+            .histograms
+            .iter()
+            .enumerate()
+            .map(|(i, h)| {
+                let total = h.buckets().map(|(_, c)| c).sum::<usize>();
+                format!("{} {} ({total})", paint(i, bar_char(i)), self.labels[i])
+            })
+            .collect();
+        writeln!(f, "{}", legend.join("; "))?;
+        let (formatter, width_range) = self.formatter();
+        // Units of every series in every row
+        let rows: Vec<Vec<usize>> = (0..self.histograms[0].buckets().count())
+            .map(|row| {
+                self.histograms
+                    .iter()
+                    .map(|h| h.units(h.buckets().nth(row).unwrap().1))
+                    .collect()
+            })
+            .collect();
+        let first = &self.histograms[0];
+        let widths: Vec<usize> = (0..self.histograms.len())
+            .map(|i| {
+                rows.iter()
+                    .map(|row| first.format_units(row[i]).len())
+                    .max()
+                    .unwrap_or(1)
+            })
+            .collect();
+        let top = rows.iter().map(|row| row.iter().sum()).max().unwrap_or(0);
+        let fixed_width = 2 * width_range + widths.iter().sum::<usize>() + widths.len() + 8;
+        let bar_len = if width > fixed_width {
+            width - fixed_width
+        } else {
+            75
+        };
+        let horizontal_scale = first.horizontal_scale(top / bar_len);
+        writeln!(f, "{horizontal_scale}")?;
+        let divisor = horizontal_scale.get_scale();
+        for ((range, _), row) in first.buckets().zip(rows.iter()) {
+            let counts: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(i, units)| {
+                    let count =
+                        format!("{:>width$}", first.format_units(*units), width = widths[i]);
+                    paint(i, &count).to_string()
+                })
+                .collect();
+            let bars: String = row
+                .iter()
+                .enumerate()
+                .map(|(i, units)| paint(i, &bar_char(i).repeat(units / divisor)).to_string())
+                .collect();
+            writeln!(
+                f,
+                "[{label}] [{counts}] {bars}",
+                label = theme.label.paint(format!(
+                    "{:>width$} .. {:>width$}",
+                    formatter.format(range.start),
+                    formatter.format(range.end),
+                    width = width_range,
+                )),
+                counts = counts.join("/"),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Render for SeriesHistogram {}
+
+/// A struct for displaying several series of values in a single XY graph,
+/// with a different marker for every series.
+pub struct SeriesPlot {
+    labels: Vec<String>,
+    plots: Vec<XyPlot>,
+    stats: Stats,
+    precision: Option<usize>,
+}
+
+impl SeriesPlot {
+    /// Creates a graph with a plot per named series, all of them with the
+    /// same y axis, built for holding the values of every series.  See
+    /// `XyPlot::new` for the meaning of the rest of parameters.
+    pub fn new(
+        series: &[(String, Vec<f64>)],
+        width: usize,
+        height: usize,
+        precision: Option<usize>,
+    ) -> Self {
+        let stats = all_stats(series, precision);
+        let plots = series
+            .iter()
+            .map(|(_, values)| {
+                let mut plot = XyPlot::new_with_stats(width, height, stats.clone(), precision);
+                plot.load(values);
+                plot
+            })
+            .collect();
+        Self {
+            labels: series.iter().map(|(label, _)| label.clone()).collect(),
+            plots,
+            stats,
+            precision,
+        }
+    }
+}
+
+impl fmt::Display for SeriesPlot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
+        let paint = |i: usize, s: &str| theme.palette[i % theme.palette.len()].paint(s.to_string());
+        let marker = |i: usize| MARKERS[i % MARKERS.len()];
+        write!(f, "{}", self.stats)?;
+        let legend: Vec<String> = self
+            .labels
+            .iter()
+            .enumerate()
+            .map(|(i, label)| format!("{} {label}", paint(i, marker(i))))
+            .collect();
+        writeln!(f, "{}", legend.join("; "))?;
+        let f64fmt = match self.precision {
+            None => F64Formatter::new_with_range(self.stats.min..self.stats.max),
+            Some(n) => F64Formatter::new(n),
+        };
+        let mut y_axis = self.plots[0].y_axis().to_vec();
+        let y_width = y_axis
+            .iter()
+            .map(|v| f64fmt.format(*v).len())
+            .max()
+            .unwrap();
+        y_axis.reverse();
+        let columns = self.plots.iter().map(|p| p.x_axis().len()).max().unwrap();
+        let mut upper = f64::INFINITY;
+        for lower in y_axis {
+            // The marker of the last series wins when several fall together
+            let mut row = vec![String::from(" "); columns];
+            for (i, plot) in self.plots.iter().enumerate() {
+                for (x, value) in plot.x_axis().iter().enumerate() {
+                    if (lower..upper).contains(value) {
+                        row[x] = paint(i, marker(i)).to_string();
+                    }
+                }
+            }
+            writeln!(
+                f,
+                "[{}] {}",
+                theme
+                    .label
+                    .paint(format!("{:>y_width$}", f64fmt.format(lower))),
+                row.concat(),
+            )?;
+            upper = lower;
+        }
+        Ok(())
+    }
+}
+
+impl Render for SeriesPlot {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yansi::Paint;
+
+    fn series() -> Vec<(String, Vec<f64>)> {
+        vec![
+            (String::from("rt"), vec![1.0, 1.5, 1.8, 3.5]),
+            (String::from("upstream_rt"), vec![1.2, 3.0, 3.2, 3.8, 5.0]),
+        ]
+    }
+
+    #[test]
+    fn test_series_histogram() {
+        let options = HistogramOptions {
+            intervals: 4,
+            precision: Some(1),
+            ..Default::default()
+        };
+        let histogram = SeriesHistogram::new(&series(), options);
+        Paint::disable();
+        let display = format!("{histogram}");
+        assert!(display.contains("Samples = 9; Min = 1.0; Max = 5.0\n"));
+        assert!(display.contains("∎ rt (4); + upstream_rt (5)\n"));
+        assert!(display.contains("[1.0 .. 2.0] [3/1] ∎∎∎+\n"));
+        assert!(display.contains("[2.0 .. 3.0] [0/0] \n"));
+        assert!(display.contains("[3.0 .. 4.0] [1/3] ∎+++\n"));
+        assert!(display.contains("[4.0 .. 5.0] [0/1] +\n"));
+    }
+
+    #[test]
+    fn test_small_multiples() {
+        let options = HistogramOptions {
+            intervals: 4,
+            precision: Some(1),
+            ..Default::default()
+        };
+        let mut histogram = SeriesHistogram::new(&series(), options);
+        histogram.set_small_multiples(true);
+        Paint::disable();
+        let display = format!("{histogram}");
+        assert!(display.contains("Samples = 9; Min = 1.0; Max = 5.0\n"));
+        assert!(display
+            .contains("rt (4)\n[1.0 .. 2.0] [3] ∎∎∎\n[2.0 .. 3.0] [0] \n[3.0 .. 4.0] [1] ∎\n"));
+        assert!(display.contains("upstream_rt (5)\n[1.0 .. 2.0] [1] ∎\n"));
+        assert!(display.contains("[3.0 .. 4.0] [3] ∎∎∎\n[4.0 .. 5.0] [1] ∎\n"));
+    }
+
+    #[test]
+    fn test_series_plot() {
+        let plot = SeriesPlot::new(&series(), 4, 4, Some(1));
+        Paint::disable();
+        let display = format!("{plot}");
+        assert!(display.contains("Samples = 9; Min = 1.0; Max = 5.0\n"));
+        assert!(display.contains("● rt; + upstream_rt\n"));
+        // Five values of the second series do not fit in four columns
+        assert!(display.contains("[4.0]     +\n"));
+        assert!(display.contains("[3.0]  +++ \n"));
+        assert!(display.contains("[2.0]      \n"));
+        assert!(display.contains("[1.0] +●●  \n"));
+    }
+}
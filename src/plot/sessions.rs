@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{DateTime, Duration, FixedOffset};
+
+use crate::format::Theme;
+use crate::plot::{date_fmt_string, human_duration, Render, Table};
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// The lines of the input sharing a key (like a session or a job id).
+struct Session {
+    key: String,
+    first: DateTime<FixedOffset>,
+    last: DateTime<FixedOffset>,
+    count: usize,
+}
+
+impl Session {
+    fn duration(&self) -> Duration {
+        self.last - self.first
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// A struct holding the time span of every key found in the input (like
+/// session lengths or job runtimes extracted from logs), longest first.
+pub struct Sessions {
+    sessions: Vec<Session>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    lines: usize,
+}
+
+impl Sessions {
+    /// Creates a `Sessions` from a slice of pairs of a timestamp and the key
+    /// of the line where it was found.
+    ///
+    /// `lines` is the number of sessions (the longest ones) to display.
+    pub fn new(terms: &[(DateTime<FixedOffset>, String)], lines: usize) -> Self {
+        let mut map: HashMap<&str, Session> = HashMap::new();
+        for (ts, key) in terms {
+            map.entry(key)
+                .and_modify(|s| {
+                    s.first = s.first.min(*ts);
+                    s.last = s.last.max(*ts);
+                    s.count += 1;
+                })
+                .or_insert_with(|| Session {
+                    key: key.clone(),
+                    first: *ts,
+                    last: *ts,
+                    count: 1,
+                });
+        }
+        let mut sessions: Vec<Session> = map.into_values().collect();
+        sessions.sort_by(|a, b| b.duration().cmp(&a.duration()).then(a.key.cmp(&b.key)));
+        Self { sessions, lines }
+    }
+
+    /// Returns the duration in seconds of every session.
+    pub fn durations(&self) -> Vec<f64> {
+        self.sessions
+            .iter()
+            .map(|s| s.duration().num_microseconds().unwrap() as f64 / 1e6)
+            .collect()
+    }
+}
+
+impl fmt::Display for Sessions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
+        let shown = self.lines.min(self.sessions.len());
+        writeln!(
+            f,
+            "Keys: {}. Longest sessions:",
+            theme.label.paint(self.sessions.len()),
+        )?;
+        let first = self.sessions.iter().map(|s| s.first).min();
+        let last = self.sessions.iter().map(|s| s.last).max();
+        let span = match (first, last) {
+            (Some(first), Some(last)) => (last - first).num_seconds(),
+            _ => 0,
+        };
+        let ts_fmt = date_fmt_string(span);
+        let width = self.sessions[..shown]
+            .iter()
+            .map(|s| s.key.chars().count())
+            .max()
+            .unwrap_or(0);
+        for session in &self.sessions[..shown] {
+            writeln!(
+                f,
+                "[{}] [{}] .. [{}] {} ({} line{})",
+                theme.label.paint(format!("{:<width$}", session.key)),
+                session.first.format(ts_fmt),
+                session.last.format(ts_fmt),
+                theme.count.paint(human_duration(session.duration())),
+                session.count,
+                if session.count == 1 { "" } else { "s" },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Render for Sessions {
+    /// Every session, rather than just the longest ones displayed.
+    fn table(&self) -> Option<Table> {
+        let mut table = Table::new(&["key", "first", "last", "seconds", "lines"]);
+        for (session, seconds) in self.sessions.iter().zip(self.durations()) {
+            table.push(vec![
+                session.key.clone().into(),
+                session.first.to_rfc3339().into(),
+                session.last.to_rfc3339().into(),
+                seconds.into(),
+                session.count.into(),
+            ]);
+        }
+        Some(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yansi::Paint;
+
+    #[test]
+    fn test_sessions() {
+        let ts = |s| DateTime::parse_from_rfc3339(s).unwrap();
+        let terms = vec![
+            (ts("2021-04-15T04:25:00+00:00"), String::from("a")),
+            (ts("2021-04-15T04:25:10+00:00"), String::from("bb")),
+            (ts("2021-04-15T04:26:00+00:00"), String::from("a")),
+            (ts("2021-04-15T04:25:30+00:00"), String::from("a")),
+            (ts("2021-04-15T04:28:00+00:00"), String::from("c")),
+        ];
+        let sessions = Sessions::new(&terms, 2);
+        assert_eq!(sessions.durations(), vec![60.0, 0.0, 0.0]);
+        Paint::disable();
+        let display = format!("{sessions}");
+        assert!(display.starts_with("Keys: 3. Longest sessions:\n"));
+        assert!(display.contains("[a ] [04:25:00.000] .. [04:26:00.000] 1m (3 lines)\n"));
+        assert!(display.contains("[bb] [04:25:10.000] .. [04:25:10.000] 0s (1 line)\n"));
+        assert!(!display.contains("[c"));
+        let csv = sessions.table().unwrap().to_csv(',');
+        assert!(csv.starts_with(
+            "key,first,last,seconds,lines\n\
+             a,2021-04-15T04:25:00+00:00,2021-04-15T04:26:00+00:00,60,3\n"
+        ));
+        assert_eq!(csv.lines().count(), 4);
+    }
+}
@@ -0,0 +1,67 @@
+use std::str::FromStr;
+
+/// Order in which the rows of a bar chart (with a label and a count each) are
+/// displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Most frequent first.
+    Count,
+    /// Alphabetical order of labels.
+    Label,
+    /// Natural order of the rows (like the order of appearance).
+    None,
+}
+
+impl FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "count" => Ok(SortOrder::Count),
+            "label" => Ok(SortOrder::Label),
+            "none" => Ok(SortOrder::None),
+            _ => Err(format!("Unknown sort order: {s}")),
+        }
+    }
+}
+
+/// Sorts `rows`, that are expected to be in their natural order, where `key`
+/// returns the label and the count of every row.
+pub(crate) fn sort_rows<T, F>(rows: &mut [T], order: SortOrder, reverse: bool, key: F)
+where
+    F: Fn(&T) -> (&str, usize),
+{
+    match order {
+        SortOrder::Count => rows.sort_by(|a, b| {
+            let (a, b) = (key(a), key(b));
+            b.1.cmp(&a.1).then(a.0.cmp(b.0))
+        }),
+        SortOrder::Label => rows.sort_by(|a, b| key(a).0.cmp(key(b).0)),
+        SortOrder::None => (),
+    }
+    if reverse {
+        rows.reverse();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_rows() {
+        let rows = [("b", 1), ("c", 3), ("a", 1)];
+        let sorted = |order, reverse| {
+            let mut rows = rows;
+            sort_rows(&mut rows, order, reverse, |r| *r);
+            rows.iter().map(|r| r.0).collect::<String>()
+        };
+        assert_eq!(sorted(SortOrder::Count, false), "cab");
+        assert_eq!(sorted(SortOrder::Count, true), "bac");
+        assert_eq!(sorted(SortOrder::Label, false), "abc");
+        assert_eq!(sorted(SortOrder::None, false), "bca");
+        assert_eq!(sorted(SortOrder::None, true), "acb");
+        assert_eq!("label".parse::<SortOrder>(), Ok(SortOrder::Label));
+        assert!("foo".parse::<SortOrder>().is_err());
+    }
+}
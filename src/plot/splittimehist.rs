@@ -1,12 +1,52 @@
+use std::cmp::Reverse;
 use std::fmt;
+use std::str::FromStr;
 
 use chrono::{DateTime, Duration, FixedOffset};
-use yansi::Color::{Blue, Cyan, Green, Magenta, Red};
+use yansi::Color;
 
-use crate::format::{HorizontalScale, BAR_CHAR};
-use crate::plot::date_fmt_string;
+use crate::format::{basis_points, format_share, HorizontalScale, Theme, BAR_CHAR, BASIS_POINTS};
+use crate::plot::{date_fmt_string, Render, Table, TimeLabel};
 
-const COLORS: &[yansi::Color] = &[Red, Blue, Magenta, Green, Cyan];
+/// Characters for the bars of the terms.  When there are more terms than
+/// colors in the palette of the theme, the colors are reused with another
+/// character.
+const BAR_CHARS: [&str; 4] = [BAR_CHAR, "+", "x", "o"];
+
+/// Color and bar character for the term in position `index`.
+fn term_style(theme: &Theme, index: usize) -> (Color, &'static str) {
+    let colors = theme.palette.len();
+    (
+        theme.palette[index % colors],
+        BAR_CHARS[(index / colors) % BAR_CHARS.len()],
+    )
+}
+
+/// How the bars of the terms in every bucket of a `SplitTimeHistogram` are
+/// displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitStyle {
+    /// One after the other, in a single bar per bucket.
+    Stacked,
+    /// In a line per term.
+    Grouped,
+    /// Like `Stacked`, with counts as percentages of the bucket total, so
+    /// that all buckets have the same length.
+    Percent,
+}
+
+impl FromStr for SplitStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stacked" => Ok(SplitStyle::Stacked),
+            "grouped" => Ok(SplitStyle::Grouped),
+            "percent" => Ok(SplitStyle::Percent),
+            _ => Err(format!("Unknown style: {s}")),
+        }
+    }
+}
 
 #[derive(Debug)]
 struct TimeBucket {
@@ -42,6 +82,9 @@ pub struct SplitTimeHistogram {
     step: Duration,
     last: usize,
     nanos: u64,
+    style: SplitStyle,
+    sort_legend: bool,
+    label: TimeLabel,
 }
 
 impl SplitTimeHistogram {
@@ -69,6 +112,9 @@ impl SplitTimeHistogram {
             step,
             last: size - 1,
             nanos: (max - min).num_microseconds().unwrap() as u64,
+            style: SplitStyle::Stacked,
+            sort_legend: false,
+            label: TimeLabel::Start,
         };
         sth.load(ts);
         sth
@@ -100,7 +146,45 @@ impl SplitTimeHistogram {
         }
     }
 
-    // Clippy gets badly confused because self.strings and COLORS may have
+    /// Sets how the bars of the terms in every bucket are displayed.
+    pub fn set_style(&mut self, style: SplitStyle) {
+        self.style = style;
+    }
+
+    /// Sets whether to list the terms in the legend from the most to the
+    /// least frequent, rather than in the order they were given.
+    pub fn set_sort_legend(&mut self, sort_legend: bool) {
+        self.sort_legend = sort_legend;
+    }
+
+    /// Sets how the buckets are labelled.
+    pub fn set_label(&mut self, label: TimeLabel) {
+        self.label = label;
+    }
+
+    // Units of every term in a bucket: counts, or basis points of the total of
+    // the bucket for the percent style
+    fn units(&self, row: &TimeBucket) -> Vec<usize> {
+        match self.style {
+            SplitStyle::Percent => {
+                let total = row.total();
+                row.count
+                    .iter()
+                    .map(|count| basis_points(*count, total))
+                    .collect()
+            }
+            _ => row.count.clone(),
+        }
+    }
+
+    fn format_units(&self, units: usize) -> String {
+        match self.style {
+            SplitStyle::Percent => format_share(units),
+            _ => units.to_string(),
+        }
+    }
+
+    // Clippy gets badly confused because self.strings and the palette may have
     // different lengths
     #[allow(clippy::needless_range_loop)]
     fn fmt_row(
@@ -111,28 +195,42 @@ impl SplitTimeHistogram {
         widths: &[usize],
         ts_fmt: &str,
     ) -> fmt::Result {
-        write!(
-            f,
-            "[{}] [",
-            Blue.paint(format!("{}", row.start.format(ts_fmt)))
-        )?;
+        let theme = Theme::current();
+        let units = self.units(row);
+        let end = row.start + self.step / self.vec.len() as i32;
+        let label = self.label.format(&(row.start..end), self.min, ts_fmt);
+        let count = |i: usize| {
+            term_style(&theme, i).0.paint(format!(
+                "{:>width$}",
+                self.format_units(units[i]),
+                width = widths[i]
+            ))
+        };
+        let bar = |i: usize| {
+            let (color, bar_char) = term_style(&theme, i);
+            color.paint(bar_char.repeat(units[i] / divisor))
+        };
+        if self.style == SplitStyle::Grouped {
+            // A line per term, with the label of the bucket in the first one
+            for i in 0..self.strings.len() {
+                match i {
+                    0 => write!(f, "[{}]", theme.label.paint(&label))?,
+                    _ => write!(f, "{:width$}", "", width = label.len() + 2)?,
+                }
+                writeln!(f, " [{}] {}", count(i), bar(i))?;
+            }
+            return Ok(());
+        }
+        write!(f, "[{}] [", theme.label.paint(&label))?;
         for i in 0..self.strings.len() {
-            write!(
-                f,
-                "{}",
-                COLORS[i].paint(format!("{:width$}", row.count[i], width = widths[i]))
-            )?;
+            write!(f, "{}", count(i))?;
             if i < self.strings.len() - 1 {
                 write!(f, "/")?;
             }
         }
         write!(f, "] ")?;
         for i in 0..self.strings.len() {
-            write!(
-                f,
-                "{}",
-                COLORS[i].paint(BAR_CHAR.repeat(row.count[i] / divisor).to_string())
-            )?;
+            write!(f, "{}", bar(i))?;
         }
         writeln!(f)
     }
@@ -140,25 +238,55 @@ impl SplitTimeHistogram {
 
 impl fmt::Display for SplitTimeHistogram {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
         let width = f.width().unwrap_or(100);
         let total = self.vec.iter().map(|r| r.total()).sum::<usize>();
-        let top = self.vec.iter().map(|r| r.total()).max().unwrap_or(1);
-        let horizontal_scale = HorizontalScale::new(top / width);
+        let horizontal_scale = match self.style {
+            SplitStyle::Stacked => {
+                let top = self.vec.iter().map(|r| r.total()).max().unwrap_or(1);
+                HorizontalScale::new(top / width)
+            }
+            SplitStyle::Grouped => {
+                let top = self.vec.iter().flat_map(|r| r.count.iter()).max();
+                HorizontalScale::new(top.copied().unwrap_or(1) / width)
+            }
+            SplitStyle::Percent => HorizontalScale::new(BASIS_POINTS / width).normalized(true),
+        };
         // These are the widths of every count column
-        let widths: Vec<usize> = (0..self.strings.len())
+        let mut widths: Vec<usize> = (0..self.strings.len())
             .map(|i| {
                 self.vec
                     .iter()
-                    .map(|r| r.count[i].to_string().len())
+                    .map(|r| self.format_units(self.units(r)[i]).len())
                     .max()
                     .unwrap()
             })
             .collect();
+        if self.style == SplitStyle::Grouped {
+            // Counts of all the terms are in the same column
+            let max = widths.iter().copied().max().unwrap_or(1);
+            widths = vec![max; widths.len()];
+        }
 
         writeln!(f, "Matches: {total}.")?;
-        for (i, s) in self.strings.iter().enumerate() {
-            let total = self.vec.iter().map(|r| r.count[i]).sum::<usize>();
-            writeln!(f, "{}: {total}.", COLORS[i].paint(s))?;
+        let totals: Vec<usize> = (0..self.strings.len())
+            .map(|i| self.vec.iter().map(|r| r.count[i]).sum())
+            .collect();
+        let mut legend: Vec<usize> = (0..self.strings.len()).collect();
+        if self.sort_legend {
+            legend.sort_by_key(|i| Reverse(totals[*i]));
+        }
+        for i in legend {
+            let (color, bar_char) = term_style(&theme, i);
+            let share = basis_points(totals[i], total);
+            writeln!(
+                f,
+                "{} {}: {} ({}).",
+                color.paint(bar_char),
+                color.paint(&self.strings[i]),
+                totals[i],
+                format_share(share)
+            )?;
         }
         writeln!(f, "{horizontal_scale}")?;
         let ts_fmt = date_fmt_string(self.step.num_seconds());
@@ -169,6 +297,21 @@ impl fmt::Display for SplitTimeHistogram {
     }
 }
 
+impl Render for SplitTimeHistogram {
+    /// Every row is a bucket, with a column with the count of every term.
+    fn table(&self) -> Option<Table> {
+        let mut columns = vec!["start"];
+        columns.extend(self.strings.iter().map(|s| s.as_str()));
+        let mut table = Table::new(&columns);
+        for row in &self.vec {
+            let mut fields = vec![row.start.to_rfc3339().into()];
+            fields.extend(row.count.iter().map(|c| (*c).into()));
+            table.push(fields);
+        }
+        Some(table)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -201,7 +344,7 @@ mod tests {
                 2,
             ));
         }
-        let th = SplitTimeHistogram::new(
+        let mut th = SplitTimeHistogram::new(
             3,
             vec!["one".to_string(), "two".to_string(), "three".to_string()],
             &vec,
@@ -209,12 +352,60 @@ mod tests {
         println!("{th}");
         let display = format!("{th}");
         assert!(display.contains("Matches: 15"));
-        assert!(display.contains("one: 1."));
-        assert!(display.contains("two: 2."));
-        assert!(display.contains("three: 12."));
+        assert!(display.contains("∎ one: 1 (6.67%).\n∎ two: 2 (13.33%).\n∎ three: 12 (80.00%).\n"));
         assert!(display.contains("represents a count of 1"));
         assert!(display.contains("[2021-04-15 04:25:00] [0/1/ 0] ∎\n"));
         assert!(display.contains("[2021-12-14 12:25:00] [1/1/ 1] ∎∎∎\n"));
         assert!(display.contains("[2022-08-14 20:25:00] [0/0/11] ∎∎∎∎∎∎∎∎∎∎∎\n"));
+        th.set_sort_legend(true);
+        let display = format!("{th}");
+        assert!(display.contains("∎ three: 12 (80.00%).\n∎ two: 2 (13.33%).\n∎ one: 1 (6.67%).\n"));
+    }
+
+    #[test]
+    fn test_styles() {
+        Paint::disable();
+        let ts = |s| DateTime::parse_from_rfc3339(s).unwrap();
+        let mut vec = vec![
+            (ts("2021-04-15T04:25:00+00:00"), 0),
+            (ts("2021-04-15T04:25:00+00:00"), 1),
+            (ts("2021-04-15T04:26:00+00:00"), 1),
+        ];
+        for _ in 0..6 {
+            vec.push((ts("2021-04-15T04:26:00+00:00"), 0));
+        }
+        let mut th = SplitTimeHistogram::new(2, vec![String::from("a"), String::from("b")], &vec);
+        th.set_style(SplitStyle::Grouped);
+        let display = format!("{th}");
+        assert!(display.contains("[04:25:00.000] [1] ∎\n               [1] ∎\n"));
+        assert!(display.contains("[04:25:30.000] [6] ∎∎∎∎∎∎\n               [1] ∎\n"));
+        assert_eq!(
+            th.table().unwrap().to_csv(','),
+            "start,a,b\n2021-04-15T04:25:00+00:00,1,1\n2021-04-15T04:25:30+00:00,6,1\n"
+        );
+        th.set_style(SplitStyle::Percent);
+        let display = format!("{th:10}");
+        assert!(display.contains("Each ∎ represents 10.00% of the values\n"));
+        assert!(display.contains("[04:25:00.000] [50.00%/50.00%] ∎∎∎∎∎∎∎∎∎∎\n"));
+        assert!(display.contains("[04:25:30.000] [85.71%/14.29%] ∎∎∎∎∎∎∎∎∎\n"));
+        assert_eq!(SplitStyle::from_str("grouped"), Ok(SplitStyle::Grouped));
+        assert!(SplitStyle::from_str("pie").is_err());
+    }
+
+    #[test]
+    fn test_many_terms() {
+        Paint::disable();
+        let ts = |s| DateTime::parse_from_rfc3339(s).unwrap();
+        let mut vec: Vec<(DateTime<FixedOffset>, usize)> = (0..7)
+            .map(|i| (ts("2021-04-15T04:25:00+00:00"), i))
+            .collect();
+        vec.push((ts("2021-04-15T04:26:00+00:00"), 6));
+        let strings = (0..7).map(|i| format!("t{i}")).collect();
+        let th = SplitTimeHistogram::new(1, strings, &vec);
+        let display = format!("{th}");
+        assert!(display.contains("∎ t0: 1 (12.50%).\n"));
+        assert!(display.contains("∎ t4: 1 (12.50%).\n"));
+        assert!(display.contains("+ t5: 1 (12.50%).\n"));
+        assert!(display.contains("[1/1/1/1/1/1/2] ∎∎∎∎∎+++\n"));
     }
 }
@@ -0,0 +1,80 @@
+/// A symmetric logarithmic transformation: linear near zero (roughly, in the
+/// [-threshold, threshold] interval) and logarithmic beyond it, for both
+/// negative and positive values.
+#[derive(Debug, Clone, Copy)]
+pub struct SymLog {
+    threshold: f64,
+}
+
+impl SymLog {
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold: threshold.abs().max(f64::MIN_POSITIVE),
+        }
+    }
+
+    /// Maps a value into the transformed space.
+    pub fn forward(&self, x: f64) -> f64 {
+        x.signum() * (x.abs() / self.threshold).ln_1p()
+    }
+
+    /// Maps a value back from the transformed space.
+    pub fn inverse(&self, y: f64) -> f64 {
+        y.signum() * self.threshold * y.abs().exp_m1()
+    }
+
+    /// Returns `n` + 1 edges splitting `start..end` in `n` intervals, of equal
+    /// width in the transformed space.
+    pub fn edges(&self, start: f64, end: f64, n: usize) -> Vec<f64> {
+        let (t_start, t_end) = (self.forward(start), self.forward(end));
+        let step = (t_end - t_start) / n as f64;
+        let mut edges: Vec<f64> = (0..n)
+            .map(|i| self.inverse(step.mul_add(i as f64, t_start)))
+            .collect();
+        edges.push(end);
+        edges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_symlog_roundtrip() {
+        let symlog = SymLog::new(10.0);
+        for x in [-12345.6, -10.0, -0.1, 0.0, 0.1, 10.0, 12345.6] {
+            assert_float_eq!(symlog.inverse(symlog.forward(x)), x, rmax <= 1e-12);
+        }
+        // Symmetric
+        assert_float_eq!(
+            symlog.forward(-500.0),
+            -symlog.forward(500.0),
+            rmax <= 1e-12
+        );
+        // Close to linear near zero, logarithmic far from it
+        assert_float_eq!(
+            symlog.forward(0.01) / symlog.forward(0.02),
+            0.5,
+            abs <= 0.001
+        );
+        assert_float_eq!(
+            symlog.forward(100000.0) - symlog.forward(10000.0),
+            10_f64.ln(),
+            abs <= 0.001
+        );
+    }
+
+    #[test]
+    fn test_symlog_edges() {
+        let symlog = SymLog::new(1.0);
+        let edges = symlog.edges(-99.0, 99.0, 4);
+        assert_eq!(edges.len(), 5);
+        assert_float_eq!(edges[0], -99.0, rmax <= 1e-12);
+        assert_float_eq!(edges[1], -9.0, rmax <= 1e-12);
+        assert_float_eq!(edges[2], 0.0, abs <= 1e-12);
+        assert_float_eq!(edges[3], 9.0, rmax <= 1e-12);
+        assert_float_eq!(edges[4], 99.0, rmax <= 1e-12);
+    }
+}
@@ -1,11 +1,28 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt;
 
-use yansi::Color::Blue;
+use crate::format::{HorizontalScale, Theme};
+use crate::plot::sort::{sort_rows, SortOrder};
+use crate::plot::{Render, Table};
 
-use crate::format::HorizontalScale;
+const OTHER_LABEL: &str = "(other)";
+const TOTAL_LABEL: &str = "(total)";
+
+/// Markers for the terms more or less frequent than in the snapshot.
+const TREND_UP: &str = "▲";
+const TREND_DOWN: &str = "▼";
 
 #[derive(Debug)]
+struct Approx {
+    capacity: usize,
+    by_count: BTreeSet<(usize, String)>,
+    // Biggest count of an evicted term
+    error: usize,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 /// A struct holding data to plot a Histogram of the most frequent terms in an
 /// arbitrary input.
 ///
@@ -13,7 +30,25 @@ use crate::format::HorizontalScale;
 /// `observe` method.
 pub struct CommonTerms {
     pub terms: HashMap<String, usize>,
+    // Order in which terms were first observed
+    #[cfg_attr(feature = "serde", serde(skip))]
+    first_seen: HashMap<String, usize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    next_seen: usize,
+    // Terms sorted by count, when tracking a bounded number of them
+    #[cfg_attr(feature = "serde", serde(skip))]
+    approx: Option<Approx>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     lines: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    totals: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    sort: SortOrder,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    reverse: bool,
+    // Counts of the terms when the last snapshot was taken
+    #[cfg_attr(feature = "serde", serde(skip))]
+    snapshot: Option<HashMap<String, usize>>,
 }
 
 impl CommonTerms {
@@ -23,43 +58,212 @@ impl CommonTerms {
     pub fn new(lines: usize) -> Self {
         Self {
             terms: HashMap::new(),
+            first_seen: HashMap::new(),
+            next_seen: 0,
+            approx: None,
             lines,
+            totals: false,
+            sort: SortOrder::Count,
+            reverse: false,
+            snapshot: None,
+        }
+    }
+
+    /// Create an empty `CommonTerms` that tracks no more than `capacity`
+    /// terms, using the Space-Saving algorithm: when a new term is observed
+    /// and there is no room for it, the least frequent term is replaced, and
+    /// the new term inherits its count.  The counts of terms are therefore
+    /// approximated (overestimated by the biggest count evicted, at most).
+    ///
+    /// `lines` is the number of lines to be displayed.
+    pub fn new_approx(lines: usize, capacity: usize) -> Self {
+        Self {
+            approx: Some(Approx {
+                capacity: capacity.max(1),
+                by_count: BTreeSet::new(),
+                error: 0,
+            }),
+            ..Self::new(lines)
+        }
+    }
+
+    /// Sets the order in which the most frequent terms are displayed (being
+    /// `SortOrder::None` the order in which they were first observed).
+    pub fn set_sort(&mut self, sort: SortOrder, reverse: bool) {
+        self.sort = sort;
+        self.reverse = reverse;
+    }
+
+    /// Sets whether to display, after the most frequent terms, a row
+    /// aggregating the rest of them (if any) and a row with the total count.
+    pub fn set_totals(&mut self, totals: bool) {
+        self.totals = totals;
+    }
+
+    /// Remembers the current counts of the terms, so that the changes since
+    /// then are displayed next to their bars (like "▲3" or "▼1"), for plots
+    /// refreshed as the input is read.
+    pub fn snapshot(&mut self) {
+        self.snapshot = Some(self.terms.clone());
+    }
+
+    /// Forgets the terms observed so far, keeping the options and the
+    /// snapshot (if any).
+    pub fn clear(&mut self) {
+        self.terms.clear();
+        self.first_seen.clear();
+        self.next_seen = 0;
+        if let Some(approx) = self.approx.as_mut() {
+            approx.by_count.clear();
+            approx.error = 0;
         }
     }
 
     /// Observe a new "term".
     pub fn observe(&mut self, term: String) {
-        *self.terms.entry(term).or_insert(0) += 1;
+        let count = match self.terms.get(&term) {
+            Some(count) => *count,
+            None => {
+                self.first_seen.insert(term.clone(), self.next_seen);
+                self.next_seen += 1;
+                self.evict_if_full()
+            }
+        };
+        if let Some(approx) = self.approx.as_mut() {
+            approx.by_count.remove(&(count, term.clone()));
+            approx.by_count.insert((count + 1, term.clone()));
+        }
+        self.terms.insert(term, count + 1);
+    }
+
+    // Makes room for a new term when tracking a bounded number of them,
+    // returning the count of the evicted term (if any).
+    fn evict_if_full(&mut self) -> usize {
+        let approx = match self.approx.as_mut() {
+            Some(approx) if self.terms.len() >= approx.capacity => approx,
+            _ => return 0,
+        };
+        let (count, evicted) = approx.by_count.pop_first().unwrap();
+        approx.error = approx.error.max(count);
+        self.terms.remove(&evicted);
+        self.first_seen.remove(&evicted);
+        count
+    }
+}
+
+impl CommonTerms {
+    // Terms and their counts, from the most frequent one
+    fn by_count(&self) -> Vec<(&String, &usize)> {
+        let mut counts: Vec<(&String, &usize)> = self.terms.iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        counts
+    }
+
+    // The most frequent terms (as returned by `by_count`) that are displayed,
+    // in display order
+    fn displayed<'a>(&self, counts: &[(&'a String, &'a usize)]) -> Vec<(&'a String, &'a usize)> {
+        let mut values = counts[..self.lines.min(counts.len())].to_vec();
+        values.sort_by_key(|x| self.first_seen.get(x.0));
+        sort_rows(&mut values, self.sort, self.reverse, |x| (x.0, *x.1));
+        values
+    }
+
+    // Change of the count of a term since the snapshot (with a leading
+    // blank), if any
+    fn trend(&self, term: &str, count: usize, theme: &Theme) -> String {
+        let before = match &self.snapshot {
+            Some(snapshot) => snapshot.get(term).copied().unwrap_or(0),
+            None => return String::new(),
+        };
+        let trend = match count.cmp(&before) {
+            Ordering::Greater => format!("{TREND_UP}{}", count - before),
+            Ordering::Less => format!("{TREND_DOWN}{}", before - count),
+            Ordering::Equal => return String::new(),
+        };
+        format!(" {}", theme.label.paint(trend))
     }
 }
 
 impl fmt::Display for CommonTerms {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
         let width = f.width().unwrap_or(100);
-        let mut counts: Vec<(&String, &usize)> = self.terms.iter().collect();
+        let counts = self.by_count();
         if counts.is_empty() {
             writeln!(f, "No data")?;
             return Ok(());
         }
-        counts.sort_by(|a, b| b.1.cmp(a.1));
-        let values = &counts[..self.lines.min(counts.len())];
-        let label_width = values.iter().fold(1, |acc, x| acc.max(x.0.len()));
-        let horizontal_scale = HorizontalScale::new(counts[0].1 / width);
-        let width_count = format!("{}", counts[0].1).len();
+        let values = self.displayed(&counts);
+        let other: usize = counts[values.len()..].iter().map(|x| x.1).sum();
+        let total: usize = counts.iter().map(|x| x.1).sum();
+        let mut label_width = values.iter().fold(1, |acc, x| acc.max(x.0.len()));
+        let mut top = *counts[0].1;
+        if self.totals {
+            label_width = label_width.max(OTHER_LABEL.len());
+            top = top.max(other);
+        }
+        let horizontal_scale = HorizontalScale::new(top / width);
+        let width_count = match self.totals {
+            true => format!("{total}").len(),
+            false => format!("{top}").len(),
+        };
+        if let Some(approx) = self.approx.as_ref().filter(|a| a.error > 0) {
+            writeln!(
+                f,
+                "Counts are approximate (overestimated by {} at most)",
+                theme.label.paint(approx.error)
+            )?;
+        }
         writeln!(f, "{horizontal_scale}")?;
         for (term, count) in values.iter() {
             writeln!(
                 f,
-                "[{label}] [{count}] {bar}",
-                label = Blue.paint(format!("{term:>label_width$}")),
+                "[{label}] [{count}] {bar}{trend}",
+                label = theme.label.paint(format!("{term:>label_width$}")),
                 count = horizontal_scale.get_count(**count, width_count),
-                bar = horizontal_scale.get_bar(**count)
+                bar = horizontal_scale.get_bar(**count),
+                trend = self.trend(term, **count, &theme),
+            )?;
+        }
+        if self.totals {
+            if other > 0 {
+                writeln!(
+                    f,
+                    "[{label}] [{count}] {bar}",
+                    label = theme.label.paint(format!("{OTHER_LABEL:>label_width$}")),
+                    count = horizontal_scale.get_count(other, width_count),
+                    bar = horizontal_scale.get_bar(other)
+                )?;
+            }
+            writeln!(
+                f,
+                "[{label}] [{count}]",
+                label = theme.label.paint(format!("{TOTAL_LABEL:>label_width$}")),
+                count = horizontal_scale.get_count(total, width_count),
             )?;
         }
         Ok(())
     }
 }
 
+impl Render for CommonTerms {
+    /// Terms not displayed are counted in a row of their own if totals were
+    /// requested.
+    fn table(&self) -> Option<Table> {
+        let counts = self.by_count();
+        let values = self.displayed(&counts);
+        let other: usize = counts[values.len()..].iter().map(|x| x.1).sum();
+        let mut table = Table::new(&["term", "count"]);
+        for (term, count) in &values {
+            table.push(vec![term.as_str().into(), (**count).into()]);
+        }
+        if self.totals && other > 0 {
+            table.push(vec![OTHER_LABEL.into(), other.into()]);
+        }
+        Some(table)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -92,5 +296,102 @@ mod tests {
         assert!(display.contains("[   foo] [100] ∎∎∎∎∎∎∎∎∎∎\n"));
         assert!(display.contains("[barbar] [ 20] ∎∎\n"));
         assert!(!display.contains("arr"));
+        assert!(!display.contains("total"));
+    }
+
+    #[test]
+    fn test_common_terms_sort() {
+        let mut terms = CommonTerms::new(3);
+        for term in ["b", "c", "a", "a", "c", "z", "c", "b", "a"] {
+            terms.observe(String::from(term));
+        }
+        let labels = |terms: &CommonTerms| {
+            Paint::disable();
+            format!("{terms}")
+                .lines()
+                .filter(|l| l.starts_with('['))
+                .map(|l| l[1..2].to_string())
+                .collect::<String>()
+        };
+        assert_eq!(labels(&terms), "acb");
+        terms.set_sort(SortOrder::Count, true);
+        assert_eq!(labels(&terms), "bca");
+        terms.set_sort(SortOrder::Label, false);
+        assert_eq!(labels(&terms), "abc");
+        terms.set_sort(SortOrder::None, false);
+        assert_eq!(labels(&terms), "bca");
+    }
+
+    #[test]
+    fn test_common_terms_approx() {
+        let mut terms = CommonTerms::new_approx(2, 3);
+        for term in ["a", "a", "a", "b", "b", "c", "d", "a", "b", "e"] {
+            terms.observe(String::from(term));
+        }
+        // "c" (count 1) was evicted by "d", that was evicted by "e"
+        assert_eq!(terms.terms.len(), 3);
+        assert_eq!(terms.terms["a"], 4);
+        assert_eq!(terms.terms["b"], 3);
+        assert_eq!(terms.terms["e"], 3);
+        assert!(!terms.first_seen.contains_key("c"));
+        terms.set_totals(true);
+        Paint::disable();
+        let display = format!("{terms:10}");
+        assert!(display.contains("(overestimated by 2 at most)\n"));
+        assert!(display.contains("[      a] [ 4] ∎∎∎∎\n"));
+        assert!(display.contains("[(total)] [10]\n"));
+        terms.clear();
+        terms.observe(String::from("c"));
+        assert_eq!(terms.terms.len(), 1);
+        assert!(!format!("{terms}").contains("overestimated"));
+        // No approximation is needed while there is room for all terms
+        let mut terms = CommonTerms::new_approx(2, 3);
+        terms.observe(String::from("a"));
+        assert!(!format!("{terms}").contains("approximate"));
+    }
+
+    #[test]
+    fn test_common_terms_trends() {
+        let mut terms = CommonTerms::new(3);
+        for term in ["foo", "foo", "bar", "bar", "bar"] {
+            terms.observe(String::from(term));
+        }
+        Paint::disable();
+        assert!(format!("{terms:10}").contains("[bar] [3] ∎∎∎\n"));
+        terms.snapshot();
+        terms.clear();
+        for term in ["foo", "foo", "foo", "bar", "baz"] {
+            terms.observe(String::from(term));
+        }
+        let display = format!("{terms:10}");
+        assert!(display.contains("[foo] [3] ∎∎∎ ▲1\n"));
+        assert!(display.contains("[bar] [1] ∎ ▼2\n"));
+        assert!(display.contains("[baz] [1] ∎ ▲1\n"));
+        terms.snapshot();
+        assert!(format!("{terms:10}").contains("[foo] [3] ∎∎∎\n"));
+    }
+
+    #[test]
+    fn test_common_terms_totals() {
+        let mut terms = CommonTerms::new(1);
+        terms.set_totals(true);
+        for term in ["foo", "foo", "foo", "bar", "bar", "baz", "qux", "qux"] {
+            terms.observe(String::from(term));
+        }
+        Paint::disable();
+        let display = format!("{terms:10}");
+        assert!(display.contains("[    foo] [3] ∎∎∎\n"));
+        assert!(display.contains("[(other)] [5] ∎∎∎∎∎\n"));
+        assert!(display.contains("[(total)] [8]\n"));
+        assert_eq!(
+            terms.table().unwrap().to_csv(','),
+            "term,count\nfoo,3\n(other),5\n"
+        );
+        let mut terms = CommonTerms::new(5);
+        terms.set_totals(true);
+        terms.observe(String::from("foo"));
+        let display = format!("{terms:10}");
+        assert!(!display.contains("other"));
+        assert!(display.contains("[(total)] [1]\n"));
     }
 }
@@ -1,37 +1,182 @@
 use std::fmt;
+use std::str::FromStr;
 
 use chrono::{DateTime, Duration, FixedOffset};
-use yansi::Color::Blue;
 
-use crate::format::HorizontalScale;
-use crate::plot::date_fmt_string;
+use crate::format::{F64Formatter, HorizontalScale, Theme, BAR_CHAR};
+use crate::plot::render::svg;
+use crate::plot::{date_fmt_string, merge_buckets, Render, Table, TimeLabel, ToSvg};
+
+/// Number of previous buckets used for telling whether a bucket is a spike.
+const SPIKE_WINDOW: usize = 10;
+/// Spikes are not looked for in the first buckets, without enough history.
+const SPIKE_MIN_HISTORY: usize = 3;
+/// Marker for the buckets detected as spikes.
+const SPIKE_MARKER: &str = "◀";
+
+/// How the values in every bucket of a `TimeHistogram` are aggregated, when
+/// it holds values rather than just counts of timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    /// Total of the values.
+    Sum,
+    /// Average of the values.
+    Avg,
+    /// Biggest value.
+    Max,
+}
+
+impl FromStr for Aggregation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sum" => Ok(Aggregation::Sum),
+            "avg" => Ok(Aggregation::Avg),
+            "max" => Ok(Aggregation::Max),
+            _ => Err(format!("Unknown aggregation: {s}")),
+        }
+    }
+}
+
+impl fmt::Display for Aggregation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Aggregation::Sum => "Sum",
+            Aggregation::Avg => "Average",
+            Aggregation::Max => "Maximum",
+        };
+        f.write_str(name)
+    }
+}
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 struct TimeBucket {
     start: DateTime<FixedOffset>,
     count: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    sum: f64,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    max: f64,
 }
 
 impl TimeBucket {
     fn new(start: DateTime<FixedOffset>) -> Self {
-        Self { start, count: 0 }
+        Self {
+            start,
+            count: 0,
+            sum: 0.0,
+            max: f64::NEG_INFINITY,
+        }
     }
 
     fn inc(&mut self) {
         self.count += 1;
     }
+
+    fn add_value(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.max = self.max.max(value);
+    }
+
+    // Aggregated value, zero for empty buckets
+    fn value(&self, aggregation: Aggregation) -> f64 {
+        match (self.count, aggregation) {
+            (0, _) => 0.0,
+            (_, Aggregation::Sum) => self.sum,
+            (count, Aggregation::Avg) => self.sum / count as f64,
+            (_, Aggregation::Max) => self.max,
+        }
+    }
+}
+
+/// Options for building a `TimeHistogram` with a `TimeHistogramBuilder`.
+#[derive(Builder, Clone)]
+#[builder(
+    name = "TimeHistogramBuilder",
+    public,
+    build_fn(private, name = "options")
+)]
+struct TimeHistogramOptions {
+    /// Number of histogram buckets to display.
+    #[builder(default = "20")]
+    intervals: usize,
+    /// If present, the number of characters to use for the longest bar when
+    /// displaying the histogram with no explicit width.
+    #[builder(setter(strip_option), default)]
+    width: Option<usize>,
+    /// How values are aggregated in every bucket, when building the histogram
+    /// with `build_values`.
+    #[builder(default = "Aggregation::Sum")]
+    aggregation: Aggregation,
+    /// If true, histograms built with `build_values` display the count of
+    /// values in every bucket too, with bars of their own.
+    #[builder(default)]
+    with_counts: bool,
+    /// How the buckets are labelled.
+    #[builder(default)]
+    label: TimeLabel,
+    /// If present, buckets over the mean of the previous ones by more than
+    /// this number of standard deviations are highlighted as spikes.
+    #[builder(setter(strip_option), default)]
+    anomalies: Option<f64>,
+}
+
+impl TimeHistogramBuilder {
+    /// Creates a `TimeHistogram` from a vector of `DateTime` elements, using
+    /// the options set in the builder.
+    pub fn build(&self, ts: &[DateTime<FixedOffset>]) -> TimeHistogram {
+        let options = self.options().unwrap();
+        let mut timehist = TimeHistogram::new(options.intervals, ts);
+        timehist.width = options.width;
+        timehist.anomalies = options.anomalies;
+        timehist.label = options.label;
+        timehist
+    }
+
+    /// Creates a `TimeHistogram` from pairs of a timestamp and a value, where
+    /// every bucket displays the aggregation of the values in it (rather than
+    /// their count), using the options set in the builder.
+    pub fn build_values(&self, points: &[(DateTime<FixedOffset>, f64)]) -> TimeHistogram {
+        let options = self.options().unwrap();
+        let mut timehist =
+            TimeHistogram::new_with_values(options.intervals, points, options.aggregation);
+        timehist.width = options.width;
+        timehist.with_counts = options.with_counts;
+        timehist.anomalies = options.anomalies;
+        timehist.label = options.label;
+        timehist
+    }
 }
 
 #[derive(Debug)]
 /// A struct holding data to plot a `TimeHistogram` of timestamp data.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TimeHistogram {
+    #[cfg_attr(feature = "serde", serde(rename = "buckets"))]
     vec: Vec<TimeBucket>,
     min: DateTime<FixedOffset>,
     max: DateTime<FixedOffset>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     step: Duration,
+    #[cfg_attr(feature = "serde", serde(skip))]
     top: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
     last: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
     nanos: u64,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    width: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    aggregation: Option<Aggregation>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    anomalies: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    with_counts: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    label: TimeLabel,
 }
 
 impl TimeHistogram {
@@ -39,15 +184,42 @@ impl TimeHistogram {
     ///
     /// `size` is the number of histogram buckets to display.
     pub fn new(size: usize, ts: &[DateTime<FixedOffset>]) -> Self {
-        let mut vec = Vec::<TimeBucket>::with_capacity(size);
         let min = *ts.iter().min().unwrap();
         let max = *ts.iter().max().unwrap();
+        let mut timehist = Self::new_empty(size, min, max);
+        timehist.load(ts);
+        timehist
+    }
+
+    /// Creates a Histogram from pairs of a timestamp and a value, where every
+    /// bucket holds the `aggregation` of the values in it.
+    ///
+    /// `size` is the number of histogram buckets to display.
+    pub fn new_with_values(
+        size: usize,
+        points: &[(DateTime<FixedOffset>, f64)],
+        aggregation: Aggregation,
+    ) -> Self {
+        let min = points.iter().map(|p| p.0).min().unwrap();
+        let max = points.iter().map(|p| p.0).max().unwrap();
+        let mut timehist = Self::new_empty(size, min, max);
+        timehist.aggregation = Some(aggregation);
+        for (ts, value) in points {
+            if let Some(slot) = timehist.find_slot(*ts) {
+                timehist.vec[slot].add_value(*value);
+            }
+        }
+        timehist
+    }
+
+    fn new_empty(size: usize, min: DateTime<FixedOffset>, max: DateTime<FixedOffset>) -> Self {
+        let mut vec = Vec::<TimeBucket>::with_capacity(size);
         let step = max - min;
         let inc = step / size as i32;
         for i in 0..size {
             vec.push(TimeBucket::new(min + (inc * i as i32)));
         }
-        let mut timehist = Self {
+        Self {
             vec,
             min,
             max,
@@ -55,9 +227,12 @@ impl TimeHistogram {
             top: 0,
             last: size - 1,
             nanos: (max - min).num_microseconds().unwrap() as u64,
-        };
-        timehist.load(ts);
-        timehist
+            width: None,
+            aggregation: None,
+            anomalies: None,
+            with_counts: false,
+            label: TimeLabel::Start,
+        }
     }
 
     /// Add to the `TimeHistogram` data the values of a slice of `DateTime`
@@ -79,47 +254,379 @@ impl TimeHistogram {
         }
     }
 
+    /// Returns the state of the histogram (its time span and the counts of its
+    /// buckets) as a JSON object, so that it can be merged with the ones of
+    /// other inputs with `TimeHistogram::from_states`.  The values of histograms
+    /// aggregating values are not kept.
+    pub fn to_state(&self) -> serde_json::Value {
+        serde_json::json!({
+            "kind": "timehist",
+            "min": self.min.to_rfc3339(),
+            "max": self.max.to_rfc3339(),
+            "counts": self.counts(),
+        })
+    }
+
+    /// Creates a `TimeHistogram` merging the states written by `to_state` for
+    /// several inputs (like the logs of several hosts).
+    ///
+    /// Bucket counts are added when all histograms span the same time with
+    /// the same buckets.  Otherwise, they are spread over buckets of the same
+    /// size covering all of them, which is approximate.  Returns the histogram
+    /// and whether the merge was exact.
+    pub fn from_states(states: &[serde_json::Value]) -> Result<(Self, bool), String> {
+        let mut spans = Vec::with_capacity(states.len());
+        for state in states {
+            if state["kind"] != "timehist" {
+                return Err(String::from("Not the state of a time histogram"));
+            }
+            let timestamp = |field: &str| {
+                state[field]
+                    .as_str()
+                    .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                    .ok_or(format!("Bad timestamp '{field}' in time histogram state"))
+            };
+            let counts: Vec<usize> = state["counts"]
+                .as_array()
+                .map(|a| {
+                    a.iter()
+                        .filter_map(|x| x.as_u64())
+                        .map(|x| x as usize)
+                        .collect()
+                })
+                .unwrap_or_default();
+            if counts.is_empty() {
+                return Err(String::from("Bad buckets in time histogram state"));
+            }
+            spans.push((timestamp("min")?, timestamp("max")?, counts));
+        }
+        Ok(Self::from_spans(&spans))
+    }
+
+    /// Merges the timestamps of `other` (like the ones read by another
+    /// thread) into the histogram.  Buckets are added if both histograms span
+    /// the same time with the same buckets.  Otherwise, counts are spread over
+    /// buckets of the same size covering both, like in
+    /// `TimeHistogram::from_states`, and aggregated values are lost.  Returns
+    /// whether the merge was exact.
+    #[allow(dead_code)] // Only used by library users
+    pub fn merge(&mut self, other: &Self) -> bool {
+        if self.min == other.min && self.max == other.max && self.vec.len() == other.vec.len() {
+            for (bucket, theirs) in self.vec.iter_mut().zip(&other.vec) {
+                bucket.count += theirs.count;
+                bucket.sum += theirs.sum;
+                bucket.max = bucket.max.max(theirs.max);
+            }
+            self.top = self.vec.iter().map(|b| b.count).max().unwrap();
+            return true;
+        }
+        let spans = [
+            (self.min, self.max, self.counts()),
+            (other.min, other.max, other.counts()),
+        ];
+        let (merged, exact) = Self::from_spans(&spans);
+        *self = Self {
+            width: self.width,
+            anomalies: self.anomalies,
+            ..merged
+        };
+        exact
+    }
+
+    // Merges the buckets of several histograms, given as their time span and
+    // counts, returning whether it was exact
+    fn from_spans(
+        spans: &[(DateTime<FixedOffset>, DateTime<FixedOffset>, Vec<usize>)],
+    ) -> (Self, bool) {
+        let start = spans.iter().map(|(min, _, _)| *min).min().unwrap();
+        let seconds = |ts: DateTime<FixedOffset>| {
+            (ts - start).num_microseconds().unwrap() as f64 / 1_000_000.0
+        };
+        let parts: Vec<(Vec<f64>, Vec<usize>)> = spans
+            .iter()
+            .map(|(min, max, counts)| {
+                let (low, high) = (seconds(*min), seconds(*max));
+                let step = (high - low) / counts.len() as f64;
+                let mut edges: Vec<f64> =
+                    (0..counts.len()).map(|i| low + step * i as f64).collect();
+                edges.push(high);
+                (edges, counts.clone())
+            })
+            .collect();
+        let (edges, counts, exact) = merge_buckets(&parts);
+        let end = start + Duration::microseconds((edges[edges.len() - 1] * 1e6).round() as i64);
+        let mut timehist = Self::new_empty(counts.len(), start, end);
+        for (bucket, count) in timehist.vec.iter_mut().zip(counts) {
+            bucket.count = count;
+        }
+        timehist.top = timehist.vec.iter().map(|b| b.count).max().unwrap();
+        (timehist, exact)
+    }
+
+    /// Returns the number of timestamps in every bucket.
+    pub fn counts(&self) -> Vec<usize> {
+        self.vec.iter().map(|row| row.count).collect()
+    }
+
     fn find_slot(&self, ts: DateTime<FixedOffset>) -> Option<usize> {
         if ts < self.min || ts > self.max {
             None
         } else {
             let x = (ts - self.min).num_microseconds().unwrap() as u64;
-            if self.nanos == 0 {
-                // All timestamps are the same.  We will have a degenerate plot
-                // (as opposed to failing hard).
-                Some(0)
-            } else {
-                Some(((x * self.vec.len() as u64 / self.nanos) as usize).min(self.last))
+            // If all timestamps are the same (nanos is zero) we will have a
+            // degenerate plot (as opposed to failing hard).
+            match (x * self.vec.len() as u64).checked_div(self.nanos) {
+                Some(slot) => Some((slot as usize).min(self.last)),
+                None => Some(0),
             }
         }
     }
+
+    // Count of every bucket, or its aggregated value
+    fn levels(&self) -> Vec<f64> {
+        self.vec
+            .iter()
+            .map(|row| match self.aggregation {
+                Some(aggregation) => row.value(aggregation),
+                None => row.count as f64,
+            })
+            .collect()
+    }
+
+    // For every bucket, the number of standard deviations its level is over
+    // the mean of the previous buckets, if that is over the threshold set for
+    // anomalies
+    fn spikes(&self) -> Vec<Option<f64>> {
+        let levels = self.levels();
+        let sigmas = match self.anomalies {
+            Some(sigmas) => sigmas,
+            None => return vec![None; levels.len()],
+        };
+        (0..levels.len())
+            .map(|i| {
+                let window = &levels[i.saturating_sub(SPIKE_WINDOW)..i];
+                if window.len() < SPIKE_MIN_HISTORY {
+                    return None;
+                }
+                let n = window.len() as f64;
+                let mean = window.iter().sum::<f64>() / n;
+                let mut std = (window.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n).sqrt();
+                if self.aggregation.is_none() {
+                    // Counts of random events have a deviation of about the
+                    // square root of their mean, even if the window is flat
+                    std = std.max(mean.sqrt()).max(1.0);
+                } else if std == 0.0 {
+                    return None;
+                }
+                let score = (levels[i] - mean) / std;
+                (score > sigmas).then_some(score)
+            })
+            .collect()
+    }
+
+    // Label of a bucket, with timestamps formatted with `ts_fmt`
+    fn bucket_label(&self, row: &TimeBucket, ts_fmt: &str) -> String {
+        let end = row.start + self.step / self.vec.len() as i32;
+        self.label.format(&(row.start..end), self.min, ts_fmt)
+    }
+
+    fn fmt_spikes(
+        &self,
+        f: &mut fmt::Formatter,
+        spikes: &[Option<f64>],
+        ts_fmt: &str,
+    ) -> fmt::Result {
+        let sigmas = match self.anomalies {
+            Some(sigmas) => sigmas,
+            None => return Ok(()),
+        };
+        let theme = Theme::current();
+        let found: Vec<(&TimeBucket, f64)> = self
+            .vec
+            .iter()
+            .zip(spikes)
+            .filter_map(|(row, spike)| spike.map(|score| (row, score)))
+            .collect();
+        match found.len() {
+            0 => return writeln!(f, "No spikes over {sigmas} sigmas."),
+            1 => writeln!(f, "1 spike over {sigmas} sigmas:")?,
+            n => writeln!(f, "{n} spikes over {sigmas} sigmas:")?,
+        }
+        for (row, score) in found {
+            writeln!(
+                f,
+                "[{}] {:.1} sigmas",
+                theme.label.paint(self.bucket_label(row, ts_fmt)),
+                score,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn fmt_values(
+        &self,
+        f: &mut fmt::Formatter,
+        aggregation: Aggregation,
+        width: usize,
+    ) -> fmt::Result {
+        let theme = Theme::current();
+        let values: Vec<f64> = self.vec.iter().map(|r| r.value(aggregation)).collect();
+        let bottom = values.iter().copied().fold(0.0, f64::min);
+        let top = values.iter().copied().fold(0.0, f64::max);
+        let formatter = F64Formatter::new_with_range(bottom..top);
+        let width_value = values
+            .iter()
+            .map(|v| formatter.format(*v).len())
+            .max()
+            .unwrap_or(1);
+        writeln!(
+            f,
+            "Matches: {}.",
+            theme.label.paint(format!(
+                "{}",
+                self.vec.iter().map(|r| r.count).sum::<usize>()
+            )),
+        )?;
+        // With counts, these take half of the width, before the values
+        let (count_bars, width) = match self.with_counts {
+            true => (width / 2, width - width / 2),
+            false => (0, width),
+        };
+        let top_count = self.vec.iter().map(|r| r.count).max().unwrap_or(0);
+        let width_count = top_count.to_string().len();
+        match self.with_counts {
+            true => writeln!(
+                f,
+                "Count and {} of the values in every bucket",
+                aggregation.to_string().to_lowercase()
+            )?,
+            false => writeln!(f, "{aggregation} of the values in every bucket")?,
+        }
+        let ts_fmt = date_fmt_string(self.step.num_seconds());
+        let spikes = self.spikes();
+        for ((row, value), spike) in self.vec.iter().zip(values).zip(&spikes) {
+            let counts = match self.with_counts {
+                true => {
+                    let units = (row.count as f64 / top_count as f64 * count_bars as f64).round();
+                    format!(
+                        "[{}] {} ",
+                        theme.count.paint(format!("{:>width_count$}", row.count)),
+                        theme
+                            .bar
+                            .paint(format!("{:<count_bars$}", BAR_CHAR.repeat(units as usize))),
+                    )
+                }
+                false => String::new(),
+            };
+            // Negative values have no bar
+            let units = match top > 0.0 {
+                true => (value.max(0.0) / top * width as f64).round() as usize,
+                false => 0,
+            };
+            let bar = BAR_CHAR.repeat(units);
+            let (bar, marker) = match spike {
+                Some(_) => (theme.label.paint(bar), spike_marker(&theme)),
+                None => (theme.bar.paint(bar), String::new()),
+            };
+            writeln!(
+                f,
+                "[{label}] {counts}[{value}] {bar}{marker}",
+                label = theme.label.paint(self.bucket_label(row, ts_fmt)),
+                value = theme
+                    .label
+                    .paint(format!("{:>width_value$}", formatter.format(value))),
+            )?;
+        }
+        self.fmt_spikes(f, &spikes, ts_fmt)
+    }
+}
+
+// Marker (with a leading blank) for the bars of the buckets detected as spikes
+fn spike_marker(theme: &Theme) -> String {
+    format!(" {}", theme.label.paint(SPIKE_MARKER))
 }
 
 impl fmt::Display for TimeHistogram {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let width = f.width().unwrap_or(100);
+        let theme = Theme::current();
+        let width = f.width().or(self.width).unwrap_or(100);
+        if let Some(aggregation) = self.aggregation {
+            return self.fmt_values(f, aggregation, width);
+        }
         let horizontal_scale = HorizontalScale::new(self.top / width);
         let width_count = format!("{}", self.top).len();
         writeln!(
             f,
             "Matches: {}.",
-            Blue.paint(format!(
+            theme.label.paint(format!(
                 "{}",
                 self.vec.iter().map(|r| r.count).sum::<usize>()
             )),
         )?;
         writeln!(f, "{horizontal_scale}")?;
         let ts_fmt = date_fmt_string(self.step.num_seconds());
-        for row in &self.vec {
+        let spikes = self.spikes();
+        for (row, spike) in self.vec.iter().zip(&spikes) {
+            let bar = horizontal_scale.get_bar(row.count);
+            let (bar, marker) = match spike {
+                Some(_) => (bar.fg(theme.label), spike_marker(&theme)),
+                None => (bar, String::new()),
+            };
             writeln!(
                 f,
-                "[{label}] [{count}] {bar}",
-                label = Blue.paint(format!("{}", row.start.format(ts_fmt))),
+                "[{label}] [{count}] {bar}{marker}",
+                label = theme.label.paint(self.bucket_label(row, ts_fmt)),
                 count = horizontal_scale.get_count(row.count, width_count),
-                bar = horizontal_scale.get_bar(row.count)
             )?;
         }
-        Ok(())
+        self.fmt_spikes(f, &spikes, ts_fmt)
+    }
+}
+
+impl Render for TimeHistogram {
+    /// With aggregated values, the count of every bar is its value rounded to
+    /// an integer (zero if negative).
+    fn rows(&self) -> Vec<(String, usize)> {
+        let ts_fmt = date_fmt_string(self.step.num_seconds());
+        self.vec
+            .iter()
+            .map(|row| {
+                let count = match self.aggregation {
+                    Some(aggregation) => row.value(aggregation).max(0.0).round() as usize,
+                    None => row.count,
+                };
+                (format!("[{}]", self.bucket_label(row, ts_fmt)), count)
+            })
+            .collect()
+    }
+
+    /// With aggregated values, the aggregation of the values of every bucket
+    /// follows its count.
+    fn table(&self) -> Option<Table> {
+        let aggregation = self.aggregation.map(|a| a.to_string().to_lowercase());
+        let mut columns = vec!["start", "end", "count"];
+        columns.extend(aggregation.as_deref());
+        let mut table = Table::new(&columns);
+        let step = self.step / self.vec.len() as i32;
+        for row in &self.vec {
+            let mut fields = vec![
+                row.start.to_rfc3339().into(),
+                (row.start + step).to_rfc3339().into(),
+                row.count.into(),
+            ];
+            if let Some(aggregation) = self.aggregation {
+                fields.push(row.value(aggregation).into());
+            }
+            table.push(fields);
+        }
+        Some(table)
+    }
+}
+
+impl ToSvg for TimeHistogram {
+    fn to_svg(&self, width: usize, theme: Theme) -> String {
+        let matches: usize = self.vec.iter().map(|r| r.count).sum();
+        svg::bar_chart(&format!("Matches: {matches}."), &self.rows(), width, theme)
     }
 }
 
@@ -147,6 +654,167 @@ mod tests {
         assert!(display.contains("[2022-08-14 20:25:00] [1] ∎\n"));
     }
 
+    #[test]
+    fn test_svg() {
+        let vec = vec![
+            DateTime::parse_from_rfc3339("2021-04-15T04:25:00+00:00").unwrap(),
+            DateTime::parse_from_rfc3339("2021-04-15T04:26:00+00:00").unwrap(),
+        ];
+        let svg = TimeHistogram::new(2, &vec).to_svg(300, Theme::DEFAULT);
+        assert!(svg.contains(">Matches: 2.</text>"));
+        assert!(svg.contains(">[04:25:00.000]</text>"));
+        assert!(svg.contains(">[04:25:30.000]</text>"));
+    }
+
+    #[test]
+    fn test_csv() {
+        let vec = vec![
+            DateTime::parse_from_rfc3339("2021-04-15T04:25:00+00:00").unwrap(),
+            DateTime::parse_from_rfc3339("2021-04-15T04:26:00+00:00").unwrap(),
+        ];
+        assert_eq!(
+            TimeHistogram::new(2, &vec).table().unwrap().to_csv(','),
+            concat!(
+                "start,end,count\n",
+                "2021-04-15T04:25:00+00:00,2021-04-15T04:25:30+00:00,1\n",
+                "2021-04-15T04:25:30+00:00,2021-04-15T04:26:00+00:00,1\n"
+            )
+        );
+        let points = [(vec[0], 2.0), (vec[0], 4.0), (vec[1], 1.0)];
+        let th = TimeHistogramBuilder::default()
+            .intervals(2)
+            .aggregation(Aggregation::Avg)
+            .build_values(&points);
+        assert!(th.table().unwrap().to_csv(',').starts_with(
+            "start,end,count,average\n2021-04-15T04:25:00+00:00,2021-04-15T04:25:30+00:00,2,3\n"
+        ));
+    }
+
+    #[test]
+    fn test_builder() {
+        Paint::disable();
+        let vec = vec![
+            DateTime::parse_from_rfc3339("2021-04-15T04:25:00+00:00").unwrap(),
+            DateTime::parse_from_rfc3339("2021-04-15T04:26:00+00:00").unwrap(),
+        ];
+        let th = TimeHistogramBuilder::default()
+            .intervals(2)
+            .width(5)
+            .build(&vec);
+        assert_eq!(th.vec.len(), 2);
+        let display = format!("{th}");
+        assert!(display.contains("[04:25:00.000] [1] ∎\n"));
+        assert!(display.contains("[04:25:30.000] [1] ∎\n"));
+    }
+
+    #[test]
+    fn test_values() {
+        Paint::disable();
+        let ts = |s| DateTime::parse_from_rfc3339(s).unwrap();
+        let points = vec![
+            (ts("2021-04-15T04:25:00+00:00"), 1000.0),
+            (ts("2021-04-15T04:25:10+00:00"), 3000.0),
+            (ts("2021-04-15T04:26:00+00:00"), 500.0),
+        ];
+        let th = TimeHistogramBuilder::default()
+            .intervals(2)
+            .width(8)
+            .aggregation(Aggregation::Avg)
+            .build_values(&points);
+        let display = format!("{th}");
+        assert!(display.contains("Matches: 3.\nAverage of the values in every bucket\n"));
+        assert!(display.contains("[04:25:00.000] [2000] ∎∎∎∎∎∎∎∎\n"));
+        assert!(display.contains("[04:25:30.000] [ 500] ∎∎\n"));
+        let th = TimeHistogram::new_with_values(2, &points, Aggregation::Sum);
+        assert_eq!(th.rows()[0].1, 4000);
+        let th = TimeHistogram::new_with_values(2, &points, Aggregation::Max);
+        assert_eq!(th.rows()[0].1, 3000);
+        let th = TimeHistogramBuilder::default()
+            .intervals(2)
+            .width(8)
+            .aggregation(Aggregation::Avg)
+            .with_counts(true)
+            .build_values(&points);
+        let display = format!("{th}");
+        assert!(display.contains("Count and average of the values in every bucket\n"));
+        assert!(display.contains("[04:25:00.000] [2] ∎∎∎∎ [2000] ∎∎∎∎\n"));
+        assert!(display.contains("[04:25:30.000] [1] ∎∎   [ 500] ∎\n"));
+        assert_eq!(Aggregation::from_str("max"), Ok(Aggregation::Max));
+        assert!(Aggregation::from_str("median").is_err());
+    }
+
+    #[test]
+    fn test_anomalies() {
+        Paint::disable();
+        let ts = |s: &str| DateTime::parse_from_rfc3339(s).unwrap();
+        let mut vec = Vec::new();
+        for minute in 0..6 {
+            let times = if minute == 4 { 20 } else { 2 };
+            for _ in 0..times {
+                vec.push(ts(&format!("2021-04-15T04:2{minute}:00+00:00")));
+            }
+        }
+        let th = TimeHistogramBuilder::default()
+            .intervals(6)
+            .anomalies(3.0)
+            .build(&vec);
+        let display = format!("{th}");
+        assert!(display.contains("[04:22:30.000] [ 2] ∎∎\n"));
+        assert!(display.contains("[04:23:20.000] [20] ∎∎∎∎∎∎∎∎∎∎∎∎∎∎∎∎∎∎∎∎ ◀\n"));
+        assert!(display.ends_with("1 spike over 3 sigmas:\n[04:23:20.000] 12.7 sigmas\n"));
+        let th = TimeHistogram::new(6, &vec);
+        assert!(!format!("{th}").contains("spike"));
+    }
+
+    #[test]
+    fn test_merge() {
+        let ts = |s: &str| DateTime::parse_from_rfc3339(s).unwrap();
+        let first = TimeHistogram::new(
+            2,
+            &[
+                ts("2023-04-15T04:25:00+00:00"),
+                ts("2023-04-15T04:25:01+00:00"),
+                ts("2023-04-15T04:26:00+00:00"),
+            ],
+        );
+        let second = TimeHistogram::new(
+            2,
+            &[
+                ts("2023-04-15T04:25:00+00:00"),
+                ts("2023-04-15T04:26:00+00:00"),
+            ],
+        );
+        let states = [first.to_state(), second.to_state()];
+        let (merged, exact) = TimeHistogram::from_states(&states).unwrap();
+        assert!(exact);
+        assert_eq!(merged.counts(), vec![3, 2]);
+        assert_eq!(merged.max, ts("2023-04-15T04:26:00+00:00"));
+        let third = TimeHistogram::new(
+            2,
+            &[
+                ts("2023-04-15T04:26:00+00:00"),
+                ts("2023-04-15T04:27:00+00:00"),
+            ],
+        );
+        let states = [first.to_state(), third.to_state()];
+        let (merged, exact) = TimeHistogram::from_states(&states).unwrap();
+        assert!(!exact);
+        assert_eq!(merged.counts(), vec![3, 2]);
+        assert_eq!(merged.max, ts("2023-04-15T04:27:00+00:00"));
+        let mut merged = TimeHistogram::new(2, &[ts("2023-04-15T04:25:00+00:00")]);
+        assert!(!merged.merge(&third));
+        assert_eq!(merged.counts(), vec![1, 2]);
+        let mut merged = TimeHistogram::new(
+            2,
+            &[
+                ts("2023-04-15T04:25:00+00:00"),
+                ts("2023-04-15T04:26:00+00:00"),
+            ],
+        );
+        assert!(merged.merge(&first));
+        assert_eq!(merged.counts(), vec![3, 2]);
+    }
+
     #[test]
     fn test_small_time_interval() {
         Paint::disable();
@@ -0,0 +1,197 @@
+use std::fmt;
+
+use chrono::{DateTime, Duration, FixedOffset};
+
+use crate::format::{F64Formatter, Theme, BAR_CHAR};
+use crate::plot::{date_fmt_string, Render, Table};
+use crate::stats::Stats;
+
+// Percentiles displayed for every bucket, with their labels
+const PERCENTILES: [&str; 3] = ["p50", "p95", "p99"];
+
+fn percentiles(stats: &Stats) -> [f64; 3] {
+    [stats.p50, stats.p95, stats.p99]
+}
+
+#[derive(Debug)]
+/// A struct holding data to plot some percentiles of values over time, with a
+/// bar for every one of them.
+pub struct TimePercentiles {
+    vec: Vec<(DateTime<FixedOffset>, Option<Stats>)>,
+    stats: Stats,
+    step: Duration,
+    precision: Option<usize>,
+}
+
+impl TimePercentiles {
+    /// Creates a `TimePercentiles` from a slice of values along with the time
+    /// they were observed.
+    ///
+    /// `size` is the number of time buckets to display.
+    ///
+    /// `precision` is an Option with the number of decimals to display.  If
+    /// "None" is used, human units will be used, with an heuristic based on the
+    /// input data for deciding the units and the decimal places.
+    pub fn new(
+        size: usize,
+        points: &[(DateTime<FixedOffset>, f64)],
+        precision: Option<usize>,
+    ) -> Self {
+        let min = points.iter().map(|p| p.0).min().unwrap();
+        let max = points.iter().map(|p| p.0).max().unwrap();
+        let step = max - min;
+        let mut buckets: Vec<Vec<f64>> = vec![Vec::new(); size];
+        let micros = step.num_microseconds().unwrap() as u64;
+        for (ts, value) in points {
+            let x = (*ts - min).num_microseconds().unwrap() as u64;
+            // If all timestamps are the same we will have a degenerate plot
+            let slot = match (x * size as u64).checked_div(micros) {
+                Some(slot) => (slot as usize).min(size - 1),
+                None => 0,
+            };
+            buckets[slot].push(*value);
+        }
+        let inc = step / size as i32;
+        let vec = buckets
+            .iter_mut()
+            .enumerate()
+            .map(|(i, values)| {
+                let stats = match values.is_empty() {
+                    true => None,
+                    false => Some(Stats::new(values, precision)),
+                };
+                (min + (inc * i as i32), stats)
+            })
+            .collect();
+        let mut values: Vec<f64> = points.iter().map(|p| p.1).collect();
+        Self {
+            vec,
+            stats: Stats::new(&mut values, precision),
+            step,
+            precision,
+        }
+    }
+}
+
+impl fmt::Display for TimePercentiles {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
+        let width = f.width().unwrap_or(110);
+        let formatter = match self.precision {
+            None => F64Formatter::new_with_range(self.stats.min..self.stats.max),
+            Some(n) => F64Formatter::new(n),
+        };
+        let ts_fmt = date_fmt_string(self.step.num_seconds());
+        let width_label = format!("{}", self.vec[0].0.format(ts_fmt)).len();
+        let width_count = format!("{}", self.stats.samples).len();
+        let width_value = self
+            .vec
+            .iter()
+            .filter_map(|(_, stats)| stats.as_ref())
+            .flat_map(percentiles)
+            .map(|v| formatter.format(v).len())
+            .max()
+            .unwrap_or(1);
+        // Bars start at zero, unless there are negative values
+        let floor = self.stats.min.min(0.0);
+        let top = self
+            .vec
+            .iter()
+            .filter_map(|(_, stats)| stats.as_ref())
+            .map(|stats| stats.p99)
+            .fold(floor, f64::max);
+        let fixed_width = width_label + width_count + 6 + 3 * (width_value + 6);
+        let bar_width = (width.saturating_sub(fixed_width) / 3).max(1);
+        write!(f, "{}", self.stats)?;
+        for (start, stats) in &self.vec {
+            let label = theme.label.paint(format!("{}", start.format(ts_fmt)));
+            let stats = match stats {
+                Some(stats) => stats,
+                None => {
+                    writeln!(f, "[{label}] [{:>width_count$}]", 0)?;
+                    continue;
+                }
+            };
+            let cells: Vec<String> = PERCENTILES
+                .iter()
+                .zip(percentiles(stats))
+                .enumerate()
+                .map(|(i, (name, value))| {
+                    let units = match top > floor {
+                        true => ((value - floor) / (top - floor) * bar_width as f64).round(),
+                        false => bar_width as f64,
+                    };
+                    let bar = BAR_CHAR.repeat(units as usize);
+                    // No trailing blanks after the last bar
+                    let bar = match i + 1 < PERCENTILES.len() {
+                        true => format!("{bar:<bar_width$}"),
+                        false => bar,
+                    };
+                    format!(
+                        "{name} {value:>width_value$} {bar}",
+                        value = formatter.format(value),
+                        bar = theme.bar.paint(bar),
+                    )
+                })
+                .collect();
+            writeln!(
+                f,
+                "[{label}] [{:>width_count$}] {}",
+                stats.samples,
+                cells.join(" "),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Render for TimePercentiles {
+    /// Percentiles of buckets with no values are null.
+    fn table(&self) -> Option<Table> {
+        let mut columns = vec!["start", "samples"];
+        columns.extend(PERCENTILES);
+        let mut table = Table::new(&columns);
+        for (start, stats) in &self.vec {
+            let mut fields = vec![start.to_rfc3339().into()];
+            match stats {
+                Some(stats) => {
+                    fields.push(stats.samples.into());
+                    fields.extend(percentiles(stats).map(|v| v.into()));
+                }
+                None => {
+                    fields.push(0.into());
+                    fields.extend(PERCENTILES.map(|_| serde_json::Value::Null));
+                }
+            }
+            table.push(fields);
+        }
+        Some(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yansi::Paint;
+
+    #[test]
+    fn test_time_percentiles() {
+        let ts = |s| DateTime::parse_from_rfc3339(s).unwrap();
+        let mut points = Vec::new();
+        for i in 1..=10 {
+            points.push((ts("2021-04-15T04:25:00+00:00"), i as f64));
+            points.push((ts("2021-04-15T04:27:00+00:00"), 2.0));
+        }
+        let tp = TimePercentiles::new(3, &points, Some(0));
+        Paint::disable();
+        let display = format!("{tp:74}");
+        assert!(display.starts_with("Samples = 20; Min = 1; Max = 10\n"));
+        assert!(display.contains(
+            "[04:25:00.000] [10] p50  6 ∎∎∎∎∎∎     p95 10 ∎∎∎∎∎∎∎∎∎∎ p99 10 ∎∎∎∎∎∎∎∎∎∎\n"
+        ));
+        assert!(display.contains("[04:25:40.000] [ 0]\n"));
+        assert!(
+            display.contains("[04:26:20.000] [10] p50  2 ∎∎         p95  2 ∎∎         p99  2 ∎∎\n")
+        );
+    }
+}
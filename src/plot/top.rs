@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::format::{F64Formatter, Theme, BAR_CHAR};
+use crate::plot::{Aggregation, Render, Table};
+
+#[derive(Debug, Default)]
+struct Totals {
+    count: usize,
+    sum: f64,
+    max: f64,
+}
+
+impl Totals {
+    fn value(&self, aggregation: Aggregation) -> f64 {
+        match aggregation {
+            Aggregation::Sum => self.sum,
+            Aggregation::Avg => self.sum / self.count as f64,
+            Aggregation::Max => self.max,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// A struct holding data to plot the keys (like client IPs) with the biggest
+/// aggregated values (like bytes sent) in some input data.
+///
+/// The struct is created empty and it will fill its data by calling its
+/// `observe` method.
+pub struct TopKeys {
+    keys: HashMap<String, Totals>,
+    lines: usize,
+    aggregation: Aggregation,
+    precision: Option<usize>,
+}
+
+impl TopKeys {
+    /// Creates an empty `TopKeys`.
+    ///
+    /// `lines` is the number of keys to be displayed, and `aggregation` how
+    /// the values of every key are ranked.  `precision` is an Option with the
+    /// number of decimals to display.  If "None" is used, human units will be
+    /// used, with an heuristic based on the input data for deciding the units
+    /// and the decimal places.
+    pub fn new(lines: usize, aggregation: Aggregation, precision: Option<usize>) -> Self {
+        Self {
+            keys: HashMap::new(),
+            lines,
+            aggregation,
+            precision,
+        }
+    }
+
+    /// Observe a new value for a key.
+    pub fn observe(&mut self, key: &str, value: f64) {
+        let totals = match self.keys.get_mut(key) {
+            Some(totals) => totals,
+            None => self.keys.entry(key.to_string()).or_insert(Totals {
+                max: f64::NEG_INFINITY,
+                ..Default::default()
+            }),
+        };
+        totals.count += 1;
+        totals.sum += value;
+        totals.max = totals.max.max(value);
+    }
+
+    /// Returns the keys with the biggest aggregated values, along with them,
+    /// from the biggest to the smallest.
+    pub fn top(&self) -> Vec<(&str, f64)> {
+        let mut values: Vec<(&str, f64)> = self
+            .keys
+            .iter()
+            .map(|(key, totals)| (key.as_str(), totals.value(self.aggregation)))
+            .collect();
+        values.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(b.0)));
+        values.truncate(self.lines);
+        values
+    }
+}
+
+impl fmt::Display for TopKeys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
+        let width = f.width().unwrap_or(100);
+        let values = self.top();
+        if values.is_empty() {
+            return writeln!(f, "No data");
+        }
+        let bottom = values.iter().map(|v| v.1).fold(0.0, f64::min);
+        let top = values.iter().map(|v| v.1).fold(0.0, f64::max);
+        let formatter = match self.precision {
+            None => F64Formatter::new_with_range(bottom..top),
+            Some(n) => F64Formatter::new(n),
+        };
+        let label_width = values.iter().fold(1, |acc, v| acc.max(v.0.len()));
+        let width_value = values
+            .iter()
+            .map(|v| formatter.format(v.1).len())
+            .max()
+            .unwrap();
+        writeln!(
+            f,
+            "Keys: {}.",
+            theme.label.paint(self.keys.len().to_string())
+        )?;
+        writeln!(f, "{} of the values per key", self.aggregation)?;
+        for (key, value) in values {
+            // Negative values have no bar
+            let units = match top > 0.0 {
+                true => (value.max(0.0) / top * width as f64).round() as usize,
+                false => 0,
+            };
+            writeln!(
+                f,
+                "[{label}] [{value}] {bar}",
+                label = theme.label.paint(format!("{key:>label_width$}")),
+                value = theme
+                    .count
+                    .paint(format!("{:>width_value$}", formatter.format(value))),
+                bar = theme.bar.paint(BAR_CHAR.repeat(units)),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Render for TopKeys {
+    fn table(&self) -> Option<Table> {
+        let mut table = Table::new(&["key", "value"]);
+        for (key, value) in self.top() {
+            table.push(vec![key.into(), value.into()]);
+        }
+        Some(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yansi::Paint;
+
+    fn top_keys(aggregation: Aggregation) -> TopKeys {
+        let mut top = TopKeys::new(2, aggregation, Some(0));
+        for (key, value) in [("a", 10.0), ("b", 25.0), ("a", 30.0), ("c", 1.0)] {
+            top.observe(key, value);
+        }
+        top
+    }
+
+    #[test]
+    fn test_top_keys() {
+        let top = top_keys(Aggregation::Sum);
+        assert_eq!(top.top(), vec![("a", 40.0), ("b", 25.0)]);
+        Paint::disable();
+        let display = format!("{top:8}");
+        assert!(display.starts_with("Keys: 3.\nSum of the values per key\n"));
+        assert!(display.contains("[a] [40] ∎∎∎∎∎∎∎∎\n"));
+        assert!(display.contains("[b] [25] ∎∎∎∎∎\n"));
+        assert!(!display.contains("[c]"));
+        assert_eq!(
+            top_keys(Aggregation::Avg).top(),
+            vec![("b", 25.0), ("a", 20.0)]
+        );
+        assert_eq!(
+            top_keys(Aggregation::Max).top(),
+            vec![("a", 30.0), ("b", 25.0)]
+        );
+    }
+
+    #[test]
+    fn test_top_keys_empty() {
+        let top = TopKeys::new(10, Aggregation::Sum, None);
+        Paint::disable();
+        assert_eq!(format!("{top}"), "No data\n");
+    }
+}
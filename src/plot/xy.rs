@@ -1,11 +1,139 @@
 use std::borrow::BorrowMut;
 use std::fmt;
 use std::ops::Range;
+use std::str::FromStr;
 
-use yansi::Color::{Blue, Red};
+use chrono::{DateTime, FixedOffset};
 
-use crate::format::F64Formatter;
-use crate::stats::Stats;
+use crate::format::{F64Formatter, Theme, MARKER};
+use crate::plot::render::{rgb, svg};
+use crate::plot::symlog::SymLog;
+use crate::plot::{print_time_axis, Image, Raster, Render, SeriesPlot, Table, ToSvg};
+use crate::stats::{OutlierMethod, Stats};
+
+/// How the values are reduced to the columns of a `XyPlot`, when there are
+/// more values than columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Downsampler {
+    /// Average of the values of every column.
+    #[default]
+    Avg,
+    /// Biggest value of every column.
+    Max,
+    /// Smallest value of every column.
+    Min,
+    /// Largest-Triangle-Three-Buckets: the value of every column that best
+    /// keeps the shape of the line (spikes included).
+    Lttb,
+}
+
+impl FromStr for Downsampler {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "avg" => Ok(Downsampler::Avg),
+            "max" => Ok(Downsampler::Max),
+            "min" => Ok(Downsampler::Min),
+            "lttb" => Ok(Downsampler::Lttb),
+            _ => Err(format!("Unknown downsampler: {s}")),
+        }
+    }
+}
+
+/// Options for building a `XyPlot` with a `XyPlotBuilder`.  See `XyPlot::new`
+/// for the meaning of `width`, `height` and `precision`.
+#[derive(Builder, Clone, Debug)]
+#[builder(name = "XyPlotBuilder", public, build_fn(private, name = "options"))]
+struct XyPlotOptions {
+    #[builder(default = "110")]
+    width: usize,
+    #[builder(default = "40")]
+    height: usize,
+    #[builder(setter(strip_option), default)]
+    precision: Option<usize>,
+    /// If present, a symmetric logarithmic scale will be used in the y axis.
+    #[builder(setter(strip_option), default)]
+    symlog: Option<f64>,
+    /// If present, the percentile rank of this value will be displayed along
+    /// with the statistics.
+    #[builder(setter(strip_option), default)]
+    rank_of: Option<f64>,
+    /// If present, the number of outliers found with this method and the
+    /// most extreme of them will be displayed along with the statistics.
+    #[builder(setter(strip_option), default)]
+    outliers: Option<OutlierMethod>,
+    /// If present, the average and standard deviation of the values excluding
+    /// this fraction of them at both tails will be displayed along with the
+    /// statistics.
+    #[builder(setter(strip_option), default)]
+    trim: Option<f64>,
+    /// If present, the confidence interval of the average at this level (like
+    /// 0.95) will be displayed along with the statistics.
+    #[builder(setter(strip_option), default)]
+    confidence: Option<f64>,
+    /// How values are reduced to columns.
+    #[builder(default)]
+    downsampler: Downsampler,
+}
+
+impl XyPlotBuilder {
+    /// Creates a `XyPlot` from a vector of numerical data, using the options
+    /// set in the builder.
+    pub fn build(&self, vec: &[f64]) -> XyPlot {
+        let options = self.options().unwrap();
+        let mut plot = XyPlot::new(vec, options.width, options.height, options.precision);
+        if options.downsampler != Downsampler::Avg {
+            plot.set_downsampler(vec, options.downsampler);
+        }
+        if let Some(threshold) = options.symlog {
+            plot.set_symlog(threshold);
+        }
+        if let Some(value) = options.rank_of {
+            plot.set_rank_of(vec, value);
+        }
+        if let Some(method) = options.outliers {
+            plot.set_outliers(vec, method);
+        }
+        if let Some(fraction) = options.trim {
+            plot.set_trim(vec, fraction);
+        }
+        if let Some(level) = options.confidence {
+            plot.set_confidence(level);
+        }
+        plot
+    }
+
+    /// Creates a graph with a plot per named series of numerical data, using
+    /// the size and precision set in the builder.  See `SeriesPlot::new`.
+    pub fn build_series(&self, series: &[(String, Vec<f64>)]) -> SeriesPlot {
+        let options = self.options().unwrap();
+        SeriesPlot::new(series, options.width, options.height, options.precision)
+    }
+
+    /// Creates a `XyPlotStream`, for feeding a `XyPlot` one value at a time
+    /// in constant memory, using the size, precision, symmetric logarithmic
+    /// scale, confidence level and downsampler set in the builder.  The rest
+    /// of statistics need every value, so they are not supported.
+    pub fn build_stream(&self) -> XyPlotStream {
+        let options = self.options().unwrap();
+        XyPlotStream {
+            chunks: Vec::with_capacity(CHUNKS_PER_COLUMN * options.width + 1),
+            chunk_size: 1,
+            stats: Stats::new_empty(options.precision),
+            pending: Vec::with_capacity(STATS_BATCH),
+            options,
+        }
+    }
+
+    /// Creates a `XyPlot` with time in the x axis from pairs of a timestamp
+    /// and a value, using the size and precision set in the builder.  See
+    /// `XyPlot::new_timed`.
+    pub fn build_timed(&self, points: &[(DateTime<FixedOffset>, f64)]) -> XyPlot {
+        let options = self.options().unwrap();
+        XyPlot::new_timed(points, options.width, options.height, options.precision)
+    }
+}
 
 #[derive(Debug)]
 /// A struct holding data to plot a XY graph.
@@ -16,6 +144,9 @@ pub struct XyPlot {
     height: usize,
     stats: Stats,
     precision: Option<usize>,
+    symlog: Option<SymLog>,
+    time_range: Option<Range<DateTime<FixedOffset>>>,
+    downsampler: Downsampler,
 }
 
 impl XyPlot {
@@ -61,23 +192,314 @@ impl XyPlot {
             height,
             stats,
             precision,
+            symlog: None,
+            time_range: None,
+            downsampler: Downsampler::Avg,
         }
     }
 
+    /// Creates a `XyPlot` from pairs of a timestamp and a value, where the x
+    /// axis is time: the data in every column is the average of the values
+    /// with a timestamp in its chunk of time (columns with no values are left
+    /// blank), and the first and last timestamps label the x axis.
+    ///
+    /// See `new` for the meaning of the rest of parameters.
+    pub fn new_timed(
+        points: &[(DateTime<FixedOffset>, f64)],
+        width: usize,
+        height: usize,
+        precision: Option<usize>,
+    ) -> Self {
+        let mut values: Vec<f64> = points.iter().map(|(_, value)| *value).collect();
+        let stats = Stats::new(&mut values, precision);
+        let mut plot = Self::new_with_stats(width, height, stats, precision);
+        plot.load_timed(points);
+        plot
+    }
+
+    /// Use a symmetric logarithmic scale in the y axis: linear in the vicinity
+    /// of zero (with `threshold` as limit) and logarithmic beyond it, for both
+    /// negative and positive values.
+    pub fn set_symlog(&mut self, threshold: f64) {
+        self.symlog = Some(SymLog::new(threshold));
+        self.y_axis = self.build_y_axis();
+    }
+
+    /// Reduce `vec` (that should be the same data used to build the plot) to
+    /// the columns of the plot with `downsampler`, rather than averaging the
+    /// values of every column.  Data loaded later with `load` is reduced the
+    /// same way.
+    pub fn set_downsampler(&mut self, vec: &[f64], downsampler: Downsampler) {
+        self.downsampler = downsampler;
+        self.x_axis.clear();
+        self.load(vec);
+    }
+
+    /// Display the percentile rank of `value` in `vec` (that should be the
+    /// same data used to build the plot) along with the statistics.
+    pub fn set_rank_of(&mut self, vec: &[f64], value: f64) {
+        self.stats.set_rank_of(vec, value);
+    }
+
+    /// Display the outliers in `vec` (that should be the same data used to
+    /// build the plot) found with `method` along with the statistics.
+    pub fn set_outliers(&mut self, vec: &[f64], method: OutlierMethod) {
+        self.stats.set_outliers(vec, method);
+    }
+
+    /// Display the average and standard deviation of `vec` (that should be
+    /// the same data used to build the plot) excluding `fraction` of the
+    /// values at both tails along with the statistics.
+    pub fn set_trim(&mut self, vec: &[f64], fraction: f64) {
+        self.stats.set_trim(vec, fraction);
+    }
+
+    /// Display the confidence interval of the average at `level` (like 0.95)
+    /// along with the statistics.
+    pub fn set_confidence(&mut self, level: f64) {
+        self.stats.set_confidence(level);
+    }
+
+    /// Returns the statistics of the data used for building the plot.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Returns the values displayed in every column.
+    pub(crate) fn x_axis(&self) -> &[f64] {
+        &self.x_axis
+    }
+
+    /// Returns the lower bounds of the rows, from the bottom one.
+    pub(crate) fn y_axis(&self) -> &[f64] {
+        &self.y_axis
+    }
+
     /// Add to the `XyPlot` data the values of a slice of numerical data.
     pub fn load(&mut self, vec: &[f64]) {
         self.width = self.width.min(vec.len());
+        if self.downsampler == Downsampler::Lttb && self.width > 2 {
+            self.x_axis.extend(lttb(vec, self.width));
+            self.y_axis = self.build_y_axis();
+            return;
+        }
         let num_chunks = vec.len() / self.width;
         let iter = vec.chunks(num_chunks);
         for x in iter {
-            let sum: f64 = x.iter().sum();
-            self.x_axis.push(sum / x.len() as f64);
+            let value = match self.downsampler {
+                Downsampler::Max => x.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                Downsampler::Min => x.iter().copied().fold(f64::INFINITY, f64::min),
+                _ => x.iter().sum::<f64>() / x.len() as f64,
+            };
+            self.x_axis.push(value);
+        }
+        self.y_axis = self.build_y_axis();
+    }
+
+    fn load_timed(&mut self, points: &[(DateTime<FixedOffset>, f64)]) {
+        let min = points.iter().map(|(ts, _)| *ts).min().unwrap();
+        let max = points.iter().map(|(ts, _)| *ts).max().unwrap();
+        self.width = self.width.min(points.len());
+        let span = (max - min).num_microseconds().unwrap() as f64;
+        let mut columns = vec![(0.0, 0); self.width];
+        for (ts, value) in points {
+            let x = match span > 0.0 {
+                true => {
+                    (ts.signed_duration_since(min).num_microseconds().unwrap() as f64 / span
+                        * self.width as f64) as usize
+                }
+                false => 0,
+            };
+            let column = &mut columns[x.min(self.width - 1)];
+            column.0 += value;
+            column.1 += 1;
+        }
+        // Columns with no values are NaN, that are in no row
+        self.x_axis = columns
+            .iter()
+            .map(|(sum, count)| sum / *count as f64)
+            .collect();
+        self.y_axis = self.build_y_axis();
+        self.time_range = Some(min..max);
+    }
+
+    fn build_y_axis(&self) -> Vec<f64> {
+        match self.symlog {
+            Some(symlog) => {
+                let mut edges = symlog.edges(self.stats.min, self.stats.max, self.height);
+                edges.truncate(self.height);
+                edges
+            }
+            None => {
+                let step = (self.stats.max - self.stats.min) / self.height as f64;
+                (0..self.height)
+                    .map(|y| step.mul_add(y as f64, self.stats.min))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Number of values added to the statistics of a `XyPlotStream` at once.
+const STATS_BATCH: usize = 4096;
+
+/// Maximum number of chunks of values kept per column by a `XyPlotStream`.
+/// The more chunks, the more evenly they are spread over columns.
+const CHUNKS_PER_COLUMN: usize = 8;
+
+/// Aggregates of consecutive values, that end up in a column of a `XyPlot`.
+#[derive(Debug, Clone, Copy)]
+struct Chunk {
+    sum: f64,
+    count: usize,
+    min: f64,
+    max: f64,
+}
+
+impl Chunk {
+    fn new(value: f64) -> Self {
+        Self {
+            sum: value,
+            count: 1,
+            min: value,
+            max: value,
+        }
+    }
+
+    fn merge(&mut self, other: &Chunk) {
+        self.sum += other.sum;
+        self.count += other.count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    // A chunk with the values of a non-empty group of them
+    fn merge_all(group: &[Chunk]) -> Chunk {
+        let mut chunk = group[0];
+        group[1..].iter().for_each(|other| chunk.merge(other));
+        chunk
+    }
+
+    fn value(&self, downsampler: Downsampler) -> f64 {
+        match downsampler {
+            Downsampler::Max => self.max,
+            Downsampler::Min => self.min,
+            _ => self.sum / self.count as f64,
         }
-        let step = (self.stats.max - self.stats.min) / self.height as f64;
-        for y in 0..self.height {
-            self.y_axis.push(step.mul_add(y as f64, self.stats.min));
+    }
+}
+
+#[derive(Debug)]
+/// A struct for building a `XyPlot` from values fed one at a time, in
+/// constant memory, so that huge inputs can be plotted.  Values are aggregated
+/// in chunks of consecutive values, which grow (merging them in pairs) as
+/// needed for keeping at most `CHUNKS_PER_COLUMN` times as many chunks as
+/// columns.
+///
+/// Percentiles are estimated (see `Stats::add`), and the
+/// Largest-Triangle-Three-Buckets downsampler picks among the averages of the
+/// chunks, rather than among the values.
+pub struct XyPlotStream {
+    chunks: Vec<Chunk>,
+    // Number of values of full chunks
+    chunk_size: usize,
+    stats: Stats,
+    // Values not added to the statistics yet
+    pending: Vec<f64>,
+    options: XyPlotOptions,
+}
+
+impl XyPlotStream {
+    /// Add a value to the plot.
+    pub fn add(&mut self, value: f64) {
+        self.pending.push(value);
+        if self.pending.len() == STATS_BATCH {
+            self.stats.add_all(&self.pending);
+            self.pending.clear();
+        }
+        match self.chunks.last_mut() {
+            Some(chunk) if chunk.count < self.chunk_size => chunk.merge(&Chunk::new(value)),
+            _ => self.chunks.push(Chunk::new(value)),
+        }
+        if self.chunks.len() > CHUNKS_PER_COLUMN * self.options.width.max(1) {
+            self.chunks = self.chunks.chunks(2).map(Chunk::merge_all).collect();
+            self.chunk_size *= 2;
+        }
+    }
+
+    /// Returns the number of values added so far.
+    pub fn samples(&self) -> usize {
+        self.stats.samples + self.pending.len()
+    }
+
+    /// Creates the `XyPlot` of the values added, that should be at least one.
+    pub fn build(mut self) -> XyPlot {
+        self.stats.add_all(&self.pending);
+        let options = self.options;
+        let width = options.width.max(1);
+        let x_axis: Vec<f64> = match options.downsampler {
+            Downsampler::Lttb if width > 2 => {
+                let averages: Vec<f64> = self
+                    .chunks
+                    .iter()
+                    .map(|c| c.value(Downsampler::Avg))
+                    .collect();
+                lttb(&averages, width)
+            }
+            downsampler => {
+                let chunks = &self.chunks;
+                let columns = width.min(chunks.len());
+                (0..columns)
+                    .map(|i| {
+                        let group =
+                            &chunks[i * chunks.len() / columns..(i + 1) * chunks.len() / columns];
+                        Chunk::merge_all(group).value(downsampler)
+                    })
+                    .collect()
+            }
+        };
+        let mut plot =
+            XyPlot::new_with_stats(options.width, options.height, self.stats, options.precision);
+        plot.width = x_axis.len();
+        plot.x_axis = x_axis;
+        plot.y_axis = plot.build_y_axis();
+        if let Some(threshold) = options.symlog {
+            plot.set_symlog(threshold);
         }
+        if let Some(level) = options.confidence {
+            plot.set_confidence(level);
+        }
+        plot
+    }
+}
+
+/// Returns `threshold` values of `vec` (that should be more than 2) chosen
+/// with the Largest-Triangle-Three-Buckets algorithm: the first and last ones,
+/// and the one of every bucket in between forming the largest triangle with
+/// the one chosen in the previous bucket and the average of the next bucket.
+fn lttb(vec: &[f64], threshold: usize) -> Vec<f64> {
+    let len = vec.len();
+    if threshold >= len {
+        return vec.to_vec();
     }
+    let every = (len - 2) as f64 / (threshold - 2) as f64;
+    let bucket = |i: usize| ((i as f64 * every) as usize + 1).min(len);
+    let mut sampled = Vec::with_capacity(threshold);
+    sampled.push(vec[0]);
+    let mut previous = 0;
+    for i in 0..threshold - 2 {
+        let next = bucket(i + 1)..bucket(i + 2);
+        let next_x = (next.start + next.end - 1) as f64 / 2.0;
+        let next_y = vec[next.clone()].iter().sum::<f64>() / next.len() as f64;
+        let (x, y) = (previous as f64, vec[previous]);
+        let area = |j: usize| ((x - next_x) * (vec[j] - y) - (x - j as f64) * (next_y - y)).abs();
+        previous = (bucket(i)..bucket(i + 1))
+            .max_by(|a, b| area(*a).partial_cmp(&area(*b)).unwrap())
+            .unwrap();
+        sampled.push(vec[previous]);
+    }
+    sampled.push(vec[len - 1]);
+    sampled
 }
 
 impl fmt::Display for XyPlot {
@@ -99,10 +521,112 @@ impl fmt::Display for XyPlot {
         for y in newvec.windows(2) {
             print_line(f, &self.x_axis, y[1]..y[0], y_width, &f64fmt)?;
         }
+        if let Some(range) = &self.time_range {
+            print_time_axis(f, range, y_width + 3, self.x_axis.len())?;
+        }
         Ok(())
     }
 }
 
+impl Render for XyPlot {
+    /// Every row is a column of the plot: its number (or the timestamp of its
+    /// start, with time in the x axis) and its value (null if it has none).
+    fn table(&self) -> Option<Table> {
+        let columns = self.x_axis.len() as i32;
+        let mut table = match &self.time_range {
+            Some(_) => Table::new(&["time", "value"]),
+            None => Table::new(&["x", "value"]),
+        };
+        for (i, value) in self.x_axis.iter().enumerate() {
+            let x = match &self.time_range {
+                Some(range) => {
+                    let start = range.start + (range.end - range.start) * i as i32 / columns;
+                    start.to_rfc3339().into()
+                }
+                None => i.into(),
+            };
+            table.push(vec![x, (*value).into()]);
+        }
+        Some(table)
+    }
+}
+
+impl ToSvg for XyPlot {
+    /// The height of the plot is half its width.
+    fn to_svg(&self, width: usize, theme: Theme) -> String {
+        let f64fmt = match self.precision {
+            None => F64Formatter::new_with_range(self.stats.min..self.stats.max),
+            Some(n) => F64Formatter::new(n),
+        };
+        let scale = |v: f64| match self.symlog {
+            Some(symlog) => symlog.forward(v),
+            None => v,
+        };
+        let (bottom, top) = (scale(self.stats.min), scale(self.stats.max));
+        let points: Vec<f64> = self
+            .x_axis
+            .iter()
+            .map(|v| match top - bottom {
+                span if span > 0.0 => (scale(*v) - bottom) / span,
+                _ => 0.5,
+            })
+            .collect();
+        svg::line_chart(
+            &self.stats.to_string(),
+            &points,
+            (
+                &f64fmt.format(self.stats.min),
+                &f64fmt.format(self.stats.max),
+            ),
+            width,
+            width / 2,
+            theme,
+        )
+    }
+}
+
+impl Raster for XyPlot {
+    /// Draws the values as a line.  For best results, the plot should have
+    /// been built with one column per horizontal pixel.
+    fn raster(&self, width: usize, height: usize, theme: Theme) -> Image {
+        let mut image = Image::new(width, height);
+        if width == 0 || height == 0 || self.x_axis.is_empty() {
+            return image;
+        }
+        let scale = |v: f64| match self.symlog {
+            Some(symlog) => symlog.forward(v),
+            None => v,
+        };
+        let (bottom, top) = (scale(self.stats.min), scale(self.stats.max));
+        let to_pixel = |i: usize, v: f64| {
+            let x = match self.x_axis.len() {
+                1 => 0,
+                n => i * (width - 1) / (n - 1),
+            };
+            let ratio = match top - bottom {
+                // A single value is drawn in the middle
+                span if span > 0.0 => (scale(v) - bottom) / span,
+                _ => 0.5,
+            };
+            (
+                x,
+                height - 1 - (ratio * (height - 1) as f64).round() as usize,
+            )
+        };
+        let points: Vec<(usize, usize)> = self
+            .x_axis
+            .iter()
+            .enumerate()
+            .map(|(i, v)| to_pixel(i, *v))
+            .collect();
+        image.set(points[0].0, points[0].1, rgb(theme.bar));
+        for segment in points.windows(2) {
+            image.line(segment[0], segment[1], rgb(theme.bar));
+        }
+        image
+    }
+}
+
 fn print_line(
     f: &mut fmt::Formatter,
     x_axis: &[f64],
@@ -110,23 +634,24 @@ fn print_line(
     y_width: usize,
     f64fmt: &F64Formatter,
 ) -> fmt::Result {
+    let theme = Theme::current();
     let mut row = format!("{: <width$}", "", width = x_axis.len());
     // The reverse in the enumeration is to avoid breaking char boundaries
-    // because of unicode char ● having more bytes than ascii chars.
+    // because of unicode char of the marker having more bytes than ascii chars.
     for (x, value) in x_axis.iter().enumerate().rev() {
         if range.contains(value) {
-            row.replace_range(x..=x, "●".as_ref());
+            row.replace_range(x..=x, MARKER);
         }
     }
     writeln!(
         f,
         "[{}] {}",
-        Blue.paint(format!(
+        theme.label.paint(format!(
             "{:>width$}",
             f64fmt.format(range.start),
             width = y_width
         )),
-        Red.paint(row),
+        theme.bar.paint(row),
     )
 }
 
@@ -163,6 +688,142 @@ mod tests {
         assert!(display.contains("[-1.000] ●  ●"));
     }
 
+    #[test]
+    fn display_test_symlog() {
+        let mut plot = XyPlot::new(&[-99.0, -5.0, 0.5, 5.0, 99.0], 5, 4, Some(1));
+        plot.set_symlog(1.0);
+        Paint::disable();
+        let display = format!("{plot}");
+        assert!(display.contains("[  9.0]     ●\n"));
+        assert!(display.contains("[  0.0]   ●● \n"));
+        assert!(display.contains("[ -9.0]  ●   \n"));
+        assert!(display.contains("[-99.0] ●    \n"));
+    }
+
+    #[test]
+    fn downsampler_test() {
+        let vec = [1.0, 1.0, 1.0, 9.0, 1.0, 1.0, 1.0, 1.0, 0.0, 1.0];
+        let columns = |downsampler| {
+            let plot = XyPlotBuilder::default()
+                .width(4)
+                .height(2)
+                .downsampler(downsampler)
+                .build(&vec);
+            plot.x_axis().to_vec()
+        };
+        assert_eq!(columns(Downsampler::Avg), vec![1.0, 5.0, 1.0, 1.0, 0.5]);
+        assert_eq!(columns(Downsampler::Max), vec![1.0, 9.0, 1.0, 1.0, 1.0]);
+        assert_eq!(columns(Downsampler::Min), vec![1.0, 1.0, 1.0, 1.0, 0.0]);
+        assert_eq!(columns(Downsampler::Lttb), vec![1.0, 9.0, 1.0, 1.0]);
+        assert_eq!(lttb(&vec, 20), vec.to_vec());
+        assert_eq!("lttb".parse::<Downsampler>(), Ok(Downsampler::Lttb));
+        assert!("foo".parse::<Downsampler>().is_err());
+    }
+
+    #[test]
+    fn stream_test() {
+        // Chunks of 16 values, 8 per column
+        let vec: Vec<f64> = (0..1280)
+            .map(|x| if x == 500 { 1e4 } else { 1.0 })
+            .collect();
+        let mut builder = XyPlotBuilder::default();
+        builder.width(10).height(4).precision(1);
+        let mut stream = builder.build_stream();
+        vec.iter().for_each(|x| stream.add(*x));
+        assert_eq!(stream.samples(), 1280);
+        let plot = stream.build();
+        assert_eq!(plot.stats().max, 1e4);
+        assert_eq!(plot.x_axis(), builder.build(&vec).x_axis());
+        let mut stream = builder.downsampler(Downsampler::Max).build_stream();
+        vec.iter().for_each(|x| stream.add(*x));
+        let plot = stream.build();
+        assert_eq!(plot.x_axis().len(), 10);
+        assert_eq!(plot.x_axis().iter().filter(|x| **x == 1e4).count(), 1);
+        let mut stream = builder.build_stream();
+        (0..100_000).for_each(|x| stream.add(x as f64));
+        assert!(stream.chunks.len() <= 80);
+        let mut stream = builder.build_stream();
+        (0..3).for_each(|x| stream.add(x as f64));
+        assert_eq!(stream.build().x_axis(), &[0.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn svg_test() {
+        let plot = XyPlot::new(&[0.0, 2.0, 1.0], 3, 1, Some(1));
+        let svg = plot.to_svg(400, Theme::DEFAULT);
+        assert!(svg.contains(">Samples = 3; Min = 0.0; Max = 2.0</text>"));
+        assert!(svg.contains(">2.0</text>"));
+        assert!(svg.contains("<polyline points="));
+    }
+
+    #[test]
+    fn csv_test() {
+        let plot = XyPlot::new(&[0.0, 2.0, 1.0], 3, 1, Some(1));
+        assert_eq!(
+            plot.table().unwrap().to_csv(','),
+            "x,value\n0,0\n1,2\n2,1\n"
+        );
+        let ts = |s| DateTime::parse_from_rfc3339(s).unwrap();
+        let points = [
+            (ts("2021-04-15T04:25:00+00:00"), 1.0),
+            (ts("2021-04-15T04:26:00+00:00"), 3.0),
+        ];
+        let plot = XyPlot::new_timed(&points, 4, 1, None);
+        assert_eq!(
+            plot.table().unwrap().to_csv('\t'),
+            "time\tvalue\n2021-04-15T04:25:00+00:00\t1\n2021-04-15T04:25:30+00:00\t3\n"
+        );
+    }
+
+    #[test]
+    fn raster_test() {
+        let plot = XyPlot::new(&[0.0, 2.0, 1.0], 3, 1, None);
+        let image = plot.raster(5, 3, Theme::DEFAULT);
+        let bar = Some(rgb(Theme::DEFAULT.bar));
+        assert_eq!(image.get(0, 2), bar);
+        assert_eq!(image.get(2, 0), bar);
+        assert_eq!(image.get(4, 1), bar);
+        assert_eq!(image.get(0, 0), None);
+        assert_eq!(image.get(4, 2), None);
+    }
+
+    #[test]
+    fn display_test_builder() {
+        let plot = XyPlotBuilder::default()
+            .width(5)
+            .height(4)
+            .precision(1)
+            .symlog(1.0)
+            .build(&[-99.0, -5.0, 0.5, 5.0, 99.0]);
+        Paint::disable();
+        let display = format!("{plot}");
+        assert!(display.contains("[  9.0]     ●\n"));
+        assert!(display.contains("[-99.0] ●    \n"));
+    }
+
+    #[test]
+    fn display_test_timed() {
+        let ts = |s: &str| DateTime::parse_from_rfc3339(s).unwrap();
+        let points = [
+            (ts("2021-04-15T06:25:00+00:00"), 1.0),
+            (ts("2021-04-15T06:25:20+00:00"), 3.0),
+            (ts("2021-04-15T06:27:00+00:00"), 4.0),
+            (ts("2021-04-15T06:29:10+00:00"), 2.0),
+            (ts("2021-04-15T06:29:20+00:00"), 4.0),
+        ];
+        let plot = XyPlotBuilder::default()
+            .width(4)
+            .height(3)
+            .precision(0)
+            .build_timed(&points);
+        Paint::disable();
+        let display = format!("{plot}");
+        assert!(display.contains("[3]  ● ●\n"));
+        assert!(display.contains("[2] ●   \n"));
+        assert!(display.contains("[1]     \n"));
+        assert!(display.contains("    06:25:00.000 06:29:20.000\n"));
+    }
+
     #[test]
     fn display_test_human_units() {
         let vector = &mut [1000000.0, -1000000.0, -2000000.0, -4000000.0];
@@ -0,0 +1,179 @@
+use std::fmt;
+
+use crate::format::Theme;
+use crate::plot::canvas::BrailleCanvas;
+use crate::plot::{CommonTerms, Render};
+
+#[derive(Debug)]
+/// A struct holding data to plot the frequency of terms versus their rank
+/// (most frequent first) in log-log axes.  Data following a power law (like
+/// Zipf's law) is displayed as a straight line.
+pub struct ZipfPlot {
+    // Sorted, most frequent first
+    counts: Vec<usize>,
+    height: usize,
+}
+
+impl ZipfPlot {
+    /// Creates a `ZipfPlot` from the terms observed in a `CommonTerms`.
+    ///
+    /// `height` is the number of "rows" to display (every row has a height of
+    /// a character, and can hold 4 dots).
+    pub fn new(terms: &CommonTerms, height: usize) -> Self {
+        let mut counts: Vec<usize> = terms.terms.values().copied().collect();
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        Self {
+            counts,
+            height: height.max(2),
+        }
+    }
+
+    /// Returns the exponent `s` of the power law `frequency ~ rank^-s` that
+    /// best fits the data, along with the coefficient of determination (R²)
+    /// of the fit in log-log space.  None is returned if there are less than 2
+    /// terms.
+    fn fit(&self) -> Option<(f64, f64)> {
+        if self.counts.len() < 2 {
+            return None;
+        }
+        let points: Vec<(f64, f64)> = self
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (((i + 1) as f64).ln(), (*c as f64).ln()))
+            .collect();
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+        let cov = points
+            .iter()
+            .map(|p| (p.0 - mean_x) * (p.1 - mean_y))
+            .sum::<f64>();
+        let var_x = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum::<f64>();
+        let var_y = points.iter().map(|p| (p.1 - mean_y).powi(2)).sum::<f64>();
+        let r2 = if var_y > 0.0 {
+            cov * cov / (var_x * var_y)
+        } else {
+            1.0
+        };
+        Some((-cov / var_x, r2))
+    }
+}
+
+impl fmt::Display for ZipfPlot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
+        if self.counts.is_empty() {
+            return writeln!(f, "No data");
+        }
+        write!(
+            f,
+            "Terms: {}",
+            theme.label.paint(self.counts.len().to_string())
+        )?;
+        match self.fit() {
+            Some((exponent, r2)) => writeln!(
+                f,
+                "; Zipf exponent = {}; R² = {}",
+                theme.label.paint(format!("{exponent:.3}")),
+                theme.label.paint(format!("{r2:.3}")),
+            )?,
+            None => writeln!(f, ".")?,
+        }
+        let log_max = (self.counts[0] as f64).log10();
+        let log_min = (self.counts[self.counts.len() - 1] as f64).log10();
+        let labels: Vec<String> = (0..self.height)
+            .map(|i| {
+                let log = log_max - (log_max - log_min) * i as f64 / (self.height - 1) as f64;
+                format!("{:.0}", 10_f64.powf(log))
+            })
+            .collect();
+        let label_width = labels.iter().map(|l| l.len()).max().unwrap();
+        let width = f
+            .width()
+            .unwrap_or(110)
+            .saturating_sub(label_width + 3)
+            .max(10);
+        let mut canvas = BrailleCanvas::new(width, self.height);
+        let log_ranks = (self.counts.len() as f64).log10();
+        for (i, count) in self.counts.iter().enumerate() {
+            let x = match log_ranks > 0.0 {
+                true => ((i + 1) as f64).log10() / log_ranks,
+                false => 0.0,
+            };
+            let y = match log_max > log_min {
+                true => ((*count as f64).log10() - log_min) / (log_max - log_min),
+                false => 0.0,
+            };
+            canvas.set(
+                (x * (canvas.dot_width() - 1) as f64).round() as usize,
+                (y * (canvas.dot_height() - 1) as f64).round() as usize,
+            );
+        }
+        for (i, label) in labels.iter().enumerate() {
+            writeln!(
+                f,
+                "[{}] {}",
+                theme.label.paint(format!("{label:>label_width$}")),
+                theme.bar.paint(canvas.row(i))
+            )?;
+        }
+        let last_rank = self.counts.len().to_string();
+        writeln!(
+            f,
+            "{:pad$}1{:>rest$}",
+            "",
+            theme.label.paint(&last_rank),
+            pad = label_width + 3,
+            rest = width - 1,
+        )
+    }
+}
+
+impl Render for ZipfPlot {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_eq::assert_float_eq;
+    use yansi::Paint;
+
+    fn zipf_terms(n: usize) -> CommonTerms {
+        let mut terms = CommonTerms::new(10);
+        for rank in 1..=n {
+            for _ in 0..(1000 / rank) {
+                terms.observe(format!("term{rank}"));
+            }
+        }
+        terms
+    }
+
+    #[test]
+    fn test_fit() {
+        let plot = ZipfPlot::new(&zipf_terms(50), 10);
+        let (exponent, r2) = plot.fit().unwrap();
+        assert_float_eq!(exponent, 1.0, abs <= 0.01);
+        assert!(r2 > 0.99);
+        assert!(ZipfPlot::new(&zipf_terms(1), 10).fit().is_none());
+    }
+
+    #[test]
+    fn test_display() {
+        let plot = ZipfPlot::new(&zipf_terms(100), 4);
+        Paint::disable();
+        let display = format!("{plot:30}");
+        let lines: Vec<&str> = display.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[0].starts_with("Terms: 100; Zipf exponent = 1.0"));
+        assert!(lines[1].starts_with("[1000] ⠁"));
+        assert!(lines[4].starts_with("[  10] "));
+        assert!(lines[4].ends_with('⣀'));
+        assert_eq!(lines[5], "       1                   100");
+    }
+
+    #[test]
+    fn test_display_empty() {
+        let plot = ZipfPlot::new(&CommonTerms::new(10), 4);
+        assert_eq!(format!("{plot}"), "No data\n");
+    }
+}
@@ -1,67 +1,431 @@
-use std::io::BufRead;
+use std::collections::HashMap;
+use std::io::Read;
 use std::ops::Range;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use regex::Regex;
+use rayon::prelude::*;
+use regex::{Captures, Regex};
 
+use crate::interop::hdr;
 use crate::plot::{CommonTerms, MatchBar, MatchBarRow};
-use crate::read::open_file;
+use crate::read::input::Record;
+use crate::read::progress::Progress;
+use crate::read::reservoir::Reservoir;
+use crate::read::{durations, human, json, logfmt, parallel};
+use crate::read::{InputOptions, Magnitudes, TimeUnit};
+use crate::stats::Extremes;
+
+/// A column of CSV input, either by position or by name (as found in the
+/// header row).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvColumn {
+    /// Zero based index of the column
+    Index(usize),
+    /// Name of the column in the header row
+    Name(String),
+}
+
+impl FromStr for CsvColumn {
+    type Err = String;
+
+    /// Parses a column from a string: a number (starting at 1) is a column
+    /// position, and anything else is a column name.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<usize>() {
+            Ok(0) => Err(String::from("Column numbers start at 1")),
+            Ok(n) => Ok(CsvColumn::Index(n - 1)),
+            Err(_) => Ok(CsvColumn::Name(s.to_string())),
+        }
+    }
+}
+
+// Label of the row counting the lines matching none of the others
+const NO_MATCH_LABEL: &str = "<no match>";
+
+// Extracts the value in a line, if any
+type LineParser = fn(&DataReader, &str) -> Option<f64>;
 
 #[derive(Debug, Default, Builder)]
 pub struct DataReader {
+    #[builder(default)]
+    input: InputOptions,
     #[builder(setter(strip_option), default)]
     range: Option<Range<f64>>,
     #[builder(setter(strip_option), default)]
     regex: Option<Regex>,
+    // Capture group of the regex with the values, instead of the default one
+    #[builder(setter(strip_option), default)]
+    capture: Option<String>,
+    // Capture group subtracted from the value, for durations logged as the
+    // timestamps of their start and end
+    #[builder(setter(strip_option), default)]
+    since: Option<String>,
+    #[builder(setter(strip_option), default)]
+    logfmt: Option<String>,
+    #[builder(setter(strip_option), default)]
+    json_field: Option<String>,
+    #[builder(setter(strip_option), default)]
+    csv_column: Option<CsvColumn>,
+    // Parse values with magnitude suffixes, like "3.5K"
+    #[builder(setter(strip_option), default)]
+    human: Option<Magnitudes>,
+    // Parse durations, like "250ms", converting them to this unit
+    #[builder(setter(strip_option), default)]
+    durations: Option<TimeUnit>,
+    // When reading matches, count every occurrence within a line
+    #[builder(default)]
+    count_occurrences: bool,
+    // When reading matches, add a row counting the lines matching none
+    #[builder(default)]
+    count_unmatched: bool,
+    // Take a value from every match of the regex in a line, not just the first
+    #[builder(default)]
+    all_matches: bool,
+    // Maximum number of values kept, chosen with reservoir sampling
+    #[builder(setter(strip_option), default)]
+    sample: Option<usize>,
+    // Parse regular files in chunks, using several threads
+    #[builder(default)]
+    parallel: bool,
+    // Values found in the input by the last read, sampled or not
+    #[builder(setter(skip))]
+    seen: AtomicUsize,
 }
 
 impl DataReader {
+    /// Reads just this range of bytes of regular files, for resuming the
+    /// processing of a growing file (see `InputOptions::set_window`).
+    pub fn set_window(&mut self, window: Range<u64>) {
+        self.input.set_window(window);
+    }
+
+    /// Returns the numerical values found in the input.  If a sample size
+    /// was set, only that many of them are returned, chosen uniformly at
+    /// random.
     pub fn read(&self, path: &str) -> Vec<f64> {
-        let mut vec: Vec<f64> = Vec::new();
-        let line_parser = match self.regex {
-            Some(_) => Self::parse_regex,
-            None => Self::parse_float,
-        };
-        for line in open_file(path).lines() {
-            match line {
-                Ok(as_string) => {
-                    if let Some(n) = line_parser(self, &as_string) {
-                        match &self.range {
-                            Some(range) => {
-                                if range.contains(&n) {
-                                    vec.push(n);
-                                }
-                            }
-                            _ => vec.push(n),
+        if self.csv_column.is_some() {
+            return self.read_with(path, |_, _| ());
+        }
+        if self.parallel {
+            if let Some(data) = self.input.map(path) {
+                return self.read_parallel(&data);
+            }
+        }
+        let line_parser = self.line_parser();
+        let mut reservoir = Reservoir::new(self.sample);
+        self.input.for_each_line(path, |line| {
+            self.parse_values(line_parser, line, |n| {
+                if self.in_range(n) {
+                    reservoir.push(n);
+                }
+            });
+        });
+        self.seen.store(reservoir.seen(), Ordering::Relaxed);
+        reservoir.into_vec()
+    }
+
+    /// Calls `observe` with every numerical value found in the input, in
+    /// order, without keeping them, so that huge inputs can be read in
+    /// constant memory.  Sampling, parallelism and CSV are not supported.
+    pub fn read_each<F: FnMut(f64)>(&self, path: &str, mut observe: F) {
+        let mut seen = 0;
+        self.input.for_each_line(path, |line| {
+            self.parse_line(line, |n| {
+                seen += 1;
+                observe(n);
+            });
+        });
+        self.seen.store(seen, Ordering::Relaxed);
+    }
+
+    /// Calls `observe` with every numerical value found in a single line (in
+    /// the range, if any), for lines not read by the reader itself (like the
+    /// ones fed to the panels of a dashboard).  CSV is not supported.
+    pub fn parse_line<F: FnMut(f64)>(&self, line: &str, mut observe: F) {
+        self.parse_values(self.line_parser(), line, |n| {
+            if self.in_range(n) {
+                observe(n);
+            }
+        });
+    }
+
+    /// Like `read`, but keeping track of where the extreme values were found
+    /// in the input.
+    pub fn read_locating(&self, path: &str, extremes: &mut Extremes) -> Vec<f64> {
+        self.read_with(path, |n, record| {
+            extremes.observe(n, record.line, record.offset, &record.text)
+        })
+    }
+
+    /// Returns the options for reading the input.
+    pub fn input(&self) -> &InputOptions {
+        &self.input
+    }
+
+    /// Returns how many values were found in the input by the last read,
+    /// including the ones discarded by sampling.
+    pub fn values_seen(&self) -> usize {
+        self.seen.load(Ordering::Relaxed)
+    }
+
+    fn read_with<F>(&self, path: &str, observe: F) -> Vec<f64>
+    where
+        F: FnMut(f64, &Record),
+    {
+        let mut reservoir = Reservoir::new(self.sample);
+        match &self.csv_column {
+            Some(column) => self.read_csv(path, column, &mut reservoir, observe),
+            None => self.read_records(path, &mut reservoir, observe),
+        }
+        self.seen.store(reservoir.seen(), Ordering::Relaxed);
+        reservoir.into_vec()
+    }
+
+    fn line_parser(&self) -> LineParser {
+        match (&self.regex, &self.logfmt, &self.json_field) {
+            (Some(_), _, _) => Self::parse_regex,
+            (None, Some(_), _) => Self::parse_logfmt,
+            (None, None, Some(_)) => Self::parse_json,
+            (None, None, None) => Self::parse_float,
+        }
+    }
+
+    // Values are parsed in chunks (several per thread) and then merged in
+    // input order.
+    fn read_parallel(&self, data: &[u8]) -> Vec<f64> {
+        let line_parser = self.line_parser();
+        let chunks = parallel::split_lines(data, rayon::current_num_threads());
+        let progress = Progress::new(data.len() as u64);
+        let values: Vec<Vec<f64>> = chunks
+            .par_iter()
+            .map(|chunk| {
+                let mut values = Vec::new();
+                for line in parallel::lines(chunk) {
+                    self.parse_values(line_parser, &String::from_utf8_lossy(line), |n| {
+                        if self.in_range(n) {
+                            values.push(n);
                         }
+                    });
+                }
+                if let Some(progress) = &progress {
+                    progress.add(chunk.len() as u64);
+                }
+                values
+            })
+            .collect();
+        let mut reservoir = Reservoir::new(self.sample);
+        values.into_iter().flatten().for_each(|n| reservoir.push(n));
+        self.seen.store(reservoir.seen(), Ordering::Relaxed);
+        reservoir.into_vec()
+    }
+
+    fn read_records<F>(&self, path: &str, reservoir: &mut Reservoir, mut observe: F)
+    where
+        F: FnMut(f64, &Record),
+    {
+        let line_parser = self.line_parser();
+        for record in self.input.records(path) {
+            match record {
+                Ok(record) => self.parse_values(line_parser, &record.text, |n| {
+                    if self.in_range(n) {
+                        observe(n, &record);
+                        reservoir.push(n);
+                    }
+                }),
+                Err(error) => error!("{}", error),
+            }
+        }
+    }
+
+    // CSV records can span several lines (because of quoting), so a proper
+    // CSV parser is used instead of the record iterator of the input options.
+    fn read_csv<F>(&self, path: &str, column: &CsvColumn, reservoir: &mut Reservoir, mut observe: F)
+    where
+        F: FnMut(f64, &Record),
+    {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(matches!(column, CsvColumn::Name(_)))
+            .flexible(true)
+            .from_reader(self.input.open(path));
+        let index = match column {
+            CsvColumn::Index(index) => *index,
+            CsvColumn::Name(name) => match reader.headers() {
+                Ok(headers) => match headers.iter().position(|h| h.trim() == name) {
+                    Some(index) => index,
+                    None => {
+                        error!("Column {} not found in CSV header", name);
+                        return;
                     }
+                },
+                Err(error) => {
+                    error!("{}", error);
+                    return;
+                }
+            },
+        };
+        for row in reader.records() {
+            match row {
+                Ok(row) => {
+                    let n = match row.get(index).and_then(|f| self.parse_float(f.trim())) {
+                        Some(n) if self.in_range(n) => n,
+                        _ => continue,
+                    };
+                    let position = row.position().unwrap();
+                    let record = Record {
+                        text: row.iter().collect::<Vec<&str>>().join(","),
+                        line: position.line() as usize,
+                        offset: position.byte(),
+                    };
+                    observe(n, &record);
+                    reservoir.push(n);
                 }
                 Err(error) => error!("{}", error),
             }
         }
-        vec
+    }
+
+    /// Reads values grouping them by the `key` capture group of the regex.
+    /// Groups are returned in order of first appearance.
+    pub fn read_groups(&self, path: &str) -> Vec<(String, Vec<f64>)> {
+        let mut groups: Vec<(String, Vec<f64>)> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        self.read_groups_with(path, |key, n| match index.get(&key) {
+            Some(i) => groups[*i].1.push(n),
+            None => {
+                index.insert(key.clone(), groups.len());
+                groups.push((key, vec![n]));
+            }
+        });
+        groups
+    }
+
+    /// Calls `observe` with the `key` capture group of the regex and the value
+    /// found in every line, for aggregating them without keeping the values.
+    pub fn read_groups_with<F: FnMut(String, f64)>(&self, path: &str, mut observe: F) {
+        self.input.for_each_line(path, |line| {
+            if let Some((key, n)) = self.parse_group(line) {
+                if self.in_range(n) {
+                    observe(key, n);
+                }
+            }
+        });
+    }
+
+    /// Reads values grouping them by the key found in their lines by
+    /// `group_by`: its `key` capture group, its first one or, failing that,
+    /// the whole match.  Lines where `group_by` does not match are skipped.
+    /// Groups are returned in order of first appearance.
+    pub fn read_grouped_by(&self, path: &str, group_by: &Regex) -> Vec<(String, Vec<f64>)> {
+        let line_parser = self.line_parser();
+        let mut groups: Vec<(String, Vec<f64>)> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        self.input.for_each_line(path, |line| {
+            let key = match group_by.captures(line) {
+                Some(cap) => match cap.name("key").or_else(|| cap.get(1)) {
+                    Some(key) => key.as_str(),
+                    None => cap.get(0).unwrap().as_str(),
+                },
+                None => {
+                    debug!("Group regex does not match '{}'", line);
+                    return;
+                }
+            };
+            self.parse_values(line_parser, line, |n| {
+                if !self.in_range(n) {
+                    return;
+                }
+                match index.get(key) {
+                    Some(i) => groups[*i].1.push(n),
+                    None => {
+                        index.insert(key.to_string(), groups.len());
+                        groups.push((key.to_string(), vec![n]));
+                    }
+                }
+            });
+        });
+        groups
+    }
+
+    /// Reads values grouping them by the named capture group of the regex
+    /// they were found in, so that several series can be read in one go.
+    /// Series are returned in the order of the groups in the regex.
+    pub fn read_series(&self, path: &str) -> Vec<(String, Vec<f64>)> {
+        let regex = self.regex.as_ref().unwrap();
+        let mut series: Vec<(String, Vec<f64>)> = regex
+            .capture_names()
+            .flatten()
+            .map(|name| (name.to_string(), Vec::new()))
+            .collect();
+        self.input.for_each_line(path, |line| {
+            let cap = match regex.captures(line) {
+                Some(cap) => cap,
+                None => {
+                    debug!("Regex does not match '{}'", line);
+                    return;
+                }
+            };
+            for (name, values) in series.iter_mut() {
+                if let Some(n) = cap.name(name).and_then(|m| self.parse_float(m.as_str())) {
+                    if self.in_range(n) {
+                        values.push(n);
+                    }
+                }
+            }
+        });
+        series
+    }
+
+    fn in_range(&self, n: f64) -> bool {
+        match &self.range {
+            Some(range) => range.contains(&n),
+            None => true,
+        }
     }
 
     fn parse_float(&self, line: &str) -> Option<f64> {
         match line.parse::<f64>() {
             Ok(n) => Some(n),
             Err(parse_error) => {
-                debug!("Cannot parse float ({}) at '{}'", parse_error, line);
-                None
+                let parsed = self
+                    .human
+                    .and_then(|magnitudes| human::parse(line, magnitudes))
+                    .or_else(|| self.durations.and_then(|unit| durations::parse(line, unit)));
+                if parsed.is_none() {
+                    debug!("Cannot parse float ({}) at '{}'", parse_error, line);
+                }
+                parsed
             }
         }
     }
 
-    fn parse_regex(&self, line: &str) -> Option<f64> {
-        match self.regex.as_ref().unwrap().captures(line) {
-            Some(cap) => {
-                if let Some(name) = cap.name("value") {
-                    self.parse_float(name.as_str())
-                } else if let Some(capture) = cap.get(1) {
-                    self.parse_float(capture.as_str())
-                } else {
-                    None
+    // Calls `observe` with the value found in a line by `line_parser` or,
+    // if all the matches of the regex are wanted, with the value of each.
+    fn parse_values<F: FnMut(f64)>(&self, line_parser: LineParser, line: &str, mut observe: F) {
+        match &self.regex {
+            Some(regex) if self.all_matches => {
+                let mut found = false;
+                for cap in regex.captures_iter(line) {
+                    found = true;
+                    if let Some(n) = self.parse_capture(&cap) {
+                        observe(n);
+                    }
+                }
+                if !found {
+                    debug!("Regex does not match '{}'", line);
                 }
             }
+            _ => {
+                if let Some(n) = line_parser(self, line) {
+                    observe(n);
+                }
+            }
+        }
+    }
+
+    fn parse_regex(&self, line: &str) -> Option<f64> {
+        match self.regex.as_ref().unwrap().captures(line) {
+            Some(cap) => self.parse_capture(&cap),
             None => {
                 debug!("Regex does not match '{}'", line);
                 None
@@ -69,42 +433,192 @@ impl DataReader {
         }
     }
 
-    pub fn read_matches(&self, path: &str, strings: Vec<&str>) -> MatchBar {
-        let mut rows = Vec::<MatchBarRow>::with_capacity(strings.len());
-        for s in strings {
-            rows.push(MatchBarRow::new(s));
+    fn parse_capture(&self, cap: &Captures) -> Option<f64> {
+        let value = if let Some(capture) = &self.capture {
+            cap.name(capture)
+                .and_then(|value| self.parse_float(value.as_str()))
+        } else if let Some(name) = cap.name("value") {
+            self.parse_float(name.as_str())
+        } else if let Some(capture) = cap.get(1) {
+            self.parse_float(capture.as_str())
+        } else {
+            None
+        };
+        match &self.since {
+            Some(since) => Some(value? - self.parse_float(cap.name(since)?.as_str())?),
+            None => value,
         }
-        for line in open_file(path).lines() {
-            match line {
-                Ok(as_string) => {
-                    for row in rows.iter_mut() {
-                        row.inc_if_matches(&as_string);
-                    }
+    }
+
+    fn parse_logfmt(&self, line: &str) -> Option<f64> {
+        let key = self.logfmt.as_ref().unwrap();
+        match logfmt::field(line, key) {
+            Some(value) => self.parse_float(&value),
+            None => {
+                debug!("No value for key {} at '{}'", key, line);
+                None
+            }
+        }
+    }
+
+    fn parse_json(&self, line: &str) -> Option<f64> {
+        let path = self.json_field.as_ref().unwrap();
+        match json::field(line, path) {
+            Some(value) => self.parse_float(&value),
+            None => {
+                debug!("No value for {} at '{}'", path, line);
+                None
+            }
+        }
+    }
+
+    fn parse_group(&self, line: &str) -> Option<(String, f64)> {
+        let regex = self.regex.as_ref()?;
+        let cap = match regex.captures(line) {
+            Some(cap) => cap,
+            None => {
+                debug!("Regex does not match '{}'", line);
+                return None;
+            }
+        };
+        let key = cap.name("key")?;
+        // The value is the `value` named group or, failing that, the first
+        // capture group that is not the key.
+        let value = match cap.name("value") {
+            Some(value) => value,
+            None => regex
+                .capture_names()
+                .enumerate()
+                .skip(1)
+                .find(|(_, name)| *name != Some("key"))
+                .and_then(|(i, _)| cap.get(i))?,
+        };
+        self.parse_float(value.as_str())
+            .map(|n| (key.as_str().to_string(), n))
+    }
+
+    /// Reads pre-aggregated values: pairs of a value and its number of
+    /// occurrences, like "0.25 1200" or "0.25,1200".  With a regex, they are
+    /// the `value` and `count` named capture groups or, failing that, the
+    /// first two capture groups.  Pairs with no occurrences are discarded.
+    pub fn read_weighted(&self, path: &str) -> Vec<(f64, usize)> {
+        let mut pairs = Vec::new();
+        self.input.for_each_line(path, |line| {
+            if let Some((n, count)) = self.parse_weighted(line) {
+                if count > 0 && self.in_range(n) {
+                    pairs.push((n, count));
                 }
-                Err(error) => error!("{}", error),
             }
+        });
+        pairs
+    }
+
+    /// Reads the output of HdrHistogram tools (see `hdr::parse`) as pairs of
+    /// a value and its number of occurrences.
+    pub fn read_hdr(&self, path: &str, divisor: f64) -> Result<Vec<(f64, usize)>, String> {
+        let mut data = Vec::new();
+        if let Err(error) = self.input.open(path).read_to_end(&mut data) {
+            return Err(format!("Could not read {path}: {error}"));
         }
-        MatchBar::new(rows)
+        let mut pairs = hdr::parse(&data, divisor)?;
+        pairs.retain(|(n, _)| self.in_range(*n));
+        Ok(pairs)
     }
 
-    pub fn read_terms(&self, path: &str, lines: usize) -> CommonTerms {
-        let mut terms = CommonTerms::new(lines);
-        let regex = self.regex.as_ref().unwrap();
-        for line in open_file(path).lines() {
-            match line {
-                Ok(as_string) => {
-                    if let Some(cap) = regex.captures(&as_string) {
-                        if let Some(name) = cap.name("value") {
-                            terms.observe(String::from(name.as_str()));
-                        } else if let Some(capture) = cap.get(1) {
-                            terms.observe(String::from(capture.as_str()));
-                        }
-                    };
+    fn parse_weighted(&self, line: &str) -> Option<(f64, usize)> {
+        let (value, count) = match &self.regex {
+            Some(regex) => {
+                let cap = match regex.captures(line) {
+                    Some(cap) => cap,
+                    None => {
+                        debug!("Regex does not match '{}'", line);
+                        return None;
+                    }
+                };
+                match (cap.name("value"), cap.name("count")) {
+                    (Some(value), Some(count)) => (value.as_str(), count.as_str()),
+                    _ => (cap.get(1)?.as_str(), cap.get(2)?.as_str()),
                 }
-                Err(error) => error!("{}", error),
+            }
+            None => {
+                let mut fields = line
+                    .split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|field| !field.is_empty());
+                match (fields.next(), fields.next()) {
+                    (Some(value), Some(count)) => (value, count),
+                    _ => {
+                        debug!("No value and count at '{}'", line);
+                        return None;
+                    }
+                }
+            }
+        };
+        match count.parse::<usize>() {
+            Ok(count) => self.parse_float(value).map(|n| (n, count)),
+            Err(parse_error) => {
+                debug!("Cannot parse count ({}) at '{}'", parse_error, line);
+                None
             }
         }
-        terms
+    }
+
+    pub fn read_matches(&self, path: &str, mut rows: Vec<MatchBarRow>) -> MatchBar {
+        let mut unmatched = 0;
+        self.input.for_each_line(path, |line| {
+            if !self.count_matches(line, &mut rows) {
+                unmatched += 1;
+            }
+        });
+        self.match_bar(rows, unmatched)
+    }
+
+    /// Counts the matches in a single line of the patterns in `rows` (see
+    /// `read_matches`), returning whether any of them matched.
+    pub fn count_matches(&self, line: &str, rows: &mut [MatchBarRow]) -> bool {
+        let mut matched = false;
+        for row in rows.iter_mut() {
+            let count = row.count;
+            match self.count_occurrences {
+                true => row.inc_by_occurrences(line),
+                false => row.inc_if_matches(line),
+            }
+            matched |= row.count > count;
+        }
+        matched
+    }
+
+    /// Returns a `MatchBar` with `rows`, and with a row for the `unmatched`
+    /// lines if they are counted.
+    pub fn match_bar(&self, mut rows: Vec<MatchBarRow>, unmatched: usize) -> MatchBar {
+        if self.count_unmatched {
+            let mut row = MatchBarRow::new(NO_MATCH_LABEL);
+            row.count = unmatched;
+            rows.push(row);
+        }
+        MatchBar::new(rows)
+    }
+
+    /// Feeds `terms` with the values of the regex capture group found in the
+    /// input.
+    pub fn read_terms(&self, path: &str, terms: &mut CommonTerms) {
+        self.read_terms_with(path, |term| terms.observe(String::from(term)));
+    }
+
+    /// Calls `observe` with every value of the regex capture group found in
+    /// the input.
+    pub fn read_terms_with<F: FnMut(&str)>(&self, path: &str, mut observe: F) {
+        self.input.for_each_line(path, |line| {
+            if let Some(term) = self.parse_term(line) {
+                observe(term);
+            }
+        });
+    }
+
+    /// Returns the value of the regex capture group in a single line (the
+    /// `value` one, or the first one), if any.
+    pub fn parse_term<'a>(&self, line: &'a str) -> Option<&'a str> {
+        let cap = self.regex.as_ref().unwrap().captures(line)?;
+        cap.name("value").or_else(|| cap.get(1)).map(|m| m.as_str())
     }
 }
 
@@ -114,6 +628,7 @@ mod tests {
     use super::*;
     use std::io::Write;
     use tempfile::NamedTempFile;
+    use yansi::Paint;
 
     #[test]
     fn basic_reader_test() {
@@ -142,6 +657,48 @@ mod tests {
         assert_eq!(vec, [1.3, -2.0]);
     }
 
+    #[test]
+    fn read_weighted() {
+        let reader = DataReaderBuilder::default()
+            .range(0.0..10.0)
+            .build()
+            .unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "1.5 10").unwrap();
+        writeln!(file, "2,3").unwrap();
+        writeln!(file, "3").unwrap();
+        writeln!(file, "4 0").unwrap();
+        writeln!(file, "5 -1").unwrap();
+        writeln!(file, "20 1").unwrap();
+        writeln!(file, "6\t 2 and more").unwrap();
+        let pairs = reader.read_weighted(file.path().to_str().unwrap());
+        assert_eq!(pairs, [(1.5, 10), (2.0, 3), (6.0, 2)]);
+        let re = Regex::new("le=(?P<value>[0-9.]+).* (?P<count>[0-9]+)$").unwrap();
+        let reader = DataReaderBuilder::default().regex(re).build().unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "latency_bucket{{le=0.5}} 120").unwrap();
+        writeln!(file, "latency_bucket{{le=1}} 7").unwrap();
+        let pairs = reader.read_weighted(file.path().to_str().unwrap());
+        assert_eq!(pairs, [(0.5, 120), (1.0, 7)]);
+    }
+
+    #[test]
+    fn human_input() {
+        let re = Regex::new("^(\\S+)\\s").unwrap();
+        let reader = DataReaderBuilder::default()
+            .regex(re)
+            .human(Magnitudes::Iec)
+            .build()
+            .unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "4.0K\t./src").unwrap();
+        writeln!(file, "1.5M\t./target").unwrap();
+        writeln!(file, "12\t./README.md").unwrap();
+        writeln!(file, "lots\t./other").unwrap();
+        let vec = reader.read(file.path().to_str().unwrap());
+        assert_eq!(vec, [4096.0, 1572864.0, 12.0]);
+    }
+
     #[test]
     fn regex_named_match() {
         let re = Regex::new("^foo ([0-9.-]+) (?P<value>[0-9.-]+)").unwrap();
@@ -157,12 +714,135 @@ mod tests {
         assert_eq!(vec, [1.6, 3.0]);
     }
 
+    #[test]
+    fn logfmt_reader() {
+        let reader = DataReaderBuilder::default()
+            .logfmt(String::from("took"))
+            .build()
+            .unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "level=info took=1.5 msg=\"took=9\"").unwrap();
+        writeln!(file, "level=info msg=nothing").unwrap();
+        writeln!(file, "took=bad").unwrap();
+        writeln!(file, "took=\"-2\"").unwrap();
+        let vec = reader.read(file.path().to_str().unwrap());
+        assert_eq!(vec, [1.5, -2.0]);
+    }
+
+    #[test]
+    fn json_reader() {
+        let reader = DataReaderBuilder::default()
+            .json_field(String::from(".http.took"))
+            .build()
+            .unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, r#"{{"http": {{"took": 1.5}}}}"#).unwrap();
+        writeln!(file, r#"{{"took": 9}}"#).unwrap();
+        writeln!(file, "not json").unwrap();
+        writeln!(file, r#"{{"http": {{"took": "-2"}}}}"#).unwrap();
+        let vec = reader.read(file.path().to_str().unwrap());
+        assert_eq!(vec, [1.5, -2.0]);
+    }
+
+    #[test]
+    fn sampled_reader() {
+        let reader = DataReaderBuilder::default().sample(10).build().unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        for i in 0..100 {
+            writeln!(file, "{i}").unwrap();
+        }
+        writeln!(file, "foobar").unwrap();
+        let vec = reader.read(file.path().to_str().unwrap());
+        assert_eq!(vec.len(), 10);
+        assert_eq!(reader.values_seen(), 100);
+        let reader = DataReaderBuilder::default().sample(1000).build().unwrap();
+        let vec = reader.read(file.path().to_str().unwrap());
+        assert_eq!(vec.len(), 100);
+        assert_eq!(reader.values_seen(), 100);
+    }
+
+    #[test]
+    fn parallel_reader() {
+        let re = Regex::new("^foo ([0-9.-]+)").unwrap();
+        let reader = DataReaderBuilder::default()
+            .regex(re)
+            .range(0.0..1000.0)
+            .parallel(true)
+            .build()
+            .unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        for i in 0..100000 {
+            writeln!(file, "foo {i}\r").unwrap();
+            writeln!(file, "bar {i}").unwrap();
+        }
+        let vec = reader.read(file.path().to_str().unwrap());
+        let expected: Vec<f64> = (0..1000).map(|i| i as f64).collect();
+        assert_eq!(vec, expected);
+        assert_eq!(reader.values_seen(), 1000);
+    }
+
+    #[test]
+    fn csv_reader() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "path,\"latency, ms\",status").unwrap();
+        writeln!(file, "/foo,1.5,200").unwrap();
+        writeln!(file, "\"/bar,baz\",2,500").unwrap();
+        writeln!(file, "\"/multi\nline\",\"-3\",200").unwrap();
+        writeln!(file, "/short").unwrap();
+        let reader = DataReaderBuilder::default()
+            .csv_column(CsvColumn::Index(1))
+            .build()
+            .unwrap();
+        assert_eq!(reader.read(file.path().to_str().unwrap()), [1.5, 2.0, -3.0]);
+        let reader = DataReaderBuilder::default()
+            .csv_column("latency, ms".parse().unwrap())
+            .build()
+            .unwrap();
+        let mut extremes = Extremes::new(1, 1, None);
+        let vec = reader.read_locating(file.path().to_str().unwrap(), &mut extremes);
+        assert_eq!(vec, [1.5, 2.0, -3.0]);
+        Paint::disable();
+        assert!(format!("{extremes}").contains("at line 4 (byte 56): /multi"));
+        let reader = DataReaderBuilder::default()
+            .csv_column("status".parse().unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(
+            reader.read(file.path().to_str().unwrap()),
+            [200.0, 500.0, 200.0]
+        );
+        let reader = DataReaderBuilder::default()
+            .csv_column("nope".parse().unwrap())
+            .build()
+            .unwrap();
+        assert!(reader.read(file.path().to_str().unwrap()).is_empty());
+        assert!("0".parse::<CsvColumn>().is_err());
+    }
+
+    #[test]
+    fn read_locating() {
+        let reader = DataReader::default();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "1.3").unwrap();
+        writeln!(file, "foobar").unwrap();
+        writeln!(file, "2").unwrap();
+        writeln!(file, "-2.7").unwrap();
+        let mut extremes = Extremes::new(2, 1, None);
+        let vec = reader.read_locating(file.path().to_str().unwrap(), &mut extremes);
+        assert_eq!(vec, [1.3, 2.0, -2.7]);
+        Paint::disable();
+        let display = format!("{extremes}");
+        assert!(display.contains("at line 4 (byte 13): -2.7\n"));
+        assert!(display.contains("at line 3 (byte 11): 2\n"));
+        assert!(display.contains("at line 1 (byte 0): 1.3\n"));
+    }
+
     #[test]
     fn regex_empty_file() {
         let reader = DataReader::default();
         let file = NamedTempFile::new().unwrap();
         let vec = reader.read(file.path().to_str().unwrap());
-        assert_eq!(vec, []);
+        assert!(vec.is_empty());
     }
 
     #[test]
@@ -180,6 +860,98 @@ mod tests {
         assert_eq!(vec, [-0.5, 0.5]);
     }
 
+    #[test]
+    fn all_matches() {
+        let reader = DataReaderBuilder::default()
+            .regex(Regex::new("=([0-9.]+)ms").unwrap())
+            .all_matches(true)
+            .range(0.0..20.0)
+            .build()
+            .unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "a=12ms b=15ms c=30ms").unwrap();
+        writeln!(file, "nothing").unwrap();
+        writeln!(file, "d=1ms").unwrap();
+        let path = file.path().to_str().unwrap();
+        assert_eq!(reader.read(path), [12.0, 15.0, 1.0]);
+        let mut extremes = Extremes::new(1, 1, None);
+        assert_eq!(reader.read_locating(path, &mut extremes), [12.0, 15.0, 1.0]);
+        let mut values = Vec::new();
+        reader.read_each(path, |n| values.push(n));
+        assert_eq!(values, [12.0, 15.0, 1.0]);
+    }
+
+    #[test]
+    fn group_reader() {
+        let re = Regex::new("^(?P<key>[a-z]+) ([0-9.-]+)").unwrap();
+        let reader = DataReaderBuilder::default()
+            .regex(re)
+            .range(0.0..10.0)
+            .build()
+            .unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "foo 1.5").unwrap();
+        writeln!(file, "bar 2").unwrap();
+        writeln!(file, "nothing").unwrap();
+        writeln!(file, "foo 3").unwrap();
+        writeln!(file, "foo 30").unwrap();
+        let groups = reader.read_groups(file.path().to_str().unwrap());
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0], (String::from("foo"), vec![1.5, 3.0]));
+        assert_eq!(groups[1], (String::from("bar"), vec![2.0]));
+        // Named value group takes precedence
+        let re = Regex::new("^([0-9.-]+) (?P<value>[0-9.-]+) (?P<key>[a-z]+)").unwrap();
+        let reader = DataReaderBuilder::default().regex(re).build().unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "1 2 foo").unwrap();
+        let groups = reader.read_groups(file.path().to_str().unwrap());
+        assert_eq!(groups, vec![(String::from("foo"), vec![2.0])]);
+    }
+
+    #[test]
+    fn grouped_by_reader() {
+        let re = Regex::new("took ([0-9.]+)").unwrap();
+        let reader = DataReaderBuilder::default().regex(re).build().unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "GET /api took 1.5").unwrap();
+        writeln!(file, "GET /login took 2").unwrap();
+        writeln!(file, "nothing took 7").unwrap();
+        writeln!(file, "GET /api took 3").unwrap();
+        writeln!(file, "GET /api").unwrap();
+        let group_by = Regex::new("GET (?P<key>/[a-z]+)").unwrap();
+        let groups = reader.read_grouped_by(file.path().to_str().unwrap(), &group_by);
+        assert_eq!(
+            groups,
+            vec![
+                (String::from("/api"), vec![1.5, 3.0]),
+                (String::from("/login"), vec![2.0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn series_reader() {
+        let re = Regex::new("rt=(?P<rt>[0-9.]+)( upstream_rt=(?P<upstream_rt>[0-9.]+))?").unwrap();
+        let reader = DataReaderBuilder::default()
+            .regex(re)
+            .range(0.0..10.0)
+            .build()
+            .unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "rt=1.5 upstream_rt=1.2").unwrap();
+        writeln!(file, "rt=2").unwrap();
+        writeln!(file, "nothing").unwrap();
+        writeln!(file, "rt=30 upstream_rt=3").unwrap();
+        let series = reader.read_series(file.path().to_str().unwrap());
+        assert_eq!(
+            series,
+            vec![
+                (String::from("rt"), vec![1.5, 2.0]),
+                (String::from("upstream_rt"), vec![1.2, 3.0]),
+            ]
+        );
+    }
+
     #[test]
     fn basic_match_reader() {
         let reader = DataReader::default();
@@ -189,16 +961,32 @@ mod tests {
         writeln!(file, "data data").unwrap();
         writeln!(file, "foobar").unwrap();
         writeln!(file, "none").unwrap();
-        let mb = reader.read_matches(
-            file.path().to_str().unwrap(),
-            vec!["random", "foobar", "data"],
-        );
+        let rows = ["random", "foobar", "data"]
+            .iter()
+            .map(|s| MatchBarRow::new(s))
+            .collect();
+        let mb = reader.read_matches(file.path().to_str().unwrap(), rows);
         assert_eq!(mb.vec[0].label, "random");
         assert_eq!(mb.vec[0].count, 0);
         assert_eq!(mb.vec[1].label, "foobar");
         assert_eq!(mb.vec[1].count, 3);
         assert_eq!(mb.vec[2].label, "data");
         assert_eq!(mb.vec[2].count, 2);
+        let reader = DataReaderBuilder::default()
+            .count_occurrences(true)
+            .build()
+            .unwrap();
+        let rows = vec![MatchBarRow::new("data")];
+        let mb = reader.read_matches(file.path().to_str().unwrap(), rows);
+        assert_eq!(mb.vec[0].count, 4);
+        let reader = DataReaderBuilder::default()
+            .count_unmatched(true)
+            .build()
+            .unwrap();
+        let rows = vec![MatchBarRow::new("data"), MatchBarRow::new("foo")];
+        let mb = reader.read_matches(file.path().to_str().unwrap(), rows);
+        assert_eq!(mb.vec[2].label, NO_MATCH_LABEL);
+        assert_eq!(mb.vec[2].count, 1);
     }
 
     #[test]
@@ -210,7 +998,8 @@ mod tests {
         writeln!(file, "foo 1.2 1.5").unwrap();
         writeln!(file, "foo 1.3 1.6").unwrap();
         writeln!(file, "foo 1.4 1.7").unwrap();
-        let ct = reader.read_terms(file.path().to_str().unwrap(), 10);
+        let mut ct = CommonTerms::new(10);
+        reader.read_terms(file.path().to_str().unwrap(), &mut ct);
         assert_eq!(ct.terms.len(), 3);
         assert_eq!(*ct.terms.get(&String::from("1.5")).unwrap(), 1);
         assert_eq!(*ct.terms.get(&String::from("1.6")).unwrap(), 2);
@@ -218,7 +1007,8 @@ mod tests {
         // Now, with no named capture group
         let re = Regex::new("^foo ([0-9.-]+) ([0-9.-]+)").unwrap();
         let reader = DataReaderBuilder::default().regex(re).build().unwrap();
-        let ct = reader.read_terms(file.path().to_str().unwrap(), 10);
+        let mut ct = CommonTerms::new(10);
+        reader.read_terms(file.path().to_str().unwrap(), &mut ct);
         assert_eq!(ct.terms.len(), 4);
         assert_eq!(*ct.terms.get(&String::from("1.1")).unwrap(), 1);
         assert_eq!(*ct.terms.get(&String::from("1.2")).unwrap(), 1);
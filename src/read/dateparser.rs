@@ -1,6 +1,10 @@
+use std::cell::{Cell, RefCell};
 use std::ops::Range;
 
-use chrono::{DateTime, FixedOffset, NaiveDateTime, NaiveTime, ParseError, TimeZone, Utc};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, ParseError,
+    TimeZone, Utc,
+};
 use regex::Regex;
 
 type DateParsingFun = dyn Fn(&str) -> Result<DateTime<FixedOffset>, ParseError>;
@@ -14,44 +18,121 @@ const DATE_FORMATS: &[&str] = &[
     "%d-%b-%Y::%H:%M:%S", // Seen in rabbitmq logs
 ];
 
+// Same, but for formats including the offset from UTC
+const TZ_DATE_FORMATS: &[&str] = &[
+    "%d/%b/%Y:%H:%M:%S %z", // Apache and nginx access logs
+];
+
 const TIME_FORMATS: &[&str] = &[
     "%H:%M:%S",     // strace -t
     "%H:%M:%S.%6f", // strace -tt (-ttt generates timestamps)
 ];
 
+// Classic syslog (RFC3164) timestamps, like "Jan 12 06:25:24", with no year
+const SYSLOG_FORMAT: &str = "%Y %b %e %H:%M:%S";
+const SYSLOG_RE: &str =
+    r"(Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec) [ 0-3]?[0-9] [0-9]{2}:[0-9]{2}:[0-9]{2}";
+
+// Syslog lines sent over the wire start with a priority, like "<34>", that in
+// RFC5424 is followed by a version number
+const SYSLOG_HEADER_RE: &str = r"^<[0-9]{1,3}>([0-9]{1,2} )?";
+
+// Seconds since boot in dmesg output, like "[    5.123456]"
+const DMESG_RE: &str = r"^\[( *[0-9]+\.[0-9]{6})\]";
+
+// Seconds since previous syscall in `strace -r` output, like "     0.000123"
+const STRACE_RELATIVE_RE: &str = r"^( *[0-9]{1,9}\.[0-9]{6}) ";
+
 // Max length that a timestamp can have
-const MAX_LEN: usize = 28;
+const MAX_LEN: usize = 36;
+
+// When parsing times with no date, a jump backwards in time bigger than this
+// (in hours) is assumed to be a day rollover (logs crossing midnight).  Smaller
+// jumps are considered to be just some disorder in the logs.
+const ROLLOVER_HOURS: i64 = 12;
+
+// Same, but for syslog timestamps (with no year) crossing new year
+const SYSLOG_ROLLOVER_DAYS: i64 = 180;
+
+// Leaves out of a line what Windows tools may leave around it (a byte order
+// mark and a carriage return), so that timestamps are found at the same
+// positions in every line.
+fn trim_line(line: &str) -> &str {
+    let line = line.strip_prefix('\u{FEFF}').unwrap_or(line);
+    line.trim_end_matches(['\r', '\n'])
+}
 
 pub struct LogDateParser {
-    range: Range<usize>,
+    // Where the timestamp was found last time
+    range: RefCell<Range<usize>>,
     parser: Box<DateParsingFun>,
+    strict_position: bool,
 }
 
 impl LogDateParser {
-    pub fn new(log_line: &str, format_string: &Option<String>) -> Result<Self, String> {
+    /// Creates a parser for the timestamps in lines similar to `log_line`.
+    ///
+    /// `base_date` is the date assigned to timestamps with no date information
+    /// (like the ones in `strace -t`).  If None, current date will be used.
+    pub fn new(
+        log_line: &str,
+        format_string: &Option<String>,
+        base_date: Option<NaiveDate>,
+    ) -> Result<Self, String> {
+        let log_line = trim_line(log_line);
         match format_string {
             Some(ts_format) => Self::new_with_format(log_line, ts_format),
-            None => Self::new_with_guess(log_line),
+            None => Self::new_with_guess(
+                log_line,
+                base_date.unwrap_or_else(|| Utc::now().date_naive()),
+            ),
         }
     }
 
-    fn new_with_guess(log_line: &str) -> Result<Self, String> {
+    fn new_with_guess(log_line: &str, base_date: NaiveDate) -> Result<Self, String> {
         // All the guess work assume that datetimes start with a digit, and that
         // digit is the first digit in the log line.  The approach is to locate
         // the 1st digit and then try to parse as much text as possible with any
         // of the "supported" formats (so that we do not lose precision digits
-        // or TZ info).
-        for (i, c) in log_line.chars().enumerate() {
-            if c.is_ascii_digit() {
-                for j in (i..(i + MAX_LEN).min(log_line.len() + 1)).rev() {
-                    if let Some(parser) = Self::guess_parser(&log_line[i..j]) {
-                        return Ok(Self {
-                            range: i..j,
-                            parser,
-                        });
-                    }
+        // or TZ info).  Syslog headers are skipped, and syslog timestamps
+        // (that start with the name of the month) are checked beforehand.
+        let skip = Regex::new(SYSLOG_HEADER_RE)
+            .unwrap()
+            .find(log_line)
+            .map_or(0, |m| m.end());
+        if let Some(m) = Regex::new(SYSLOG_RE).unwrap().find(&log_line[skip..]) {
+            if !log_line[skip..skip + m.start()].contains(|c: char| c.is_ascii_digit()) {
+                return Ok(Self::with_range(
+                    skip + m.start()..skip + m.end(),
+                    Self::syslog_parser(base_date),
+                ));
+            }
+        }
+        // Relative timestamps, that are interpreted as offsets from the epoch
+        if let Some(cap) = Regex::new(DMESG_RE).unwrap().captures(log_line) {
+            let m = cap.get(1).unwrap();
+            return Ok(Self::with_range(m.range(), Self::relative_parser(false)));
+        }
+        if let Some(cap) = Regex::new(STRACE_RELATIVE_RE).unwrap().captures(log_line) {
+            let m = cap.get(1).unwrap();
+            return Ok(Self::with_range(m.range(), Self::relative_parser(true)));
+        }
+        // Timestamps within brackets (like in access logs) are tried as well,
+        // as the 1st digit may belong to something else (like an IP address).
+        let first_digit = log_line[skip..]
+            .find(|c: char| c.is_ascii_digit())
+            .map(|i| skip + i);
+        let bracketed = log_line[skip..]
+            .find('[')
+            .map(|i| skip + i + 1)
+            .filter(|i| log_line[*i..].starts_with(|c: char| c.is_ascii_digit()));
+        for i in first_digit.into_iter().chain(bracketed) {
+            for j in (i..(i + MAX_LEN).min(log_line.len() + 1)).rev() {
+                // Ranges not at char boundaries are not valid strings
+                let text = log_line.get(i..j);
+                if let Some(parser) = text.and_then(|s| Self::guess_parser(s, base_date)) {
+                    return Ok(Self::with_range(i..j, parser));
                 }
-                break;
             }
         }
         Err(format!("Could not parse a timestamp in {log_line}"))
@@ -62,11 +143,12 @@ impl LogDateParser {
         // approach with 1st log line, but capping the max length we scan for
         for i in 0..log_line.len() {
             for j in (i..(i + (MAX_LEN * 2)).min(log_line.len() + 1)).rev() {
-                if NaiveDateTime::parse_from_str(&log_line[i..j], format_string).is_ok() {
+                let text = log_line.get(i..j);
+                if text.is_some_and(|s| NaiveDateTime::parse_from_str(s, format_string).is_ok()) {
                     let fmt = Box::new(format_string.to_string());
-                    return Ok(Self {
-                        range: i..j,
-                        parser: Box::new(move |string: &str| {
+                    return Ok(Self::with_range(
+                        i..j,
+                        Box::new(move |string: &str| {
                             match NaiveDateTime::parse_from_str(string, &fmt) {
                                 Ok(naive) => {
                                     let date_time: DateTime<Utc> =
@@ -78,7 +160,7 @@ impl LogDateParser {
                                 Err(err) => Err(err),
                             }
                         }),
-                    });
+                    ));
                 }
             }
         }
@@ -88,15 +170,55 @@ impl LogDateParser {
         ))
     }
 
+    fn with_range(range: Range<usize>, parser: Box<DateParsingFun>) -> Self {
+        Self {
+            range: RefCell::new(range),
+            parser,
+            strict_position: false,
+        }
+    }
+
+    /// If `strict` is true, timestamps are looked for only in the same
+    /// position they were found in the first line.  Otherwise, when a
+    /// timestamp cannot be parsed in that position, it is looked for in the
+    /// rest of the line (as the length of what precedes it might vary).
+    pub fn set_strict_position(&mut self, strict: bool) {
+        self.strict_position = strict;
+    }
+
     pub fn parse(&self, s: &str) -> Result<DateTime<FixedOffset>, ParseError> {
-        let range = self.range.start.min(s.len())..self.range.end.min(s.len());
-        (self.parser)(&s[range])
+        let s = trim_line(s);
+        let range = self.range.borrow().clone();
+        let range = range.start.min(s.len())..range.end.min(s.len());
+        let result = (self.parser)(s.get(range).unwrap_or(""));
+        if result.is_err() && !self.strict_position {
+            if let Some((range, date_time)) = self.relocate(s) {
+                self.range.replace(range);
+                return Ok(date_time);
+            }
+        }
+        result
     }
 
-    // today method is deprecated, and there is no easy way to convert "today"
-    // as a DateTime<FixedOffset>
-    #[allow(deprecated)]
-    fn guess_parser(s: &str) -> Option<Box<DateParsingFun>> {
+    // Looks for a timestamp of the same length as the one found last time,
+    // starting at the beginning of a word.
+    fn relocate(&self, s: &str) -> Option<(Range<usize>, DateTime<FixedOffset>)> {
+        let len = self.range.borrow().len();
+        let mut previous = None;
+        for (i, c) in s.char_indices() {
+            let word_start = !previous.is_some_and(|p: char| p.is_alphanumeric());
+            previous = Some(c);
+            if !word_start || i + len > s.len() || !s.is_char_boundary(i + len) {
+                continue;
+            }
+            if let Ok(date_time) = (self.parser)(&s[i..i + len]) {
+                return Some((i..i + len, date_time));
+            }
+        }
+        None
+    }
+
+    fn guess_parser(s: &str, base_date: NaiveDate) -> Option<Box<DateParsingFun>> {
         if DateTime::parse_from_rfc3339(s).is_ok() {
             return Some(Box::new(DateTime::parse_from_rfc3339));
         } else if DateTime::parse_from_rfc2822(s).is_ok() {
@@ -127,6 +249,25 @@ impl LogDateParser {
                     Err(_) => DateTime::parse_from_rfc3339(""),
                 }
             }));
+        } else if let Some(digits) = Self::looks_like_epoch_subsecs(s) {
+            let unit = 10_i64.pow(digits);
+            return Some(Box::new(move |string: &str| match string.parse::<i64>() {
+                Ok(epoch) => {
+                    let nanosecs = (epoch % unit) as u32 * 10_u32.pow(9 - digits);
+                    let naive = NaiveDateTime::from_timestamp_opt(epoch / unit, nanosecs).unwrap();
+                    let date_time: DateTime<Utc> = Utc.from_local_datetime(&naive).unwrap();
+                    Ok(date_time
+                        .with_timezone(&TimeZone::from_offset(&FixedOffset::west_opt(0).unwrap())))
+                }
+                Err(_) => DateTime::parse_from_rfc3339(""),
+            }));
+        }
+        for format in TZ_DATE_FORMATS.iter() {
+            if DateTime::parse_from_str(s, format).is_ok() {
+                return Some(Box::new(move |string: &str| {
+                    DateTime::parse_from_str(string, format)
+                }));
+            }
         }
         for format in DATE_FORMATS.iter() {
             if NaiveDateTime::parse_from_str(s, format).is_ok() {
@@ -145,21 +286,79 @@ impl LogDateParser {
         }
         for format in TIME_FORMATS.iter() {
             if NaiveTime::parse_from_str(s, format).is_ok() {
-                return Some(Box::new(
-                    move |string: &str| match NaiveTime::parse_from_str(string, format) {
-                        Ok(naive_time) => {
-                            Ok(Utc::today().and_time(naive_time).unwrap().with_timezone(
-                                &TimeZone::from_offset(&FixedOffset::west_opt(0).unwrap()),
-                            ))
-                        }
-                        Err(err) => Err(err),
-                    },
-                ));
+                return Some(Self::time_parser(format, base_date));
             }
         }
         None
     }
 
+    // Returns a parser for times with no date.  The parser keeps as state the
+    // date in use (initially `base_date`) and the last time parsed, so that
+    // logs crossing midnight get their dates increased.
+    fn time_parser(format: &'static str, base_date: NaiveDate) -> Box<DateParsingFun> {
+        let date = Cell::new(base_date);
+        let last = Cell::new(None::<NaiveTime>);
+        Box::new(move |string: &str| {
+            let naive_time = NaiveTime::parse_from_str(string, format)?;
+            if let Some(previous) = last.get() {
+                if previous - naive_time > Duration::hours(ROLLOVER_HOURS) {
+                    date.set(date.get().succ_opt().unwrap_or(date.get()));
+                }
+            }
+            last.set(Some(naive_time));
+            let date_time: DateTime<Utc> = Utc.from_utc_datetime(&date.get().and_time(naive_time));
+            Ok(date_time.with_timezone(&TimeZone::from_offset(&FixedOffset::west_opt(0).unwrap())))
+        })
+    }
+
+    // Returns a parser for (possibly space padded) seconds with microsecond
+    // precision, as an offset from the epoch.  If `accumulate` is true, every
+    // value is the offset from the previous one (like in `strace -r`) instead.
+    fn relative_parser(accumulate: bool) -> Box<DateParsingFun> {
+        let total = Cell::new(Duration::zero());
+        Box::new(move |string: &str| {
+            let (secs, micros) = match string.trim_start().split_once('.') {
+                Some((secs, micros)) if micros.len() == 6 => (secs, micros),
+                _ => return DateTime::parse_from_rfc3339(""),
+            };
+            let offset = match (secs.parse::<i64>(), micros.parse::<i64>()) {
+                (Ok(secs), Ok(micros)) => Duration::seconds(secs) + Duration::microseconds(micros),
+                _ => return DateTime::parse_from_rfc3339(""),
+            };
+            let offset = match accumulate {
+                true => {
+                    total.set(total.get() + offset);
+                    total.get()
+                }
+                false => offset,
+            };
+            let date_time: DateTime<Utc> = Utc.timestamp_opt(0, 0).unwrap() + offset;
+            Ok(date_time.with_timezone(&TimeZone::from_offset(&FixedOffset::west_opt(0).unwrap())))
+        })
+    }
+
+    // Returns a parser for syslog timestamps, that have no year.  The year of
+    // `base_date` is assumed, and it is increased when logs cross new year.
+    fn syslog_parser(base_date: NaiveDate) -> Box<DateParsingFun> {
+        let year = Cell::new(base_date.year());
+        let last = Cell::new(None::<NaiveDateTime>);
+        Box::new(move |string: &str| {
+            let parse = |year: i32| {
+                NaiveDateTime::parse_from_str(&format!("{year} {string}"), SYSLOG_FORMAT)
+            };
+            let mut naive = parse(year.get())?;
+            if let Some(previous) = last.get() {
+                if previous - naive > Duration::days(SYSLOG_ROLLOVER_DAYS) {
+                    year.set(year.get() + 1);
+                    naive = parse(year.get())?;
+                }
+            }
+            last.set(Some(naive));
+            let date_time: DateTime<Utc> = Utc.from_utc_datetime(&naive);
+            Ok(date_time.with_timezone(&TimeZone::from_offset(&FixedOffset::west_opt(0).unwrap())))
+        })
+    }
+
     // Returns true if string looks like a unix-like timestamp of arbitrary
     // precision
     fn looks_like_timestamp(s: &str) -> bool {
@@ -167,6 +366,19 @@ impl LogDateParser {
             .unwrap()
             .is_match(s)
     }
+
+    // Returns the number of subsecond digits (3 or 6) if string looks like an
+    // unix-like timestamp in milliseconds or microseconds
+    fn looks_like_epoch_subsecs(s: &str) -> Option<u32> {
+        if !s.bytes().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        match s.len() {
+            13 => Some(3),
+            16 => Some(6),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -174,9 +386,13 @@ mod tests {
 
     use super::*;
 
+    fn guess(log_line: &str) -> Result<LogDateParser, String> {
+        LogDateParser::new_with_guess(log_line, Utc::now().date_naive())
+    }
+
     #[test]
     fn test_rfc3339_brackets() {
-        let r = LogDateParser::new_with_guess("[1996-12-19T16:39:57-08:00] foobar").unwrap();
+        let r = guess("[1996-12-19T16:39:57-08:00] foobar").unwrap();
         assert_eq!(
             r.parse("[2096-11-19T16:39:57-08:00]"),
             DateTime::parse_from_rfc3339("2096-11-19T16:39:57-08:00")
@@ -185,7 +401,7 @@ mod tests {
 
     #[test]
     fn test_rfc3339_no_brackets() {
-        let r = LogDateParser::new_with_guess("2021-04-25T16:57:15.337Z foobar").unwrap();
+        let r = guess("2021-04-25T16:57:15.337Z foobar").unwrap();
         assert_eq!(
             r.parse("2031-04-25T16:57:15.337Z"),
             DateTime::parse_from_rfc3339("2031-04-25T16:57:15.337Z")
@@ -194,7 +410,7 @@ mod tests {
 
     #[test]
     fn test_rfc2822() {
-        let r = LogDateParser::new_with_guess("12 Jul 2003 10:52:37 +0200 foobar").unwrap();
+        let r = guess("12 Jul 2003 10:52:37 +0200 foobar").unwrap();
         assert_eq!(
             r.parse("22 Jun 2003 10:52:37 +0500"),
             DateTime::parse_from_rfc2822("22 Jun 2003 10:52:37 +0500")
@@ -203,7 +419,7 @@ mod tests {
 
     #[test]
     fn test_bad_bracket() {
-        let r = LogDateParser::new_with_guess("[12 Jul 2003 10:52:37 +0200 foobar").unwrap();
+        let r = guess("[12 Jul 2003 10:52:37 +0200 foobar").unwrap();
         assert_eq!(
             r.parse("[22 Jun 2003 10:52:37 +0500"),
             DateTime::parse_from_rfc2822("22 Jun 2003 10:52:37 +0500")
@@ -212,7 +428,7 @@ mod tests {
 
     #[test]
     fn test_prefix() {
-        let r = LogDateParser::new_with_guess("foobar 1996-12-19T16:39:57-08:00 foobar").unwrap();
+        let r = guess("foobar 1996-12-19T16:39:57-08:00 foobar").unwrap();
         assert_eq!(
             r.parse("foobar 2096-11-19T16:39:57-08:00"),
             DateTime::parse_from_rfc3339("2096-11-19T16:39:57-08:00")
@@ -221,61 +437,81 @@ mod tests {
 
     #[test]
     fn test_bad_format() {
-        assert!(LogDateParser::new_with_guess("996-12-19T16:39:57-08:00 foobar").is_err());
+        assert!(guess("996-12-19T16:39:57-08:00 foobar").is_err());
     }
 
     #[test]
     fn test_short_line() {
-        assert!(LogDateParser::new_with_guess("9").is_err());
+        assert!(guess("9").is_err());
     }
 
     #[test]
     fn test_empty_line() {
-        assert!(LogDateParser::new_with_guess("").is_err());
+        assert!(guess("").is_err());
     }
 
     #[test]
     fn test_timestamps() {
-        let r = LogDateParser::new_with_guess("ts 1619688527.018165").unwrap();
+        let r = guess("ts 1619688527.018165").unwrap();
         assert_eq!(
             r.parse("ts 1619655527.888165"),
             DateTime::parse_from_rfc3339("2021-04-29T00:18:47.888165+00:00")
         );
-        let r = LogDateParser::new_with_guess("1619688527.123").unwrap();
+        let r = guess("1619688527.123").unwrap();
         assert_eq!(
             r.parse("1619655527.123"),
             DateTime::parse_from_rfc3339("2021-04-29T00:18:47.123+00:00")
         );
-        let r = LogDateParser::new_with_guess("1619688527").unwrap();
+        let r = guess("1619688527").unwrap();
         assert_eq!(
             r.parse("1619655527.123"),
             DateTime::parse_from_rfc3339("2021-04-29T00:18:47+00:00")
         );
     }
 
+    #[test]
+    fn test_epoch_subsecs() {
+        let r = guess(r#"{"ts": 1619688527018, "msg": "foo"}"#).unwrap();
+        assert_eq!(
+            r.parse(r#"{"ts": 1619655527888, "msg": "foo"}"#),
+            DateTime::parse_from_rfc3339("2021-04-29T00:18:47.888+00:00")
+        );
+        let r = guess("CreateTime:1619688527018165 key").unwrap();
+        assert_eq!(
+            r.parse("CreateTime:1619655527000042 key"),
+            DateTime::parse_from_rfc3339("2021-04-29T00:18:47.000042+00:00")
+        );
+        assert!(r.parse("CreateTime:16196555270000xx key").is_err());
+    }
+
     #[test]
     fn test_known_formats() {
-        let r = LogDateParser::new_with_guess("2021-04-28 06:25:24,321").unwrap();
+        let r = guess("2021-04-28 06:25:24,321").unwrap();
         assert_eq!(
             r.parse("2021-04-28 06:25:24,321"),
             DateTime::parse_from_rfc3339("2021-04-28T06:25:24.321+00:00")
         );
-        let r = LogDateParser::new_with_guess("2021-04-28 06:25:24").unwrap();
+        let r = guess("2021-04-28 06:25:24").unwrap();
         assert_eq!(
             r.parse("2021-04-28 06:25:24"),
             DateTime::parse_from_rfc3339("2021-04-28T06:25:24+00:00")
         );
-        let r = LogDateParser::new_with_guess("28-Apr-2021::12:10:42").unwrap();
+        let r = guess("28-Apr-2021::12:10:42").unwrap();
         assert_eq!(
             r.parse("28-Apr-2021::12:10:42"),
             DateTime::parse_from_rfc3339("2021-04-28T12:10:42+00:00")
         );
-        let r = LogDateParser::new_with_guess("2019/12/19 05:01:02").unwrap();
+        let r = guess("2019/12/19 05:01:02").unwrap();
         assert_eq!(
             r.parse("2019/12/19 05:01:02"),
             DateTime::parse_from_rfc3339("2019-12-19T05:01:02+00:00")
         );
-        let r = LogDateParser::new_with_guess("11:29:13.120535").unwrap();
+        let r = guess("127.0.0.1 - - [10/Oct/2000:13:55:36 -0700] \"GET /\" 200").unwrap();
+        assert_eq!(
+            r.parse("127.0.0.1 - - [11/Oct/2000:13:55:36 +0200] \"GET /\" 200"),
+            DateTime::parse_from_rfc3339("2000-10-11T13:55:36+02:00")
+        );
+        let r = guess("11:29:13.120535").unwrap();
         let now_as_date = format!("{}", Utc::now().date_naive());
         assert_eq!(
             r.parse("11:29:13.120535"),
@@ -285,16 +521,152 @@ mod tests {
                 "T11:29:13.120535+00:00"
             ))
         );
-        let r = LogDateParser::new_with_guess("11:29:13").unwrap();
+        let r = guess("11:29:13").unwrap();
         assert_eq!(
             r.parse("11:29:13.120535"),
             DateTime::parse_from_rfc3339(&format!("{}{}", &now_as_date[..10], "T11:29:13+00:00"))
         );
     }
 
+    #[test]
+    fn test_midnight_rollover() {
+        let base_date = NaiveDate::from_ymd_opt(2021, 12, 31).unwrap();
+        let r = LogDateParser::new("23:59:58.100000", &None, Some(base_date)).unwrap();
+        assert_eq!(
+            r.parse("23:59:58.100000"),
+            DateTime::parse_from_rfc3339("2021-12-31T23:59:58.1+00:00")
+        );
+        // Small disorder in logs is not a rollover
+        assert_eq!(
+            r.parse("23:59:57.100000"),
+            DateTime::parse_from_rfc3339("2021-12-31T23:59:57.1+00:00")
+        );
+        assert_eq!(
+            r.parse("00:00:01.000000"),
+            DateTime::parse_from_rfc3339("2022-01-01T00:00:01+00:00")
+        );
+        assert_eq!(
+            r.parse("10:00:00.000000"),
+            DateTime::parse_from_rfc3339("2022-01-01T10:00:00+00:00")
+        );
+    }
+
+    #[test]
+    fn test_syslog() {
+        let base_date = NaiveDate::from_ymd_opt(2021, 6, 1).unwrap();
+        let r = LogDateParser::new("Jan 12 06:25:24 host sshd[42]: foo", &None, Some(base_date))
+            .unwrap();
+        assert_eq!(
+            r.parse("Jan 12 06:25:24 host sshd[42]: foo"),
+            DateTime::parse_from_rfc3339("2021-01-12T06:25:24+00:00")
+        );
+        let r = LogDateParser::new("<13>Dec 31 23:59:59 host foo", &None, Some(base_date)).unwrap();
+        assert_eq!(
+            r.parse("<13>Dec 31 23:59:59 host foo"),
+            DateTime::parse_from_rfc3339("2021-12-31T23:59:59+00:00")
+        );
+        // Days are padded with spaces, and new year is detected
+        assert_eq!(
+            r.parse("<13>Jan  1 00:00:01 host foo"),
+            DateTime::parse_from_rfc3339("2022-01-01T00:00:01+00:00")
+        );
+        assert!(r.parse("<13>Foo 12 00:00:01 host foo").is_err());
+        // Month names found after the digits of the timestamp are not used
+        let r = guess("2021-04-28 06:25:24 Jan 12 06:25:24").unwrap();
+        assert_eq!(
+            r.parse("2021-04-28 06:25:24 Jan 12 06:25:24"),
+            DateTime::parse_from_rfc3339("2021-04-28T06:25:24+00:00")
+        );
+    }
+
+    #[test]
+    fn test_rfc5424() {
+        let r = guess("<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog").unwrap();
+        assert_eq!(
+            r.parse("<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog"),
+            DateTime::parse_from_rfc3339("2003-10-11T22:14:15.003Z")
+        );
+        let r = guess("<34>1 2003-08-24T05:14:15.000003-07:00 192.0.2.1 myproc").unwrap();
+        assert_eq!(
+            r.parse("<34>1 2003-08-24T05:14:15.000003-07:00 192.0.2.1 myproc"),
+            DateTime::parse_from_rfc3339("2003-08-24T05:14:15.000003-07:00")
+        );
+    }
+
+    #[test]
+    fn test_relocation() {
+        let mut r = guess("INFO 2021-04-28 06:25:24 foo").unwrap();
+        assert_eq!(
+            r.parse("WARNING 2021-04-28 06:25:25 foo"),
+            DateTime::parse_from_rfc3339("2021-04-28T06:25:25+00:00")
+        );
+        // The new position is the first one tried from now on
+        assert_eq!(
+            r.parse("ERROR 2021-04-28 06:25:26 foo"),
+            DateTime::parse_from_rfc3339("2021-04-28T06:25:26+00:00")
+        );
+        assert!(r.parse("DEBUG 2021-04-28 foo").is_err());
+        r.set_strict_position(true);
+        assert!(r.parse("INFO 2021-04-28 06:25:27 foo").is_err());
+        assert_eq!(
+            r.parse("DEBUG 2021-04-28 06:25:28 foo"),
+            DateTime::parse_from_rfc3339("2021-04-28T06:25:28+00:00")
+        );
+    }
+
+    #[test]
+    fn test_windows_lines() {
+        // A byte order mark in the first line does not shift the position
+        // of timestamps, nor carriage returns make them harder to find
+        let mut r = LogDateParser::new("\u{FEFF}2021-04-28 06:25:24 foo\r", &None, None).unwrap();
+        r.set_strict_position(true);
+        assert_eq!(
+            r.parse("2021-04-28 06:25:25 bar\r"),
+            DateTime::parse_from_rfc3339("2021-04-28T06:25:25+00:00")
+        );
+        let r = LogDateParser::new("\u{FFFD}é 1619688527\r\n", &None, None).unwrap();
+        assert_eq!(
+            r.parse("\u{FFFD}é 1619688528\r"),
+            DateTime::parse_from_rfc3339("2021-04-29T09:28:48+00:00")
+        );
+    }
+
+    #[test]
+    fn test_relative() {
+        let r = guess("[    0.000000] Linux version 5.10.0").unwrap();
+        assert_eq!(
+            r.parse("[    5.123456] usb 1-1: new device"),
+            DateTime::parse_from_rfc3339("1970-01-01T00:00:05.123456+00:00")
+        );
+        assert_eq!(
+            r.parse("[12345.000001] foo"),
+            DateTime::parse_from_rfc3339("1970-01-01T03:25:45.000001+00:00")
+        );
+        assert!(r.parse("[    5.1234] foo").is_err());
+        let r = guess("     0.000000 execve(\"/bin/ls\", [\"ls\"], 0x7ffd) = 0").unwrap();
+        assert_eq!(
+            r.parse("     0.000000 execve(\"/bin/ls\", [\"ls\"], 0x7ffd) = 0"),
+            DateTime::parse_from_rfc3339("1970-01-01T00:00:00+00:00")
+        );
+        assert_eq!(
+            r.parse("     0.500000 brk(NULL) = 0x55d0"),
+            DateTime::parse_from_rfc3339("1970-01-01T00:00:00.5+00:00")
+        );
+        assert_eq!(
+            r.parse("     1.000123 brk(NULL) = 0x55d0"),
+            DateTime::parse_from_rfc3339("1970-01-01T00:00:01.500123+00:00")
+        );
+        // Absolute timestamps (strace -ttt) are not relative
+        let r = guess("1619688527.018165 brk(NULL) = 0x55d0").unwrap();
+        assert_eq!(
+            r.parse("1619655527.888165 brk(NULL) = 0x55d0"),
+            DateTime::parse_from_rfc3339("2021-04-29T00:18:47.888165+00:00")
+        );
+    }
+
     #[test]
     fn test_tricky_line() {
-        let r = LogDateParser::new_with_guess("[1996-12-19T16:39:57-08:00] foobar").unwrap();
+        let r = guess("[1996-12-19T16:39:57-08:00] foobar").unwrap();
         assert!(r.parse("nothing").is_err());
     }
 
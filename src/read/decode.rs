@@ -0,0 +1,205 @@
+use std::io::{self, BufRead, BufReader, Read};
+use std::str::FromStr;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16LE_BOM: &[u8] = b"\xFF\xFE";
+
+/// Text encodings of the input.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8, unless the input starts with a UTF-16LE byte order mark.
+    #[default]
+    Auto,
+    /// UTF-8.
+    Utf8,
+    /// UTF-16, little endian (as written by many Windows tools).
+    Utf16Le,
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "utf-8" | "utf8" => Ok(Self::Utf8),
+            "utf-16le" | "utf16le" => Ok(Self::Utf16Le),
+            _ => Err(format!("Unknown encoding '{s}'")),
+        }
+    }
+}
+
+impl Encoding {
+    /// Returns the actual encoding of data starting with `head`, and the
+    /// length of its byte order mark (zero if there is none).
+    pub fn detect(self, head: &[u8]) -> (Self, usize) {
+        match self {
+            Self::Utf16Le if head.starts_with(UTF16LE_BOM) => (Self::Utf16Le, UTF16LE_BOM.len()),
+            Self::Utf16Le => (Self::Utf16Le, 0),
+            _ if head.starts_with(UTF8_BOM) => (Self::Utf8, UTF8_BOM.len()),
+            Self::Auto if head.starts_with(UTF16LE_BOM) => (Self::Utf16Le, UTF16LE_BOM.len()),
+            _ => (Self::Utf8, 0),
+        }
+    }
+}
+
+/// Returns `io::BufRead` yielding the contents of `reader` as UTF-8, with the
+/// byte order mark (if any) skipped and UTF-16LE text converted.  Invalid
+/// UTF-8 is left for the readers of lines to deal with.
+pub fn decode<R: Read + 'static>(reader: R, encoding: Encoding) -> Box<dyn BufRead> {
+    let mut reader = BufReader::new(reader);
+    // Read errors are left for later reads to report
+    let head = match reader.fill_buf() {
+        Ok(data) => &data[..data.len().min(UTF8_BOM.len())],
+        Err(_) => &[],
+    };
+    let (encoding, bom) = encoding.detect(head);
+    reader.consume(bom);
+    match encoding {
+        Encoding::Utf16Le => Box::new(BufReader::new(Utf16LeReader::new(reader))),
+        _ => Box::new(reader),
+    }
+}
+
+/// Converts UTF-16LE text to UTF-8, replacing invalid code units with U+FFFD.
+struct Utf16LeReader<R> {
+    inner: R,
+    // Last byte of a code unit split between reads
+    odd: Option<u8>,
+    // First half of a surrogate pair split between reads
+    high: Option<u16>,
+    decoded: Vec<u8>,
+    position: usize,
+}
+
+impl<R: BufRead> Utf16LeReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            odd: None,
+            high: None,
+            decoded: Vec::new(),
+            position: 0,
+        }
+    }
+
+    // Decodes the next chunk of input.  Returns false at the end of it.
+    fn refill(&mut self) -> io::Result<bool> {
+        self.decoded.clear();
+        self.position = 0;
+        let data = self.inner.fill_buf()?;
+        let length = data.len();
+        if length == 0 {
+            if self.odd.take().is_some() | self.high.take().is_some() {
+                let mut buffer = [0; 4];
+                let replacement = char::REPLACEMENT_CHARACTER.encode_utf8(&mut buffer);
+                self.decoded.extend_from_slice(replacement.as_bytes());
+            }
+            return Ok(!self.decoded.is_empty());
+        }
+        let mut bytes: Vec<u8> = self
+            .odd
+            .take()
+            .into_iter()
+            .chain(data.iter().copied())
+            .collect();
+        self.inner.consume(length);
+        if bytes.len() % 2 == 1 {
+            self.odd = bytes.pop();
+        }
+        let mut units: Vec<u16> = self
+            .high
+            .take()
+            .into_iter()
+            .chain(
+                bytes
+                    .chunks_exact(2)
+                    .map(|b| u16::from_le_bytes([b[0], b[1]])),
+            )
+            .collect();
+        if matches!(units.last(), Some(0xD800..=0xDBFF)) {
+            self.high = units.pop();
+        }
+        let text: String = char::decode_utf16(units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect();
+        self.decoded = text.into_bytes();
+        Ok(true)
+    }
+}
+
+impl<R: BufRead> Read for Utf16LeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.decoded.len() {
+            if !self.refill()? {
+                return Ok(0);
+            }
+        }
+        let length = buf.len().min(self.decoded.len() - self.position);
+        buf[..length].copy_from_slice(&self.decoded[self.position..self.position + length]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(data: Vec<u8>, encoding: Encoding) -> String {
+        let mut text = String::new();
+        decode(io::Cursor::new(data), encoding)
+            .read_to_string(&mut text)
+            .unwrap();
+        text
+    }
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_utf8() {
+        assert_eq!(
+            read_all(b"\xEF\xBB\xBF1\r\n2\n".to_vec(), Encoding::Auto),
+            "1\r\n2\n"
+        );
+        assert_eq!(read_all(b"\xEF\xBB\xBF1".to_vec(), Encoding::Utf8), "1");
+        assert_eq!(read_all(b"1\n".to_vec(), Encoding::Auto), "1\n");
+        assert_eq!(read_all(Vec::new(), Encoding::Auto), "");
+    }
+
+    #[test]
+    fn test_utf16le() {
+        let mut data = UTF16LE_BOM.to_vec();
+        data.extend(utf16le("1.5\r\n2 \u{1F600}\n"));
+        assert_eq!(
+            read_all(data.clone(), Encoding::Auto),
+            "1.5\r\n2 \u{1F600}\n"
+        );
+        assert_eq!(read_all(utf16le("3\n"), Encoding::Utf16Le), "3\n");
+        // An incomplete code unit at the end
+        data.push(b'x');
+        assert!(read_all(data, Encoding::Auto).ends_with("\n\u{FFFD}"));
+    }
+
+    #[test]
+    fn test_split_reads() {
+        // Code units and surrogate pairs split between reads of the input
+        let data = utf16le("a\u{1F600}b");
+        let mut reader = Utf16LeReader::new(BufReader::with_capacity(3, io::Cursor::new(data)));
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "a\u{1F600}b");
+    }
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!("auto".parse::<Encoding>(), Ok(Encoding::Auto));
+        assert_eq!("UTF-8".parse::<Encoding>(), Ok(Encoding::Utf8));
+        assert_eq!("utf-16le".parse::<Encoding>(), Ok(Encoding::Utf16Le));
+        assert!("latin1".parse::<Encoding>().is_err());
+        assert_eq!(Encoding::Auto.detect(b"\xFF\xFE1"), (Encoding::Utf16Le, 2));
+        assert_eq!(Encoding::Utf8.detect(b"\xFF\xFE1"), (Encoding::Utf8, 0));
+    }
+}
@@ -0,0 +1,74 @@
+use std::str::FromStr;
+
+// Suffixes of the units understood in durations, with their length in
+// seconds.  Longer suffixes go first, so that "ms" is not taken for "s".
+const UNITS: &[(&str, f64)] = &[
+    ("ns", 1e-9),
+    ("us", 1e-6),
+    ("µs", 1e-6),
+    ("ms", 1e-3),
+    ("min", 60.0),
+    ("s", 1.0),
+    ("m", 60.0),
+    ("h", 3600.0),
+    ("d", 86400.0),
+];
+
+/// A unit to express durations in, when converting them to plain numbers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeUnit {
+    seconds: f64,
+}
+
+impl FromStr for TimeUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match UNITS.iter().find(|(suffix, _)| *suffix == s) {
+            Some((_, seconds)) => Ok(TimeUnit { seconds: *seconds }),
+            None => Err(format!("Unknown time unit: {s}")),
+        }
+    }
+}
+
+/// Parses a duration like "250ms", "1.2s" or "3m20s" (a sequence of numbers
+/// with a unit each), returning it in `unit`.
+pub fn parse(s: &str, unit: TimeUnit) -> Option<f64> {
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    let mut seconds = 0.0;
+    while !rest.is_empty() {
+        let number_end = rest.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+        let number = rest[..number_end].parse::<f64>().ok()?;
+        rest = rest[number_end..].trim_start();
+        let (suffix, length) = UNITS.iter().find(|(suffix, _)| rest.starts_with(suffix))?;
+        seconds += number * length;
+        rest = rest[suffix.len()..].trim_start();
+    }
+    Some(seconds / unit.seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_parse() {
+        let seconds = TimeUnit::from_str("s").unwrap();
+        let millis = TimeUnit::from_str("ms").unwrap();
+        assert_float_eq!(parse("250ms", seconds).unwrap(), 0.25, abs <= 1e-9);
+        assert_float_eq!(parse("1.2s", millis).unwrap(), 1200.0, abs <= 1e-9);
+        assert_float_eq!(parse("3m20s", seconds).unwrap(), 200.0, abs <= 1e-9);
+        assert_float_eq!(parse("1h 30min", seconds).unwrap(), 5400.0, abs <= 1e-9);
+        assert_float_eq!(parse("15µs", millis).unwrap(), 0.015, abs <= 1e-9);
+        assert_float_eq!(parse("2d", seconds).unwrap(), 172800.0, abs <= 1e-9);
+        assert_eq!(parse("250", seconds), None);
+        assert_eq!(parse("ms", seconds), None);
+        assert_eq!(parse("3x", seconds), None);
+        assert_eq!(parse("", seconds), None);
+        assert!(TimeUnit::from_str("fortnight").is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use std::str::FromStr;
+
+// Magnitude suffixes, in increasing order
+const SUFFIXES: &[char] = &['K', 'M', 'G', 'T', 'P', 'E'];
+
+/// How to interpret bare magnitude suffixes, like the "K" in "3.5K".
+/// Suffixes like "Ki" (or "KiB") always are powers of 1024.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Magnitudes {
+    /// Powers of 1000, like in metrics.
+    Si,
+    /// Powers of 1024, like in `du -h` or `ls -lh`.
+    Iec,
+}
+
+impl FromStr for Magnitudes {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "si" => Ok(Magnitudes::Si),
+            "iec" => Ok(Magnitudes::Iec),
+            _ => Err(format!("Unknown magnitudes: {s}")),
+        }
+    }
+}
+
+/// Parses a number with an optional magnitude suffix, like "3.5K", "2.1 M"
+/// or "512KiB", returning it as a plain float.  A trailing "B" (bytes) is
+/// ignored, and "k" is accepted as "K".
+pub fn parse(s: &str, magnitudes: Magnitudes) -> Option<f64> {
+    let s = s.trim();
+    let s = s.strip_suffix('B').unwrap_or(s);
+    let (s, iec_suffix) = match s.strip_suffix('i') {
+        Some(s) => (s, true),
+        None => (s, false),
+    };
+    let suffix = s.chars().last()?.to_ascii_uppercase();
+    let (number, exponent) = match SUFFIXES.iter().position(|c| *c == suffix) {
+        Some(position) => (&s[..s.len() - 1], position as i32 + 1),
+        None if !iec_suffix => (s, 0),
+        None => return None,
+    };
+    let base: f64 = match iec_suffix || magnitudes == Magnitudes::Iec {
+        true => 1024.0,
+        false => 1000.0,
+    };
+    number
+        .trim_end()
+        .parse::<f64>()
+        .ok()
+        .map(|n| n * base.powi(exponent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("3.5K", Magnitudes::Si), Some(3500.0));
+        assert_eq!(parse("3.5k", Magnitudes::Si), Some(3500.0));
+        assert_eq!(parse("2 M", Magnitudes::Si), Some(2_000_000.0));
+        assert_eq!(parse("512KiB", Magnitudes::Si), Some(524_288.0));
+        assert_eq!(parse("1Gi", Magnitudes::Si), Some(1_073_741_824.0));
+        assert_eq!(parse("4.0K", Magnitudes::Iec), Some(4096.0));
+        assert_eq!(parse("100B", Magnitudes::Si), Some(100.0));
+        assert_eq!(parse("-1.5T", Magnitudes::Si), Some(-1.5e12));
+        assert_eq!(parse("12", Magnitudes::Iec), Some(12.0));
+        assert_eq!(parse("12i", Magnitudes::Si), None);
+        assert_eq!(parse("3.5X", Magnitudes::Si), None);
+        assert_eq!(parse("K", Magnitudes::Si), None);
+        assert_eq!(parse("", Magnitudes::Si), None);
+        assert_eq!(Magnitudes::from_str("iec"), Ok(Magnitudes::Iec));
+        assert!(Magnitudes::from_str("binary").is_err());
+    }
+}
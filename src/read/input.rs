@@ -0,0 +1,919 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::ops::{Deref, Range};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use memmap2::Mmap;
+use rand::rngs::ThreadRng;
+use rand::{thread_rng, Rng};
+use regex::Regex;
+
+use crate::read::json::ArrayReader;
+use crate::read::net::{open_tcp, open_udp, open_unix};
+use crate::read::progress::Progress;
+use crate::read::{decode, open_file, parallel, Encoding};
+
+/// How the input is split in records.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum Delimiter {
+    /// Lines, ending with "\n" or "\r\n".
+    #[default]
+    Newline,
+    /// Records ending with some string (like "\0", as written by `find
+    /// -print0`).
+    Custom(Vec<u8>),
+    /// The elements of JSON arrays (like `[1, 2.5, 3]`), split on the commas
+    /// out of strings and nested arrays or objects.
+    JsonArray,
+}
+
+impl Delimiter {
+    // What records end with, once JSON arrays are split in lines
+    fn end(&self) -> &[u8] {
+        match self {
+            Self::Custom(bytes) => bytes,
+            _ => b"\n",
+        }
+    }
+}
+
+impl FromStr for Delimiter {
+    type Err = String;
+
+    /// Parses a delimiter, where "\0", "\t", "\n", "\r" and "\\" are escape
+    /// sequences, and "json-array" stands for `Delimiter::JsonArray`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "json-array" {
+            return Ok(Self::JsonArray);
+        }
+        let mut bytes = Vec::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            let c = match c {
+                '\\' => match chars.next() {
+                    Some('0') => '\0',
+                    Some('t') => '\t',
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('\\') => '\\',
+                    Some(other) => return Err(format!("Unknown escape sequence '\\{other}'")),
+                    None => '\\',
+                },
+                c => c,
+            };
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+        }
+        match bytes.as_slice() {
+            b"" => Err(String::from("Empty delimiter")),
+            b"\n" => Ok(Self::Newline),
+            _ => Ok(Self::Custom(bytes)),
+        }
+    }
+}
+
+/// Options shared by all the readers, describing how the input is split in
+/// records (typically, lines) before any value or timestamp is extracted from
+/// them.
+#[derive(Debug, Default, Clone, Builder)]
+pub struct InputOptions {
+    /// Lines matching this regex are continuation lines (for instance, the
+    /// lines of a stack trace) and they will be appended to the previous
+    /// record.
+    #[builder(setter(strip_option), default)]
+    multiline_join: Option<Regex>,
+    /// Lines matching this regex start a new record, and the ones that do not
+    /// (like the lines of a stack trace after a log line) are appended to the
+    /// previous record.
+    #[builder(setter(strip_option), default)]
+    multiline_start: Option<Regex>,
+    /// For network inputs (like "udp://0.0.0.0:8125"), for how long to listen
+    /// for incoming data.  Mandatory for UDP, and optional for stream sockets
+    /// (which are also read until closed).
+    #[builder(setter(strip_option), default)]
+    listen: Option<Duration>,
+    /// For regular files, the range of bytes to read (for resuming the
+    /// processing of a growing file where a previous run left it).
+    #[builder(setter(strip_option), default)]
+    window: Option<Range<u64>>,
+    /// Encoding of the text of files and stdin (UTF-8 by default, or UTF-16LE
+    /// if the input starts with its byte order mark).  Invalid UTF-8 is
+    /// replaced with U+FFFD rather than discarding the lines containing it.
+    #[builder(default)]
+    encoding: Encoding,
+    /// What records end with (a newline, by default).
+    #[builder(default)]
+    delimiter: Delimiter,
+    /// Number of lines (records as split by the delimiter, before joining
+    /// multi-line ones) to skip at the start of the input, like headers.
+    #[builder(default)]
+    skip: usize,
+    /// Maximum number of lines to read, after the skipped ones.  Reading
+    /// stops after them, so the rest of the input is never read.
+    #[builder(setter(strip_option), default)]
+    head: Option<usize>,
+    /// Read only the last lines of the input (after `skip` and `head`).
+    #[builder(setter(strip_option), default)]
+    tail: Option<usize>,
+    /// Read just one of every that many lines (of the ones selected with
+    /// skip, head and tail), for quick previews of huge inputs.
+    #[builder(setter(strip_option), default)]
+    every: Option<usize>,
+    /// Read lines with this probability (like 0.01), chosen at random.
+    #[builder(setter(strip_option), default)]
+    sample_rate: Option<f64>,
+    // Lines kept and read by the last sampling
+    #[builder(setter(skip))]
+    sampled: Arc<(AtomicUsize, AtomicUsize)>,
+}
+
+/// The contents of a file mapped in memory (or of the window of bytes of it
+/// to be read, see `InputOptions::window`).
+pub struct Mapped {
+    map: Mmap,
+    range: Range<usize>,
+}
+
+impl Deref for Mapped {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.map[self.range.clone()]
+    }
+}
+
+impl InputOptions {
+    /// Sets the range of bytes to read from regular files.
+    pub fn set_window(&mut self, window: Range<u64>) {
+        self.window = Some(window);
+    }
+
+    /// Returns an iterator over the records in the file at `path` ("-" for
+    /// stdin, "udp://address:port" for listening to UDP datagrams), along with
+    /// their location.
+    pub fn records(&self, path: &str) -> Records {
+        Records {
+            reader: self.open(path),
+            delimiter: self.delimiter.clone(),
+            multiline_join: self.multiline_join.clone(),
+            multiline_start: self.multiline_start.clone(),
+            pending: None,
+            line: 0,
+            offset: 0,
+        }
+    }
+
+    /// Returns `io::BufRead` for the input at `path` ("-" for stdin,
+    /// "udp://address:port" for listening to UDP datagrams, "tcp://host:port"
+    /// or "unix:///path/to/socket" for connecting to a stream socket).  Exits
+    /// the program with exit code 1 if input cannot be opened.
+    ///
+    /// With `Delimiter::JsonArray`, every element of the arrays in the input
+    /// is read as a line.
+    pub fn open(&self, path: &str) -> Box<dyn BufRead> {
+        let reader = self.open_input(path);
+        let reader: Box<dyn BufRead> = match self.delimiter {
+            Delimiter::JsonArray => Box::new(BufReader::new(ArrayReader::new(reader))),
+            _ => reader,
+        };
+        if self.skip == 0 && self.head.is_none() && self.tail.is_none() && !self.is_sampled() {
+            return reader;
+        }
+        self.sampled.0.store(0, Ordering::Relaxed);
+        self.sampled.1.store(0, Ordering::Relaxed);
+        Box::new(BufReader::new(SelectedLines {
+            inner: reader,
+            end: self.delimiter.end().to_vec(),
+            skip: self.skip,
+            head: self.head,
+            tail: self.tail,
+            every: self.every,
+            sample_rate: self.sample_rate,
+            sampled: self.sampled.clone(),
+            rng: thread_rng(),
+            read: 0,
+            queue: VecDeque::new(),
+            drained: false,
+            current: Vec::new(),
+            position: 0,
+        }))
+    }
+
+    fn open_input(&self, path: &str) -> Box<dyn BufRead> {
+        if let Some(reader) = self.open_window(path) {
+            return reader;
+        }
+        let stream = match path.strip_prefix("tcp://") {
+            Some(address) => Some(open_tcp(address, self.listen)),
+            None => path
+                .strip_prefix("unix://")
+                .map(|socket| open_unix(socket, self.listen)),
+        };
+        match stream {
+            Some(Ok(reader)) => return reader,
+            Some(Err(error)) => {
+                error!("Could not connect to {}: {}", path, error);
+                panic!("{}", error);
+            }
+            None => (),
+        }
+        let address = match path.strip_prefix("udp://") {
+            Some(address) => address,
+            None => return open_file(path, self.encoding),
+        };
+        let duration = match self.listen {
+            Some(duration) => duration,
+            None => {
+                error!("A duration is needed for listening at {}", path);
+                panic!("No duration for network input");
+            }
+        };
+        match open_udp(address, duration) {
+            Ok(reader) => reader,
+            Err(error) => {
+                error!("Could not listen at {}: {}", path, error);
+                panic!("{}", error);
+            }
+        }
+    }
+
+    // The window of bytes of the regular file at `path`, if one is set
+    fn open_window(&self, path: &str) -> Option<Box<dyn BufRead>> {
+        let window = self.window.as_ref()?;
+        let mut file = File::open(path).ok()?;
+        if !file.metadata().ok()?.is_file() {
+            return None;
+        }
+        if let Err(error) = file.seek(SeekFrom::Start(window.start)) {
+            error!("Could not read {}: {}", path, error);
+            panic!("{}", error);
+        }
+        let length = window.end.saturating_sub(window.start);
+        Some(decode(file.take(length), self.encoding))
+    }
+
+    /// Returns the contents of the file at `path` mapped in memory (just the
+    /// window of bytes to read, if set), as long as it is a regular file and
+    /// every record is an input line and its text is UTF-8.  A byte order
+    /// mark at the start of the file is left out.  Returns None otherwise (for
+    /// instance, for stdin).
+    pub fn map(&self, path: &str) -> Option<Mapped> {
+        if self.is_multiline()
+            || self.is_sampled()
+            || self.delimiter != Delimiter::Newline
+            || path == "-"
+            || path.starts_with("udp://")
+        {
+            return None;
+        }
+        let file = File::open(path).ok()?;
+        if !file.metadata().ok()?.is_file() {
+            return None;
+        }
+        // SAFETY: the file could be modified by other processes while mapped,
+        // but the worst outcome of that would be reading garbage values, as
+        // the contents are decoded as UTF-8 before parsing them.
+        let map = unsafe { Mmap::map(&file) }.ok()?;
+        let (encoding, bom) = self.encoding.detect(&map);
+        if encoding == Encoding::Utf16Le {
+            return None;
+        }
+        let range = match &self.window {
+            Some(window) => {
+                let end = (window.end as usize).min(map.len());
+                (window.start as usize).max(bom).min(end)..end
+            }
+            None => bom.min(map.len())..map.len(),
+        };
+        let selected = self.select(&map[range.clone()]);
+        let range = range.start + selected.start..range.start + selected.end;
+        Some(Mapped { map, range })
+    }
+
+    // The range of `data` with the lines to read (see `skip`, `head` and
+    // `tail`), found without going through the lines out of it.
+    fn select(&self, data: &[u8]) -> Range<usize> {
+        let next_line = |from: usize| match data[from..].iter().position(|b| *b == b'\n') {
+            Some(index) => from + index + 1,
+            None => data.len(),
+        };
+        let mut start = 0;
+        for _ in 0..self.skip {
+            start = next_line(start);
+        }
+        let mut end = data.len();
+        if let Some(head) = self.head {
+            end = start;
+            for _ in 0..head {
+                end = next_line(end);
+            }
+        }
+        if let Some(tail) = self.tail {
+            let mut line_start = end;
+            // The newline ending the last line does not start another one
+            let mut searched = match data[start..end].ends_with(b"\n") {
+                true => end - 1,
+                false => end,
+            };
+            for _ in 0..tail {
+                match data[start..searched].iter().rposition(|b| *b == b'\n') {
+                    Some(index) => {
+                        line_start = start + index + 1;
+                        searched = start + index;
+                    }
+                    None => {
+                        line_start = start;
+                        break;
+                    }
+                }
+            }
+            start = line_start;
+        }
+        start..end
+    }
+
+    /// Calls `f` with the text of every record in the file at `path` ("-" for
+    /// stdin).  Unlike `lines`, no string is allocated per line: regular files
+    /// are mapped in memory and other inputs are read in a reused buffer.
+    pub fn for_each_line<F: FnMut(&str)>(&self, path: &str, mut f: F) {
+        if self.is_multiline() {
+            for record in self.records(path) {
+                match record {
+                    Ok(record) => f(&record.text),
+                    Err(error) => error!("{}", error),
+                }
+            }
+        } else if let Some(data) = self.map(path) {
+            if data.is_empty() {
+                return;
+            }
+            let progress = Progress::new(data.len() as u64);
+            for line in parallel::lines(&data) {
+                if let Some(progress) = &progress {
+                    progress.add(line.len() as u64 + 1);
+                }
+                f(&String::from_utf8_lossy(line));
+            }
+        } else {
+            let mut reader = self.open(path);
+            let mut buffer = Vec::new();
+            loop {
+                buffer.clear();
+                match read_record(&mut reader, &self.delimiter, &mut buffer) {
+                    Ok(0) => return,
+                    Ok(_) => f(&String::from_utf8_lossy(&buffer)),
+                    Err(error) => {
+                        error!("{}", error);
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    // Whether records may span several lines
+    fn is_multiline(&self) -> bool {
+        self.multiline_join.is_some() || self.multiline_start.is_some()
+    }
+
+    fn is_sampled(&self) -> bool {
+        self.every.is_some() || self.sample_rate.is_some()
+    }
+
+    /// Returns the number of lines kept by sampling (see `every` and
+    /// `sample_rate`) in the last read, and the number of lines read.  None
+    /// if lines are not sampled.
+    pub fn sampled_lines(&self) -> Option<(usize, usize)> {
+        match self.is_sampled() {
+            true => Some((
+                self.sampled.0.load(Ordering::Relaxed),
+                self.sampled.1.load(Ordering::Relaxed),
+            )),
+            false => None,
+        }
+    }
+
+    /// Returns an iterator over the text of the records in the file at `path`
+    /// ("-" for stdin).  Unless some multiline option is used, every record is
+    /// an input line.
+    pub fn lines(&self, path: &str) -> impl Iterator<Item = io::Result<String>> {
+        self.records(path).map(|r| r.map(|record| record.text))
+    }
+}
+
+/// Returns the offset right after the last complete line of the regular file
+/// at `path` (or `start`, if there is no complete line after it), so that
+/// lines still being written are left for later.
+pub fn complete_lines_end(path: &str, start: u64) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut end = file.metadata()?.len();
+    let mut buffer = [0; 4096];
+    while end > start {
+        let chunk = (end - start).min(buffer.len() as u64);
+        file.seek(SeekFrom::Start(end - chunk))?;
+        file.read_exact(&mut buffer[..chunk as usize])?;
+        if let Some(newline) = buffer[..chunk as usize].iter().rposition(|b| *b == b'\n') {
+            return Ok(end - chunk + newline as u64 + 1);
+        }
+        end -= chunk;
+    }
+    Ok(start)
+}
+
+// Reads a record into `buffer`, along with the `end` delimiting it (if not at
+// the end of the input).  Returns the number of bytes consumed from `reader`
+// (zero at the end of the input).
+fn read_raw_record(
+    reader: &mut dyn BufRead,
+    end: &[u8],
+    buffer: &mut Vec<u8>,
+) -> io::Result<usize> {
+    let last = end[end.len() - 1];
+    let mut consumed = 0;
+    loop {
+        let read = reader.read_until(last, buffer)?;
+        consumed += read;
+        // Either a whole delimiter or the end of the input was found
+        if read == 0 || buffer.last() != Some(&last) || buffer.ends_with(end) {
+            return Ok(consumed);
+        }
+    }
+}
+
+// Reads a record into `buffer`, without its delimiter.  Returns the number of
+// bytes consumed from `reader` (zero at the end of the input).
+fn read_record(
+    reader: &mut Box<dyn BufRead>,
+    delimiter: &Delimiter,
+    buffer: &mut Vec<u8>,
+) -> io::Result<usize> {
+    let end = delimiter.end();
+    let consumed = read_raw_record(reader, end, buffer)?;
+    if buffer.ends_with(end) {
+        buffer.truncate(buffer.len() - end.len());
+        if *delimiter == Delimiter::Newline && buffer.ends_with(b"\r") {
+            buffer.pop();
+        }
+    }
+    Ok(consumed)
+}
+
+/// Reads the lines selected with `InputOptions` skip, head and tail, and
+/// sampled with every and sample rate.
+struct SelectedLines {
+    inner: Box<dyn BufRead>,
+    end: Vec<u8>,
+    skip: usize,
+    head: Option<usize>,
+    tail: Option<usize>,
+    every: Option<usize>,
+    sample_rate: Option<f64>,
+    // Lines kept and read by sampling
+    sampled: Arc<(AtomicUsize, AtomicUsize)>,
+    rng: ThreadRng,
+    // Lines read so far, skipped or not
+    read: usize,
+    // The last lines read, for the tail
+    queue: VecDeque<Vec<u8>>,
+    drained: bool,
+    // Line being read, and how much of it was read
+    current: Vec<u8>,
+    position: usize,
+}
+
+impl SelectedLines {
+    // Next line after the skipped ones, up to the head
+    fn next_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            if matches!(self.head, Some(head) if self.read >= self.skip + head) {
+                return Ok(None);
+            }
+            let mut line = Vec::new();
+            if read_raw_record(&mut self.inner, &self.end, &mut line)? == 0 {
+                return Ok(None);
+            }
+            self.read += 1;
+            if self.read > self.skip {
+                return Ok(Some(line));
+            }
+        }
+    }
+
+    fn next_selected(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let tail = match self.tail {
+            Some(tail) => tail,
+            None => return self.next_line(),
+        };
+        if !self.drained {
+            while let Some(mut line) = self.next_line()? {
+                // The last line may have no delimiter
+                if !line.ends_with(&self.end) {
+                    line.extend_from_slice(&self.end);
+                }
+                if self.queue.len() == tail {
+                    self.queue.pop_front();
+                }
+                if tail > 0 {
+                    self.queue.push_back(line);
+                }
+            }
+            self.drained = true;
+        }
+        Ok(self.queue.pop_front())
+    }
+
+    fn next_sampled(&mut self) -> io::Result<Option<Vec<u8>>> {
+        while let Some(line) = self.next_selected()? {
+            let index = self.sampled.1.fetch_add(1, Ordering::Relaxed);
+            let kept = self.every.map_or(true, |every| index % every == 0)
+                && self
+                    .sample_rate
+                    .map_or(true, |rate| self.rng.gen::<f64>() < rate);
+            if kept {
+                self.sampled.0.fetch_add(1, Ordering::Relaxed);
+                return Ok(Some(line));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Read for SelectedLines {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.current.len() {
+            match self.next_sampled()? {
+                Some(line) => {
+                    self.current = line;
+                    self.position = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+        let length = buf.len().min(self.current.len() - self.position);
+        buf[..length].copy_from_slice(&self.current[self.position..self.position + length]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+/// A record of input data, and where it was found in the input.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Record {
+    /// Text of the record.  Records spanning several lines are joined with a
+    /// newline character.
+    pub text: String,
+    /// Line number (starting at 1) where the record starts.
+    pub line: usize,
+    /// Byte offset where the record starts.
+    pub offset: u64,
+}
+
+/// Iterator over input records.
+pub struct Records {
+    reader: Box<dyn BufRead>,
+    delimiter: Delimiter,
+    multiline_join: Option<Regex>,
+    multiline_start: Option<Regex>,
+    pending: Option<Record>,
+    // Lines and bytes consumed so far
+    line: usize,
+    offset: u64,
+}
+
+impl Records {
+    fn read_line(&mut self) -> Option<io::Result<Record>> {
+        let mut bytes = Vec::new();
+        match read_record(&mut self.reader, &self.delimiter, &mut bytes) {
+            Ok(0) => None,
+            Ok(n) => {
+                // Invalid UTF-8 is replaced rather than losing the whole line
+                let text = match String::from_utf8(bytes) {
+                    Ok(text) => text,
+                    Err(error) => String::from_utf8_lossy(error.as_bytes()).into_owned(),
+                };
+                let record = Record {
+                    text,
+                    line: self.line + 1,
+                    offset: self.offset,
+                };
+                self.line += 1;
+                self.offset += n as u64;
+                Some(Ok(record))
+            }
+            Err(error) => {
+                self.line += 1;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+impl Iterator for Records {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.multiline_join.is_none() && self.multiline_start.is_none() {
+            return self.read_line();
+        }
+        loop {
+            match self.read_line() {
+                Some(Ok(line)) => {
+                    // Whether the line is to be appended to the previous record
+                    let continues = self
+                        .multiline_join
+                        .as_ref()
+                        .is_some_and(|re| re.is_match(&line.text))
+                        || self
+                            .multiline_start
+                            .as_ref()
+                            .is_some_and(|re| !re.is_match(&line.text));
+                    match self.pending.as_mut() {
+                        Some(record) if continues => {
+                            record.text.push('\n');
+                            record.text.push_str(&line.text);
+                        }
+                        _ => {
+                            if let Some(record) = self.pending.replace(line) {
+                                return Some(Ok(record));
+                            }
+                        }
+                    }
+                }
+                Some(Err(error)) => return Some(Err(error)),
+                None => return self.pending.take().map(Ok),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn collect(options: &InputOptions, file: &NamedTempFile) -> Vec<String> {
+        options
+            .lines(file.path().to_str().unwrap())
+            .map(|r| r.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn records_no_join() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "foo\n  bar\nbaz").unwrap();
+        assert_eq!(
+            collect(&InputOptions::default(), &file),
+            vec!["foo", "  bar", "baz"]
+        );
+    }
+
+    #[test]
+    fn window() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "foo\nbar\nbaz\nqu").unwrap();
+        let path = file.path().to_str().unwrap();
+        assert_eq!(complete_lines_end(path, 0).unwrap(), 12);
+        assert_eq!(complete_lines_end(path, 12).unwrap(), 12);
+        let options = InputOptionsBuilder::default()
+            .window(4..12)
+            .build()
+            .unwrap();
+        assert_eq!(collect(&options, &file), vec!["bar", "baz"]);
+        let mut lines = Vec::new();
+        options.for_each_line(path, |line| lines.push(line.to_string()));
+        assert_eq!(lines, vec!["bar", "baz"]);
+        assert_eq!(&*options.map(path).unwrap(), b"bar\nbaz\n");
+    }
+
+    #[test]
+    fn records_join() {
+        let options = InputOptionsBuilder::default()
+            .multiline_join(Regex::new("^\\s").unwrap())
+            .build()
+            .unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "  orphan\nfoo\n  bar\n  baz\nnext\n  last").unwrap();
+        assert_eq!(
+            collect(&options, &file),
+            vec!["  orphan", "foo\n  bar\n  baz", "next\n  last"]
+        );
+        let file = NamedTempFile::new().unwrap();
+        assert!(collect(&options, &file).is_empty());
+    }
+
+    #[test]
+    fn records_start() {
+        let options = InputOptionsBuilder::default()
+            .multiline_start(Regex::new("^[0-9]").unwrap())
+            .build()
+            .unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "orphan\n1 foo\nException\n  at bar\n2 baz\n\n3 last").unwrap();
+        assert_eq!(
+            collect(&options, &file),
+            vec!["orphan", "1 foo\nException\n  at bar", "2 baz\n", "3 last"]
+        );
+        let mut lines = Vec::new();
+        options.for_each_line(file.path().to_str().unwrap(), |line| {
+            lines.push(line.to_string())
+        });
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn for_each_line() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "foo\r\n  bar\n\nlast").unwrap();
+        let path = file.path().to_str().unwrap();
+        let mut lines = Vec::new();
+        InputOptions::default().for_each_line(path, |line| lines.push(line.to_string()));
+        assert_eq!(lines, vec!["foo", "  bar", "", "last"]);
+        let options = InputOptionsBuilder::default()
+            .multiline_join(Regex::new("^\\s").unwrap())
+            .build()
+            .unwrap();
+        lines.clear();
+        options.for_each_line(path, |line| lines.push(line.to_string()));
+        assert_eq!(lines, vec!["foo\n  bar", "", "last"]);
+        let file = NamedTempFile::new().unwrap();
+        InputOptions::default().for_each_line(file.path().to_str().unwrap(), |_| {
+            panic!("No lines expected")
+        });
+    }
+
+    #[test]
+    fn windows_text() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"\xEF\xBB\xBF1.5\r\n2\xFF\n3\n").unwrap();
+        let path = file.path().to_str().unwrap();
+        let expected = vec!["1.5", "2\u{FFFD}", "3"];
+        assert_eq!(collect(&InputOptions::default(), &file), expected);
+        let mut lines = Vec::new();
+        InputOptions::default().for_each_line(path, |line| lines.push(line.to_string()));
+        assert_eq!(lines, expected);
+        assert_eq!(
+            &*InputOptions::default().map(path).unwrap(),
+            b"1.5\r\n2\xFF\n3\n"
+        );
+        let mut file = NamedTempFile::new().unwrap();
+        let text: Vec<u8> = "\u{FEFF}1.5\r\n2\n"
+            .encode_utf16()
+            .flat_map(|u| u.to_le_bytes())
+            .collect();
+        file.write_all(&text).unwrap();
+        let path = file.path().to_str().unwrap();
+        assert!(InputOptions::default().map(path).is_none());
+        lines.clear();
+        InputOptions::default().for_each_line(path, |line| lines.push(line.to_string()));
+        assert_eq!(lines, vec!["1.5", "2"]);
+    }
+
+    #[test]
+    fn delimiters() {
+        assert_eq!("\\n".parse::<Delimiter>(), Ok(Delimiter::Newline));
+        assert_eq!("\\0".parse::<Delimiter>(), Ok(Delimiter::Custom(vec![0])));
+        assert_eq!(
+            "-\\t\\\\".parse::<Delimiter>(),
+            Ok(Delimiter::Custom(b"-\t\\".to_vec()))
+        );
+        assert_eq!("json-array".parse::<Delimiter>(), Ok(Delimiter::JsonArray));
+        assert!("".parse::<Delimiter>().is_err());
+        assert!("\\x".parse::<Delimiter>().is_err());
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "a b\n\0c\0\0d").unwrap();
+        let options = InputOptionsBuilder::default()
+            .delimiter(Delimiter::Custom(vec![0]))
+            .build()
+            .unwrap();
+        assert_eq!(collect(&options, &file), vec!["a b\n", "c", "", "d"]);
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "1::2:3::::4::").unwrap();
+        let options = InputOptionsBuilder::default()
+            .delimiter(Delimiter::Custom(b"::".to_vec()))
+            .build()
+            .unwrap();
+        assert_eq!(collect(&options, &file), vec!["1", "2:3", "", "4"]);
+        let mut records = options.records(file.path().to_str().unwrap());
+        records.next();
+        assert_eq!(records.next().unwrap().unwrap().offset, 3);
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "[1, 2,\n3]").unwrap();
+        let options = InputOptionsBuilder::default()
+            .delimiter(Delimiter::JsonArray)
+            .build()
+            .unwrap();
+        let mut lines = Vec::new();
+        options.for_each_line(file.path().to_str().unwrap(), |line| {
+            lines.push(line.to_string())
+        });
+        assert_eq!(lines, vec!["1", "2", "3"]);
+    }
+
+    #[test]
+    fn selection() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "h\n1\n2\n3\n4\n5").unwrap();
+        let path = file.path().to_str().unwrap();
+        let check = |skip: usize, head: Option<usize>, tail: Option<usize>, expected: &[&str]| {
+            let mut builder = InputOptionsBuilder::default();
+            builder.skip(skip);
+            if let Some(head) = head {
+                builder.head(head);
+            }
+            if let Some(tail) = tail {
+                builder.tail(tail);
+            }
+            let options = builder.build().unwrap();
+            // Records are read as a stream, and lines from the mapped file
+            assert_eq!(collect(&options, &file), expected);
+            let mut lines = Vec::new();
+            options.for_each_line(path, |line| lines.push(line.to_string()));
+            assert_eq!(lines, expected);
+        };
+        check(1, None, None, &["1", "2", "3", "4", "5"]);
+        check(1, Some(2), None, &["1", "2"]);
+        check(0, None, Some(2), &["4", "5"]);
+        check(1, Some(3), Some(2), &["2", "3"]);
+        check(2, None, Some(10), &["2", "3", "4", "5"]);
+        check(0, None, Some(0), &[]);
+        check(10, None, Some(1), &[]);
+        check(0, Some(0), None, &[]);
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "1\n2\n3").unwrap();
+        let options = InputOptionsBuilder::default().tail(1).build().unwrap();
+        assert_eq!(collect(&options, &file), vec!["3"]);
+        assert_eq!(
+            &*options.map(file.path().to_str().unwrap()).unwrap(),
+            b"3\n"
+        );
+    }
+
+    #[test]
+    fn sampling() {
+        let mut file = NamedTempFile::new().unwrap();
+        for i in 0..1000 {
+            writeln!(file, "{i}").unwrap();
+        }
+        let path = file.path().to_str().unwrap();
+        assert_eq!(InputOptions::default().sampled_lines(), None);
+        let options = InputOptionsBuilder::default()
+            .skip(1)
+            .every(100)
+            .build()
+            .unwrap();
+        let mut lines = Vec::new();
+        options.for_each_line(path, |line| lines.push(line.to_string()));
+        assert_eq!(lines[..3], ["1", "101", "201"]);
+        assert_eq!(options.sampled_lines(), Some((10, 999)));
+        let options = InputOptionsBuilder::default()
+            .sample_rate(0.1)
+            .build()
+            .unwrap();
+        let (kept, read) = options.sampled_lines().unwrap();
+        assert_eq!((kept, read), (0, 0));
+        let kept = collect(&options, &file).len();
+        assert_eq!(options.sampled_lines(), Some((kept, 1000)));
+        assert!(kept > 30 && kept < 200);
+    }
+
+    #[test]
+    fn records_location() {
+        let options = InputOptionsBuilder::default()
+            .multiline_join(Regex::new("^\\s").unwrap())
+            .build()
+            .unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "foo\r\n  bar\nbaz\nlast").unwrap();
+        let records: Vec<Record> = options
+            .records(file.path().to_str().unwrap())
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(
+            records,
+            vec![
+                Record {
+                    text: String::from("foo\n  bar"),
+                    line: 1,
+                    offset: 0
+                },
+                Record {
+                    text: String::from("baz"),
+                    line: 3,
+                    offset: 11
+                },
+                Record {
+                    text: String::from("last"),
+                    line: 4,
+                    offset: 15
+                },
+            ]
+        );
+    }
+}
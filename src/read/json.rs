@@ -0,0 +1,160 @@
+use std::io::{self, BufRead, Read};
+
+use serde_json::Value;
+
+/// Returns the value addressed by `path` in a line containing a JSON object, as
+/// a string.  Path is a simple dot separated list of keys (like ".http.status"),
+/// where numbers can be used as indexes of arrays (like ".timings.0").  Returns
+/// None if line is not valid JSON, the path does not exist, or the value is not
+/// a string or a number.
+pub fn field(line: &str, path: &str) -> Option<String> {
+    let document: Value = match serde_json::from_str(line) {
+        Ok(document) => document,
+        Err(error) => {
+            debug!("Cannot parse JSON ({}) at '{}'", error, line);
+            return None;
+        }
+    };
+    let mut value = &document;
+    for key in path.split('.').filter(|key| !key.is_empty()) {
+        value = match value {
+            Value::Object(map) => map.get(key)?,
+            Value::Array(vec) => vec.get(key.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Turns JSON arrays (like `[1, 2.5, {"a": 3}]`) into a line per element,
+/// splitting them on the commas that are not inside strings or nested arrays
+/// and objects.  Whitespace out of strings is dropped, so that arrays can span
+/// several lines.
+pub struct ArrayReader<R> {
+    inner: R,
+    // Nesting of arrays and objects (1 is within the outer array)
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    // Whether something was written since the last line ending
+    pending: bool,
+}
+
+impl<R: BufRead> ArrayReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            pending: false,
+        }
+    }
+
+    // Returns what `byte` of the input becomes in the output, if anything
+    fn translate(&mut self, byte: u8) -> Option<u8> {
+        if self.in_string {
+            match (self.escaped, byte) {
+                (false, b'\\') => self.escaped = true,
+                (false, b'"') => self.in_string = false,
+                _ => self.escaped = false,
+            }
+            return Some(byte);
+        }
+        match byte {
+            b' ' | b'\t' | b'\r' | b'\n' => None,
+            b'[' if self.depth == 0 => {
+                self.depth = 1;
+                None
+            }
+            b',' | b']' if self.depth == 1 => {
+                if byte == b']' {
+                    self.depth = 0;
+                }
+                Some(b'\n').filter(|_| std::mem::take(&mut self.pending))
+            }
+            _ => {
+                match byte {
+                    b'"' => self.in_string = true,
+                    b'[' | b'{' => self.depth += 1,
+                    b']' | b'}' => self.depth = self.depth.saturating_sub(1).max(1),
+                    _ => (),
+                }
+                Some(byte)
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Read for ArrayReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+        while written == 0 && !buf.is_empty() {
+            let data = self.inner.fill_buf()?;
+            if data.is_empty() {
+                // A last element with no closing bracket
+                if std::mem::take(&mut self.pending) {
+                    buf[0] = b'\n';
+                    return Ok(1);
+                }
+                return Ok(0);
+            }
+            let data = data.to_vec();
+            let mut consumed = 0;
+            for byte in data {
+                if written == buf.len() {
+                    break;
+                }
+                consumed += 1;
+                if let Some(byte) = self.translate(byte) {
+                    self.pending = byte != b'\n';
+                    buf[written] = byte;
+                    written += 1;
+                }
+            }
+            self.inner.consume(consumed);
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_field() {
+        let line = r#"{"ts": "2021-04-15T06:25:31Z", "took": 12.5, "http": {"status": 200, "timings": [1, 2]}, "ok": true}"#;
+        assert_eq!(field(line, ".took").unwrap(), "12.5");
+        assert_eq!(field(line, "took").unwrap(), "12.5");
+        assert_eq!(field(line, ".ts").unwrap(), "2021-04-15T06:25:31Z");
+        assert_eq!(field(line, ".http.status").unwrap(), "200");
+        assert_eq!(field(line, ".http.timings.1").unwrap(), "2");
+        assert!(field(line, ".http.timings.2").is_none());
+        assert!(field(line, ".http.status.foo").is_none());
+        assert!(field(line, ".http").is_none());
+        assert!(field(line, ".ok").is_none());
+        assert!(field(line, ".missing").is_none());
+        assert!(field("not json", ".took").is_none());
+    }
+
+    #[test]
+    fn test_array_reader() {
+        let data = "[1, 2.5,\n -3, {\"a\": [4, 5]}, \"x,]\\\"\"]\n[6] 7";
+        let mut text = String::new();
+        ArrayReader::new(data.as_bytes())
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "1\n2.5\n-3\n{\"a\":[4,5]}\n\"x,]\\\"\"\n6\n7\n");
+        text.clear();
+        ArrayReader::new("[]".as_bytes())
+            .read_to_string(&mut text)
+            .unwrap();
+        assert_eq!(text, "");
+    }
+}
@@ -0,0 +1,75 @@
+use std::borrow::Cow;
+
+/// Returns the value of `key` in a logfmt formatted line (a sequence of
+/// `key=value` pairs separated by spaces, where values can be double quoted).
+/// Returns None if the key is not present or it has no value.
+pub fn field<'a>(line: &'a str, key: &str) -> Option<Cow<'a, str>> {
+    let mut rest = line;
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return None;
+        }
+        let key_end = rest.find(|c: char| c == '=' || c.is_whitespace());
+        let (this_key, after_key) = match key_end {
+            Some(i) => rest.split_at(i),
+            None => (rest, ""),
+        };
+        let (value, after_value) = match after_key.strip_prefix('=') {
+            Some(tail) => parse_value(tail),
+            None => (None, after_key),
+        };
+        if this_key == key {
+            return value.filter(|v| !v.is_empty());
+        }
+        rest = after_value;
+    }
+}
+
+// Parses a value at the start of `s`, returning it along with the remaining
+// text.
+fn parse_value(s: &str) -> (Option<Cow<'_, str>>, &str) {
+    let quoted = match s.strip_prefix('"') {
+        Some(quoted) => quoted,
+        None => {
+            let end = s.find(char::is_whitespace).unwrap_or(s.len());
+            return (Some(Cow::Borrowed(&s[..end])), &s[end..]);
+        }
+    };
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return (Some(Cow::Owned(value)), &quoted[i + 1..]),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, escaped)) => value.push(escaped),
+                None => break,
+            },
+            _ => value.push(c),
+        }
+    }
+    // Unterminated quotes: the value is invalid
+    (None, "")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_field() {
+        let line = "ts=2021-04-15T06:25:31Z level=info msg=\"GET /foo \\\"x\\\"\" took=12.5 debug";
+        assert_eq!(field(line, "took").unwrap(), "12.5");
+        assert_eq!(field(line, "ts").unwrap(), "2021-04-15T06:25:31Z");
+        assert_eq!(field(line, "msg").unwrap(), "GET /foo \"x\"");
+        assert!(field(line, "debug").is_none());
+        assert!(field(line, "missing").is_none());
+        assert!(field(line, "foo").is_none());
+        assert!(field("a=1 b=\"unterminated", "b").is_none());
+        assert!(field("a= b=2", "a").is_none());
+        assert_eq!(field("a= b=2", "b").unwrap(), "2");
+    }
+}
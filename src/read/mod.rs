@@ -1,22 +1,51 @@
-pub use self::buckets::{DataReader, DataReaderBuilder};
-pub use self::splittimes::{SplitTimeReader, SplitTimeReaderBuilder};
-pub use self::times::TimeReaderBuilder;
+pub use self::buckets::{CsvColumn, DataReader, DataReaderBuilder};
+pub use self::decode::Encoding;
+pub use self::durations::TimeUnit;
+pub use self::human::Magnitudes;
+pub use self::input::{Delimiter, InputOptions, InputOptionsBuilder};
+pub use self::presets::Preset;
+pub use self::splittimes::SplitTimeReaderBuilder;
+pub use self::times::{TimeReader, TimeReaderBuilder, TimeStream};
 
 mod buckets;
 mod dateparser;
+mod decode;
+mod durations;
+mod human;
+pub mod input;
+mod json;
+mod logfmt;
+mod net;
+mod parallel;
+mod presets;
+mod progress;
+mod reservoir;
 mod splittimes;
 mod times;
 
 use std::fs::File;
-use std::io::{self, BufReader};
+use std::io;
+
+use self::decode::decode;
+use self::progress::{Progress, ProgressReader};
 
 /// Return `io::BufRead` from a path, falling back to using stdin if path is "-".
-/// Exits the program with exit code 1 if path does not exist.
-fn open_file(path: &str) -> Box<dyn io::BufRead> {
+/// Exits the program with exit code 1 if path does not exist.  Progress of
+/// reading regular files is displayed in stderr, if it is a terminal.
+///
+/// Text is read as UTF-8, converted from `encoding` if needed (see
+/// `Encoding`), and with any byte order mark skipped.
+fn open_file(path: &str, encoding: Encoding) -> Box<dyn io::BufRead> {
     match path {
-        "-" => Box::new(BufReader::new(io::stdin())),
+        "-" => decode(io::stdin(), encoding),
         _ => match File::open(path) {
-            Ok(fd) => Box::new(io::BufReader::new(fd)),
+            Ok(fd) => match fd.metadata().ok().filter(|m| m.is_file()) {
+                Some(metadata) => match Progress::new(metadata.len()) {
+                    Some(progress) => decode(ProgressReader::new(fd, progress), encoding),
+                    None => decode(fd, encoding),
+                },
+                None => decode(fd, encoding),
+            },
             Err(error) => {
                 error!("Could not open {}: {}", path, error);
                 panic!("{}", error);
@@ -33,6 +62,6 @@ mod tests {
     #[test]
     #[should_panic]
     fn test_bad_file() {
-        open_file("/no/good");
+        open_file("/no/good", Encoding::Auto);
     }
 }
@@ -0,0 +1,262 @@
+use std::io::{self, BufReader, Read};
+use std::net::{TcpStream, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::time::{Duration, Instant};
+
+// Big enough for any UDP datagram
+const MAX_DATAGRAM: usize = 65536;
+
+/// Binds an UDP socket to `address` and returns a reader with the contents of
+/// the datagrams received during `duration`.  This is suitable for statsd-like
+/// protocols, where every datagram contains one or more lines.
+pub fn open_udp(address: &str, duration: Duration) -> io::Result<Box<dyn io::BufRead>> {
+    let socket = UdpSocket::bind(address)?;
+    info!(
+        "Listening at udp://{} for {:?}",
+        socket.local_addr()?,
+        duration
+    );
+    Ok(Box::new(BufReader::new(UdpReader::new(
+        socket,
+        Instant::now() + duration,
+    ))))
+}
+
+/// A reader over the datagrams received by an UDP socket, until a deadline is
+/// reached (then the reader behaves as if end of file was reached).  Every
+/// datagram is terminated with a newline, if it is not already.
+struct UdpReader {
+    socket: UdpSocket,
+    deadline: Instant,
+    buffer: Vec<u8>,
+    pos: usize,
+}
+
+impl UdpReader {
+    fn new(socket: UdpSocket, deadline: Instant) -> Self {
+        Self {
+            socket,
+            deadline,
+            buffer: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    // Waits for next datagram.  Returns false if the deadline was reached.
+    fn receive(&mut self) -> io::Result<bool> {
+        let mut datagram = vec![0; MAX_DATAGRAM];
+        loop {
+            let now = Instant::now();
+            if now >= self.deadline {
+                return Ok(false);
+            }
+            self.socket.set_read_timeout(Some(self.deadline - now))?;
+            match self.socket.recv(&mut datagram) {
+                Ok(0) => continue,
+                Ok(n) => {
+                    datagram.truncate(n);
+                    if datagram[n - 1] != b'\n' {
+                        datagram.push(b'\n');
+                    }
+                    self.buffer = datagram;
+                    self.pos = 0;
+                    return Ok(true);
+                }
+                Err(error)
+                    if error.kind() == io::ErrorKind::WouldBlock
+                        || error.kind() == io::ErrorKind::TimedOut
+                        || error.kind() == io::ErrorKind::Interrupted =>
+                {
+                    continue
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+/// Connects to the TCP server at `address` and returns a reader with the data
+/// sent by it, until it closes the connection or `duration` (if any) elapses.
+pub fn open_tcp(address: &str, duration: Option<Duration>) -> io::Result<Box<dyn io::BufRead>> {
+    let stream = TcpStream::connect(address)?;
+    info!("Reading from tcp://{}", stream.peer_addr()?);
+    Ok(Box::new(BufReader::new(StreamReader::new(
+        stream, duration,
+    ))))
+}
+
+/// Connects to the Unix domain socket at `path` and returns a reader with the
+/// data sent through it, until it is closed or `duration` (if any) elapses.
+#[cfg(unix)]
+pub fn open_unix(path: &str, duration: Option<Duration>) -> io::Result<Box<dyn io::BufRead>> {
+    let stream = UnixStream::connect(path)?;
+    info!("Reading from unix://{}", path);
+    Ok(Box::new(BufReader::new(StreamReader::new(
+        stream, duration,
+    ))))
+}
+
+#[cfg(not(unix))]
+pub fn open_unix(_path: &str, _duration: Option<Duration>) -> io::Result<Box<dyn io::BufRead>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Unix domain sockets are not supported in this platform",
+    ))
+}
+
+/// Streams whose reads can time out.
+trait TimedStream: Read {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+}
+
+impl TimedStream for TcpStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+}
+
+#[cfg(unix)]
+impl TimedStream for UnixStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UnixStream::set_read_timeout(self, timeout)
+    }
+}
+
+/// A reader over a stream socket, behaving as if end of file was reached when
+/// the deadline (if any) is reached.
+struct StreamReader<S> {
+    stream: S,
+    deadline: Option<Instant>,
+}
+
+impl<S: TimedStream> StreamReader<S> {
+    fn new(stream: S, duration: Option<Duration>) -> Self {
+        Self {
+            stream,
+            deadline: duration.map(|duration| Instant::now() + duration),
+        }
+    }
+}
+
+impl<S: TimedStream> Read for StreamReader<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(deadline) = self.deadline {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Ok(0);
+                }
+                self.stream.set_read_timeout(Some(deadline - now))?;
+            }
+            match self.stream.read(buf) {
+                Err(error)
+                    if error.kind() == io::ErrorKind::WouldBlock
+                        || error.kind() == io::ErrorKind::TimedOut
+                        || error.kind() == io::ErrorKind::Interrupted =>
+                {
+                    continue
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Read for UdpReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.buffer.len() && !self.receive()? {
+            return Ok(0);
+        }
+        let n = buf.len().min(self.buffer.len() - self.pos);
+        buf[..n].copy_from_slice(&self.buffer[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::io::{BufRead, Write};
+    use std::net::TcpListener;
+    #[cfg(unix)]
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+
+    #[test]
+    fn test_udp_reader() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = socket.local_addr().unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender.send_to(b"foo:1|ms", address).unwrap();
+        sender.send_to(b"bar:2|ms\nbaz:3|ms\n", address).unwrap();
+        let reader = BufReader::new(UdpReader::new(
+            socket,
+            Instant::now() + Duration::from_millis(300),
+        ));
+        let lines: Vec<String> = reader.lines().map(|l| l.unwrap()).collect();
+        assert_eq!(lines, vec!["foo:1|ms", "bar:2|ms", "baz:3|ms"]);
+    }
+
+    #[test]
+    fn test_tcp_reader() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"foo 1\nbar 2\n").unwrap();
+        });
+        let lines: Vec<String> = open_tcp(&address, None)
+            .unwrap()
+            .lines()
+            .map(|l| l.unwrap())
+            .collect();
+        server.join().unwrap();
+        assert_eq!(lines, vec!["foo 1", "bar 2"]);
+    }
+
+    #[test]
+    fn test_tcp_reader_deadline() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"foo 1\n").unwrap();
+            // Keep the connection open after the deadline
+            thread::sleep(Duration::from_millis(500));
+        });
+        let lines: Vec<String> = open_tcp(&address, Some(Duration::from_millis(200)))
+            .unwrap()
+            .lines()
+            .map(|l| l.unwrap())
+            .collect();
+        assert_eq!(lines, vec!["foo 1"]);
+        server.join().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix_reader() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"foo 1\nbar 2").unwrap();
+        });
+        let lines: Vec<String> = open_unix(path.to_str().unwrap(), None)
+            .unwrap()
+            .lines()
+            .map(|l| l.unwrap())
+            .collect();
+        server.join().unwrap();
+        assert_eq!(lines, vec!["foo 1", "bar 2"]);
+    }
+
+    #[test]
+    fn test_udp_bad_address() {
+        assert!(open_udp("not an address", Duration::from_millis(1)).is_err());
+    }
+}
@@ -0,0 +1,58 @@
+/// Chunks smaller than this are not worth the overhead of a thread
+const MIN_CHUNK_SIZE: usize = 1 << 20;
+
+/// Splits `data` into chunks of whole lines, so that they can be processed in
+/// parallel.  The number of chunks is a few times the number of threads, so
+/// that work is evenly distributed among them.
+pub fn split_lines(data: &[u8], threads: usize) -> Vec<&[u8]> {
+    let size = MIN_CHUNK_SIZE.max(data.len() / (threads * 4).max(1));
+    split_lines_by_size(data, size)
+}
+
+fn split_lines_by_size(data: &[u8], size: usize) -> Vec<&[u8]> {
+    let mut chunks = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let end = match rest.iter().skip(size).position(|b| *b == b'\n') {
+            Some(index) => size + index + 1,
+            None => rest.len(),
+        };
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+/// Returns an iterator over the lines of a chunk, without their line endings.
+pub fn lines(chunk: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let chunk = chunk.strip_suffix(b"\n").unwrap_or(chunk);
+    chunk
+        .split(|b| *b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_lines() {
+        let data = b"1\n22\n333\n4444";
+        assert_eq!(
+            split_lines_by_size(data, 2),
+            [&b"1\n22\n"[..], &b"333\n"[..], &b"4444"[..]]
+        );
+        assert_eq!(split_lines_by_size(data, 100), [&data[..]]);
+        assert!(split_lines_by_size(b"", 2).is_empty());
+        assert_eq!(split_lines(data, 8), [&data[..]]);
+    }
+
+    #[test]
+    fn test_lines() {
+        let found: Vec<&[u8]> = lines(b"1\r\n\n22\n").collect();
+        assert_eq!(found, [&b"1"[..], &b""[..], &b"22"[..]]);
+        let found: Vec<&[u8]> = lines(b"1\n22").collect();
+        assert_eq!(found, [&b"1"[..], &b"22"[..]]);
+    }
+}
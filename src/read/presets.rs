@@ -0,0 +1,412 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::read::{DataReaderBuilder, TimeReaderBuilder};
+
+/// A well known log format: a regex with named capture groups for its fields
+/// and the format of its timestamps (captured by the `time` group).
+#[derive(Debug)]
+pub struct Preset {
+    /// Name of the preset, as used in the command line.
+    pub name: &'static str,
+    /// Regex with named capture groups for the fields of a log line.
+    pub regex: &'static str,
+    /// Format of the timestamp in the `time` capture group.
+    pub ts_format: &'static str,
+    /// Capture group used for values unless other is requested.
+    pub value: &'static str,
+    /// Unit the values are converted to if they are durations with units
+    /// (like "1.5ms"), or empty if they are plain numbers.
+    pub durations: &'static str,
+    /// Capture group subtracted from the default value (when it is the end
+    /// of something starting at `since`), or empty.
+    pub since: &'static str,
+}
+
+// Combined log format, optionally followed by the request time
+const NGINX_RE: &str = concat!(
+    r#"^(?P<remote_addr>\S+) \S+ (?P<remote_user>\S+) \[(?P<time>[^\]]+)\] "#,
+    r#""(?P<request>[^"]*)" (?P<status>\d{3}) (?P<bytes>\d+|-) "#,
+    r#""(?P<referer>[^"]*)" "(?P<user_agent>[^"]*)"(?: (?P<request_time>[0-9.]+))?"#,
+);
+
+// Combined log format, optionally followed by the request time in
+// microseconds (%D)
+const APACHE_RE: &str = concat!(
+    r#"^(?P<remote_addr>\S+) \S+ (?P<remote_user>\S+) \[(?P<time>[^\]]+)\] "#,
+    r#""(?P<request>[^"]*)" (?P<status>\d{3}) (?P<bytes>\d+|-)"#,
+    r#"(?: "(?P<referer>[^"]*)" "(?P<user_agent>[^"]*)")?(?: (?P<request_time>\d+))?"#,
+);
+
+// HTTP log format (option httplog)
+const HAPROXY_RE: &str = concat!(
+    r#"haproxy\[\d+\]: (?P<client>\S+) \[(?P<time>[^\]]+)\] (?P<frontend>\S+) "#,
+    r#"(?P<backend>\S+) (?P<tq>-?\d+)/(?P<tw>-?\d+)/(?P<tc>-?\d+)/(?P<tr>-?\d+)/"#,
+    r#"\+?(?P<request_time>\d+) (?P<status>-?\d+) \+?(?P<bytes>\d+)"#,
+);
+
+// Default access log format
+const ENVOY_RE: &str = concat!(
+    r#"^\[(?P<time>[^\]]+)\] "(?P<request>[^"]*)" (?P<status>\d+) (?P<flags>\S+) "#,
+    r#"(?P<bytes_received>\d+) (?P<bytes>\d+) (?P<request_time>\d+) "#,
+    r#"(?P<upstream_time>\d+|-)"#,
+);
+
+// Results of `vegeta encode` (JSON, one per line), with the latency in
+// nanoseconds
+const VEGETA_RE: &str = concat!(
+    r#""code":(?P<code>\d+),"timestamp":"(?P<time>[^"]+)","latency":(?P<latency>\d+),"#,
+    r#""bytes_out":(?P<bytes_out>\d+),"bytes_in":(?P<bytes_in>\d+)"#,
+);
+
+// Results of `hey -o csv`, with times in seconds (the offset of every request
+// is relative to the start of the test)
+const HEY_RE: &str = concat!(
+    r#"^(?P<response_time>[0-9.]+),(?P<dns_dialup>[0-9.]+),(?P<dns>[0-9.]+),"#,
+    r#"(?P<request_write>[0-9.]+),(?P<response_delay>[0-9.]+),"#,
+    r#"(?P<response_read>[0-9.]+),(?P<status>\d+),(?P<time>[0-9.]+)$"#,
+);
+
+// Latency distribution printed by `wrk --latency` (or by wrk2)
+const WRK_RE: &str = r#"^\s+(?P<percentile>[0-9.]+)%\s+(?P<latency>[0-9.]+[a-z]+)\s*$"#;
+
+// JMeter results (.jtl files) in CSV with the default columns, with the
+// timestamps in milliseconds since the epoch and times in milliseconds
+const JMETER_RE: &str = concat!(
+    r#"^(?P<time>\d{13}),(?P<elapsed>\d+),(?P<label>[^,]*),(?P<status>[^,]*),"#,
+    r#"(?P<message>[^,]*),(?P<thread>[^,]*),(?P<data_type>[^,]*),(?P<success>true|false),"#,
+    r#"(?P<failure>[^,]*),(?P<bytes>\d+),(?P<sent_bytes>\d+)"#,
+    r#"(?:,(?P<threads>\d+),\d+,(?P<url>[^,]*),(?P<latency>\d+),\d+,(?P<connect>\d+))?"#,
+);
+
+// Requests in a Gatling simulation.log (text format, with or without the
+// user id of older versions), logged as their start and end in milliseconds
+// since the epoch
+const GATLING_RE: &str = concat!(
+    r#"^REQUEST\t(?:\d+\t)?(?P<group>[^\t]*)\t(?P<name>[^\t]*)\t"#,
+    r#"(?P<time>\d{13})\t(?P<end>\d{13})\t(?P<status>OK|KO)"#,
+);
+
+// Presets defined by the user (in the config file)
+static USER_PRESETS: OnceLock<Vec<Preset>> = OnceLock::new();
+
+/// Presets for the logs of common servers.
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "nginx",
+        regex: NGINX_RE,
+        ts_format: "%d/%b/%Y:%H:%M:%S %z",
+        value: "request_time",
+        durations: "",
+        since: "",
+    },
+    Preset {
+        name: "apache",
+        regex: APACHE_RE,
+        ts_format: "%d/%b/%Y:%H:%M:%S %z",
+        value: "request_time",
+        durations: "",
+        since: "",
+    },
+    Preset {
+        name: "haproxy",
+        regex: HAPROXY_RE,
+        ts_format: "%d/%b/%Y:%H:%M:%S%.3f",
+        value: "request_time",
+        durations: "",
+        since: "",
+    },
+    Preset {
+        name: "envoy",
+        regex: ENVOY_RE,
+        ts_format: "%Y-%m-%dT%H:%M:%S%.3fZ",
+        value: "request_time",
+        durations: "",
+        since: "",
+    },
+    Preset {
+        name: "vegeta",
+        regex: VEGETA_RE,
+        ts_format: "",
+        value: "latency",
+        durations: "",
+        since: "",
+    },
+    Preset {
+        name: "hey",
+        regex: HEY_RE,
+        ts_format: "%s%.f",
+        value: "response_time",
+        durations: "",
+        since: "",
+    },
+    Preset {
+        name: "wrk",
+        regex: WRK_RE,
+        ts_format: "",
+        value: "latency",
+        durations: "ms",
+        since: "",
+    },
+    Preset {
+        name: "jmeter",
+        regex: JMETER_RE,
+        ts_format: "",
+        value: "elapsed",
+        durations: "",
+        since: "",
+    },
+    Preset {
+        name: "gatling",
+        regex: GATLING_RE,
+        ts_format: "",
+        value: "end",
+        durations: "",
+        since: "time",
+    },
+];
+
+impl Preset {
+    /// Returns the preset with the given name, if any.  User presets take
+    /// precedence over the builtin ones.
+    pub fn find(name: &str) -> Option<&'static Preset> {
+        USER_PRESETS
+            .get()
+            .into_iter()
+            .flatten()
+            .chain(PRESETS)
+            .find(|p| p.name == name)
+    }
+
+    /// Makes available `presets` (defined by the user) along with the builtin
+    /// ones.  Only the first call has any effect.
+    pub fn register(presets: Vec<Preset>) {
+        let _ = USER_PRESETS.set(presets);
+    }
+
+    /// Configures a `DataReaderBuilder` for reading the values of the
+    /// `capture` group of this preset (or its default one if None).  Returns
+    /// an error if there is no such capture group.
+    pub fn configure_data(
+        &self,
+        builder: &mut DataReaderBuilder,
+        capture: Option<&str>,
+    ) -> Result<(), String> {
+        let regex = Regex::new(self.regex).unwrap();
+        if capture.is_none() && !self.since.is_empty() {
+            builder.since(self.since.to_string());
+        }
+        let capture = capture.unwrap_or(self.value);
+        if !regex.capture_names().any(|name| name == Some(capture)) {
+            return Err(format!(
+                "No field {} in {} preset (available: {})",
+                capture,
+                self.name,
+                regex
+                    .capture_names()
+                    .flatten()
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            ));
+        }
+        builder.regex(regex).capture(capture.to_string());
+        if let Ok(unit) = self.durations.parse() {
+            builder.durations(unit);
+        }
+        Ok(())
+    }
+
+    /// Configures a `TimeReaderBuilder` for reading the timestamps of the log
+    /// lines in this preset.  The timestamps are searched in the `time` group
+    /// if present, and their format is guessed if the preset has none.
+    pub fn configure_time(&self, builder: &mut TimeReaderBuilder) {
+        let regex = Regex::new(self.regex).unwrap();
+        if regex.capture_names().any(|name| name == Some("time")) {
+            builder.ts_capture(String::from("time"));
+        }
+        if !self.ts_format.is_empty() {
+            builder.ts_format(self.ts_format.to_string());
+        }
+        builder.regex(regex);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use chrono::DateTime;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn read_values(preset: &str, capture: Option<&str>, lines: &[&str]) -> Vec<f64> {
+        let mut file = NamedTempFile::new().unwrap();
+        for line in lines {
+            writeln!(file, "{line}").unwrap();
+        }
+        let mut builder = DataReaderBuilder::default();
+        Preset::find(preset)
+            .unwrap()
+            .configure_data(&mut builder, capture)
+            .unwrap();
+        builder.build().unwrap().read(file.path().to_str().unwrap())
+    }
+
+    fn read_first_time(preset: &str, line: &str) -> String {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "{line}").unwrap();
+        let mut builder = TimeReaderBuilder::default();
+        Preset::find(preset).unwrap().configure_time(&mut builder);
+        let times = builder.build().unwrap().read(file.path().to_str().unwrap());
+        times[0].to_rfc3339()
+    }
+
+    #[test]
+    fn test_nginx() {
+        let lines = [
+            r#"10.0.0.1 - - [15/Apr/2021:06:25:31 +0000] "GET / HTTP/1.1" 200 612 "-" "curl/7.68.0" 0.012"#,
+            r#"10.0.0.123 - bob [15/Apr/2021:06:25:32 +0000] "GET /x HTTP/1.1" 404 0 "-" "curl/7.68.0""#,
+            "garbage",
+        ];
+        assert_eq!(read_values("nginx", None, &lines), [0.012]);
+        assert_eq!(read_values("nginx", Some("status"), &lines), [200.0, 404.0]);
+        assert_eq!(read_values("nginx", Some("bytes"), &lines), [612.0, 0.0]);
+        assert_eq!(
+            read_first_time("nginx", lines[1]),
+            "2021-04-15T06:25:32+00:00"
+        );
+    }
+
+    #[test]
+    fn test_apache() {
+        let lines = [
+            r#"127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] "GET /a.gif HTTP/1.0" 200 2326"#,
+            r#"127.0.0.1 - - [10/Oct/2000:13:55:37 -0700] "GET / HTTP/1.0" 200 99 "-" "Mozilla" 1500"#,
+        ];
+        assert_eq!(read_values("apache", None, &lines), [1500.0]);
+        assert_eq!(read_values("apache", Some("bytes"), &lines), [2326.0, 99.0]);
+        assert_eq!(
+            read_first_time("apache", lines[0]),
+            "2000-10-10T13:55:36+00:00"
+        );
+    }
+
+    #[test]
+    fn test_haproxy() {
+        let line = concat!(
+            "Feb  6 12:14:14 localhost haproxy[14389]: 10.0.1.2:33317 ",
+            "[06/Feb/2009:12:14:14.655] http-in static/srv1 10/0/30/69/109 200 2750 - - ---- ",
+            r#"1/1/1/1/0 0/0 {1wt.eu} {} "GET /index.html HTTP/1.1""#
+        );
+        assert_eq!(read_values("haproxy", None, &[line]), [109.0]);
+        assert_eq!(read_values("haproxy", Some("tr"), &[line]), [69.0]);
+        assert_eq!(
+            read_first_time("haproxy", line),
+            "2009-02-06T12:14:14.655+00:00"
+        );
+    }
+
+    #[test]
+    fn test_envoy() {
+        let line = concat!(
+            r#"[2016-04-15T20:17:00.310Z] "POST /api/v1/locations HTTP/2" 204 - 154 0 226 100 "#,
+            r#""10.0.35.28" "nsq2http" "cc21d9b0-cf5c-432b-8c7e-98aeb7988cd2" "locations" "#,
+            r#""tcp://10.0.2.1:80""#
+        );
+        assert_eq!(read_values("envoy", None, &[line]), [226.0]);
+        assert_eq!(read_values("envoy", Some("status"), &[line]), [204.0]);
+        assert_eq!(
+            DateTime::parse_from_rfc3339(&read_first_time("envoy", line)).unwrap(),
+            DateTime::parse_from_rfc3339("2016-04-15T20:17:00.310Z").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_vegeta() {
+        let line = concat!(
+            r#"{"attack":"","seq":0,"code":200,"timestamp":"2020-09-04T18:45:47.071347+02:00","#,
+            r#""latency":2365468,"bytes_out":0,"bytes_in":12,"error":"","body":null}"#
+        );
+        assert_eq!(read_values("vegeta", None, &[line]), [2365468.0]);
+        assert_eq!(read_values("vegeta", Some("code"), &[line]), [200.0]);
+        assert_eq!(
+            read_first_time("vegeta", line),
+            "2020-09-04T18:45:47.071347+02:00"
+        );
+    }
+
+    #[test]
+    fn test_hey() {
+        let lines = [
+            "response-time,DNS+dialup,DNS,Request-write,Response-delay,Response-read,status-code,offset",
+            "0.0123,0.0050,0.0001,0.0000,0.0071,0.0001,200,1.5032",
+        ];
+        assert_eq!(read_values("hey", None, &lines), [0.0123]);
+        assert_eq!(read_values("hey", Some("status"), &lines), [200.0]);
+        assert_eq!(
+            read_first_time("hey", lines[1]),
+            "1970-01-01T00:00:01.503200+00:00"
+        );
+    }
+
+    #[test]
+    fn test_wrk() {
+        let lines = [
+            "  Latency Distribution",
+            "     50%  635.91us",
+            "     99%    1.20ms",
+            "    Latency   661.87us  118.90us   2.01ms   84.27%",
+        ];
+        assert_eq!(read_values("wrk", None, &lines), [0.63591, 1.2]);
+        assert_eq!(read_values("wrk", Some("percentile"), &lines), [50.0, 99.0]);
+    }
+
+    #[test]
+    fn test_jmeter() {
+        let lines = [
+            "timeStamp,elapsed,label,responseCode,responseMessage,threadName,dataType,success,failureMessage,bytes,sentBytes,grpThreads,allThreads,URL,Latency,IdleTime,Connect",
+            "1617776400123,245,Home,200,OK,Users 1-1,text,true,,1591,120,1,1,https://example.com/,240,0,12",
+            "1617776401000,31,Login,500,Internal Server Error,Users 1-2,text,false,,80,90",
+        ];
+        assert_eq!(read_values("jmeter", None, &lines), [245.0, 31.0]);
+        assert_eq!(
+            read_values("jmeter", Some("status"), &lines),
+            [200.0, 500.0]
+        );
+        assert_eq!(read_values("jmeter", Some("latency"), &lines), [240.0]);
+        assert_eq!(
+            read_first_time("jmeter", lines[1]),
+            "2021-04-07T06:20:00.123+00:00"
+        );
+    }
+
+    #[test]
+    fn test_gatling() {
+        let lines = [
+            "RUN\tbasic.BasicSimulation\tbasicsimulation\t1617776400000\t \t3.9.5",
+            "USER\tScenario\tSTART\t1617776400100",
+            "REQUEST\t\trequest_1\t1617776400123\t1617776400368\tOK\t ",
+            "REQUEST\t7\tcheckout\trequest_2\t1617776401000\t1617776401031\tKO\tstatus.find.is(200)",
+        ];
+        assert_eq!(read_values("gatling", None, &lines), [245.0, 31.0]);
+        assert_eq!(
+            read_values("gatling", Some("end"), &lines[2..3]),
+            [1617776400368.0]
+        );
+        assert_eq!(
+            read_first_time("gatling", lines[2]),
+            "2021-04-07T06:20:00.123+00:00"
+        );
+    }
+
+    #[test]
+    fn test_bad_capture() {
+        let mut builder = DataReaderBuilder::default();
+        let result = Preset::find("nginx")
+            .unwrap()
+            .configure_data(&mut builder, Some("nope"));
+        assert!(result.unwrap_err().contains("available: remote_addr, "));
+        assert!(Preset::find("nope").is_none());
+    }
+}
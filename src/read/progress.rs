@@ -0,0 +1,154 @@
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Bytes processed between checks for redrawing the progress bar
+const STEP: u64 = 1 << 20;
+/// Quick reads do not show any progress at all
+const DELAY: Duration = Duration::from_secs(1);
+/// Minimum time between redraws
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+/// Width of the bar, in characters
+const BAR_WIDTH: usize = 30;
+
+const BYTE_UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+
+/// A progress bar for reading big files, drawn in stderr.
+pub struct Progress {
+    total: u64,
+    done: AtomicU64,
+    start: Instant,
+    last_draw: Mutex<Option<Instant>>,
+    drawn: AtomicBool,
+}
+
+impl Progress {
+    /// Returns a progress bar for processing `total` bytes, unless stderr is
+    /// not a terminal.
+    pub fn new(total: u64) -> Option<Self> {
+        if total == 0 || atty::isnt(atty::Stream::Stderr) {
+            return None;
+        }
+        Some(Self {
+            total,
+            done: AtomicU64::new(0),
+            start: Instant::now(),
+            last_draw: Mutex::new(None),
+            drawn: AtomicBool::new(false),
+        })
+    }
+
+    /// Records that `bytes` more bytes were processed, redrawing the bar if
+    /// needed.  This can be called concurrently from several threads.
+    pub fn add(&self, bytes: u64) {
+        let done = self.done.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        if done / STEP == (done - bytes) / STEP {
+            return;
+        }
+        let elapsed = self.start.elapsed();
+        if elapsed < DELAY {
+            return;
+        }
+        // Other threads will redraw it soon enough
+        let mut last_draw = match self.last_draw.try_lock() {
+            Ok(last_draw) => last_draw,
+            Err(_) => return,
+        };
+        if matches!(*last_draw, Some(instant) if instant.elapsed() < REDRAW_INTERVAL) {
+            return;
+        }
+        *last_draw = Some(Instant::now());
+        self.drawn.store(true, Ordering::Relaxed);
+        eprint!("\r{}", self.line(done.min(self.total), elapsed));
+        io::stderr().flush().ok();
+    }
+
+    fn line(&self, done: u64, elapsed: Duration) -> String {
+        let ratio = done as f64 / self.total as f64;
+        let filled = (ratio * BAR_WIDTH as f64) as usize;
+        let eta = match done {
+            0 => Duration::ZERO,
+            _ => elapsed.mul_f64((self.total - done) as f64 / done as f64),
+        };
+        format!(
+            "[{:#<filled$}{:-<empty$}] {:3.0}% {} / {}, ETA {}",
+            "",
+            "",
+            ratio * 100.0,
+            human_bytes(done),
+            human_bytes(self.total),
+            humantime::format_duration(Duration::from_secs(eta.as_secs())),
+            empty = BAR_WIDTH - filled,
+        )
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if self.drawn.load(Ordering::Relaxed) {
+            // Erase the bar
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+/// A reader reporting to a progress bar the bytes read through it.
+pub struct ProgressReader<R> {
+    inner: R,
+    progress: Progress,
+}
+
+impl<R: Read> ProgressReader<R> {
+    pub fn new(inner: R, progress: Progress) -> Self {
+        Self { inner, progress }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.progress.add(n as u64);
+        Ok(n)
+    }
+}
+
+fn human_bytes(bytes: u64) -> String {
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < BYTE_UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} {}", bytes, BYTE_UNITS[0]),
+        _ => format!("{:.1} {}", value, BYTE_UNITS[unit]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_human_bytes() {
+        assert_eq!(human_bytes(10), "10 B");
+        assert_eq!(human_bytes(1536), "1.5 KB");
+        assert_eq!(human_bytes(3 << 30), "3.0 GB");
+    }
+
+    #[test]
+    fn test_line() {
+        let progress = Progress {
+            total: 4 << 20,
+            done: AtomicU64::new(0),
+            start: Instant::now(),
+            last_draw: Mutex::new(None),
+            drawn: AtomicBool::new(false),
+        };
+        assert_eq!(
+            progress.line(1 << 20, Duration::from_secs(10)),
+            "[#######-----------------------]  25% 1.0 MB / 4.0 MB, ETA 30s"
+        );
+    }
+}
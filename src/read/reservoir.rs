@@ -0,0 +1,74 @@
+use rand::rngs::ThreadRng;
+use rand::{thread_rng, Rng};
+
+/// Collects values, keeping at most `capacity` of them, chosen uniformly at
+/// random among all the values pushed (reservoir sampling).  Without
+/// capacity, all the values are kept.
+pub struct Reservoir {
+    capacity: Option<usize>,
+    seen: usize,
+    values: Vec<f64>,
+    rng: ThreadRng,
+}
+
+impl Reservoir {
+    pub fn new(capacity: Option<usize>) -> Self {
+        Self {
+            capacity,
+            seen: 0,
+            values: Vec::new(),
+            rng: thread_rng(),
+        }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        self.seen += 1;
+        match self.capacity {
+            Some(capacity) if self.values.len() >= capacity => {
+                let index = self.rng.gen_range(0..self.seen);
+                if index < capacity {
+                    self.values[index] = value;
+                }
+            }
+            _ => self.values.push(value),
+        }
+    }
+
+    /// Returns how many values were pushed, including the discarded ones.
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    pub fn into_vec(self) -> Vec<f64> {
+        self.values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unbounded() {
+        let mut reservoir = Reservoir::new(None);
+        for i in 0..1000 {
+            reservoir.push(i as f64);
+        }
+        assert_eq!(reservoir.seen(), 1000);
+        assert_eq!(reservoir.into_vec().len(), 1000);
+    }
+
+    #[test]
+    fn test_bounded() {
+        let mut reservoir = Reservoir::new(Some(100));
+        for i in 0..10000 {
+            reservoir.push(i as f64);
+        }
+        assert_eq!(reservoir.seen(), 10000);
+        let vec = reservoir.into_vec();
+        assert_eq!(vec.len(), 100);
+        // Values from the whole input, not only from the beginning
+        assert!(vec.iter().any(|n| *n >= 5000.0));
+        assert!(vec.iter().all(|n| (0.0..10000.0).contains(n)));
+    }
+}
@@ -1,22 +1,26 @@
-use std::io::BufRead;
-
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, FixedOffset, NaiveDate};
 
 use crate::read::dateparser::LogDateParser;
-use crate::read::open_file;
+use crate::read::InputOptions;
 
 #[derive(Default, Builder)]
 pub struct SplitTimeReader {
+    #[builder(default)]
+    input: InputOptions,
     #[builder(setter(strip_option), default)]
     matches: Vec<String>,
     #[builder(setter(strip_option), default)]
     ts_format: Option<String>,
+    #[builder(setter(strip_option), default)]
+    base_date: Option<NaiveDate>,
+    #[builder(default)]
+    strict_position: bool,
 }
 
 impl SplitTimeReader {
     pub fn read(&self, path: &str) -> Vec<(DateTime<FixedOffset>, usize)> {
         let mut vec: Vec<(DateTime<FixedOffset>, usize)> = Vec::new();
-        let mut iterator = open_file(path).lines();
+        let mut iterator = self.input.lines(path);
         let first_line = match iterator.next() {
             Some(Ok(as_string)) => as_string,
             Some(Err(error)) => {
@@ -25,13 +29,14 @@ impl SplitTimeReader {
             }
             _ => return vec,
         };
-        let parser = match LogDateParser::new(&first_line, &self.ts_format) {
+        let mut parser = match LogDateParser::new(&first_line, &self.ts_format, self.base_date) {
             Ok(p) => p,
             Err(error) => {
                 error!("Could not figure out parsing strategy: {}", error);
                 return vec;
             }
         };
+        parser.set_strict_position(self.strict_position);
         if let Ok(x) = parser.parse(&first_line) {
             self.push_conditionally(x, &mut vec, &first_line);
         }
@@ -1,56 +1,113 @@
-use std::io::BufRead;
+use std::borrow::Cow;
 
-use chrono::{DateTime, Duration, FixedOffset};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate};
 use regex::Regex;
 
 use crate::read::dateparser::LogDateParser;
-use crate::read::open_file;
+use crate::read::InputOptions;
+use crate::read::{json, logfmt};
 
 #[derive(Default, Builder)]
 pub struct TimeReader {
+    #[builder(default)]
+    input: InputOptions,
     #[builder(setter(strip_option), default)]
     regex: Option<Regex>,
+    // Capture group of the regex with the timestamp, instead of the whole line
+    #[builder(setter(strip_option), default)]
+    ts_capture: Option<String>,
     #[builder(setter(strip_option), default)]
     ts_format: Option<String>,
     #[builder(setter(strip_option), default)]
+    logfmt: Option<String>,
+    #[builder(setter(strip_option), default)]
+    json_field: Option<String>,
+    #[builder(setter(strip_option), default)]
+    base_date: Option<NaiveDate>,
+    #[builder(default)]
+    strict_position: bool,
+    #[builder(setter(strip_option), default)]
     duration: Option<Duration>,
     #[builder(default)]
     early_stop: bool,
+    // Regex for the values read by `read_values`
+    #[builder(setter(strip_option), default)]
+    value_regex: Option<Regex>,
 }
 
 impl TimeReader {
+    /// Returns the options for reading the input.
+    pub fn input(&self) -> &InputOptions {
+        &self.input
+    }
+
     pub fn read(&self, path: &str) -> Vec<DateTime<FixedOffset>> {
-        let mut vec: Vec<DateTime<FixedOffset>> = Vec::new();
-        let mut iterator = open_file(path).lines();
-        let first_line = match iterator.next() {
-            Some(Ok(as_string)) => as_string,
-            Some(Err(error)) => {
-                error!("{}", error);
-                return vec;
+        let vec = self.read_with(path, |_| Some(()));
+        vec.into_iter().map(|(d, _)| d).collect()
+    }
+
+    /// Like `read`, but returning along with every timestamp the term captured
+    /// by the regex in its line (the `value` capture group, or the first one
+    /// but `ts`).  Lines with no term are skipped.
+    pub fn read_terms(&self, path: &str) -> Vec<(DateTime<FixedOffset>, String)> {
+        self.read_with(path, |line| self.term(line))
+    }
+
+    /// Like `read`, but returning along with every timestamp the number
+    /// captured by the value regex in its line (the `value` capture group, or
+    /// the first one).  Lines with no such number are skipped.
+    pub fn read_values(&self, path: &str) -> Vec<(DateTime<FixedOffset>, f64)> {
+        self.read_with(path, |line| self.value(line))
+    }
+
+    fn read_with<T, F>(&self, path: &str, extract: F) -> Vec<(DateTime<FixedOffset>, T)>
+    where
+        F: Fn(&str) -> Option<T>,
+    {
+        let mut vec: Vec<(DateTime<FixedOffset>, T)> = Vec::new();
+        let mut iterator = self.input.lines(path);
+        // The parsing strategy is decided with the first line with a timestamp
+        let (first_line, first_ts) = loop {
+            match iterator.next() {
+                Some(Ok(as_string)) => {
+                    if let Some(ts) = self.timestamp_text(&as_string) {
+                        let ts = ts.into_owned();
+                        break (as_string, ts);
+                    }
+                }
+                Some(Err(error)) => {
+                    error!("{}", error);
+                    return vec;
+                }
+                _ => return vec,
             }
-            _ => return vec,
         };
-        let parser = match LogDateParser::new(&first_line, &self.ts_format) {
+        let mut parser = match LogDateParser::new(&first_ts, &self.ts_format, self.base_date) {
             Ok(p) => p,
             Err(error) => {
                 error!("Could not figure out parsing strategy: {}", error);
                 return vec;
             }
         };
+        parser.set_strict_position(self.strict_position);
         let mut cut_datetime: Option<DateTime<FixedOffset>> = None;
-        if let Ok(x) = parser.parse(&first_line) {
+        if let Ok(x) = parser.parse(&first_ts) {
             if self.early_stop {
                 if let Some(duration) = self.duration {
                     cut_datetime = Some(x + duration);
                 }
             }
-            self.push_conditionally(x, &mut vec, &first_line, None);
+            self.push_conditionally(x, &mut vec, &first_line, None, &extract);
         }
         for line in iterator {
             match line {
                 Ok(string) => {
-                    if let Ok(x) = parser.parse(&string) {
-                        if self.push_conditionally(x, &mut vec, &string, cut_datetime) {
+                    let parsed = match self.timestamp_text(&string) {
+                        Some(ts) => parser.parse(&ts),
+                        None => continue,
+                    };
+                    if let Ok(x) = parsed {
+                        if self.push_conditionally(x, &mut vec, &string, cut_datetime, &extract) {
                             break;
                         }
                     }
@@ -60,38 +117,135 @@ impl TimeReader {
         }
         if cut_datetime.is_none() {
             if let Some(duration) = self.duration {
-                if let Some(min) = vec.iter().min() {
-                    let max = *min + duration;
-                    vec.retain(|&d| d <= max);
+                if let Some(min) = vec.iter().map(|(d, _)| *d).min() {
+                    let max = min + duration;
+                    vec.retain(|(d, _)| *d <= max);
                 }
             }
         }
         vec
     }
 
-    fn push_conditionally(
+    /// Returns the text where timestamp should be found: the value of the
+    /// logfmt key, JSON field or regex capture group if requested, or the
+    /// whole line otherwise.
+    fn timestamp_text<'a>(&self, line: &'a str) -> Option<Cow<'a, str>> {
+        if let Some(key) = &self.logfmt {
+            return logfmt::field(line, key);
+        }
+        if let Some(path) = &self.json_field {
+            return json::field(line, path).map(Cow::Owned);
+        }
+        match (&self.ts_capture, &self.regex) {
+            (Some(capture), Some(re)) => re
+                .captures(line)
+                .and_then(|cap| cap.name(capture))
+                .map(|m| Cow::Borrowed(m.as_str())),
+            _ => Some(Cow::Borrowed(line)),
+        }
+    }
+
+    /// Returns the value of the `key` or `value` capture group of the regex in
+    /// the line, or the first capture group (skipping the `ts` one) if there is
+    /// no such group.
+    fn term(&self, line: &str) -> Option<String> {
+        let re = self.regex.as_ref()?;
+        let cap = re.captures(line)?;
+        let term = match cap.name("key").or_else(|| cap.name("value")) {
+            Some(m) => m,
+            None => re
+                .capture_names()
+                .enumerate()
+                .skip(1)
+                .find(|(_, name)| *name != Some("ts"))
+                .and_then(|(i, _)| cap.get(i))?,
+        };
+        Some(term.as_str().to_string())
+    }
+
+    fn value(&self, line: &str) -> Option<f64> {
+        let cap = self.value_regex.as_ref()?.captures(line)?;
+        let value = cap.name("value").or_else(|| cap.get(1))?;
+        match value.as_str().trim().parse::<f64>() {
+            Ok(n) => Some(n),
+            Err(_) => {
+                debug!("Failed to parse value '{}'", value.as_str());
+                None
+            }
+        }
+    }
+
+    fn push_conditionally<T, F>(
         &self,
         d: DateTime<FixedOffset>,
-        vec: &mut Vec<DateTime<FixedOffset>>,
+        vec: &mut Vec<(DateTime<FixedOffset>, T)>,
         line: &str,
         cut_datetime: Option<DateTime<FixedOffset>>,
-    ) -> bool {
+        extract: &F,
+    ) -> bool
+    where
+        F: Fn(&str) -> Option<T>,
+    {
         if let Some(cut) = cut_datetime {
             if cut < d {
                 return self.early_stop;
             }
         }
         if let Some(re) = &self.regex {
-            if re.is_match(line) {
-                vec.push(d);
+            if !re.is_match(line) {
+                return false;
             }
-        } else {
-            vec.push(d);
-        };
+        }
+        if let Some(item) = extract(line) {
+            vec.push((d, item));
+        }
         false
     }
 }
 
+/// Finds the timestamps of lines given one at a time (like the ones fed to the
+/// panels of a dashboard), with the regex, format and other options of a
+/// `TimeReader`.  Like in `TimeReader::read`, the parsing strategy is decided
+/// with the first line with a timestamp.  Durations are not supported.
+pub struct TimeStream {
+    reader: TimeReader,
+    parser: Option<LogDateParser>,
+}
+
+impl TimeStream {
+    /// Creates a `TimeStream` with the options of `reader`.
+    pub fn new(reader: TimeReader) -> Self {
+        Self {
+            reader,
+            parser: None,
+        }
+    }
+
+    /// Returns the timestamp of a line, or None if it has no timestamp or it
+    /// does not match the regex of the reader.
+    pub fn timestamp(&mut self, line: &str) -> Option<DateTime<FixedOffset>> {
+        let text = self.reader.timestamp_text(line)?;
+        if self.parser.is_none() {
+            let reader = &self.reader;
+            match LogDateParser::new(&text, &reader.ts_format, reader.base_date) {
+                Ok(mut parser) => {
+                    parser.set_strict_position(reader.strict_position);
+                    self.parser = Some(parser);
+                }
+                Err(error) => {
+                    debug!("Could not figure out parsing strategy: {}", error);
+                    return None;
+                }
+            }
+        }
+        let ts = self.parser.as_ref()?.parse(&text).ok()?;
+        match &self.reader.regex {
+            Some(re) if !re.is_match(line) => None,
+            _ => Some(ts),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -145,6 +299,136 @@ mod tests {
         );
     }
 
+    #[test]
+    fn time_reader_with_ts_capture() {
+        let mut builder = TimeReaderBuilder::default();
+        builder
+            .regex(Regex::new(r"^\S+ (?P<ts>\S+ \S+) ").unwrap())
+            .ts_capture(String::from("ts"));
+        let reader = builder.build().unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "INFO 2021-04-15 06:25:31 foo").unwrap();
+        writeln!(file, "WARNING 2021-04-15 06:25:32 bar").unwrap();
+        writeln!(file, "garbage").unwrap();
+        writeln!(file, "E 2021-04-15 06:25:33 baz").unwrap();
+        let ts = reader.read(file.path().to_str().unwrap());
+        assert_eq!(
+            ts,
+            vec![
+                DateTime::parse_from_rfc3339("2021-04-15T06:25:31+00:00").unwrap(),
+                DateTime::parse_from_rfc3339("2021-04-15T06:25:32+00:00").unwrap(),
+                DateTime::parse_from_rfc3339("2021-04-15T06:25:33+00:00").unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn time_reader_with_terms() {
+        let mut builder = TimeReaderBuilder::default();
+        builder.regex(Regex::new(r"user=(\w+)").unwrap());
+        let reader = builder.build().unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "2021-04-15T06:25:31+00:00 user=alice").unwrap();
+        writeln!(file, "2021-04-15T06:25:32+00:00 nobody").unwrap();
+        writeln!(file, "2021-04-15T06:25:33+00:00 user=bob").unwrap();
+        let terms = reader.read_terms(file.path().to_str().unwrap());
+        assert_eq!(
+            terms,
+            vec![
+                (
+                    DateTime::parse_from_rfc3339("2021-04-15T06:25:31+00:00").unwrap(),
+                    String::from("alice")
+                ),
+                (
+                    DateTime::parse_from_rfc3339("2021-04-15T06:25:33+00:00").unwrap(),
+                    String::from("bob")
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn time_reader_with_key() {
+        let mut builder = TimeReaderBuilder::default();
+        builder.regex(Regex::new(r"(\w+)=(?P<key>\w+)").unwrap());
+        let reader = builder.build().unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "2021-04-15T06:25:31+00:00 level=error").unwrap();
+        let terms = reader.read_terms(file.path().to_str().unwrap());
+        assert_eq!(terms.len(), 1);
+        assert_eq!(terms[0].1, "error");
+    }
+
+    #[test]
+    fn time_reader_with_values() {
+        let mut builder = TimeReaderBuilder::default();
+        builder
+            .regex(Regex::new("GET").unwrap())
+            .value_regex(Regex::new(r"bytes=(\d+)").unwrap());
+        let reader = builder.build().unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "2021-04-15T06:25:31+00:00 GET bytes=512").unwrap();
+        writeln!(file, "2021-04-15T06:25:32+00:00 POST bytes=128").unwrap();
+        writeln!(file, "2021-04-15T06:25:33+00:00 GET").unwrap();
+        writeln!(file, "2021-04-15T06:25:34+00:00 GET bytes=64").unwrap();
+        let values = reader.read_values(file.path().to_str().unwrap());
+        assert_eq!(
+            values,
+            vec![
+                (
+                    DateTime::parse_from_rfc3339("2021-04-15T06:25:31+00:00").unwrap(),
+                    512.0
+                ),
+                (
+                    DateTime::parse_from_rfc3339("2021-04-15T06:25:34+00:00").unwrap(),
+                    64.0
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn time_reader_with_logfmt() {
+        let mut builder = TimeReaderBuilder::default();
+        builder.logfmt(String::from("ts"));
+        let reader = builder.build().unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "level=info msg=\"starting at 1999-01-01\"").unwrap();
+        writeln!(file, "id=1234 ts=2021-04-15T06:25:31+00:00 level=info").unwrap();
+        writeln!(file, "id=2021 ts=\"2021-04-15T06:26:31+00:00\"").unwrap();
+        let ts = reader.read(file.path().to_str().unwrap());
+        assert_eq!(
+            ts,
+            vec![
+                DateTime::parse_from_rfc3339("2021-04-15T06:25:31+00:00").unwrap(),
+                DateTime::parse_from_rfc3339("2021-04-15T06:26:31+00:00").unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn time_reader_with_json() {
+        let mut builder = TimeReaderBuilder::default();
+        builder.json_field(String::from(".meta.ts"));
+        let reader = builder.build().unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, r#"{{"msg": "starting at 1999-01-01"}}"#).unwrap();
+        writeln!(
+            file,
+            r#"{{"id": 1234, "meta": {{"ts": "2021-04-15T06:25:31+00:00"}}}}"#
+        )
+        .unwrap();
+        writeln!(file, r#"{{"meta": {{"ts": "2021-04-15T06:26:31+00:00"}}}}"#).unwrap();
+        let ts = reader.read(file.path().to_str().unwrap());
+        assert_eq!(
+            ts,
+            vec![
+                DateTime::parse_from_rfc3339("2021-04-15T06:25:31+00:00").unwrap(),
+                DateTime::parse_from_rfc3339("2021-04-15T06:26:31+00:00").unwrap()
+            ]
+        );
+    }
+
     #[test]
     fn time_with_duration() {
         let mut builder = TimeReaderBuilder::default();
@@ -190,6 +474,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn time_reader_with_base_date() {
+        let mut builder = TimeReaderBuilder::default();
+        builder.base_date(NaiveDate::from_ymd_opt(2021, 4, 15).unwrap());
+        let reader = builder.build().unwrap();
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "23:59:59 foo").unwrap();
+        writeln!(file, "00:00:01 foo").unwrap();
+        let ts = reader.read(file.path().to_str().unwrap());
+        assert_eq!(ts.len(), 2);
+        assert_eq!(
+            ts[0],
+            DateTime::parse_from_rfc3339("2021-04-15T23:59:59+00:00").unwrap()
+        );
+        assert_eq!(
+            ts[1],
+            DateTime::parse_from_rfc3339("2021-04-16T00:00:01+00:00").unwrap()
+        );
+    }
+
     #[test]
     fn time_reader_with_bad_format() {
         let mut builder = TimeReaderBuilder::default();
@@ -217,4 +521,20 @@ mod tests {
         let ts = reader.read(file.path().to_str().unwrap());
         assert_eq!(ts.len(), 0);
     }
+    #[test]
+    fn time_stream() {
+        let mut builder = TimeReaderBuilder::default();
+        builder.regex(Regex::new("f.o").unwrap());
+        let mut stream = TimeStream::new(builder.build().unwrap());
+        assert_eq!(stream.timestamp("garbage"), None);
+        assert_eq!(
+            stream.timestamp("[2021-04-15T06:25:31+00:00] foobar"),
+            Some(DateTime::parse_from_rfc3339("2021-04-15T06:25:31+00:00").unwrap())
+        );
+        assert_eq!(stream.timestamp("[2021-04-15T06:26:31+00:00] bar"), None);
+        assert_eq!(
+            stream.timestamp("[2021-04-15T06:27:31+00:00] foobar"),
+            Some(DateTime::parse_from_rfc3339("2021-04-15T06:27:31+00:00").unwrap())
+        );
+    }
 }
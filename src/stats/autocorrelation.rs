@@ -0,0 +1,46 @@
+/// Computes the autocorrelation of a series of values (like the counts of the
+/// buckets of a time histogram) for every lag from zero up to `max_lag`
+/// (capped to the length of the series minus one).
+///
+/// Autocorrelations are in the [-1, 1] range, the one for lag zero being 1.
+/// A series with no variance has no meaningful autocorrelation, so an empty
+/// vector is returned for it.
+pub fn autocorrelation(series: &[f64], max_lag: usize) -> Vec<f64> {
+    if series.is_empty() {
+        return Vec::new();
+    }
+    let mean = series.iter().sum::<f64>() / series.len() as f64;
+    let deviations: Vec<f64> = series.iter().map(|x| x - mean).collect();
+    let variance: f64 = deviations.iter().map(|d| d * d).sum();
+    if variance == 0.0 {
+        return Vec::new();
+    }
+    (0..=max_lag.min(series.len() - 1))
+        .map(|lag| {
+            deviations
+                .iter()
+                .zip(&deviations[lag..])
+                .map(|(a, b)| a * b)
+                .sum::<f64>()
+                / variance
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_autocorrelation() {
+        assert!(autocorrelation(&[], 3).is_empty());
+        assert!(autocorrelation(&[2.0, 2.0, 2.0], 3).is_empty());
+        let acf = autocorrelation(&[1.0, 0.0, 1.0, 0.0, 1.0, 0.0], 10);
+        assert_eq!(acf.len(), 6);
+        assert_float_eq!(acf[0], 1.0, abs <= 1e-9);
+        assert_float_eq!(acf[1], -5.0 / 6.0, abs <= 1e-9);
+        assert_float_eq!(acf[2], 4.0 / 6.0, abs <= 1e-9);
+    }
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+
+use crate::format::{F64Formatter, Theme};
+use crate::plot::Render;
+use crate::stats::Stats;
+
+/// Statistics compared with the ones of a baseline, in display order.
+const FIELDS: &[&str] = &[
+    "samples", "min", "max", "avg", "std", "p50", "p90", "p95", "p99",
+];
+
+/// The statistics of a previous run (as written by `lowcharts summary
+/// --output-format json`), for comparing the current ones with them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Baseline {
+    values: Vec<(&'static str, f64)>,
+}
+
+impl Baseline {
+    /// Parses the statistics written as JSON by a previous run.  Statistics
+    /// missing in it are not compared.
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let value: serde_json::Value =
+            serde_json::from_str(text).map_err(|error| format!("Bad JSON: {error}"))?;
+        let values: Vec<(&'static str, f64)> = FIELDS
+            .iter()
+            .filter_map(|field| value.get(field)?.as_f64().map(|x| (*field, x)))
+            .collect();
+        if values.is_empty() {
+            return Err(String::from("No statistics found in the baseline"));
+        }
+        Ok(Self { values })
+    }
+
+    /// Compares `stats` with the baseline, considering regressions the
+    /// statistics growing more than `tolerance` (a fraction, like 0.05).  The
+    /// number of samples is compared, but it is never a regression.
+    pub fn compare(&self, stats: &Stats, tolerance: f64) -> BaselineComparison {
+        let current = stats.to_json();
+        let deltas = self
+            .values
+            .iter()
+            .map(|(field, before)| Delta {
+                field,
+                before: *before,
+                after: current[field].as_f64().unwrap(),
+            })
+            .collect();
+        BaselineComparison {
+            deltas,
+            tolerance,
+            precision: stats.precision,
+        }
+    }
+}
+
+/// The change of a statistic since a baseline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delta {
+    /// Name of the statistic, like `p99`.
+    pub field: &'static str,
+    /// Value in the baseline.
+    pub before: f64,
+    /// Current value.
+    pub after: f64,
+}
+
+impl Delta {
+    /// Returns the change as a fraction of the value in the baseline (None if
+    /// it was zero).
+    pub fn relative(&self) -> Option<f64> {
+        match self.before {
+            0.0 => None,
+            x => Some((self.after - x) / x.abs()),
+        }
+    }
+
+    fn is_regression(&self, tolerance: f64) -> bool {
+        if self.field == "samples" || self.after <= self.before {
+            return false;
+        }
+        // Anything growing from zero is a regression
+        !matches!(self.relative(), Some(relative) if relative <= tolerance)
+    }
+}
+
+/// The statistics of the input compared with the ones of a baseline.
+#[derive(Debug)]
+pub struct BaselineComparison {
+    deltas: Vec<Delta>,
+    tolerance: f64,
+    precision: Option<usize>,
+}
+
+impl BaselineComparison {
+    /// Returns the statistics that grew beyond the tolerance.
+    pub fn regressions(&self) -> Vec<&Delta> {
+        self.deltas
+            .iter()
+            .filter(|delta| delta.is_regression(self.tolerance))
+            .collect()
+    }
+
+    /// Returns the comparison as a JSON object, with an entry per statistic.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut value = serde_json::json!({});
+        for delta in &self.deltas {
+            value[delta.field] = serde_json::json!({
+                "before": delta.before,
+                "after": delta.after,
+                "delta": delta.after - delta.before,
+                "relative": delta.relative(),
+                "regression": delta.is_regression(self.tolerance),
+            });
+        }
+        value
+    }
+}
+
+impl fmt::Display for BaselineComparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
+        let values = self
+            .deltas
+            .iter()
+            .filter(|delta| delta.field != "samples")
+            .flat_map(|delta| [delta.before, delta.after]);
+        let formatter = match self.precision {
+            None => {
+                let min = values.clone().fold(f64::INFINITY, f64::min);
+                let max = values.fold(f64::NEG_INFINITY, f64::max);
+                F64Formatter::new_with_range(min.min(max)..max.max(min))
+            }
+            Some(n) => F64Formatter::new(n),
+        };
+        let format = |delta: &Delta, x: f64| match delta.field {
+            "samples" => format!("{x}"),
+            _ => formatter.format(x),
+        };
+        let rows: Vec<(String, String, String)> = self
+            .deltas
+            .iter()
+            .map(|delta| {
+                let change = delta.after - delta.before;
+                let sign = if change >= 0.0 { "+" } else { "-" };
+                (
+                    format(delta, delta.before),
+                    format(delta, delta.after),
+                    format!("{}{}", sign, format(delta, change.abs())),
+                )
+            })
+            .collect();
+        let width = |column: fn(&(String, String, String)) -> &String| {
+            rows.iter().map(|row| column(row).len()).max().unwrap_or(0)
+        };
+        let (w1, w2, w3) = (width(|r| &r.0), width(|r| &r.1), width(|r| &r.2));
+        writeln!(
+            f,
+            "Compared with baseline (tolerance {}%):",
+            (self.tolerance * 1000.0).round() / 10.0
+        )?;
+        for (delta, (before, after, change)) in self.deltas.iter().zip(rows) {
+            let relative = match delta.relative() {
+                Some(relative) => format!("{:+.1}%", relative * 100.0),
+                None => String::from("n/a"),
+            };
+            write!(
+                f,
+                "{:<7} {:>w1$} -> {:>w2$} {} ({})",
+                delta.field,
+                before,
+                theme.label.paint(format!("{after:>w2$}")),
+                theme.count.paint(format!("{change:>w3$}")),
+                relative,
+            )?;
+            if delta.is_regression(self.tolerance) {
+                write!(f, " {}", theme.bar.paint("regression"))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Render for BaselineComparison {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yansi::Paint;
+
+    #[test]
+    fn test_baseline() {
+        let before = Stats::new(&mut [1.0, 2.0, 3.0, 4.0], None);
+        let baseline = Baseline::from_json(&before.to_json().to_string()).unwrap();
+        assert_eq!(baseline.values.len(), FIELDS.len());
+        let after = Stats::new(&mut [1.0, 2.0, 3.0, 4.05, 5.0], Some(2));
+        let comparison = baseline.compare(&after, 0.05);
+        let regressions: Vec<&str> = comparison.regressions().iter().map(|d| d.field).collect();
+        assert_eq!(regressions, vec!["max", "avg", "std", "p90", "p95", "p99"]);
+        Paint::disable();
+        let display = format!("{comparison}");
+        assert!(display.starts_with("Compared with baseline (tolerance 5%):\n"));
+        assert!(display.contains("samples    4 ->    5    +1 (+25.0%)\n"));
+        assert!(display.contains("min     1.00 -> 1.00 +0.00 (+0.0%)\n"));
+        assert!(display.contains("max     4.00 -> 5.00 +1.00 (+25.0%) regression\n"));
+        let value = comparison.to_json();
+        assert_eq!(value["max"]["delta"], 1.0);
+        assert_eq!(value["max"]["regression"], true);
+        assert_eq!(value["min"]["regression"], false);
+    }
+
+    #[test]
+    fn test_bad_baseline() {
+        assert!(Baseline::from_json("foo").is_err());
+        assert!(Baseline::from_json(r#"{"foo": 1}"#).is_err());
+        let baseline = Baseline::from_json(r#"{"p99": 0, "max": "a"}"#).unwrap();
+        assert_eq!(baseline.values, vec![("p99", 0.0)]);
+        let after = Stats::new(&mut [1.0], None);
+        assert_eq!(baseline.compare(&after, 0.1).regressions().len(), 1);
+    }
+}
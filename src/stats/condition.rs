@@ -0,0 +1,215 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::stats::Stats;
+
+// Longest operators first, so that ">=" is not parsed as ">"
+const OPERATORS: &[&str] = &[">=", "<=", "==", "!=", ">", "<"];
+
+const FIELDS: &[&str] = &[
+    "min",
+    "max",
+    "avg",
+    "std",
+    "var",
+    "samples",
+    "outliers",
+    "trimmed_avg",
+    "trimmed_std",
+];
+
+/// One side of a condition: a statistic or a number.
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Field(String),
+    Percentile(f64),
+    Number(f64),
+}
+
+impl Operand {
+    fn parse(s: &str, condition: &str) -> Result<Self, String> {
+        if let Ok(value) = s.parse::<f64>() {
+            return Ok(Self::Number(value));
+        }
+        if FIELDS.contains(&s) {
+            return Ok(Self::Field(s.to_string()));
+        }
+        match s.strip_prefix('p').map(|p| p.parse::<f64>()) {
+            Some(Ok(p)) if (0.0..=100.0).contains(&p) && (p * 10.0).fract() == 0.0 => {
+                Ok(Self::Percentile(p))
+            }
+            Some(Ok(_)) => Err(format!(
+                "Bad percentile '{s}' in '{condition}' (from p0 to p100, in steps of 0.1)"
+            )),
+            _ => Err(format!(
+                "Unknown field '{}' in '{}' (available: {}, or a percentile like p99.9)",
+                s,
+                condition,
+                FIELDS.join(", ")
+            )),
+        }
+    }
+
+    // The value in `stats`, if they have it
+    fn value(&self, stats: &Stats) -> Option<f64> {
+        let value = match self {
+            Self::Number(value) => *value,
+            Self::Percentile(p) => stats.percentile(*p),
+            Self::Field(field) => match field.as_str() {
+                "min" => stats.min,
+                "max" => stats.max,
+                "avg" => stats.avg,
+                "std" => stats.std,
+                "var" => stats.var,
+                "samples" => stats.samples as f64,
+                "outliers" => stats.outliers.as_ref()?.count as f64,
+                "trimmed_avg" => stats.trimmed.as_ref()?.avg,
+                "trimmed_std" => stats.trimmed.as_ref()?.std,
+                _ => unreachable!("Unknown field"),
+            },
+        };
+        Some(value)
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Field(field) => write!(f, "{field}"),
+            Self::Percentile(p) => write!(f, "p{p}"),
+            Self::Number(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// A comparison between a statistic (like `p99` or `avg`) and a number or
+/// another statistic, like `p99 > 250` or `p99.9 < 10 * p50`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    left: Operand,
+    operator: &'static str,
+    factor: Option<f64>,
+    right: Operand,
+}
+
+impl Condition {
+    /// Returns the value of the statistic of the condition in `stats`, or
+    /// None if it is one not computed for them (like `outliers`, when no
+    /// outlier detection was requested).
+    pub fn field_value(&self, stats: &Stats) -> Option<f64> {
+        self.left.value(stats)
+    }
+
+    /// Returns the value the statistic of the condition is compared with in
+    /// `stats`, or None if it is one not computed for them.
+    pub fn threshold(&self, stats: &Stats) -> Option<f64> {
+        Some(self.factor.unwrap_or(1.0) * self.right.value(stats)?)
+    }
+
+    /// Returns whether the condition holds for `stats`, or None if it uses
+    /// statistics not computed for them.
+    pub fn holds(&self, stats: &Stats) -> Option<bool> {
+        let x = self.field_value(stats)?;
+        let y = self.threshold(stats)?;
+        Some(match self.operator {
+            ">=" => x >= y,
+            "<=" => x <= y,
+            "==" => x == y,
+            "!=" => x != y,
+            ">" => x > y,
+            "<" => x < y,
+            _ => unreachable!("Unknown operator"),
+        })
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    /// Parses a condition like `p99 > 250`.  Fields are min, max, avg, std,
+    /// var, samples, outliers, trimmed_avg, trimmed_std and any percentile
+    /// (like p50 or p99.9).  The right side can also be a statistic, or a
+    /// number times a statistic, like `p99 < 3 * p50`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (index, operator) = OPERATORS
+            .iter()
+            .filter_map(|op| s.find(op).map(|index| (index, *op)))
+            .min_by_key(|(index, op)| (*index, usize::MAX - op.len()))
+            .ok_or(format!("Missing comparison operator in '{s}'"))?;
+        let left = match Operand::parse(s[..index].trim(), s)? {
+            Operand::Number(value) => return Err(format!("Bad field '{value}' in '{s}'")),
+            operand => operand,
+        };
+        let right = s[index + operator.len()..].trim();
+        let (factor, right) = match right.split_once('*') {
+            Some((factor, right)) => match factor.trim().parse::<f64>() {
+                Ok(factor) => (Some(factor), right.trim()),
+                Err(_) => return Err(format!("Bad number '{}' in '{}'", factor.trim(), s)),
+            },
+            None => (None, right),
+        };
+        let right = match Operand::parse(right, s) {
+            Ok(Operand::Number(_)) if factor.is_some() => {
+                return Err(format!("Bad field '{right}' in '{s}'"))
+            }
+            Ok(operand) => operand,
+            Err(_) if factor.is_none() && !right.starts_with(char::is_alphabetic) => {
+                return Err(format!("Bad number '{right}' in '{s}'"))
+            }
+            Err(error) => return Err(error),
+        };
+        Ok(Self {
+            left,
+            operator,
+            factor,
+            right,
+        })
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} ", self.left, self.operator)?;
+        if let Some(factor) = self.factor {
+            write!(f, "{factor} * ")?;
+        }
+        write!(f, "{}", self.right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let condition = Condition::from_str("p99 > 250").unwrap();
+        assert_eq!(condition.to_string(), "p99 > 250");
+        let condition = Condition::from_str(" avg>=-1.5").unwrap();
+        assert_eq!(condition.to_string(), "avg >= -1.5");
+        let condition = Condition::from_str("p99.9<3*p50").unwrap();
+        assert_eq!(condition.to_string(), "p99.9 < 3 * p50");
+        assert!(Condition::from_str("p99 250").is_err());
+        assert!(Condition::from_str("p101 > 250").is_err());
+        assert!(Condition::from_str("p99.99 > 250").is_err());
+        assert!(Condition::from_str("foo > 250").is_err());
+        assert!(Condition::from_str("p99 > foo").is_err());
+        assert!(Condition::from_str("p99 > 3 * 4").is_err());
+        assert!(Condition::from_str("250 > p99").is_err());
+    }
+
+    #[test]
+    fn test_holds() {
+        let stats = Stats::new(&mut [1.0, 2.0, 3.0, 4.0], None);
+        let holds = |s: &str| Condition::from_str(s).unwrap().holds(&stats);
+        assert_eq!(holds("max >= 4"), Some(true));
+        assert_eq!(holds("max > 4"), Some(false));
+        assert_eq!(holds("samples == 4"), Some(true));
+        assert_eq!(holds("min != 2"), Some(true));
+        assert_eq!(holds("avg < 3"), Some(true));
+        assert_eq!(holds("p50 <= 2"), Some(false));
+        assert_eq!(holds("p100 == max"), Some(true));
+        assert_eq!(holds("max <= 4 * min"), Some(true));
+        assert_eq!(holds("outliers > 0"), None);
+    }
+}
@@ -0,0 +1,130 @@
+/// Confidence interval of the average of the population the values are a
+/// sample of.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConfidenceInterval {
+    /// Confidence level, like 0.95.
+    pub level: f64,
+    /// Lower bound of the interval.
+    pub low: f64,
+    /// Upper bound of the interval.
+    pub high: f64,
+}
+
+impl ConfidenceInterval {
+    /// Computes the interval, at `level` (between 0 and 1), for the average
+    /// given the average, the (population) standard deviation and the number
+    /// of samples of the values, or None if there are not enough samples.
+    ///
+    /// The interval uses Student's t-distribution, so it is sound for small
+    /// samples of normally distributed values, and for big samples of almost
+    /// anything.
+    pub fn for_mean(avg: f64, std: f64, samples: usize, level: f64) -> Option<Self> {
+        if samples < 2 {
+            return None;
+        }
+        let n = samples as f64;
+        // Sample standard deviation, from the population one
+        let sample_std = std * (n / (n - 1.0)).sqrt();
+        let t = t_quantile(0.5 + level / 2.0, n - 1.0);
+        let margin = t * sample_std / n.sqrt();
+        Some(Self {
+            level,
+            low: avg - margin,
+            high: avg + margin,
+        })
+    }
+}
+
+/// Approximates the quantile function of the standard normal distribution,
+/// with a relative error below 1.2e-9 (Acklam's algorithm).
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e+01,
+        2.209460984245205e+02,
+        -2.759285104469687e+02,
+        1.38357751867269e+02,
+        -3.066479806614716e+01,
+        2.506628277459239e+00,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e+01,
+        1.615858368580409e+02,
+        -1.556989798598866e+02,
+        6.680131188771972e+01,
+        -1.328068155288572e+01,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-03,
+        -3.223964580411365e-01,
+        -2.400758277161838e+00,
+        -2.549732539343734e+00,
+        4.374664141464968e+00,
+        2.938163982698783e+00,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-03,
+        3.224671290700398e-01,
+        2.445134137142996e+00,
+        3.754408661907416e+00,
+    ];
+    const LOW: f64 = 0.02425;
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+/// Approximates the quantile function of Student's t-distribution with `df`
+/// degrees of freedom, expanding it around the normal one (Cornish-Fisher).
+/// Precision is good for 3 or more degrees of freedom; the exact values are
+/// used for less.
+fn t_quantile(p: f64, df: f64) -> f64 {
+    if df == 1.0 {
+        return (std::f64::consts::PI * (p - 0.5)).tan();
+    }
+    if df == 2.0 {
+        let a = 4.0 * p * (1.0 - p);
+        return 2.0 * (p - 0.5) * (2.0 / a).sqrt();
+    }
+    let z = normal_quantile(p);
+    let (z3, z5, z7) = (z.powi(3), z.powi(5), z.powi(7));
+    z + (z3 + z) / (4.0 * df)
+        + (5.0 * z5 + 16.0 * z3 + 3.0 * z) / (96.0 * df.powi(2))
+        + (3.0 * z7 + 19.0 * z5 + 17.0 * z3 - 15.0 * z) / (384.0 * df.powi(3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_quantiles() {
+        assert_float_eq!(normal_quantile(0.975), 1.95996, abs <= 0.00001);
+        assert_float_eq!(normal_quantile(0.005), -2.57583, abs <= 0.00001);
+        assert_float_eq!(t_quantile(0.975, 1.0), 12.7062, abs <= 0.0001);
+        assert_float_eq!(t_quantile(0.975, 2.0), 4.3027, abs <= 0.0001);
+        assert_float_eq!(t_quantile(0.975, 9.0), 2.2622, abs <= 0.001);
+        assert_float_eq!(t_quantile(0.995, 30.0), 2.7500, abs <= 0.001);
+    }
+
+    #[test]
+    fn test_for_mean() {
+        assert!(ConfidenceInterval::for_mean(1.0, 0.0, 1, 0.95).is_none());
+        let interval = ConfidenceInterval::for_mean(10.0, 3.0, 10, 0.95).unwrap();
+        // Sample std is 3.1623, so the margin is 2.2622 * 3.1623 / 3.1623
+        assert_float_eq!(interval.low, 7.7378, abs <= 0.001);
+        assert_float_eq!(interval.high, 12.2622, abs <= 0.001);
+    }
+}
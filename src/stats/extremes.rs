@@ -0,0 +1,243 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fmt;
+
+use crate::format::{F64Formatter, Theme};
+use crate::plot::Render;
+
+// Max number of chars of input text displayed for every value
+const MAX_TEXT_LEN: usize = 60;
+
+#[derive(Debug, Clone)]
+/// A numerical value, along with the location of the input where it was found.
+pub struct LocatedValue {
+    /// The value itself.
+    pub value: f64,
+    /// Line number (starting at 1) of the input.
+    pub line: usize,
+    /// Byte offset in the input.
+    pub offset: u64,
+    /// Text of the input where the value was found.
+    pub text: String,
+}
+
+// A located value ordered by its value, for keeping them in heaps
+#[derive(Debug, Clone)]
+struct Entry(LocatedValue);
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.value.total_cmp(&other.0.value)
+    }
+}
+
+#[derive(Debug)]
+/// A struct keeping track, in bounded memory, of where the smallest and the
+/// biggest values of a stream of numerical data were found.
+pub struct Extremes {
+    top_size: usize,
+    bottom_size: usize,
+    // Min-heap with the biggest values
+    top: BinaryHeap<Reverse<Entry>>,
+    // Max-heap with the smallest values
+    bottom: BinaryHeap<Entry>,
+    precision: Option<usize>,
+}
+
+impl Extremes {
+    /// Creates an empty `Extremes`.
+    ///
+    /// `top_size` and `bottom_size` are the number of biggest and smallest
+    /// values to keep track of (the first of them being the maximum and the
+    /// minimum, respectively).  `precision` is an Option with the number of
+    /// decimals to display.  If "None" is used, human units will be used.
+    pub fn new(top_size: usize, bottom_size: usize, precision: Option<usize>) -> Self {
+        let top_size = top_size.max(1);
+        let bottom_size = bottom_size.max(1);
+        Self {
+            top_size,
+            bottom_size,
+            top: BinaryHeap::with_capacity(top_size + 1),
+            bottom: BinaryHeap::with_capacity(bottom_size + 1),
+            precision,
+        }
+    }
+
+    /// Observe a new value, found in `text` at the given location.
+    pub fn observe(&mut self, value: f64, line: usize, offset: u64, text: &str) {
+        let located = || {
+            Entry(LocatedValue {
+                value,
+                line,
+                offset,
+                text: text.to_string(),
+            })
+        };
+        if self.top.len() < self.top_size
+            || self.top.peek().is_some_and(|min| value > min.0 .0.value)
+        {
+            self.top.push(Reverse(located()));
+            if self.top.len() > self.top_size {
+                self.top.pop();
+            }
+        }
+        if self.bottom.len() < self.bottom_size
+            || self.bottom.peek().is_some_and(|max| value < max.0.value)
+        {
+            self.bottom.push(located());
+            if self.bottom.len() > self.bottom_size {
+                self.bottom.pop();
+            }
+        }
+    }
+
+    /// Returns the biggest values, biggest first.
+    fn top(&self) -> Vec<LocatedValue> {
+        let sorted = self.top.clone().into_sorted_vec();
+        sorted.into_iter().map(|entry| entry.0 .0).collect()
+    }
+
+    /// Returns the smallest values, smallest first.
+    fn bottom(&self) -> Vec<LocatedValue> {
+        let sorted = self.bottom.clone().into_sorted_vec();
+        sorted.into_iter().map(|entry| entry.0).collect()
+    }
+
+    fn write_value(
+        &self,
+        f: &mut fmt::Formatter,
+        label: &str,
+        located: &LocatedValue,
+        formatter: &F64Formatter,
+    ) -> fmt::Result {
+        let theme = Theme::current();
+        let first_line = located.text.lines().next().unwrap_or("").trim();
+        let text = if first_line.chars().count() > MAX_TEXT_LEN {
+            let truncated: String = first_line.chars().take(MAX_TEXT_LEN - 3).collect();
+            format!("{truncated}...")
+        } else {
+            first_line.to_string()
+        };
+        writeln!(
+            f,
+            "{label} = {value} at line {line} (byte {offset}): {text}",
+            value = theme.label.paint(formatter.format(located.value)),
+            line = theme.label.paint(located.line),
+            offset = located.offset,
+        )
+    }
+}
+
+impl fmt::Display for Extremes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (top, bottom) = (self.top(), self.bottom());
+        if top.is_empty() {
+            return Ok(());
+        }
+        let formatter = match self.precision {
+            None => F64Formatter::new_with_range(bottom[0].value..top[0].value),
+            Some(n) => F64Formatter::new(n),
+        };
+        writeln!(f, "Location of extreme values:")?;
+        for (i, located) in bottom.iter().enumerate() {
+            let label = match i {
+                0 => String::from("Min"),
+                _ => format!("Bottom {}", i + 1),
+            };
+            self.write_value(f, &label, located, &formatter)?;
+        }
+        for (i, located) in top.iter().enumerate() {
+            let label = match i {
+                0 => String::from("Max"),
+                _ => format!("Top {}", i + 1),
+            };
+            self.write_value(f, &label, located, &formatter)?;
+        }
+        Ok(())
+    }
+}
+
+impl Render for Extremes {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yansi::Paint;
+
+    #[test]
+    fn test_extremes() {
+        let mut extremes = Extremes::new(3, 1, Some(1));
+        for (i, value) in [5.0, 1.0, 7.0, 3.0, 9.0, 0.5, 8.0].iter().enumerate() {
+            extremes.observe(*value, i + 1, i as u64 * 10, &format!("  value {value}"));
+        }
+        assert_eq!(
+            extremes
+                .bottom()
+                .iter()
+                .map(|v| v.value)
+                .collect::<Vec<f64>>(),
+            vec![0.5]
+        );
+        assert_eq!(
+            extremes.top().iter().map(|v| v.value).collect::<Vec<f64>>(),
+            vec![9.0, 8.0, 7.0]
+        );
+        Paint::disable();
+        let display = format!("{extremes}");
+        assert!(!display.contains("Bottom"));
+        assert!(display.contains("Min = 0.5 at line 6 (byte 50): value 0.5\n"));
+        assert!(display.contains("Max = 9.0 at line 5 (byte 40): value 9\n"));
+        assert!(display.contains("Top 2 = 8.0 at line 7 (byte 60): value 8\n"));
+        assert!(display.contains("Top 3 = 7.0 at line 3 (byte 20): value 7\n"));
+    }
+
+    #[test]
+    fn test_extremes_bottom() {
+        let mut extremes = Extremes::new(2, 3, Some(0));
+        for (i, value) in [5.0, 1.0, 7.0, 3.0, 1.0, 9.0, 0.5].iter().enumerate() {
+            extremes.observe(*value, i + 1, i as u64, &format!("v{i}"));
+        }
+        assert_eq!(
+            extremes
+                .bottom()
+                .iter()
+                .map(|v| v.value)
+                .collect::<Vec<f64>>(),
+            vec![0.5, 1.0, 1.0]
+        );
+        assert_eq!(
+            extremes.top().iter().map(|v| v.value).collect::<Vec<f64>>(),
+            vec![9.0, 7.0]
+        );
+        Paint::disable();
+        let display = format!("{extremes}");
+        assert!(display.contains("\nMin = 0 at line 7 (byte 6): v6\nBottom 2 = 1 at line"));
+        assert!(display.contains("\nBottom 3 = 1 at line"));
+        assert!(display.contains("\nMax = 9 at line 6 (byte 5): v5\nTop 2 = 7 at line 3"));
+    }
+
+    #[test]
+    fn test_extremes_long_text() {
+        let mut extremes = Extremes::new(1, 1, Some(0));
+        assert_eq!(format!("{extremes}"), "");
+        extremes.observe(1.0, 1, 0, &format!("{}\nsecond line", "x".repeat(100)));
+        Paint::disable();
+        let display = format!("{extremes}");
+        assert!(display.contains(&format!("(byte 0): {}...\n", "x".repeat(57))));
+        assert!(!display.contains("second line"));
+    }
+}
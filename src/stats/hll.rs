@@ -0,0 +1,142 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::format::Theme;
+use crate::plot::{Render, Table};
+
+// Number of bits of the hash used for choosing a register, by default.  That
+// means 16K registers (16KB of memory) and a standard error of ~0.8%.
+const DEFAULT_PRECISION: u8 = 14;
+
+#[derive(Debug, Clone)]
+/// A HyperLogLog sketch, for estimating the number of distinct elements of a
+/// stream of data in constant memory.
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_PRECISION)
+    }
+}
+
+impl HyperLogLog {
+    /// Creates an empty `HyperLogLog`.
+    ///
+    /// `precision` is the number of bits (between 4 and 16) of the hashes of
+    /// the elements used for selecting one of the 2^precision registers.
+    /// More registers mean more accurate estimations.
+    pub fn new(precision: u8) -> Self {
+        let precision = precision.clamp(4, 16);
+        Self {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    /// Observe a new element.
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        let hash = hasher.finish();
+        let index = (hash >> (64 - self.precision)) as usize;
+        // Position of the leftmost 1 in the rest of bits (a sentinel bit
+        // bounds it when they are all zeros)
+        let rest = (hash << self.precision) | (1 << (self.precision - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    /// Returns the estimated number of distinct elements observed.
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|r| 2_f64.powi(-(*r as i32)))
+            .sum();
+        let estimate = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            // Linear counting is more accurate for small cardinalities
+            m * (m / zeros as f64).ln()
+        } else {
+            estimate
+        }
+    }
+
+    /// Returns the relative standard error of the estimations.
+    pub fn standard_error(&self) -> f64 {
+        1.04 / (self.registers.len() as f64).sqrt()
+    }
+}
+
+impl fmt::Display for HyperLogLog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
+        writeln!(
+            f,
+            "Distinct values: ~{} (standard error {:.1}%)",
+            theme.label.paint(format!("{:.0}", self.estimate())),
+            self.standard_error() * 100.0
+        )
+    }
+}
+
+impl Render for HyperLogLog {
+    fn table(&self) -> Option<Table> {
+        let mut table = Table::new(&["distinct", "standard_error"]);
+        table.push(vec![
+            self.estimate().round().into(),
+            self.standard_error().into(),
+        ]);
+        Some(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yansi::Paint;
+
+    #[test]
+    fn test_hll() {
+        let mut hll = HyperLogLog::default();
+        assert_eq!(hll.estimate(), 0.0);
+        for i in 0..10 {
+            hll.insert(&format!("term{}", i % 5));
+        }
+        assert_eq!(hll.estimate().round(), 5.0);
+        assert_eq!(
+            hll.table().unwrap().rows[0][0],
+            serde_json::Value::from(5.0)
+        );
+        for i in 0..100_000 {
+            hll.insert(&format!("term{i}"));
+        }
+        let error = (hll.estimate() - 100_000.0).abs() / 100_000.0;
+        assert!(error < 3.0 * hll.standard_error());
+        Paint::disable();
+        assert!(format!("{hll}").ends_with(" (standard error 0.8%)\n"));
+    }
+
+    #[test]
+    fn test_hll_precision() {
+        let mut hll = HyperLogLog::new(1);
+        assert_eq!(hll.registers.len(), 16);
+        for i in 0..1000 {
+            hll.insert(&i);
+        }
+        let error = (hll.estimate() - 1000.0).abs() / 1000.0;
+        assert!(error < 3.0 * hll.standard_error());
+    }
+}
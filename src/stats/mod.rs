@@ -1,10 +1,34 @@
 use std::fmt;
 
-use yansi::Color::Blue;
+use crate::format::{F64Formatter, Theme};
+use crate::plot::{Render, Table};
 
-use crate::format::F64Formatter;
+pub use self::autocorrelation::autocorrelation;
+pub use self::baseline::{Baseline, BaselineComparison};
+pub use self::condition::Condition;
+pub use self::confidence::ConfidenceInterval;
+pub use self::extremes::Extremes;
+pub use self::hll::HyperLogLog;
+pub use self::outliers::{OutlierMethod, Outliers};
+pub use self::peaks::find_peaks;
+pub use self::sketch::QuantileSketch;
+pub use self::transform::Transform;
+pub use self::trim::Trimmed;
 
-#[derive(Debug)]
+pub mod autocorrelation;
+pub mod baseline;
+pub mod condition;
+pub mod confidence;
+pub mod extremes;
+pub mod hll;
+pub mod outliers;
+pub mod peaks;
+pub mod sketch;
+pub mod transform;
+pub mod trim;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 /// A struct holding statistical data regarding a unsorted set of numerical
 /// values.
 pub struct Stats {
@@ -20,6 +44,7 @@ pub struct Stats {
     pub var: f64,
     /// Number of samples of the input values.
     pub samples: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
     precision: Option<usize>, // If None, then human friendly display will be used
 
     /// 50 percentile
@@ -30,8 +55,29 @@ pub struct Stats {
     pub p95: f64,
     /// 99 percentile
     pub p99: f64,
+    // A value and its percentile rank, if requested
+    rank: Option<(f64, f64)>,
+    // Every percentile, from 0 to 100 in steps of 0.1
+    #[cfg_attr(feature = "serde", serde(skip))]
+    quantiles: Vec<f64>,
+    // Replaces the quantiles once values are added one by one
+    #[cfg_attr(feature = "serde", serde(skip))]
+    sketch: Option<QuantileSketch>,
+    /// Values out of the fences of an outlier detection method, if requested.
+    pub outliers: Option<Outliers>,
+    /// Statistics excluding the tails of the values, if requested.
+    pub trimmed: Option<Trimmed>,
+    /// Confidence interval of the average, if requested (and there are at
+    /// least two samples).
+    pub confidence: Option<ConfidenceInterval>,
 }
 
+/// Number of outliers displayed along with the statistics
+const TOP_OUTLIERS: usize = 5;
+
+/// Number of steps between percentiles 0 and 100 kept in the statistics
+const QUANTILE_STEPS: usize = 1000;
+
 fn percentiles(vec: &mut [f64]) -> (f64, f64, f64, f64) {
     vec.sort_by(|a, b| a.partial_cmp(b).unwrap());
 
@@ -44,6 +90,64 @@ fn percentiles(vec: &mut [f64]) -> (f64, f64, f64, f64) {
     (p50, p90, p95, p99)
 }
 
+// Every percentile in steps of 0.1 of sorted values
+fn quantiles(sorted: &[f64]) -> Vec<f64> {
+    let len = sorted.len();
+    (0..=QUANTILE_STEPS)
+        .map(|step| sorted[((len * step) / QUANTILE_STEPS).min(len - 1)])
+        .collect()
+}
+
+// Like `percentiles`, for values with a count of occurrences each
+fn weighted_percentiles(pairs: &mut [(f64, usize)], samples: usize) -> (f64, f64, f64, f64) {
+    pairs.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let nth = |index: usize| {
+        let mut seen = 0;
+        for (value, count) in pairs.iter() {
+            seen += count;
+            if seen > index {
+                return *value;
+            }
+        }
+        pairs[pairs.len() - 1].0
+    };
+    (
+        nth(samples / 2),
+        nth((samples * 9) / 10),
+        nth((samples * 95) / 100),
+        nth((samples * 99) / 100),
+    )
+}
+
+// Like `quantiles`, for sorted values with a count of occurrences each
+fn weighted_quantiles(sorted: &[(f64, usize)], samples: usize) -> Vec<f64> {
+    let mut quantiles = Vec::with_capacity(QUANTILE_STEPS + 1);
+    let mut pairs = sorted.iter();
+    let (mut value, mut seen) = (sorted[0].0, 0);
+    for step in 0..=QUANTILE_STEPS {
+        let index = ((samples * step) / QUANTILE_STEPS).min(samples - 1);
+        while seen <= index {
+            let (next, count) = pairs.next().unwrap();
+            value = *next;
+            seen += count;
+        }
+        quantiles.push(value);
+    }
+    quantiles
+}
+
+/// Returns the percentile rank of `value` in a set of numerical values: the
+/// percentage of values that are less than or equal to it.  This is the
+/// inverse of the quantile function.
+pub fn percentile_rank(vec: &[f64], value: f64) -> f64 {
+    if vec.is_empty() {
+        return f64::NAN;
+    }
+    let below = vec.iter().filter(|x| **x <= value).count();
+    100.0 * below as f64 / vec.len() as f64
+}
+
 impl Stats {
     /// Creates a Stats struct from a vector of numerical data.
     ///
@@ -64,6 +168,7 @@ impl Stats {
         let var = temp / vec.len() as f64;
         let std = var.sqrt();
         let (p50, p90, p95, p99) = percentiles(vec);
+        let quantiles = quantiles(vec);
         Self {
             min,
             max,
@@ -76,12 +181,366 @@ impl Stats {
             p90,
             p95,
             p99,
+            rank: None,
+            quantiles,
+            sketch: None,
+            outliers: None,
+            trimmed: None,
+            confidence: None,
+        }
+    }
+
+    /// Creates a Stats struct from pre-aggregated numerical data: pairs of a
+    /// value and its number of occurrences, that should be positive.
+    ///
+    /// `precision` works like in `Stats::new`.
+    pub fn new_weighted(pairs: &mut [(f64, usize)], precision: Option<usize>) -> Self {
+        let samples = pairs.iter().map(|(_, count)| count).sum::<usize>();
+        let mut max = pairs[0].0;
+        let mut min = max;
+        let sum = pairs
+            .iter()
+            .map(|(x, count)| x * *count as f64)
+            .sum::<f64>();
+        let avg = sum / samples as f64;
+        let mut temp: f64 = 0.0;
+        for (val, count) in pairs.iter() {
+            max = max.max(*val);
+            min = min.min(*val);
+            temp += (avg - *val).powi(2) * *count as f64;
+        }
+        let var = temp / samples as f64;
+        let std = var.sqrt();
+        let (p50, p90, p95, p99) = weighted_percentiles(pairs, samples);
+        let quantiles = weighted_quantiles(pairs, samples);
+        Self {
+            min,
+            max,
+            avg,
+            std,
+            var,
+            samples,
+            precision,
+            p50,
+            p90,
+            p95,
+            p99,
+            rank: None,
+            quantiles,
+            sketch: None,
+            outliers: None,
+            trimmed: None,
+            confidence: None,
+        }
+    }
+
+    /// Creates a Stats struct with no values, to be added later with `add`.
+    /// Its statistics are NaN until then.
+    ///
+    /// `precision` works like in `Stats::new`.
+    pub fn new_empty(precision: Option<usize>) -> Self {
+        Self {
+            min: f64::NAN,
+            max: f64::NAN,
+            avg: f64::NAN,
+            std: f64::NAN,
+            var: f64::NAN,
+            samples: 0,
+            precision,
+            p50: f64::NAN,
+            p90: f64::NAN,
+            p95: f64::NAN,
+            p99: f64::NAN,
+            rank: None,
+            quantiles: Vec::new(),
+            sketch: Some(QuantileSketch::default()),
+            outliers: None,
+            trimmed: None,
+            confidence: None,
+        }
+    }
+
+    /// Adds a value to the statistics, without keeping it.  The average and
+    /// variance are updated exactly (with Welford's method), while
+    /// percentiles are estimated from then on with a `QuantileSketch` (with
+    /// a relative error of 1%).
+    ///
+    /// The optional statistics (like the rank of a value) are discarded, as
+    /// they need every value.
+    #[allow(dead_code)] // Only used by library users
+    pub fn add(&mut self, value: f64) {
+        self.add_all(&[value]);
+    }
+
+    /// Like `add`, for several values at once, which is faster (percentiles
+    /// are estimated once for all of them).
+    pub fn add_all(&mut self, values: &[f64]) {
+        if values.is_empty() {
+            return;
+        }
+        if self.sketch.is_none() {
+            self.sketch = Some(self.to_sketch());
+            self.quantiles = Vec::new();
+        }
+        let sketch = self.sketch.as_mut().unwrap();
+        for value in values.iter().copied() {
+            sketch.insert(value);
+            if self.samples == 0 {
+                self.min = value;
+                self.max = value;
+                self.avg = value;
+                self.var = 0.0;
+            } else {
+                let samples = self.samples as f64 + 1.0;
+                let delta = value - self.avg;
+                let avg = self.avg + delta / samples;
+                self.var = (self.var * self.samples as f64 + delta * (value - avg)) / samples;
+                self.avg = avg;
+                self.min = self.min.min(value);
+                self.max = self.max.max(value);
+            }
+            self.samples += 1;
+        }
+        self.std = self.var.sqrt();
+        self.p50 = sketch.quantile(0.5);
+        self.p90 = sketch.quantile(0.9);
+        self.p95 = sketch.quantile(0.95);
+        self.p99 = sketch.quantile(0.99);
+        self.rank = None;
+        self.outliers = None;
+        self.trimmed = None;
+        self.confidence = None;
+    }
+
+    // A sketch with the values of the quantiles, weighted so that they add up
+    // to the number of samples
+    fn to_sketch(&self) -> QuantileSketch {
+        let mut sketch = QuantileSketch::default();
+        let steps = self.quantiles.len();
+        for (step, value) in self.quantiles.iter().enumerate() {
+            let count = self.samples * (step + 1) / steps - self.samples * step / steps;
+            sketch.insert_count(*value, count);
+        }
+        sketch
+    }
+
+    // Every percentile, from 0 to 100 in steps of 0.1
+    fn quantile_values(&self) -> Vec<f64> {
+        match &self.sketch {
+            Some(sketch) => (0..=QUANTILE_STEPS)
+                .map(|step| sketch.quantile(step as f64 / QUANTILE_STEPS as f64))
+                .collect(),
+            None => self.quantiles.clone(),
+        }
+    }
+
+    /// Returns the `p` percentile (from 0 to 100) of the input values, with a
+    /// resolution of 0.1 (so that `percentile(99.9)` works, but
+    /// `percentile(99.99)` is rounded to it).
+    pub fn percentile(&self, p: f64) -> f64 {
+        let step = (p.clamp(0.0, 100.0) * QUANTILE_STEPS as f64 / 100.0).round();
+        match &self.sketch {
+            Some(sketch) => sketch.quantile(step / QUANTILE_STEPS as f64),
+            None => self.quantiles[step as usize],
+        }
+    }
+
+    /// Computes the percentile rank of `value` in `vec` (that should be the
+    /// same data used to build the stats), so that it is displayed along with
+    /// the rest of statistics.
+    pub fn set_rank_of(&mut self, vec: &[f64], value: f64) {
+        self.rank = Some((value, percentile_rank(vec, value)));
+    }
+
+    /// Computes the confidence interval of the average at `level` (like 0.95),
+    /// so that it is displayed along with the rest of statistics.
+    pub fn set_confidence(&mut self, level: f64) {
+        self.confidence = ConfidenceInterval::for_mean(self.avg, self.std, self.samples, level);
+    }
+
+    /// Computes the average and standard deviation of `vec` (that should be
+    /// the same data used to build the stats) excluding `fraction` of the
+    /// values at both tails, so that they are displayed along with the rest of
+    /// statistics.
+    pub fn set_trim(&mut self, vec: &[f64], fraction: f64) {
+        let mut sorted = vec.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        self.trimmed = Some(Trimmed::new(&sorted, fraction));
+    }
+
+    /// Finds the outliers in `vec` (that should be the same data used to build
+    /// the stats) with `method`, so that their number and the most extreme of
+    /// them are displayed along with the rest of statistics.
+    pub fn set_outliers(&mut self, vec: &[f64], method: OutlierMethod) {
+        let mut sorted = vec.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        self.outliers = Some(Outliers::new(
+            &sorted,
+            method,
+            TOP_OUTLIERS,
+            self.avg,
+            self.std,
+        ));
+    }
+
+    /// Returns the state of the statistics as a JSON object, so that they can
+    /// be merged with the ones of other inputs later on (see `from_state` and
+    /// `merge`).  Unlike `to_json`, it holds every percentile (in steps of 0.1).
+    pub fn to_state(&self) -> serde_json::Value {
+        serde_json::json!({
+            "samples": self.samples,
+            "min": self.min,
+            "max": self.max,
+            "avg": self.avg,
+            "var": self.var,
+            "quantiles": self.quantile_values(),
+        })
+    }
+
+    /// Parses the state written by `to_state`.
+    pub fn from_state(state: &serde_json::Value) -> Result<Self, String> {
+        let number = |field: &str| {
+            state[field]
+                .as_f64()
+                .ok_or(format!("Missing statistic '{field}'"))
+        };
+        let quantiles: Vec<f64> = match state["quantiles"].as_array() {
+            Some(array) if array.len() == QUANTILE_STEPS + 1 => {
+                array.iter().filter_map(|q| q.as_f64()).collect()
+            }
+            _ => Vec::new(),
+        };
+        if quantiles.len() != QUANTILE_STEPS + 1 {
+            return Err(String::from("Missing or bad percentiles"));
+        }
+        let quantile = |step: usize| quantiles[step * QUANTILE_STEPS / 100];
+        let var = number("var")?;
+        Ok(Self {
+            min: number("min")?,
+            max: number("max")?,
+            avg: number("avg")?,
+            std: var.sqrt(),
+            var,
+            samples: number("samples")? as usize,
+            precision: None,
+            p50: quantile(50),
+            p90: quantile(90),
+            p95: quantile(95),
+            p99: quantile(99),
+            rank: None,
+            outliers: None,
+            trimmed: None,
+            confidence: None,
+            quantiles,
+            sketch: None,
+        })
+    }
+
+    /// Merges the statistics of several inputs (like the same metric in
+    /// several hosts) into the ones of all of them.  Percentiles are
+    /// approximated from the ones of every input; the rest are exact.
+    pub fn merge_all(parts: &[Stats], precision: Option<usize>) -> Self {
+        let parts: Vec<&Stats> = parts.iter().filter(|stats| stats.samples > 0).collect();
+        if parts.is_empty() {
+            return Self::new_empty(precision);
         }
+        let mut pairs: Vec<(f64, usize)> = parts
+            .iter()
+            .flat_map(|stats| {
+                stats
+                    .quantile_values()
+                    .into_iter()
+                    .map(move |q| (q, stats.samples))
+            })
+            .collect();
+        let mut merged = Stats::new_weighted(&mut pairs, precision);
+        let samples: usize = parts.iter().map(|stats| stats.samples).sum();
+        let avg = parts
+            .iter()
+            .map(|stats| stats.avg * stats.samples as f64)
+            .sum::<f64>()
+            / samples as f64;
+        // Pooled variance: the one of every part plus the spread of averages
+        let var = parts
+            .iter()
+            .map(|stats| stats.samples as f64 * (stats.var + (stats.avg - avg).powi(2)))
+            .sum::<f64>()
+            / samples as f64;
+        merged.samples = samples;
+        merged.min = parts
+            .iter()
+            .map(|stats| stats.min)
+            .fold(f64::INFINITY, f64::min);
+        merged.max = parts
+            .iter()
+            .map(|stats| stats.max)
+            .fold(f64::NEG_INFINITY, f64::max);
+        merged.avg = avg;
+        merged.var = var;
+        merged.std = var.sqrt();
+        merged
+    }
+
+    /// Merges the statistics of `other` (like the ones of the values read by
+    /// another thread) into these ones, as in `Stats::merge_all`.  The
+    /// optional statistics (like the rank of a value) are not kept.
+    pub fn merge(&mut self, other: &Self) {
+        *self = Self::merge_all(&[self.clone(), other.clone()], self.precision);
+    }
+
+    /// Returns the statistics as a JSON object, for scripts.  Values are not
+    /// rounded, and the optional statistics are only present when computed.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut value = serde_json::json!({
+            "samples": self.samples,
+            "min": self.min,
+            "max": self.max,
+            "avg": self.avg,
+            "var": self.var,
+            "std": self.std,
+            "p50": self.p50,
+            "p90": self.p90,
+            "p95": self.p95,
+            "p99": self.p99,
+        });
+        if let Some((value_of, rank)) = self.rank {
+            value["rank"] = serde_json::json!({"value": value_of, "percentile": rank});
+        }
+        if let Some(interval) = &self.confidence {
+            value["confidence"] = serde_json::json!({
+                "level": interval.level,
+                "low": interval.low,
+                "high": interval.high,
+            });
+        }
+        if let Some(trimmed) = &self.trimmed {
+            value["trimmed"] = serde_json::json!({
+                "fraction": trimmed.fraction,
+                "avg": trimmed.avg,
+                "std": trimmed.std,
+                "winsorized_avg": trimmed.winsorized_avg,
+            });
+        }
+        if let Some(outliers) = &self.outliers {
+            let method = match outliers.method {
+                OutlierMethod::Iqr => "iqr",
+                OutlierMethod::ZScore => "zscore",
+            };
+            value["outliers"] = serde_json::json!({
+                "method": method,
+                "count": outliers.count,
+                "low": outliers.low,
+                "high": outliers.high,
+                "top": outliers.top,
+            });
+        }
+        value
     }
 }
 
 impl fmt::Display for Stats {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let theme = Theme::current();
         let formatter = match self.precision {
             None => F64Formatter::new_with_range(self.min..self.max),
             Some(n) => F64Formatter::new(n),
@@ -89,25 +548,94 @@ impl fmt::Display for Stats {
         writeln!(
             f,
             "Samples = {len}; Min = {min}; Max = {max}",
-            len = Blue.paint(self.samples.to_string()),
-            min = Blue.paint(formatter.format(self.min)),
-            max = Blue.paint(formatter.format(self.max)),
+            len = theme.label.paint(self.samples.to_string()),
+            min = theme.label.paint(formatter.format(self.min)),
+            max = theme.label.paint(formatter.format(self.max)),
         )?;
         writeln!(
             f,
             "Average = {avg}; Variance = {var}; STD = {std}",
-            avg = Blue.paint(formatter.format(self.avg)),
-            var = Blue.paint(format!("{:.3}", self.var)),
-            std = Blue.paint(format!("{:.3}", self.std)),
+            avg = theme.label.paint(formatter.format(self.avg)),
+            var = theme.label.paint(format!("{:.3}", self.var)),
+            std = theme.label.paint(format!("{:.3}", self.std)),
         )?;
         writeln!(
             f,
             "p50 = {p50}; p90 = {p90}; p95 = {p95}; p99 = {p99}",
-            p50 = Blue.paint(formatter.format(self.p50)),
-            p90 = Blue.paint(formatter.format(self.p90)),
-            p95 = Blue.paint(formatter.format(self.p95)),
-            p99 = Blue.paint(formatter.format(self.p99)),
-        )
+            p50 = theme.label.paint(formatter.format(self.p50)),
+            p90 = theme.label.paint(formatter.format(self.p90)),
+            p95 = theme.label.paint(formatter.format(self.p95)),
+            p99 = theme.label.paint(formatter.format(self.p99)),
+        )?;
+        if let Some((value, rank)) = self.rank {
+            writeln!(
+                f,
+                "{value} is p{rank}",
+                value = theme.label.paint(formatter.format(value)),
+                rank = theme.label.paint(format!("{rank:.1}")),
+            )?;
+        }
+        if let Some(interval) = &self.confidence {
+            writeln!(
+                f,
+                "{percent}% confidence interval of average = [{low} .. {high}]",
+                percent = (interval.level * 1000.0).round() / 10.0,
+                low = theme.label.paint(formatter.format(interval.low)),
+                high = theme.label.paint(formatter.format(interval.high)),
+            )?;
+        }
+        if let Some(trimmed) = &self.trimmed {
+            writeln!(
+                f,
+                "Trimmed ({percent}%): Average = {avg}; STD = {std}; Winsorized average = {win}",
+                percent = (trimmed.fraction * 1000.0).round() / 10.0,
+                avg = theme.label.paint(formatter.format(trimmed.avg)),
+                std = theme.label.paint(format!("{:.3}", trimmed.std)),
+                win = theme.label.paint(formatter.format(trimmed.winsorized_avg)),
+            )?;
+        }
+        if let Some(outliers) = &self.outliers {
+            let method = match outliers.method {
+                OutlierMethod::Iqr => "IQR",
+                OutlierMethod::ZScore => "z-score",
+            };
+            write!(
+                f,
+                "Outliers ({method}) = {count}; Fences = [{low} .. {high}]",
+                count = theme.label.paint(outliers.count.to_string()),
+                low = theme.label.paint(formatter.format(outliers.low)),
+                high = theme.label.paint(formatter.format(outliers.high)),
+            )?;
+            if !outliers.top.is_empty() {
+                let top: Vec<String> = outliers
+                    .top
+                    .iter()
+                    .map(|x| theme.label.paint(formatter.format(*x)).to_string())
+                    .collect();
+                write!(f, "; Most extreme = {}", top.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Render for Stats {
+    /// A statistic per row, as in `to_json`, with the ones of objects named
+    /// like `confidence.low`.
+    fn table(&self) -> Option<Table> {
+        let mut table = Table::new(&["statistic", "value"]);
+        for (key, value) in self.to_json().as_object().unwrap() {
+            match value {
+                serde_json::Value::Object(fields) => {
+                    for (field, value) in fields {
+                        table.push(vec![format!("{key}.{field}").into(), value.clone()]);
+                    }
+                }
+                value => table.push(vec![key.as_str().into(), value.clone()]),
+            }
+        }
+        Some(table)
     }
 }
 
@@ -118,6 +646,97 @@ mod tests {
     use rand::{seq::SliceRandom, thread_rng};
     use yansi::Paint;
 
+    #[test]
+    fn test_any_percentile() {
+        let mut vec: Vec<f64> = (0..2000).map(f64::from).collect();
+        vec.shuffle(&mut thread_rng());
+        let stats = Stats::new(&mut vec, None);
+        assert_eq!(stats.percentile(0.0), 0.0);
+        assert_eq!(stats.percentile(99.0), stats.p99);
+        assert_eq!(stats.percentile(99.9), 1998.0);
+        assert_eq!(stats.percentile(100.0), 1999.0);
+        let mut pairs = vec![(2.0, 1), (1.0, 998), (3.0, 1)];
+        let stats = Stats::new_weighted(&mut pairs, None);
+        assert_eq!(stats.percentile(50.0), 1.0);
+        assert_eq!(stats.percentile(99.9), 3.0);
+        assert_eq!(stats.percentile(99.8), 2.0);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut first: Vec<f64> = (0..1000).map(f64::from).collect();
+        let mut second: Vec<f64> = (1000..3000).map(f64::from).collect();
+        let mut all: Vec<f64> = (0..3000).map(f64::from).collect();
+        let first = Stats::from_state(&Stats::new(&mut first, None).to_state()).unwrap();
+        let second = Stats::new(&mut second, None);
+        let mut merged = first.clone();
+        merged.precision = Some(1);
+        merged.merge(&second);
+        assert_eq!(merged.p50, Stats::merge_all(&[first, second], Some(1)).p50);
+        let expected = Stats::new(&mut all, None);
+        assert_eq!(merged.samples, 3000);
+        assert_eq!(merged.min, 0.0);
+        assert_eq!(merged.max, 2999.0);
+        assert_float_eq!(merged.avg, expected.avg, abs <= 1e-9);
+        assert_float_eq!(merged.std, expected.std, abs <= 1e-6);
+        assert_float_eq!(merged.p50, expected.p50, abs <= 3.0);
+        assert_float_eq!(merged.p99, expected.p99, abs <= 3.0);
+        assert!(Stats::from_state(&serde_json::json!({"samples": 1})).is_err());
+    }
+
+    #[test]
+    fn test_add() {
+        let mut vec: Vec<f64> = (1..=2000).map(f64::from).collect();
+        let expected = Stats::new(&mut vec, None);
+        let mut stats = Stats::new_empty(None);
+        assert!(stats.avg.is_nan());
+        for x in vec.iter() {
+            stats.add(*x);
+        }
+        assert_eq!(stats.samples, 2000);
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.max, 2000.0);
+        assert_float_eq!(stats.avg, expected.avg, abs <= 1e-9);
+        assert_float_eq!(stats.var, expected.var, rmax <= 1e-9);
+        assert_float_eq!(stats.p50, expected.p50, rmax <= 0.01);
+        assert_float_eq!(stats.p99, expected.p99, rmax <= 0.01);
+        assert_float_eq!(stats.percentile(99.9), 1999.0, rmax <= 0.01);
+        let mut stats = Stats::new(&mut vec[..1000], None);
+        stats.set_rank_of(&vec[..1000], 500.0);
+        for x in vec[1000..].iter() {
+            stats.add(*x);
+        }
+        assert_eq!(stats.samples, 2000);
+        assert_float_eq!(stats.avg, expected.avg, abs <= 1e-9);
+        assert_float_eq!(stats.p90, expected.p90, rmax <= 0.01);
+        assert_eq!(stats.rank, None);
+        let merged = Stats::merge_all(&[stats, Stats::new_empty(None)], None);
+        assert_eq!(merged.samples, 2000);
+        assert_float_eq!(merged.p50, expected.p50, rmax <= 0.02);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let mut vec = [1.0, 2.0, 3.0, 4.0];
+        let mut stats = Stats::new(&mut vec, None);
+        let value = stats.to_json();
+        assert_eq!(value["samples"], 4);
+        assert_eq!(value["max"], 4.0);
+        assert_eq!(value["avg"], 2.5);
+        assert!(value.get("trimmed").is_none());
+        stats.set_trim(&vec, 0.25);
+        stats.set_rank_of(&vec, 2.0);
+        let value = stats.to_json();
+        assert_eq!(value["trimmed"]["fraction"], 0.25);
+        assert_eq!(value["rank"]["value"], 2.0);
+        let table = stats.table().unwrap();
+        assert_eq!(table.columns, vec!["statistic", "value"]);
+        assert!(table.rows.contains(&vec!["samples".into(), 4.into()]));
+        assert!(table
+            .rows
+            .contains(&vec!["trimmed.fraction".into(), 0.25.into()]));
+    }
+
     #[test]
     fn basic_test() {
         let stats = Stats::new(&mut [1.1, 3.3, 2.2], Some(3));
@@ -163,4 +782,70 @@ mod tests {
         assert!(display.contains("p95 = 95.0"));
         assert!(display.contains("p99 = 99.0"));
     }
+
+    #[test]
+    fn test_weighted() {
+        let mut pairs = vec![(3.0, 1), (1.0, 8), (2.0, 1)];
+        let stats = Stats::new_weighted(&mut pairs, Some(1));
+        let mut vec = vec![1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 2.0, 3.0];
+        let expected = Stats::new(&mut vec, Some(1));
+        assert_eq!(stats.samples, 10);
+        assert_eq!((stats.min, stats.max), (1.0, 3.0));
+        assert_float_eq!(stats.avg, expected.avg, abs <= 0.0001);
+        assert_float_eq!(stats.var, expected.var, abs <= 0.0001);
+        assert_eq!(
+            (stats.p50, stats.p90, stats.p95, stats.p99),
+            (expected.p50, expected.p90, expected.p95, expected.p99)
+        );
+    }
+
+    #[test]
+    fn test_rank() {
+        let mut vec: Vec<f64> = (1..=1000).map(|i| i as f64).collect();
+        assert_float_eq!(percentile_rank(&vec, 972.0), 97.2, abs <= 0.0001);
+        assert_float_eq!(percentile_rank(&vec, 0.0), 0.0, abs <= 0.0001);
+        assert_float_eq!(percentile_rank(&vec, 5000.0), 100.0, abs <= 0.0001);
+        assert!(percentile_rank(&[], 1.0).is_nan());
+        let mut stats = Stats::new(&mut vec, Some(1));
+        stats.set_rank_of(&vec, 972.0);
+        Paint::disable();
+        assert!(format!("{stats}").contains("\n972.0 is p97.2\n"));
+    }
+
+    #[test]
+    fn test_trim() {
+        let mut vec = vec![5.0, 1.0, 2.0, 3.0, 4.0, 6.0, 7.0, 8.0, 900.0, -300.0];
+        let mut stats = Stats::new(&mut vec, Some(1));
+        stats.set_trim(&vec, 0.1);
+        Paint::disable();
+        assert!(format!("{stats}")
+            .contains("\nTrimmed (10%): Average = 4.5; STD = 2.291; Winsorized average = 4.5\n"));
+    }
+
+    #[test]
+    fn test_confidence() {
+        let mut vec: Vec<f64> = (1..=10).map(|i| i as f64).collect();
+        let mut stats = Stats::new(&mut vec, Some(2));
+        stats.set_confidence(0.95);
+        Paint::disable();
+        assert!(
+            format!("{stats}").contains("\n95% confidence interval of average = [3.33 .. 7.67]\n")
+        );
+        let mut stats = Stats::new(&mut [1.0], Some(2));
+        stats.set_confidence(0.95);
+        assert!(!format!("{stats}").contains("confidence"));
+    }
+
+    #[test]
+    fn test_outliers() {
+        let mut vec = vec![5.0, 1.0, 2.0, 3.0, 4.0, 6.0, 7.0, 90.0, -30.0];
+        let mut stats = Stats::new(&mut vec, Some(1));
+        stats.set_outliers(&vec, OutlierMethod::Iqr);
+        Paint::disable();
+        assert!(format!("{stats}").contains(
+            "\nOutliers (IQR) = 2; Fences = [-4.0 .. 12.0]; Most extreme = 90.0, -30.0\n"
+        ));
+        stats.set_outliers(&vec, OutlierMethod::ZScore);
+        assert!(format!("{stats}").contains("\nOutliers (z-score) = 0; Fences = ["));
+    }
 }
@@ -0,0 +1,104 @@
+use std::str::FromStr;
+
+/// Number of standard deviations from the average beyond which values are
+/// outliers, with `OutlierMethod::ZScore`.
+const Z_SCORE_LIMIT: f64 = 3.0;
+
+/// Number of interquartile ranges beyond the quartiles from which values are
+/// outliers, with `OutlierMethod::Iqr` (Tukey's fences).
+const IQR_FACTOR: f64 = 1.5;
+
+/// Methods for deciding which values are outliers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum OutlierMethod {
+    /// Values further than 1.5 interquartile ranges from the quartiles.
+    Iqr,
+    /// Values further than 3 standard deviations from the average.
+    ZScore,
+}
+
+impl FromStr for OutlierMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "iqr" => Ok(OutlierMethod::Iqr),
+            "zscore" => Ok(OutlierMethod::ZScore),
+            _ => Err(format!("Unknown outlier detection method: {s}")),
+        }
+    }
+}
+
+/// Values out of the fences computed with some `OutlierMethod`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Outliers {
+    pub method: OutlierMethod,
+    /// Values below this one are outliers.
+    pub low: f64,
+    /// Values above this one are outliers.
+    pub high: f64,
+    /// Number of outliers.
+    pub count: usize,
+    /// The most extreme outliers, the furthest from the fences first.
+    pub top: Vec<f64>,
+}
+
+impl Outliers {
+    /// Finds the outliers in `vec`, which must be sorted, keeping the `top`
+    /// most extreme ones.  `avg` and `std` are the ones of `vec`.
+    pub fn new(vec: &[f64], method: OutlierMethod, top: usize, avg: f64, std: f64) -> Self {
+        let (low, high) = match method {
+            OutlierMethod::Iqr => {
+                let (q1, q3) = (vec[vec.len() / 4], vec[vec.len() * 3 / 4]);
+                let iqr = q3 - q1;
+                (q1 - IQR_FACTOR * iqr, q3 + IQR_FACTOR * iqr)
+            }
+            OutlierMethod::ZScore => (avg - Z_SCORE_LIMIT * std, avg + Z_SCORE_LIMIT * std),
+        };
+        let distance = |x: f64| (low - x).max(x - high);
+        let mut outliers: Vec<f64> = vec
+            .iter()
+            .copied()
+            .filter(|x| *x < low || *x > high)
+            .collect();
+        let count = outliers.len();
+        outliers.sort_by(|a, b| distance(*b).partial_cmp(&distance(*a)).unwrap());
+        outliers.truncate(top);
+        Self {
+            method,
+            low,
+            high,
+            count,
+            top: outliers,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iqr() {
+        let mut vec: Vec<f64> = (1..=20).map(|i| i as f64).collect();
+        vec.insert(0, -40.0);
+        vec.push(100.0);
+        vec.push(50.0);
+        vec.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let outliers = Outliers::new(&vec, OutlierMethod::Iqr, 2, 0.0, 0.0);
+        assert_eq!(outliers.count, 3);
+        assert_eq!(outliers.top, vec![100.0, -40.0]);
+        assert_eq!((outliers.low, outliers.high), (-13.0, 35.0));
+    }
+
+    #[test]
+    fn test_zscore() {
+        let outliers = Outliers::new(&[2.0, 5.0, 9.0, 20.0], OutlierMethod::ZScore, 5, 5.0, 1.0);
+        assert_eq!(outliers.count, 2);
+        assert_eq!(outliers.top, vec![20.0, 9.0]);
+        assert_eq!(OutlierMethod::from_str("zscore"), Ok(OutlierMethod::ZScore));
+        assert!(OutlierMethod::from_str("mad").is_err());
+    }
+}
@@ -0,0 +1,64 @@
+/// Finds the peaks in a sequence of counts (like the buckets of an histogram),
+/// returning their indexes in ascending order.
+///
+/// A peak is a local maximum (a plateau of equal counts is considered a single
+/// peak, located at its center) whose prominence is at least `min_prominence`.
+/// The prominence of a peak is how much it stands out over the lowest valley
+/// separating it from a higher peak (or from the edges of the sequence), so
+/// small bumps caused by noise are discarded.
+pub fn find_peaks(counts: &[usize], min_prominence: usize) -> Vec<usize> {
+    let mut peaks = Vec::new();
+    let mut i = 0;
+    while i < counts.len() {
+        // Find the end of the plateau starting at i
+        let mut j = i;
+        while j + 1 < counts.len() && counts[j + 1] == counts[i] {
+            j += 1;
+        }
+        let rising = i == 0 || counts[i - 1] < counts[i];
+        let falling = j + 1 == counts.len() || counts[j + 1] < counts[i];
+        if rising && falling && prominence(counts, i, j) >= min_prominence.max(1) {
+            peaks.push((i + j) / 2);
+        }
+        i = j + 1;
+    }
+    peaks
+}
+
+// Prominence of the plateau between `start` and `end` (inclusive).
+fn prominence(counts: &[usize], start: usize, end: usize) -> usize {
+    let height = counts[start];
+    let left = counts[..start]
+        .iter()
+        .rev()
+        .take_while(|c| **c <= height)
+        .min()
+        .copied()
+        .unwrap_or(0);
+    let right = counts[end + 1..]
+        .iter()
+        .take_while(|c| **c <= height)
+        .min()
+        .copied()
+        .unwrap_or(0);
+    height - left.max(right)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_find_peaks() {
+        assert_eq!(find_peaks(&[], 1), vec![] as Vec<usize>);
+        assert_eq!(find_peaks(&[0, 0, 0], 1), vec![] as Vec<usize>);
+        assert_eq!(find_peaks(&[1, 5, 2, 0, 3, 9, 1], 1), vec![1, 5]);
+        assert_eq!(find_peaks(&[1, 5, 2, 0, 3, 9, 1], 5), vec![5]);
+        // Plateaus
+        assert_eq!(find_peaks(&[7, 7, 7, 1, 4, 4, 0], 3), vec![1, 4]);
+        // Small bumps next to a bigger peak are not prominent
+        assert_eq!(find_peaks(&[1, 8, 6, 7, 2, 0], 2), vec![1]);
+        assert_eq!(find_peaks(&[1, 8, 6, 7, 2, 0], 1), vec![1, 3]);
+    }
+}
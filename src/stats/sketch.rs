@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+
+// Relative error of the estimated quantiles, by default
+const DEFAULT_ACCURACY: f64 = 0.01;
+
+#[derive(Debug, Clone)]
+/// A quantile sketch (in the fashion of DDSketch), for estimating the
+/// percentiles of a stream of numerical data in little memory.
+///
+/// Values are counted in buckets growing exponentially in size, so that the
+/// estimations are within a relative error of the actual values, and a
+/// million of distinct magnitudes fit in a few thousands of buckets.
+pub struct QuantileSketch {
+    gamma: f64,
+    positive: BTreeMap<i32, usize>,
+    negative: BTreeMap<i32, usize>,
+    zeros: usize,
+    count: usize,
+}
+
+impl Default for QuantileSketch {
+    fn default() -> Self {
+        Self::new(DEFAULT_ACCURACY)
+    }
+}
+
+impl QuantileSketch {
+    /// Creates an empty `QuantileSketch`.
+    ///
+    /// `accuracy` is the relative error of the estimations (like 0.01), that
+    /// is capped between 0.0001 and 0.5.
+    pub fn new(accuracy: f64) -> Self {
+        let accuracy = accuracy.clamp(1e-4, 0.5);
+        Self {
+            gamma: (1.0 + accuracy) / (1.0 - accuracy),
+            positive: BTreeMap::new(),
+            negative: BTreeMap::new(),
+            zeros: 0,
+            count: 0,
+        }
+    }
+
+    /// Observe a new value.
+    pub fn insert(&mut self, value: f64) {
+        self.insert_count(value, 1);
+    }
+
+    /// Observe `count` occurrences of a value.  NaN values are ignored.
+    pub fn insert_count(&mut self, value: f64, count: usize) {
+        if value.is_nan() || count == 0 {
+            return;
+        }
+        if value >= f64::MIN_POSITIVE {
+            *self.positive.entry(self.index(value)).or_insert(0) += count;
+        } else if value <= -f64::MIN_POSITIVE {
+            *self.negative.entry(self.index(-value)).or_insert(0) += count;
+        } else {
+            self.zeros += count;
+        }
+        self.count += count;
+    }
+
+    /// Returns the estimated value at quantile `q` (from 0 to 1), choosing
+    /// the values like `Stats` does for percentiles.  Returns NaN if no value
+    /// was observed.
+    pub fn quantile(&self, q: f64) -> f64 {
+        if self.count == 0 {
+            return f64::NAN;
+        }
+        let index = ((q.clamp(0.0, 1.0) * self.count as f64) as usize).min(self.count - 1);
+        let mut seen = 0;
+        for (bucket, count) in self.negative.iter().rev() {
+            seen += count;
+            if seen > index {
+                return -self.value(*bucket);
+            }
+        }
+        seen += self.zeros;
+        if seen > index {
+            return 0.0;
+        }
+        for (bucket, count) in self.positive.iter() {
+            seen += count;
+            if seen > index {
+                return self.value(*bucket);
+            }
+        }
+        unreachable!()
+    }
+
+    // Bucket of a positive value
+    fn index(&self, value: f64) -> i32 {
+        (value.ln() / self.gamma.ln()).ceil() as i32
+    }
+
+    // Value representing the ones in a bucket (with the least relative error)
+    fn value(&self, index: i32) -> f64 {
+        2.0 * self.gamma.powi(index) / (self.gamma + 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantiles() {
+        let mut sketch = QuantileSketch::default();
+        assert!(sketch.quantile(0.5).is_nan());
+        for i in -1000..=10000 {
+            sketch.insert(i as f64);
+        }
+        sketch.insert(f64::NAN);
+        assert_eq!(sketch.count, 11001);
+        assert_eq!(sketch.quantile(0.09091), 0.0);
+        let relative = |x: f64, expected: f64| ((x - expected) / expected).abs();
+        assert!(relative(sketch.quantile(0.0), -1000.0) <= 0.01);
+        assert!(relative(sketch.quantile(0.5), 4500.0) <= 0.01);
+        assert!(relative(sketch.quantile(0.99), 9891.0) <= 0.01);
+        assert!(relative(sketch.quantile(1.0), 10000.0) <= 0.01);
+        assert!(sketch.positive.len() < 500);
+    }
+
+    #[test]
+    fn test_counts() {
+        let mut sketch = QuantileSketch::new(0.001);
+        sketch.insert_count(5.0, 99);
+        sketch.insert_count(1e9, 1);
+        sketch.insert_count(2.0, 0);
+        assert_eq!(sketch.count, 100);
+        assert!((sketch.quantile(0.5) - 5.0).abs() <= 0.005);
+        assert!((sketch.quantile(0.99) - 1e9).abs() <= 1e6);
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A transformation applied to numerical values before they are plotted.
+pub enum Transform {
+    /// Standard score: number of standard deviations from the average.
+    ZScore,
+    /// Natural logarithm.  Only positive values are kept.
+    Log,
+    /// Square root.  Only non negative values are kept.
+    Sqrt,
+}
+
+impl Transform {
+    /// Returns the transformed values.  Values outside of the domain of the
+    /// transformation (like negative values for a logarithm) are discarded.
+    pub fn apply(&self, vec: &[f64]) -> Vec<f64> {
+        match self {
+            Transform::ZScore => {
+                let len = vec.len() as f64;
+                let avg = vec.iter().sum::<f64>() / len;
+                let std = (vec.iter().map(|x| (x - avg).powi(2)).sum::<f64>() / len).sqrt();
+                vec.iter()
+                    .map(|x| if std > 0.0 { (x - avg) / std } else { 0.0 })
+                    .collect()
+            }
+            Transform::Log => vec.iter().filter(|x| **x > 0.0).map(|x| x.ln()).collect(),
+            Transform::Sqrt => vec
+                .iter()
+                .filter(|x| **x >= 0.0)
+                .map(|x| x.sqrt())
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for Transform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "zscore" => Ok(Transform::ZScore),
+            "log" => Ok(Transform::Log),
+            "sqrt" => Ok(Transform::Sqrt),
+            _ => Err(format!("Unknown transform: {s}")),
+        }
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label = match self {
+            Transform::ZScore => "z-score, (x - avg) / std",
+            Transform::Log => "ln(x)",
+            Transform::Sqrt => "sqrt(x)",
+        };
+        write!(f, "{label}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_zscore() {
+        let vec = Transform::ZScore.apply(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        assert_float_eq!(vec[0], -1.5, rmax <= f64::EPSILON);
+        assert_float_eq!(vec[7], 2.0, rmax <= f64::EPSILON);
+        assert_eq!(Transform::ZScore.apply(&[3.0, 3.0]), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_domain() {
+        assert_eq!(Transform::Log.apply(&[-1.0, 0.0, 1.0]), vec![0.0]);
+        assert_eq!(Transform::Sqrt.apply(&[-1.0, 0.0, 9.0]), vec![0.0, 3.0]);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("log".parse::<Transform>(), Ok(Transform::Log));
+        assert!("foo".parse::<Transform>().is_err());
+        assert_eq!(Transform::Sqrt.to_string(), "sqrt(x)");
+    }
+}
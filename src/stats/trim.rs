@@ -0,0 +1,57 @@
+/// Statistics robust to a few garbage values, computed by discarding (or
+/// clamping) a fraction of the values at both tails.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Trimmed {
+    /// Fraction of the values discarded at every tail.
+    pub fraction: f64,
+    /// Average of the values, without the tails.
+    pub avg: f64,
+    /// Standard deviation of the values, without the tails.
+    pub std: f64,
+    /// Average of the values, with the tails clamped to the most extreme
+    /// values kept.
+    pub winsorized_avg: f64,
+}
+
+impl Trimmed {
+    /// Computes the statistics of `vec`, which must be sorted and not empty,
+    /// excluding `fraction` (below 0.5) of the values at every tail.
+    pub fn new(vec: &[f64], fraction: f64) -> Self {
+        let len = vec.len();
+        let cut = ((len as f64 * fraction) as usize).min((len - 1) / 2);
+        let kept = &vec[cut..len - cut];
+        let avg = kept.iter().sum::<f64>() / kept.len() as f64;
+        let var = kept.iter().map(|x| (x - avg).powi(2)).sum::<f64>() / kept.len() as f64;
+        let clamped = kept[0] * cut as f64 + kept[kept.len() - 1] * cut as f64;
+        let winsorized_avg = (kept.iter().sum::<f64>() + clamped) / len as f64;
+        Self {
+            fraction,
+            avg,
+            std: var.sqrt(),
+            winsorized_avg,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_trimmed() {
+        let mut vec: Vec<f64> = (1..=8).map(|i| i as f64).collect();
+        vec.push(1000.0);
+        vec.insert(0, -1000.0);
+        let trimmed = Trimmed::new(&vec, 0.1);
+        assert_float_eq!(trimmed.avg, 4.5, abs <= 0.0001);
+        assert_float_eq!(trimmed.std, 2.2913, abs <= 0.0001);
+        assert_float_eq!(trimmed.winsorized_avg, 4.5, abs <= 0.0001);
+        let trimmed = Trimmed::new(&vec, 0.0);
+        assert_float_eq!(trimmed.avg, 3.6, abs <= 0.0001);
+        let trimmed = Trimmed::new(&[1.0, 2.0, 6.0], 0.4);
+        assert_float_eq!(trimmed.avg, 2.0, abs <= 0.0001);
+        assert_float_eq!(trimmed.winsorized_avg, 2.0, abs <= 0.0001);
+    }
+}
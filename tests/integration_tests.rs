@@ -4,6 +4,9 @@ use assert_cmd::Command;
 use predicates::prelude::*;
 use tempfile::NamedTempFile;
 
+#[path = "../src/examples.rs"]
+mod examples;
+
 #[test]
 fn test_help_works() {
     let mut cmd = Command::cargo_bin("lowcharts").unwrap();
@@ -73,6 +76,43 @@ fn test_timehist() {
         .stderr(predicate::str::contains("Not enough data to process"));
 }
 
+#[test]
+fn test_timehist_bucket_labels() {
+    let input = "2021-04-28 06:25:00 a\n2021-04-28 06:27:00 b\n2021-04-28 06:29:00 c\n";
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("timehist")
+        .arg("--intervals")
+        .arg("2")
+        .arg("--label")
+        .arg("range")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[06:25:00.000..06:27:00.000] [1] ∎\n",
+        ))
+        .stdout(predicate::str::contains(
+            "[06:27:00.000..06:29:00.000] [2] ∎∎\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("split-timehist")
+        .arg("a")
+        .arg("c")
+        .arg("--intervals")
+        .arg("2")
+        .arg("--label")
+        .arg("relative")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[+00:00:00.000] [1/0] ∎\n"))
+        .stdout(predicate::str::contains("[+00:02:00.000] [0/1] ∎\n"));
+}
+
 #[test]
 fn test_hist() {
     let mut cmd = Command::cargo_bin("lowcharts").unwrap();
@@ -120,143 +160,2540 @@ fn test_matchbar() {
 }
 
 #[test]
-fn test_splittime() {
+fn test_matchbar_regex() {
     let mut cmd = Command::cargo_bin("lowcharts").unwrap();
-    cmd.arg("split-timehist")
-        .arg("1")
+    cmd.arg("--color")
+        .arg("no")
+        .arg("matches")
+        .arg("--regex-match")
+        .arg(r"\bid\b")
+        .arg("^foo[0-9]$")
+        .write_stdin("id 1\npid 2\nfoo3\nfoo44\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\n[\\bid\\b    ] [1] ∎\n"))
+        .stdout(predicate::str::contains("\n[^foo[0-9]$] [1] ∎\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("matches")
+        .arg("--regex-match")
+        .arg("(")
+        .write_stdin("foo\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to parse regex"));
+}
+
+#[test]
+fn test_matchbar_patterns_file() {
+    let mut patterns = NamedTempFile::new().unwrap();
+    write!(
+        patterns,
+        "# Alert keywords\n  # Indented\nfoo \n\nba[rz]\r\n"
+    )
+    .unwrap();
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("matches")
+        .arg("--regex-match")
+        .arg("--patterns-file")
+        .arg(patterns.path())
+        .arg("gnat")
+        .write_stdin("foo1\nbar2\nfoo3\nbaz4\nfoo5\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\n[gnat  ] [0] \n[foo   ] [3] ∎∎∎\n",
+        ))
+        .stdout(predicate::str::contains("\n[ba[rz]] [2] ∎∎\n"))
+        .stdout(predicate::str::contains("Alert").not())
+        .stdout(predicate::str::contains("Indented").not());
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("matches")
+        .arg("--patterns-file")
+        .arg("/no/such/file")
+        .write_stdin("foo\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Could not read patterns"));
+}
+
+#[test]
+fn test_bar_char_and_marker() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("--bar-char")
+        .arg("#")
+        .arg("matches")
+        .arg("foo")
+        .write_stdin("foo\nfoo\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Each # represents a count of 1\n"))
+        .stdout(predicate::str::contains("\n[foo] [2] ##\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("plot")
+        .arg("--marker")
+        .arg("*")
+        .arg("--height")
         .arg("2")
-        .arg("3")
+        .write_stdin("1\n2\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("●").not())
+        .stdout(predicate::str::contains("[1.500]  *\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--bar-char")
+        .arg("##")
+        .arg("matches")
+        .arg("foo")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_fine_bars() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("matches")
+        .arg("--fine-bars")
+        .arg("--width")
         .arg("4")
-        .arg("5")
-        .arg("6")
+        .arg("foo")
+        .arg("bar")
+        .write_stdin("foo\nfoo\nfoo\nfoo\nfoo\nfoo\nfoo\nfoo\nfoo\nbar\n")
         .assert()
-        .failure()
+        .success()
+        .stdout(predicate::str::contains("Each █ represents a count of 2\n"))
+        .stdout(predicate::str::contains("\n[foo] [9] ████▌\n"))
+        .stdout(predicate::str::contains("\n[bar] [1] ▌\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--fine-bars")
+        .arg("--bar-char")
+        .arg("#")
+        .arg("matches")
+        .arg("foo")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_theme() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("yes")
+        .arg("--theme")
+        .arg("colorblind")
+        .arg("matches")
+        .arg("foo")
+        .write_stdin("foo\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[38;5;74mfoo\x1b[0m"))
+        .stdout(predicate::str::contains("\x1b[38;5;214m∎\x1b[0m\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--theme")
+        .arg("neon")
+        .arg("matches")
+        .arg("foo")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_fail_if() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--fail-if")
+        .arg("max > 3")
+        .arg("--fail-if")
+        .arg("p50 > 100")
+        .write_stdin("1\n2\n4\n")
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains("Samples = 3"))
         .stderr(predicate::str::contains(
-            "Only 5 different sub-groups are supported",
-        ));
+            "Condition 'max > 3' holds (value: 4)",
+        ))
+        .stderr(predicate::str::contains("p50").not());
     let mut cmd = Command::cargo_bin("lowcharts").unwrap();
-    cmd.arg("split-timehist")
-        .arg("A")
-        .arg("B")
-        .arg("C")
+    cmd.arg("plot")
+        .arg("--fail-if")
+        .arg("avg<2")
+        .write_stdin("1\n2\n4\n")
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--fail-if")
+        .arg("p50.05 > 3")
+        .write_stdin("1\n2\n4\n")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Bad percentile 'p50.05'"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--fail-if")
+        .arg("outliers > 0")
+        .write_stdin("1\n2\n4\n")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("uses statistics not computed"));
+}
+
+#[test]
+fn test_assert() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("summary")
+        .arg("--assert")
+        .arg("p99.9 <= 4")
+        .arg("--assert")
+        .arg("max < 2 * p50")
+        .arg("--assert-report")
+        .arg("tap")
+        .write_stdin("1\n2\n4\n")
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains("Samples = 3"))
+        .stdout(predicate::str::contains(
+            "1..2\nok 1 - p99.9 <= 4\nnot ok 2 - max < 2 * p50\n",
+        ))
+        .stdout(predicate::str::contains(
+            "message: 'value is 4, compared with 4'",
+        ))
+        .stderr(predicate::str::contains("Assertion 'max < 2 * p50' failed"));
+    let file = NamedTempFile::new().unwrap();
+    let path = file.path();
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--assert")
+        .arg("avg < 3")
+        .arg("--assert-report")
+        .arg("junit")
+        .arg("--assert-report-file")
+        .arg(path)
+        .write_stdin("1\n2\n4\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("testsuite").not());
+    let report = std::fs::read_to_string(path).unwrap();
+    assert!(report.contains(r#"<testsuite name="stdin" tests="1" failures="0">"#));
+    assert!(report.contains(r#"<testcase name="avg &lt; 3" classname="lowcharts"/>"#));
+}
+
+#[test]
+fn test_sample() {
+    let input: String = (0..1000).map(|i| format!("{i}\n")).collect();
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--sample")
+        .arg("50")
+        .write_stdin(input.clone())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Sampled 50 of 1000 values (5.00%)\n",
+        ))
+        .stdout(predicate::str::contains("Samples = 50;"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("plot")
+        .arg("--sample")
+        .arg("5000")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Sampled").not())
+        .stdout(predicate::str::contains("Samples = 1000;"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--sample")
+        .arg("0")
+        .write_stdin("1\n")
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_parallel() {
+    let mut file = NamedTempFile::new().unwrap();
+    for i in 0..200000 {
+        writeln!(file, "took {} ms", i % 100).unwrap();
+    }
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--parallel")
+        .arg("--regex")
+        .arg("took ([0-9]+)")
         .arg("--intervals")
         .arg("2")
-        .write_stdin("1619655527.888165 A\n1619655528.888165 A\n1619655527.888165 B\n")
+        .arg(file.path())
         .assert()
         .success()
-        .stdout(predicate::str::contains("Matches: 3."))
-        .stdout(predicate::str::contains("A: 2"))
-        .stdout(predicate::str::contains("B: 1."))
-        .stdout(predicate::str::contains("C: 0."))
-        .stdout(predicate::str::contains("Each ∎ represents a count of 1\n"))
-        .stdout(predicate::str::contains("[00:18:47.888165] [1/1/0] ∎∎\n"))
-        .stdout(predicate::str::contains("[00:18:48.388165] [1/0/0] ∎\n"));
+        .stdout(predicate::str::contains("Samples = 200000;"))
+        .stdout(predicate::str::contains("[ 0.0 .. 49.5] [100000]"));
 }
 
 #[test]
-fn test_plot() {
+fn test_graphics() {
     let mut cmd = Command::cargo_bin("lowcharts").unwrap();
-    match NamedTempFile::new() {
-        Ok(ref mut file) => {
-            writeln!(file, "1").unwrap();
-            writeln!(file, "2").unwrap();
-            writeln!(file, "3").unwrap();
-            writeln!(file, "4").unwrap();
-            writeln!(file, "none").unwrap();
-            cmd.arg("--verbose")
-                .arg("--color")
-                .arg("no")
-                .arg("plot")
-                .arg(file.path().to_str().unwrap())
-                .arg("--height")
-                .arg("4")
-                .assert()
-                .success()
-                .stdout(predicate::str::contains(
-                    "Samples = 4; Min = 1.000; Max = 4.000\n",
-                ))
-                .stdout(predicate::str::contains("\n[3.250]    ●"))
-                .stdout(predicate::str::contains("\n[2.500]   ●"))
-                .stdout(predicate::str::contains("\n[1.750]  ●"))
-                .stdout(predicate::str::contains("\n[1.000] ●"))
-                .stderr(predicate::str::contains("[DEBUG] Cannot parse float"));
-        }
-        Err(_) => panic!("Could not create temp file"),
-    }
+    cmd.arg("hist")
+        .arg("--graphics")
+        .arg("sixel")
+        .write_stdin("1\n2\n2\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Samples = 3;"))
+        .stdout(predicate::str::starts_with("Samples").and(predicate::str::contains("\x1bP0;1;0q")))
+        .stdout(predicate::str::contains("∎").not());
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("plot")
+        .arg("--graphics")
+        .arg("kitty")
+        .write_stdin("1\n2\n2\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b_Ga=T,f=32,"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("plot")
+        .arg("--graphics")
+        .arg("auto")
+        .write_stdin("1\n2\n2\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b").not());
 }
 
 #[test]
-fn test_plot_precision() {
+fn test_svg_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("chart.svg");
     let mut cmd = Command::cargo_bin("lowcharts").unwrap();
-    match NamedTempFile::new() {
-        Ok(ref mut file) => {
-            writeln!(file, "1").unwrap();
-            writeln!(file, "2").unwrap();
-            writeln!(file, "3").unwrap();
-            writeln!(file, "4").unwrap();
-            writeln!(file, "none").unwrap();
-            cmd.arg("--verbose")
-                .arg("--color")
-                .arg("no")
-                .arg("plot")
-                .arg(file.path().to_str().unwrap())
-                .arg("--height")
-                .arg("4")
-                .arg("--precision")
-                .arg("1")
-                .assert()
-                .success()
-                .stdout(predicate::str::contains(
-                    "Samples = 4; Min = 1.0; Max = 4.0\n",
-                ))
-                .stdout(predicate::str::contains("\n[3.2]    ●"))
-                .stdout(predicate::str::contains("\n[2.5]   ●"))
-                .stdout(predicate::str::contains("\n[1.8]  ●"))
-                .stdout(predicate::str::contains("\n[1.0] ●"))
-                .stderr(predicate::str::contains("[DEBUG] Cannot parse float"));
-        }
-        Err(_) => panic!("Could not create temp file"),
-    }
+    cmd.arg("hist")
+        .arg("--output")
+        .arg(&path)
+        .write_stdin("1\n2\n2\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Samples = 3;"));
+    let svg = std::fs::read_to_string(&path).unwrap();
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.contains(">Samples = 3; Min = 1.000; Max = 2.000</text>"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("plot")
+        .arg("-o")
+        .arg(dir.path().join("missing").join("chart.svg"))
+        .write_stdin("1\n2\n2\n")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Could not write"));
 }
 
 #[test]
-fn test_hist_negative_min() {
+fn test_export_data() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("points.csv");
     let mut cmd = Command::cargo_bin("lowcharts").unwrap();
     cmd.arg("hist")
-        .arg("--min")
-        .arg("-1")
-        .arg("--max")
-        .arg("10.1")
-        .write_stdin("4.2\n2.4\n-2\n")
+        .arg("--intervals")
+        .arg("2")
+        .arg("--export-data")
+        .arg(&path)
+        .write_stdin("1\n2\n2\n")
         .assert()
         .success()
-        .stdout(predicate::str::contains(
-            "Samples = 2; Min = 2.400; Max = 4.200",
-        ));
+        .stdout(predicate::str::contains("Samples = 3;"));
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "lower,upper,count\n1,1.5,1\n1.5,2,2\n"
+    );
+    let path = dir.path().join("points.tsv");
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("plot")
+        .arg("--export-data")
+        .arg(&path)
+        .write_stdin("1\n2\n")
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "x\tvalue\n0\t1\n1\t2\n"
+    );
 }
 
 #[test]
-fn test_common() {
+fn test_global_format() {
     let mut cmd = Command::cargo_bin("lowcharts").unwrap();
-    match NamedTempFile::new() {
-        Ok(ref mut file) => {
-            writeln!(file, "foo").unwrap();
-            writeln!(file, "x").unwrap();
-            writeln!(file, "foo").unwrap();
-            writeln!(file, "x").unwrap();
-            writeln!(file, "foo").unwrap();
-            cmd.arg("--color")
-                .arg("no")
-                .arg("common-terms")
-                .arg(file.path().to_str().unwrap())
-                .arg("--lines")
-                .arg("4")
-                .assert()
-                .success()
-                .stdout(predicate::str::contains("Each ∎ represents a count of 1\n"))
-                .stdout(predicate::str::contains("\n[foo] [3] ∎∎∎\n"))
-                .stdout(predicate::str::contains("\n[  x] [2] ∎∎\n"));
-        }
-        Err(_) => panic!("Could not create temp file"),
+    cmd.arg("--output-format")
+        .arg("csv")
+        .arg("hist")
+        .arg("--intervals")
+        .arg("2")
+        .arg("--sample")
+        .arg("3")
+        .write_stdin("1\n2\n2\n2\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("lower,upper,count\n"))
+        .stdout(predicate::str::contains("Sampled").not());
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--output-format")
+        .arg("json")
+        .arg("matches")
+        .arg("a")
+        .arg("b")
+        .write_stdin("a\nb\na\n")
+        .assert()
+        .success()
+        .stdout("[{\"count\":2,\"label\":\"a\"},{\"count\":1,\"label\":\"b\"}]\n");
+    // The format can be given after the sub-command too
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--output-format")
+        .arg("plain-text")
+        .write_stdin("1\n2\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Samples = 2;"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--output-format")
+        .arg("json")
+        .arg("summary")
+        .write_stdin("1\n2\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"samples\":2"));
+}
+
+// Runs lowcharts with `--output-format json`, checking that all that it
+// writes to standard output is JSON
+fn json_output(args: &[&str], stdin: &str) -> (serde_json::Value, String) {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    let output = cmd
+        .arg("--output-format")
+        .arg("json")
+        .args(args)
+        .write_stdin(stdin)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let value = serde_json::from_slice(&output.stdout).unwrap();
+    (value, String::from_utf8(output.stderr).unwrap())
+}
+
+#[test]
+fn test_json_format() {
+    let times = "[2021-04-15 04:25:00] a\n\
+                 [2021-04-15 04:26:00] b\n\
+                 [2021-04-15 04:40:00] a\n\
+                 [2021-04-15 04:41:00] b\n";
+    let (gaps, _) = json_output(&["gaps"], times);
+    assert_eq!(gaps[0]["seconds"], 840.0);
+    assert_eq!(gaps[0]["start"], "2021-04-15T04:26:00+00:00");
+    let (period, _) = json_output(&["period"], times);
+    assert!(period.is_array());
+    let (sessions, _) = json_output(&["sessions", "--regex", "([ab])$"], times);
+    assert_eq!(sessions[0]["key"], "a");
+    assert_eq!(sessions[0]["lines"], 2);
+    let (deltas, stderr) = json_output(&["interarrival", "--intervals", "2"], times);
+    assert_eq!(deltas[0]["count"], 2);
+    assert!(stderr.contains("Coefficient of variation"));
+    let (distinct, _) = json_output(&["distinct"], "a\nb\na\n");
+    assert_eq!(distinct[0]["distinct"], 2.0);
+    let mut baseline = NamedTempFile::new().unwrap();
+    let (stats, _) = json_output(&["summary"], "1\n2\n3\n4\n");
+    baseline.write_all(stats.to_string().as_bytes()).unwrap();
+    let path = baseline.path().to_str().unwrap();
+    let (buckets, stderr) = json_output(&["hist", "--baseline", path], "1\n2\n3\n8\n");
+    assert_eq!(buckets[0]["lower"], 1.0);
+    assert!(stderr.contains("Compared with baseline"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--output-format")
+        .arg("csv")
+        .arg("summary")
+        .write_stdin("1\n2\n3\n4\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("statistic,value\navg,2.5\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--output-format")
+        .arg("json")
+        .arg("period")
+        .arg("--plot")
+        .write_stdin(times)
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_json_format_of_pairs() {
+    let mut other = NamedTempFile::new().unwrap();
+    other.write_all(b"3\n4\n4\n").unwrap();
+    let path = other.path().to_str().unwrap();
+    let (buckets, _) = json_output(&["compare", "-", path, "--intervals", "2"], "1\n2\n3\n");
+    assert_eq!(buckets[0]["before"], 2);
+    assert_eq!(buckets[1]["after"], 3);
+    let (buckets, _) = json_output(
+        &["hist", "--overlay", path, "--intervals", "2"],
+        "1\n2\n3\n",
+    );
+    assert_eq!(buckets[0]["first"], 2);
+    assert_eq!(buckets[1]["second"], 3);
+    // Plots with no data fail
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--output-format")
+        .arg("json")
+        .arg("zipf")
+        .write_stdin("a\nb\na\n")
+        .assert()
+        .code(2)
+        .stdout("");
+}
+
+#[test]
+fn test_markdown_format() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("yes")
+        .arg("matches")
+        .arg("--output-format")
+        .arg("markdown")
+        .arg("a|b")
+        .arg("c")
+        .write_stdin("a|b\nc\nc\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "| Bucket | Count |\n\
+             |--------|------:|\n\
+             | a\\|b   |     1 |\n\
+             | c      |     2 |\n\
+             \n\
+             ```text\n",
+        ))
+        .stdout(predicate::str::contains("[c  ] [2] ##\n```\n"))
+        .stdout(predicate::str::contains("\x1b").not());
+}
+
+#[test]
+fn test_hist_overlay() {
+    let mut file = NamedTempFile::new().unwrap();
+    writeln!(file, "3\n4\n4").unwrap();
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--intervals")
+        .arg("3")
+        .arg("--overlay")
+        .arg(file.path())
+        .write_stdin("1\n2\n3\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 6; Min = 1.000; Max = 4.000\n",
+        ))
+        .stdout(predicate::str::contains("∎ - (3); + "))
+        .stdout(predicate::str::contains("[1.000 .. 2.000] [1] [0] ∎\n"))
+        .stdout(predicate::str::contains("[3.000 .. 4.000] [1] [3] #++\n"));
+}
+
+#[test]
+fn test_gaps() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("gaps")
+        .arg("--threshold")
+        .arg("30s")
+        .write_stdin(
+            "2021-04-28 06:25:00 start\n\
+             2021-04-28 06:25:20 working\n\
+             2021-04-28 06:27:05 working\n\
+             2021-04-28 06:27:10 done\n",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Matches: 4. Gaps longer than 30s: 1.\n\
+             [2021-04-28 06:25:20] .. [2021-04-28 06:27:05] 1m 45s\n",
+        ));
+}
+
+#[test]
+fn test_timeplot() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("timeplot")
+        .arg("--regex")
+        .arg("rt=([0-9.]+)")
+        .arg("--width")
+        .arg("4")
+        .arg("--height")
+        .arg("3")
+        .arg("--precision")
+        .arg("1")
+        .write_stdin(
+            "2021-04-28 06:25:00 rt=1\n\
+             2021-04-28 06:25:20 rt=3\n\
+             2021-04-28 06:27:05 rt=4\n\
+             2021-04-28 06:29:10 rt=none\n\
+             2021-04-28 06:31:20 rt=2\n",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 4; Min = 1.0; Max = 4.0\n",
+        ))
+        .stdout(predicate::str::contains(
+            "[3.0]  ●  \n\
+             [2.0] ●  ●\n\
+             [1.0]     \n      06:25:00 06:31:20\n",
+        ));
+}
+
+#[test]
+fn test_timepercentiles() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("timepercentiles")
+        .arg("--regex")
+        .arg("rt=([0-9.]+)")
+        .arg("--intervals")
+        .arg("3")
+        .arg("--width")
+        .arg("80")
+        .arg("--precision")
+        .arg("1")
+        .write_stdin(
+            "2021-04-28 06:25:00 rt=1\n\
+             2021-04-28 06:25:20 rt=3\n\
+             2021-04-28 06:27:00 rt=4\n\
+             2021-04-28 06:31:20 rt=2\n\
+             2021-04-28 06:31:20 rt=8\n",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[06:25:00] [3] p50 3.0 ∎∎∎∎∎        p95 4.0 ∎∎∎∎∎∎       p99 4.0 ∎∎∎∎∎∎\n\
+             [06:27:06] [0]\n\
+             [06:29:13] [2] p50 8.0 ∎∎∎∎∎∎∎∎∎∎∎∎ p95 8.0 ∎∎∎∎∎∎∎∎∎∎∎∎ p99 8.0 ∎∎∎∎∎∎∎∎∎∎∎∎\n",
+        ));
+}
+
+#[test]
+fn test_heatmap() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("heatmap")
+        .arg("--regex")
+        .arg("rt=([0-9.]+)")
+        .arg("--width")
+        .arg("3")
+        .arg("--height")
+        .arg("2")
+        .arg("--precision")
+        .arg("0")
+        .write_stdin(
+            "2021-04-28 06:25:00 rt=1\n\
+             2021-04-28 06:25:00 rt=1\n\
+             2021-04-28 06:26:00 rt=1\n\
+             2021-04-28 06:26:00 rt=9\n\
+             2021-04-28 06:27:00 rt=9\n",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "▒ <= 1; █ <= 2\n\
+             [5]  ▒▒\n\
+             [1] █▒ \n",
+        ));
+}
+
+#[test]
+fn test_common_terms_over_time() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("common-terms")
+        .arg("--regex")
+        .arg("(GET|POST) ")
+        .arg("--over-time")
+        .arg("2")
+        .arg("--intervals")
+        .arg("2")
+        .write_stdin(
+            "2021-04-28 06:25:00 GET /a\n\
+             2021-04-28 06:25:10 GET /b\n\
+             2021-04-28 06:26:00 POST /a\n\
+             2021-04-28 06:27:00 GET /c\n\
+             2021-04-28 06:27:00 GET /a\n",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "∎ GET: 4 (80.00%).\n∎ POST: 1 (20.00%).\n",
+        ))
+        .stdout(predicate::str::contains(
+            "[06:25:00.000] [2/0] ∎∎\n\
+             [06:26:00.000] [2/1] ∎∎∎\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("common-terms")
+        .arg("--over-time")
+        .arg("6")
+        .write_stdin("2021-04-28 06:25:00 GET /a\n")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_timehist_values() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("timehist")
+        .arg("--value-regex")
+        .arg("bytes=([0-9]+)")
+        .arg("--agg")
+        .arg("max")
+        .arg("--intervals")
+        .arg("2")
+        .arg("--width")
+        .arg("6")
+        .write_stdin(
+            "2021-04-28 06:25:00 GET bytes=1000\n\
+             2021-04-28 06:25:10 GET bytes=3000\n\
+             2021-04-28 06:26:00 GET\n\
+             2021-04-28 06:27:00 GET bytes=500\n",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Matches: 3.\n\
+             Maximum of the values in every bucket\n\
+             [06:25:00.000] [3000] ∎∎∎∎∎∎\n\
+             [06:26:00.000] [ 500] ∎\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("timehist")
+        .arg("--value-regex")
+        .arg("bytes=([0-9]+)")
+        .arg("--with-counts")
+        .arg("--intervals")
+        .arg("2")
+        .arg("--width")
+        .arg("8")
+        .write_stdin(
+            "2021-04-28 06:25:00 GET bytes=1000\n\
+             2021-04-28 06:25:10 GET bytes=3000\n\
+             2021-04-28 06:27:00 GET bytes=500\n",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Count and sum of the values in every bucket\n\
+             [06:25:00.000] [2] ∎∎∎∎ [4000] ∎∎∎∎\n\
+             [06:26:00.000] [1] ∎∎   [ 500] ∎\n",
+        ));
+}
+
+#[test]
+fn test_outliers() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--outliers")
+        .arg("--precision")
+        .arg("1")
+        .write_stdin("5\n1\n2\n3\n4\n6\n7\n90\n-30\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Outliers (IQR) = 2; Fences = [-4.0 .. 12.0]; Most extreme = 90.0, -30.0\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("plot")
+        .arg("--outliers=zscore")
+        .write_stdin("1\n2\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Outliers (z-score) = 0; Fences = [",
+        ));
+}
+
+#[test]
+fn test_trim() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--trim")
+        .arg("10%")
+        .arg("--precision")
+        .arg("1")
+        .write_stdin("5\n1\n2\n3\n4\n6\n7\n8\n900\n-300\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Trimmed (10%): Average = 4.5; STD = 2.291; Winsorized average = 4.5\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("plot")
+        .arg("--trim")
+        .arg("0.5")
+        .write_stdin("1\n2\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("between 0 and 50%"));
+}
+
+#[test]
+fn test_confidence() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--confidence")
+        .arg("--precision")
+        .arg("2")
+        .write_stdin("1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "95% confidence interval of average = [3.33 .. 7.67]\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("plot")
+        .arg("--confidence=99%")
+        .write_stdin("1\n2\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "99% confidence interval of average = [",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--confidence=100")
+        .write_stdin("1\n2\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("between 0 and 100%"));
+}
+
+#[test]
+fn test_pre_aggregated() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--pre-aggregated")
+        .arg("--intervals")
+        .arg("2")
+        .arg("--precision")
+        .arg("1")
+        .write_stdin("1 30\n2,10\n4 60\nfoo 3\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 100; Min = 1.0; Max = 4.0\n\
+             Average = 2.9; Variance = 1.890; STD = 1.375\n\
+             p50 = 4.0; p90 = 4.0; p95 = 4.0; p99 = 4.0\n",
+        ))
+        .stdout(predicate::str::contains("[1.0 .. 2.5] [40] "))
+        .stdout(predicate::str::contains("[2.5 .. 4.0] [60] "));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--pre-aggregated")
+        .arg("--trim")
+        .arg("1%")
+        .write_stdin("1 30\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_hdr() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("latency.hlog");
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--intervals")
+        .arg("2")
+        .arg("--hdr-output")
+        .arg(&path)
+        .arg("--hdr-divisor")
+        .arg("10")
+        .write_stdin("1\n1\n3\n")
+        .assert()
+        .success();
+    let log = std::fs::read_to_string(&path).unwrap();
+    assert!(log.contains("#[MaxValueDivisor: 10.000]\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--hdr")
+        .arg("--hdr-divisor")
+        .arg("10")
+        .arg("--precision")
+        .arg("1")
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 3; Min = 1.5; Max = 2.5\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--hdr")
+        .write_stdin(
+            "       Value   Percentile   TotalCount 1/(1-Percentile)\n\
+             \x20      1.000     0.000000            4         1.00\n\
+             \x20      9.000     1.000000           10          inf\n",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 10; Min = 1.000; Max = 9.000\n",
+        ));
+}
+
+#[test]
+fn test_human_input() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--human-input")
+        .arg("--precision")
+        .arg("0")
+        .write_stdin("1.5K\n2k\n1KiB\n500\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 4; Min = 500; Max = 2000\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("plot")
+        .arg("--human-input=iec")
+        .arg("--regex")
+        .arg("^(\\S+)\\s")
+        .write_stdin("4.0K\tsrc\n1.0M\ttarget\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 2; Min = 4 K; Max = 1049 K\n",
+        ));
+}
+
+#[test]
+fn test_duration_input() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--duration-input=ms")
+        .arg("--regex")
+        .arg("took ([0-9.a-z]+)")
+        .arg("--precision")
+        .arg("1")
+        .write_stdin("GET / took 250ms\nGET /a took 1.2s\nGET /b took 3m20s\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 3; Min = 250.0; Max = 200000.0\n",
+        ));
+}
+
+#[test]
+fn test_series() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--series")
+        .arg("--regex")
+        .arg("rt=(?P<rt>[0-9.]+) up=(?P<up>[0-9.]+)")
+        .arg("--intervals")
+        .arg("2")
+        .arg("--precision")
+        .arg("1")
+        .write_stdin("rt=1 up=2\nrt=3 up=4\nrt=1.5 up=3.5\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 6; Min = 1.0; Max = 4.0\n",
+        ))
+        .stdout(predicate::str::contains("∎ rt (3); + up (3)\n"))
+        .stdout(predicate::str::contains("[1.0 .. 2.5] [2/1] ∎∎+\n"))
+        .stdout(predicate::str::contains("[2.5 .. 4.0] [1/2] ∎++\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("plot")
+        .arg("--series")
+        .arg("--regex")
+        .arg("rt=([0-9.]+)")
+        .write_stdin("rt=1\n")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_hist_group_by() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--group-by")
+        .arg("GET (?P<key>/[a-z]+)")
+        .arg("--regex")
+        .arg("took ([0-9.]+)")
+        .arg("--intervals")
+        .arg("2")
+        .arg("--precision")
+        .arg("1")
+        .write_stdin("GET /api took 1\nGET /login took 4\nGET /api took 1.5\nPOST /api took 9\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 3; Min = 1.0; Max = 4.0\n",
+        ))
+        .stdout(predicate::str::contains(
+            "/api (2)\n[1.0 .. 2.5] [2] ∎∎\n[2.5 .. 4.0] [0] \n",
+        ))
+        .stdout(predicate::str::contains(
+            "/login (1)\n[1.0 .. 2.5] [0] \n[2.5 .. 4.0] [1] ∎\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--group-by")
+        .arg("(")
+        .write_stdin("1\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to parse regex"));
+}
+
+#[test]
+fn test_matchbar_count_occurrences() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("matches")
+        .arg("--count-occurrences")
+        .arg("foo")
+        .arg("bar")
+        .write_stdin("foo foo bar\nfoo\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Matches: 4."))
+        .stdout(predicate::str::contains("\n[foo] [3] ∎∎∎\n"));
+}
+
+#[test]
+fn test_matchbar_word() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("matches")
+        .arg("--word")
+        .arg("ERR")
+        .write_stdin("ERROR 1\nERR 2\nTERRIBLE 3\nan ERR: 4\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\n[ERR] [2] ∎∎\n"));
+}
+
+#[test]
+fn test_matchbar_invert() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("matches")
+        .arg("--invert")
+        .arg("foo")
+        .arg("bar")
+        .write_stdin("foo\nbar\nbaz\nqux\nfoo bar\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\n[<no match>] [2] ∎∎\n"));
+}
+
+#[test]
+fn test_splittime() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("split-timehist")
+        .arg("1")
+        .arg("2")
+        .arg("3")
+        .arg("4")
+        .arg("5")
+        .arg("6")
+        .write_stdin("1619655527.888165 1\n1619655528.888165 6\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("∎ 3: 0 (0.00%).\n"))
+        .stdout(predicate::str::contains("+ 6: 2 (25.00%).\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("split-timehist")
+        .arg("A")
+        .arg("B")
+        .arg("C")
+        .arg("--intervals")
+        .arg("2")
+        .write_stdin("1619655527.888165 A\n1619655528.888165 A\n1619655527.888165 B\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Matches: 3."))
+        .stdout(predicate::str::contains("A: 2"))
+        .stdout(predicate::str::contains("B: 1 (33.33%)."))
+        .stdout(predicate::str::contains("C: 0 (0.00%)."))
+        .stdout(predicate::str::contains("Each ∎ represents a count of 1\n"))
+        .stdout(predicate::str::contains("[00:18:47.888165] [1/1/0] ∎∎\n"))
+        .stdout(predicate::str::contains("[00:18:48.388165] [1/0/0] ∎\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("split-timehist")
+        .arg("A")
+        .arg("B")
+        .arg("--sort-legend")
+        .write_stdin("1619655527.888165 A\n1619655528.888165 B\n1619655529.888165 B\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Matches: 3.\n∎ B: 2 (66.67%).\n∎ A: 1 (33.33%).\n",
+        ));
+}
+
+#[test]
+fn test_splittime_style() {
+    let input = "1619655527.888165 A\n1619655528.888165 A\n1619655527.888165 B\n";
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("split-timehist")
+        .arg("A")
+        .arg("B")
+        .arg("--intervals")
+        .arg("2")
+        .arg("--style")
+        .arg("grouped")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[00:18:47.888165] [1] ∎\n                  [1] ∎\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("split-timehist")
+        .arg("A")
+        .arg("B")
+        .arg("--intervals")
+        .arg("2")
+        .arg("--style")
+        .arg("percent")
+        .arg("--width")
+        .arg("30")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Each ∎ represents 3.33% of the values\n",
+        ))
+        .stdout(predicate::str::contains("[ 50.00%/50.00%] ∎∎∎∎∎∎∎∎∎∎"))
+        .stdout(predicate::str::contains("[100.00%/ 0.00%] ∎∎∎∎∎∎∎∎∎∎"));
+}
+
+#[test]
+fn test_splittime_group_by_regex() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("split-timehist")
+        .arg("--group-by-regex")
+        .arg(r" (?P<key>\d{3})$")
+        .arg("--top")
+        .arg("2")
+        .arg("--intervals")
+        .arg("2")
+        .write_stdin(
+            "2021-04-15T04:25:00Z GET 200\n2021-04-15T04:26:00Z GET 404\n\
+             2021-04-15T04:27:00Z GET 200\n2021-04-15T04:28:00Z GET 500\n",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Matches: 3.\n∎ 200: 2 (66.67%).\n∎ 404: 1 (33.33%).\n",
+        ))
+        .stdout(predicate::str::contains("500").not())
+        .stdout(predicate::str::contains("[04:26:00.000] [1/1] ∎∎\n"));
+}
+
+#[test]
+fn test_timehist_anomalies() {
+    let mut input = String::new();
+    for second in 0..6 {
+        let times = if second == 4 { 20 } else { 2 };
+        for _ in 0..times {
+            input.push_str(&format!("2021-04-15T04:25:0{second}Z GET\n"));
+        }
+    }
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("timehist")
+        .arg("--intervals")
+        .arg("6")
+        .arg("--anomalies")
+        .write_stdin(input.as_str())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[20] ∎∎∎∎∎∎∎∎∎∎∎∎∎∎∎∎∎∎∎∎ ◀\n"))
+        .stdout(predicate::str::contains("1 spike over 3 sigmas:\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("timehist")
+        .arg("--anomalies=-1")
+        .write_stdin(input.as_str())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("should be a positive number"));
+}
+
+#[test]
+fn test_period() {
+    let mut input = String::new();
+    for second in 0..600 {
+        let times = if second % 60 < 5 { 10 } else { 0 };
+        for _ in 0..times {
+            input.push_str(&format!(
+                "2021-04-15T04:{:02}:{:02}Z GET\n",
+                second / 60,
+                second % 60
+            ));
+        }
+    }
+    input.push_str("2021-04-15T04:10:00Z GET\n");
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("period")
+        .arg("--intervals")
+        .arg("120")
+        .arg("--plot")
+        .arg("--height")
+        .arg("5")
+        .write_stdin(input.as_str())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Buckets of 5s.\n"))
+        .stdout(predicate::str::contains("Strong 1m cycle (autocorrelation"))
+        .stdout(predicate::str::contains("Samples = 61;"));
+}
+
+#[test]
+fn test_dash() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("dash")
+        .arg("--panel")
+        .arg("timehist --intervals 2")
+        .arg("-p")
+        .arg("matches GET 'NOT FOUND'")
+        .write_stdin("2021-04-15T04:25:00Z GET 200\n2021-04-15T04:26:00Z GET 404 NOT FOUND\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("── timehist --intervals 2 \n"))
+        .stdout(predicate::str::contains("[04:25:30.000] [1] ∎\n"))
+        .stdout(predicate::str::contains("── matches GET 'NOT FOUND' \n"))
+        .stdout(predicate::str::contains("[NOT FOUND] [1] ∎\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("dash")
+        .arg("--window")
+        .arg("1h")
+        .arg("--panel")
+        .arg("matches GET")
+        .write_stdin("GET\nGET\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[GET] [2] ∎∎\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("dash")
+        .arg("--panel")
+        .arg("timehist --bogus")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid panel 'timehist --bogus'"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("dash")
+        .arg("--panel")
+        .arg("hist --outliers")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--outliers cannot be used in a panel",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("dash")
+        .arg("--panel")
+        .arg("timehist")
+        .write_stdin("")
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn test_interarrival() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("interarrival")
+        .arg("-i")
+        .arg("2")
+        .write_stdin(
+            "2021-04-15T04:25:00.0Z a\n2021-04-15T04:25:00.1Z a\n\
+             2021-04-15T04:25:00.2Z a\n2021-04-15T04:25:10.0Z a\n\
+             2021-04-15T04:25:10.1Z a\n2021-04-15T04:25:30.0Z a\n",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 5; Min = 0.1; Max = 19.9\n",
+        ))
+        .stdout(predicate::str::contains("[ 0.1 .. 10.0] [4] ∎∎∎∎\n"))
+        .stdout(predicate::str::contains(
+            "Coefficient of variation = 1.32; events are arriving in bursts.\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("interarrival")
+        .write_stdin("2021-04-15T04:25:00Z a\n")
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn test_sessions() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("sessions")
+        .arg("--regex")
+        .arg(r"job=(?P<key>\w+)")
+        .arg("--lines")
+        .arg("2")
+        .arg("-i")
+        .arg("2")
+        .write_stdin(
+            "2021-04-15T04:25:00Z job=a start\n2021-04-15T04:25:10Z job=b start\n\
+             2021-04-15T04:26:00Z job=a end\n2021-04-15T04:27:10Z job=b end\n\
+             2021-04-15T04:28:00Z job=c start\n",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Keys: 3. Longest sessions:\n"))
+        .stdout(predicate::str::contains(
+            "[b] [04:25:10.000] .. [04:27:10.000] 2m (2 lines)\n",
+        ))
+        .stdout(predicate::str::contains("[c]").not())
+        .stdout(predicate::str::contains("[ 60.00 .. 120.00] [2] ∎∎\n"));
+}
+
+#[test]
+fn test_describe() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("describe")
+        .arg("--precision")
+        .arg("1")
+        .arg("--width")
+        .arg("30")
+        .write_stdin("1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 10; Min = 1.0; Max = 10.0\n",
+        ))
+        .stdout(predicate::str::contains("Outliers (IQR) = 0"))
+        .stdout(predicate::str::contains(concat!(
+            "Percentiles:\n",
+            "   p1    p5   p10   p25   p50   p75   p90   p95   p99 p99.9\n",
+        )))
+        .stdout(predicate::str::contains("[ 1.0 ..  2.8] [2] ∎∎\n"));
+}
+
+#[test]
+fn test_summary() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("summary")
+        .arg("--max")
+        .arg("10")
+        .arg("--confidence")
+        .write_stdin("1\n2\n3\n4\n100\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 4; Min = 1.000; Max = 4.000\n",
+        ))
+        .stdout(predicate::str::contains(
+            "95% confidence interval of average",
+        ))
+        .stdout(predicate::str::contains("∎").not());
+    // Levels need an equal sign, and the usage says so
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("summary")
+        .arg("--help")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--confidence[=<LEVEL>]\n"))
+        .stdout(predicate::str::contains("like '--confidence=99%'"))
+        .stdout(predicate::str::contains("--outliers[=<METHOD>]\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("summary")
+        .arg("--regex")
+        .arg("took ([0-9]+) ms")
+        .arg("--output-format")
+        .arg("json")
+        .write_stdin("took 1 ms\ntook 3 ms\nnone\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""samples":2"#))
+        .stdout(predicate::str::contains(r#""avg":2.0"#))
+        .stdout(predicate::str::contains("confidence").not());
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("summary").write_stdin("none\n").assert().code(1);
+}
+
+#[test]
+fn test_baseline() {
+    let mut baseline = NamedTempFile::new().unwrap();
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    let output = cmd
+        .arg("summary")
+        .arg("--output-format")
+        .arg("json")
+        .write_stdin("1\n2\n3\n4\n")
+        .output()
+        .unwrap();
+    baseline.write_all(&output.stdout).unwrap();
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("summary")
+        .arg("--precision")
+        .arg("1")
+        .arg("--baseline")
+        .arg(baseline.path())
+        .arg("--tolerance")
+        .arg("30%")
+        .write_stdin("1\n2\n3\n5\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Compared with baseline (tolerance 30%):\n",
+        ))
+        .stdout(predicate::str::contains(
+            "avg     2.5 -> 2.8 +0.2 (+10.0%)\n",
+        ))
+        .stdout(predicate::str::contains(
+            "max     4.0 -> 5.0 +1.0 (+25.0%)\n",
+        ))
+        .stdout(predicate::str::contains(
+            "std     1.1 -> 1.5 +0.4 (+32.3%) regression\n",
+        ))
+        .stderr(predicate::str::contains(
+            "Regressions since the baseline: std",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("summary")
+        .arg("--output-format")
+        .arg("json")
+        .arg("--baseline")
+        .arg(baseline.path())
+        .write_stdin("1\n2\n3\n4\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""baseline":{"#))
+        .stdout(predicate::str::contains(r#""regression":true"#).not());
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--baseline")
+        .arg("/nonexistent/baseline.json")
+        .write_stdin("1\n2\n")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Could not read baseline"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("summary")
+        .arg("--baseline")
+        .arg(baseline.path())
+        .arg("--tolerance=-5%")
+        .write_stdin("1\n2\n")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "The tolerance should be non-negative (like 0, 5% or 0.05)",
+        ));
+}
+
+#[test]
+fn test_merge() {
+    let first = NamedTempFile::new().unwrap();
+    let second = NamedTempFile::new().unwrap();
+    for (file, input) in [(&first, "1\n2\n3\n4\n"), (&second, "1\n4\n4\n4\n")] {
+        let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+        cmd.arg("hist")
+            .arg("--intervals")
+            .arg("3")
+            .arg("--save-state")
+            .arg(file.path())
+            .write_stdin(input)
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("merge")
+        .arg("--precision")
+        .arg("1")
+        .arg(first.path())
+        .arg(second.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 8; Min = 1.0; Max = 4.0\n",
+        ))
+        .stdout(predicate::str::contains("[1.0 .. 2.0] [2] ∎∎\n"))
+        .stdout(predicate::str::contains("[3.0 .. 4.0] [5] ∎∎∎∎∎\n"))
+        .stderr(predicate::str::is_empty());
+    let timehist = NamedTempFile::new().unwrap();
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("timehist")
+        .arg("--save-state")
+        .arg(timehist.path())
+        .write_stdin("[2021-04-15T06:25:31+00:00] a\n[2021-04-15T06:26:31+00:00] b\n")
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("merge")
+        .arg(first.path())
+        .arg(timehist.path())
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Not the state of an histogram"));
+}
+
+#[test]
+fn test_state() {
+    let dir = tempfile::tempdir().unwrap();
+    let state = dir.path().join("state.json");
+    let mut input = NamedTempFile::new().unwrap();
+    write!(input, "1\n2\n3\n4\n5").unwrap();
+    let path = input.path().to_path_buf();
+    let run = || {
+        let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+        cmd.arg("--color")
+            .arg("no")
+            .arg("hist")
+            .arg("--intervals")
+            .arg("2")
+            .arg("--precision")
+            .arg("1")
+            .arg("--state")
+            .arg(&state)
+            .arg(&path)
+            .assert()
+            .success()
+    };
+    // The last line is incomplete, so it is left for later
+    run().stdout(predicate::str::contains(
+        "Samples = 4; Min = 1.0; Max = 4.0\n",
+    ));
+    write!(input, "\n2\n").unwrap();
+    run()
+        .stdout(predicate::str::contains(
+            "Samples = 6; Min = 1.0; Max = 5.0\n",
+        ))
+        .stderr(predicate::str::contains("approximate"));
+    writeln!(input, "3").unwrap();
+    run()
+        .stdout(predicate::str::contains(
+            "Samples = 7; Min = 1.0; Max = 5.0\n",
+        ))
+        .stderr(predicate::str::is_empty());
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--state")
+        .arg(&state)
+        .write_stdin("1\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("must be a regular file"));
+}
+
+#[test]
+fn test_plot_downsampler() {
+    let input = "1\n1\n1\n9\n1\n1\n1\n1\n1\n1\n";
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("plot")
+        .arg("--width")
+        .arg("4")
+        .arg("--height")
+        .arg("4")
+        .arg("--precision")
+        .arg("0")
+        .arg("--downsampler")
+        .arg("lttb")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[7]  ●  \n"))
+        .stdout(predicate::str::contains("[1] ● ●●\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("plot")
+        .arg("--downsampler")
+        .arg("median")
+        .write_stdin(input)
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_plot_stream() {
+    let input: String = (1..=1000).map(|i| format!("{i}\n")).collect();
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("plot")
+        .arg("--width")
+        .arg("10")
+        .arg("--height")
+        .arg("3")
+        .arg("--precision")
+        .arg("1")
+        .arg("--stream")
+        .write_stdin(input.clone())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 1000; Min = 1.0; Max = 1000.0\n",
+        ))
+        .stdout(predicate::str::contains("[667.0]        ●●●\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("plot")
+        .arg("--stream")
+        .arg("--sample")
+        .arg("10")
+        .write_stdin(input)
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_windows_input() {
+    let mut file = NamedTempFile::new().unwrap();
+    let text: Vec<u8> = "\u{FEFF}1\r\n2\r\n3\r\n"
+        .encode_utf16()
+        .flat_map(|u| u.to_le_bytes())
+        .collect();
+    file.write_all(&text).unwrap();
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 3; Min = 1.000; Max = 3.000\n",
+        ));
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(b"\xEF\xBB\xBF2021-04-15 04:25:00 a\xFF\r\n2021-04-15 04:25:30 b\r\n")
+        .unwrap();
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("timehist")
+        .arg("--intervals")
+        .arg("2")
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Matches: 2."));
+}
+
+#[test]
+fn test_delimiter() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--delimiter")
+        .arg("\\0")
+        .write_stdin("1\x002\x003")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 3; Min = 1.000; Max = 3.000\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--delimiter")
+        .arg("json-array")
+        .arg("--json-field")
+        .arg(".took")
+        .write_stdin(r#"[{"took": 2}, {"took": 4, "tags": ["a", "b"]}]"#)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 2; Min = 2.000; Max = 4.000\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--delimiter")
+        .arg("\\q")
+        .write_stdin("1")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_multiline() {
+    let log = "2021-04-15 04:25:00 ERROR oops\n\
+               java.lang.NullPointerException: ERROR\n    \
+               at Foo.bar(Foo.java:42)\n\
+               2021-04-15 04:25:01 INFO fine\n";
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("matches")
+        .arg("--multiline")
+        .arg("^\\d{4}-")
+        .arg("ERROR")
+        .arg("INFO")
+        .write_stdin(log)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[ERROR] [1]"))
+        .stdout(predicate::str::contains("[INFO ] [1]"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("matches")
+        .arg("--multiline")
+        .arg("^\\d")
+        .arg("--multiline-join")
+        .arg("^\\s")
+        .arg("ERROR")
+        .write_stdin(log)
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_all_matches() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--regex")
+        .arg("=([0-9]+)ms")
+        .arg("--all-matches")
+        .arg("--parallel")
+        .write_stdin("shard1=12ms shard2=15ms\nshard1=10ms\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 3; Min = 10.000; Max = 15.000\n",
+        ));
+}
+
+#[test]
+fn test_line_selection() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--skip")
+        .arg("1")
+        .arg("--head")
+        .arg("4")
+        .arg("--tail")
+        .arg("3")
+        .write_stdin("header\n1\n2\n3\n4\n5\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 3; Min = 2.000; Max = 4.000\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--head")
+        .arg("-1")
+        .write_stdin("1\n")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_line_sampling() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--every")
+        .arg("2")
+        .write_stdin("1\n2\n3\n4\n5\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "Sampled 3 of 5 lines (60.00%)\n",
+        ))
+        .stdout(predicate::str::contains(
+            "Samples = 3; Min = 1.000; Max = 5.000\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("timehist")
+        .arg("--sample-rate")
+        .arg("1")
+        .arg("--intervals")
+        .arg("2")
+        .write_stdin("2021-04-15 04:25:00 a\n2021-04-15 04:25:30 b\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "Sampled 2 of 2 lines (100.00%)\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--sample-rate")
+        .arg("2")
+        .write_stdin("1\n")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_plot() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    match NamedTempFile::new() {
+        Ok(ref mut file) => {
+            writeln!(file, "1").unwrap();
+            writeln!(file, "2").unwrap();
+            writeln!(file, "3").unwrap();
+            writeln!(file, "4").unwrap();
+            writeln!(file, "none").unwrap();
+            cmd.arg("--verbose")
+                .arg("--color")
+                .arg("no")
+                .arg("plot")
+                .arg(file.path().to_str().unwrap())
+                .arg("--height")
+                .arg("4")
+                .assert()
+                .success()
+                .stdout(predicate::str::contains(
+                    "Samples = 4; Min = 1.000; Max = 4.000\n",
+                ))
+                .stdout(predicate::str::contains("\n[3.250]    ●"))
+                .stdout(predicate::str::contains("\n[2.500]   ●"))
+                .stdout(predicate::str::contains("\n[1.750]  ●"))
+                .stdout(predicate::str::contains("\n[1.000] ●"))
+                .stderr(predicate::str::contains("[DEBUG] Cannot parse float"));
+        }
+        Err(_) => panic!("Could not create temp file"),
+    }
+}
+
+#[test]
+fn test_plot_precision() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    match NamedTempFile::new() {
+        Ok(ref mut file) => {
+            writeln!(file, "1").unwrap();
+            writeln!(file, "2").unwrap();
+            writeln!(file, "3").unwrap();
+            writeln!(file, "4").unwrap();
+            writeln!(file, "none").unwrap();
+            cmd.arg("--verbose")
+                .arg("--color")
+                .arg("no")
+                .arg("plot")
+                .arg(file.path().to_str().unwrap())
+                .arg("--height")
+                .arg("4")
+                .arg("--precision")
+                .arg("1")
+                .assert()
+                .success()
+                .stdout(predicate::str::contains(
+                    "Samples = 4; Min = 1.0; Max = 4.0\n",
+                ))
+                .stdout(predicate::str::contains("\n[3.2]    ●"))
+                .stdout(predicate::str::contains("\n[2.5]   ●"))
+                .stdout(predicate::str::contains("\n[1.8]  ●"))
+                .stdout(predicate::str::contains("\n[1.0] ●"))
+                .stderr(predicate::str::contains("[DEBUG] Cannot parse float"));
+        }
+        Err(_) => panic!("Could not create temp file"),
+    }
+}
+
+#[test]
+fn test_hist_negative_min() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--min")
+        .arg("-1")
+        .arg("--max")
+        .arg("10.1")
+        .write_stdin("4.2\n2.4\n-2\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 2; Min = 2.400; Max = 4.200",
+        ));
+}
+
+#[test]
+fn test_common() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    match NamedTempFile::new() {
+        Ok(ref mut file) => {
+            writeln!(file, "foo").unwrap();
+            writeln!(file, "x").unwrap();
+            writeln!(file, "foo").unwrap();
+            writeln!(file, "x").unwrap();
+            writeln!(file, "foo").unwrap();
+            cmd.arg("--color")
+                .arg("no")
+                .arg("common-terms")
+                .arg(file.path().to_str().unwrap())
+                .arg("--lines")
+                .arg("4")
+                .assert()
+                .success()
+                .stdout(predicate::str::contains("Each ∎ represents a count of 1\n"))
+                .stdout(predicate::str::contains("\n[foo] [3] ∎∎∎\n"))
+                .stdout(predicate::str::contains("\n[  x] [2] ∎∎\n"));
+        }
+        Err(_) => panic!("Could not create temp file"),
+    }
+}
+
+#[test]
+fn test_top() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("top")
+        .arg("--regex")
+        .arg("([0-9]+)")
+        .write_stdin("1\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("capture group named `key`"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("top")
+        .arg("--regex")
+        .arg("(?<key>[0-9.]+) ([0-9]+)")
+        .arg("--precision")
+        .arg("0")
+        .arg("--lines")
+        .arg("2")
+        .arg("--width")
+        .arg("10")
+        .write_stdin("10.0.0.1 500\n10.0.0.2 100\n10.0.0.1 500\n10.0.0.3 50\nnone\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Keys: 3.\nSum of the values per key\n",
+        ))
+        .stdout(predicate::str::contains("[10.0.0.1] [1000] ∎∎∎∎∎∎∎∎∎∎\n"))
+        .stdout(predicate::str::contains("[10.0.0.2] [ 100] ∎\n"))
+        .stdout(predicate::str::contains("10.0.0.3").not());
+}
+
+#[test]
+fn test_groupstats() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("groupstats")
+        .arg("--regex")
+        .arg("1")
+        .write_stdin("GET 1\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("capture group named `key`"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("groupstats")
+        .arg("--regex")
+        .arg("(?<key>[A-Z]+) ([0-9.]+)")
+        .arg("--precision")
+        .arg("1")
+        .write_stdin("GET 1\nPOST 4\nGET 3\nnone\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Groups: 2."))
+        .stdout(predicate::str::contains(
+            "GET         2     2.0     3.0     3.0\n",
+        ))
+        .stdout(predicate::str::contains(
+            "POST        1     4.0     4.0     4.0\n",
+        ));
+}
+
+#[test]
+fn test_timehist_base_date() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("timehist")
+        .arg("--base-date")
+        .arg("yesterday")
+        .write_stdin("23:59:59 foo\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to parse base date"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("timehist")
+        .arg("--base-date")
+        .arg("2021-04-15")
+        .arg("--intervals")
+        .arg("2")
+        .write_stdin("23:00:00 foo\n23:59:59 foo\n00:59:58 foo\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Matches: 3."))
+        .stdout(predicate::str::contains("[23:00:00] [1] ∎\n"))
+        .stdout(predicate::str::contains("[23:59:59] [2] ∎∎\n"));
+}
+
+#[test]
+fn test_timehist_ts_capture() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("timehist")
+        .arg("--regex")
+        .arg(r"^\S+ (?P<ts>\S+ \S+) ")
+        .arg("--intervals")
+        .arg("2")
+        .write_stdin("INFO 2021-04-15 06:00:00 foo\nWARNING 2021-04-15 06:00:01 bar\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Matches: 2."));
+}
+
+#[test]
+fn test_timehist_strict_position() {
+    let input = "INFO 2021-04-15 06:00:00 foo\nWARNING 2021-04-15 06:00:01 bar\n";
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("timehist")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Matches: 2."));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("timehist")
+        .arg("--strict-position")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Not enough data"));
+}
+
+#[test]
+fn test_compare() {
+    let mut before = NamedTempFile::new().unwrap();
+    writeln!(before, "1\n2\n2\n4").unwrap();
+    let mut after = NamedTempFile::new().unwrap();
+    writeln!(after, "3\n4\n5\n5").unwrap();
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("compare")
+        .arg(before.path().to_str().unwrap())
+        .arg(after.path().to_str().unwrap())
+        .arg("--intervals")
+        .arg("2")
+        .arg("--precision")
+        .arg("1")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Before:\nSamples = 4; Min = 1.0; Max = 4.0\n",
+        ))
+        .stdout(predicate::str::contains(
+            "After:\nSamples = 4; Min = 3.0; Max = 5.0\n",
+        ))
+        .stdout(predicate::str::contains("[1.0 .. 3.0] [3] ∎∎∎\n"))
+        .stdout(predicate::str::contains("             [0]  (-3)\n"))
+        .stdout(predicate::str::contains("[3.0 .. 5.0] [1] ∎\n"))
+        .stdout(predicate::str::contains("             [4] ∎∎∎∎ (+3)\n"));
+}
+
+#[test]
+fn test_compare_normalized() {
+    let mut before = NamedTempFile::new().unwrap();
+    writeln!(before, "1\n2\n2\n4").unwrap();
+    let mut after = NamedTempFile::new().unwrap();
+    writeln!(after, "3\n4\n5\n5\n5\n5\n5\n5").unwrap();
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("compare")
+        .arg(before.path().to_str().unwrap())
+        .arg(after.path().to_str().unwrap())
+        .arg("--intervals")
+        .arg("2")
+        .arg("--precision")
+        .arg("1")
+        .arg("--width")
+        .arg("50")
+        .arg("--normalize")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Each ∎ represents 5.88% of the values\n",
+        ))
+        .stdout(predicate::str::contains(
+            "[1.0 .. 3.0] [ 75.00%] ∎∎∎∎∎∎∎∎∎∎∎∎\n",
+        ))
+        .stdout(predicate::str::contains(
+            "             [  0.00%]  (-75.00%)\n",
+        ))
+        .stdout(predicate::str::contains("[3.0 .. 5.0] [ 25.00%] ∎∎∎∎\n"))
+        .stdout(predicate::str::contains(
+            "             [100.00%] ∎∎∎∎∎∎∎∎∎∎∎∎∎∎∎∎∎ (+75.00%)\n",
+        ));
+}
+
+#[test]
+fn test_multiline_join() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("matches")
+        .arg("--multiline-join")
+        .arg("^\\s")
+        .arg("ERROR")
+        .arg("Exception")
+        .write_stdin("ERROR foo\n  Exception\n  Exception\nERROR bar\nINFO Exception\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\n[ERROR    ] [2] ∎∎\n"))
+        .stdout(predicate::str::contains("\n[Exception] [2] ∎∎\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("timehist")
+        .arg("--multiline-join")
+        .arg("(")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to parse regex"));
+}
+
+#[test]
+fn test_hist_locate() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--locate")
+        .arg("--precision")
+        .arg("1")
+        .arg("--regex")
+        .arg("took ([0-9.]+)")
+        .write_stdin("GET /a took 4.2\nGET /b took 2.4\nnoise\nGET /c took 0.1\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Location of extreme values:\n"))
+        .stdout(predicate::str::contains(
+            "Min = 0.1 at line 4 (byte 38): GET /c took 0.1\n",
+        ))
+        .stdout(predicate::str::contains(
+            "Max = 4.2 at line 1 (byte 0): GET /a took 4.2\n",
+        ))
+        .stdout(predicate::str::contains(
+            "Top 2 = 2.4 at line 2 (byte 16): GET /b took 2.4\n",
+        ));
+    // With data formats, the location goes to stderr
+    let (buckets, stderr) = json_output(&["plot", "--locate", "--height", "2"], "1\n5\n2\n");
+    assert_eq!(buckets.as_array().unwrap().len(), 3);
+    assert!(stderr.contains("Max = 5.000 at line 2 (byte 2): 5\n"));
+}
+
+#[test]
+fn test_hist_symlog() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--symlog")
+        .arg("-1")
+        .write_stdin("1\n2\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("threshold should be a positive"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--symlog")
+        .arg("1")
+        .arg("--intervals")
+        .arg("2")
+        .arg("--precision")
+        .arg("1")
+        .write_stdin("-99\n-5\n5\n40\n99\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\n[-99.0 ..   0.0] [2] ∎∎\n"))
+        .stdout(predicate::str::contains("\n[  0.0 ..  99.0] [3] ∎∎∎\n"));
+}
+
+#[test]
+fn test_hist_transform() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--transform")
+        .arg("sqrt")
+        .arg("--intervals")
+        .arg("2")
+        .arg("--precision")
+        .arg("1")
+        .write_stdin("-1\n1\n4\n9\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Discarded 1 values"))
+        .stdout(predicate::str::contains("Transformed values: sqrt(x)\n"))
+        .stdout(predicate::str::contains("\n[1.0 .. 2.0] [1] ∎\n"))
+        .stdout(predicate::str::contains("\n[2.0 .. 3.0] [2] ∎∎\n"));
+}
+
+#[test]
+fn test_hist_logfmt() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--logfmt")
+        .arg("took")
+        .write_stdin(
+            "level=info took=2.4 path=/\nlevel=info msg=\"no value\"\nlevel=warn took=4.2\n",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 2; Min = 2.400; Max = 4.200",
+        ));
+}
+
+#[test]
+fn test_hist_json_field() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--json-field")
+        .arg(".http.took")
+        .write_stdin(
+            "{\"http\": {\"took\": 2.4}}\n{\"msg\": \"no value\"}\n{\"http\": {\"took\": 4.2}}\n",
+        )
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 2; Min = 2.400; Max = 4.200",
+        ));
+}
+
+#[test]
+fn test_hist_rank_of() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("hist")
+        .arg("--rank-of")
+        .arg("3.5")
+        .write_stdin("1\n2\n3\n4\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\n3.500 is p75.0\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("plot")
+        .arg("--rank-of")
+        .arg("foo")
+        .write_stdin("1\n2\n3\n4\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Value for rank should be a number",
+        ));
+}
+
+#[test]
+fn test_hist_csv() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--csv")
+        .arg("--column")
+        .arg("latency")
+        .write_stdin("path,latency\n\"/foo,bar\",2.4\n/baz,4.2\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 2; Min = 2.400; Max = 4.200",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--csv")
+        .arg("--column")
+        .arg("0")
+        .write_stdin("1\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Column numbers start at 1"));
+}
+
+#[test]
+fn test_plot_show_extremes() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("plot")
+        .arg("--show-extremes")
+        .arg("2")
+        .arg("--precision")
+        .arg("0")
+        .write_stdin("5\n1\n7\n3\n9\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\nMin = 1 at line 2 (byte 2): 1\n",
+        ))
+        .stdout(predicate::str::contains(
+            "\nBottom 2 = 3 at line 4 (byte 6): 3\n",
+        ))
+        .stdout(predicate::str::contains(
+            "\nMax = 9 at line 5 (byte 8): 9\n",
+        ))
+        .stdout(predicate::str::contains(
+            "\nTop 2 = 7 at line 3 (byte 4): 7\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--show-extremes")
+        .arg("0")
+        .write_stdin("1\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("should be a positive integer"));
+}
+
+#[test]
+fn test_hist_preset() {
+    let log = concat!(
+        "10.0.0.1 - - [15/Apr/2021:06:25:31 +0000] \"GET / HTTP/1.1\" 200 612 \"-\" \"curl\" 0.5\n",
+        "10.0.0.2 - - [15/Apr/2021:06:26:31 +0000] \"GET / HTTP/1.1\" 500 0 \"-\" \"curl\" 1.5\n",
+    );
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--preset")
+        .arg("nginx")
+        .write_stdin(log)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 2; Min = 0.500; Max = 1.500",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("hist")
+        .arg("--preset")
+        .arg("nginx")
+        .arg("--capture")
+        .arg("status")
+        .write_stdin(log)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 2; Min = 200.00; Max = 500.00",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("timehist")
+        .arg("--preset")
+        .arg("nginx")
+        .arg("--intervals")
+        .arg("2")
+        .write_stdin(log)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Matches: 2."));
+}
+
+#[test]
+fn test_config_file() {
+    let mut config = NamedTempFile::new().unwrap();
+    writeln!(
+        config,
+        r#"
+[defaults]
+color = "no"
+precision = 1
+
+[defaults.timehist]
+intervals = 1
+
+[presets.myapp]
+regex = '^(?P<time>\S+ \S+) took (?P<latency>[0-9.]+)'
+format = "%Y-%m-%d %H:%M:%S"
+value = "latency"
+"#
+    )
+    .unwrap();
+    let log = "2021-04-15 06:25:31 took 0.5\n2021-04-15 06:25:33 took 1.5\n";
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.env("LOWCHARTS_CONFIG", config.path())
+        .arg("hist")
+        .arg("--preset")
+        .arg("myapp")
+        .write_stdin(log)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Samples = 2; Min = 0.5; Max = 1.5\n",
+        ));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.env("LOWCHARTS_CONFIG", config.path())
+        .arg("hist")
+        .arg("--preset")
+        .arg("myapp")
+        .arg("--precision")
+        .arg("2")
+        .write_stdin(log)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Min = 0.50; Max = 1.50\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.env("LOWCHARTS_CONFIG", config.path())
+        .arg("timehist")
+        .arg("--preset")
+        .arg("myapp")
+        .write_stdin(log)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\n[06:25:31.000] [2] ∎∎\n"));
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.env("LOWCHARTS_CONFIG", config.path())
+        .arg("hist")
+        .arg("--preset")
+        .arg("nope")
+        .write_stdin(log)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown preset nope"));
+    writeln!(config, "[presets.broken]").unwrap();
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.env("LOWCHARTS_CONFIG", config.path())
+        .arg("hist")
+        .write_stdin(log)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Missing regex in preset broken"));
+}
+
+#[test]
+fn test_zipf() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("zipf")
+        .arg("--height")
+        .arg("2")
+        .arg("--width")
+        .arg("20")
+        .write_stdin("a\na\na\na\nb\nb\nc\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Terms: 3; Zipf exponent = "))
+        .stdout(predicate::str::starts_with("Terms: 3;"))
+        .stdout(predicate::str::ends_with("    1              3\n"));
+}
+
+#[test]
+fn test_distinct() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("distinct")
+        .arg("--regex")
+        .arg("ip=(\\S+)")
+        .write_stdin("ip=a\nip=b\nip=a\nfoo\nip=c\n")
+        .assert()
+        .success()
+        .stdout("Distinct values: ~3 (standard error 0.8%)\n");
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("--color")
+        .arg("no")
+        .arg("distinct")
+        .arg("--over-time")
+        .arg("--intervals")
+        .arg("2")
+        .arg("--regex")
+        .arg("ip=(\\S+)")
+        .write_stdin("00:00:00 ip=a\n00:00:01 ip=a\n00:00:02 ip=b\n00:00:03 ip=c\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\n[00:00:00.000] [1] ∎\n"))
+        .stdout(predicate::str::contains("\n[00:00:01.500] [2] ∎∎\n"));
+}
+
+#[test]
+fn test_examples() {
+    let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+    cmd.arg("examples")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "# Top exceptions in application logs\n\
+             lowcharts common-terms --regex '([A-Za-z]+(Error|Exception))' app.log\n",
+        ));
+}
+
+#[test]
+fn test_examples_work() {
+    for example in examples::EXAMPLES {
+        let mut cmd = Command::cargo_bin("lowcharts").unwrap();
+        cmd.arg("--color")
+            .arg("no")
+            .args(example.args)
+            .arg(format!("tests/fixtures/{}", example.input))
+            .assert()
+            .success()
+            .stdout(predicate::str::is_empty().not())
+            .stderr(predicate::str::is_empty());
     }
 }